@@ -410,6 +410,12 @@ pub enum FocusCommand {
     #[strum(message = "Show Hover")]
     #[strum(serialize = "show_hover")]
     ShowHover,
+    #[strum(message = "Peek Next Result")]
+    #[strum(serialize = "peek.next")]
+    PeekNext,
+    #[strum(message = "Peek Previous Result")]
+    #[strum(serialize = "peek.previous")]
+    PeekPrevious,
     #[strum(message = "Go to Next Difference")]
     #[strum(serialize = "next_diff")]
     NextDiff,
@@ -206,6 +206,8 @@ pub struct DocLines {
     /// This will be displayed even on views that are not focused.
     /// (line, col)
     pub inline_completion: Option<(String, usize, usize)>,
+    /// Git blame annotation shown at the end of a line. (line, text)
+    pub blame_lens:        Option<(usize, String)>,
     pub preedit:           PreeditData,
     // tree-sitter
     pub syntax:            Syntax,
@@ -266,6 +268,7 @@ impl DocLines {
             diagnostics,
             completion_lens: None,
             inline_completion: None,
+            blame_lens: None,
             preedit: PreeditData::new(cx),
             syntax,
             semantic_styles: None,
@@ -1150,6 +1153,8 @@ impl DocLines {
                         start_position,
                     ))?;
                     return Ok(ClickResult::MatchFolded);
+                } else if let PhantomTextKind::Blame = phantom.kind {
+                    return Ok(ClickResult::MatchBlame(phantom.line));
                 }
                 ClickResult::MatchWithoutLocation
             } else {
@@ -1457,10 +1462,13 @@ impl DocLines {
     //     }
     // }
 
+    /// `reserved` keeps `count` blank lines under an origin line, as
+    /// `(line, count)`, for a view embedded in the editor.
     pub fn compute_screen_lines_new(
         &mut self,
         base: Rect,
         view_kind: EditorViewKind,
+        reserved: Option<(usize, usize)>,
     ) -> Result<(ScreenLines, Vec<FoldingDisplayItem>, Vec<VisualLine>)> {
         debug!("_compute_screen_lines base={base:?} kind={view_kind:?}");
         let line_height = self.config.line_height;
@@ -1471,7 +1479,12 @@ impl DocLines {
         let (folded, changes, is_normal) = match view_kind {
             EditorViewKind::Normal => (
                 self.folding_ranges.get_all_folded_range(self.buffer()),
-                vec![],
+                reserved
+                    .map(|(line, count)| DiffResult::Empty {
+                        lines: line + 1..line + 1 + count,
+                    })
+                    .into_iter()
+                    .collect(),
                 true,
             ),
             EditorViewKind::Diff { changes, .. } => {
@@ -2324,6 +2337,29 @@ impl DocLines {
             text.push(inline_completion_text);
         }
 
+        if let Some((_, blame)) = self
+            .blame_lens
+            .as_ref()
+            .filter(|(blame_line, _)| *blame_line == line)
+        {
+            let col = buffer.line_end_col(line, true)?;
+            if !folded_ranges.contain_offset(start_offset + col) {
+                text.push(PhantomText {
+                    kind: PhantomTextKind::Blame,
+                    col,
+                    text: blame.clone(),
+                    fg: Some(self.config.inlay_hint_fg),
+                    font_size: Some(self.config.inlay_hint_font_size()),
+                    bg: None,
+                    under_line: None,
+                    final_col: col,
+                    line,
+                    visual_merge_col: col,
+                    origin_merge_col: col,
+                });
+            }
+        }
+
         if let Some(preedit) = preedit_phantom.as_ref() {
             if preedit.line == line {
                 text.push(preedit.clone());
@@ -4323,6 +4359,28 @@ impl PubUpdateLines {
         Ok(())
     }
 
+    pub fn set_blame_lens(&mut self, line: usize, blame: String) {
+        if self
+            .blame_lens
+            .as_ref()
+            .is_some_and(|(blame_line, text)| *blame_line == line && *text == blame)
+        {
+            return;
+        }
+        self.blame_lens = Some((line, blame));
+        self.on_update_lines();
+        self.signals.update_paint_text();
+        self.trigger_signals();
+    }
+
+    pub fn clear_blame_lens(&mut self) {
+        if self.blame_lens.take().is_some() {
+            self.on_update_lines();
+            self.signals.update_paint_text();
+            self.trigger_signals();
+        }
+    }
+
     pub fn set_syntax_with_rev(&mut self, syntax: Syntax, rev: u64) -> Result<bool> {
         if self.buffer().rev() != rev {
             return Ok(false);
@@ -4538,6 +4596,8 @@ pub enum ClickResult {
     MatchWithoutLocation,
     MatchFolded,
     MatchHint(Location),
+    /// The blame annotation of the line
+    MatchBlame(usize),
 }
 
 #[derive(Debug)]
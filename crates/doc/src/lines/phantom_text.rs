@@ -292,6 +292,8 @@ pub enum PhantomTextKind {
     InlayHint,
     /// Error lens
     Diagnostic,
+    /// Git blame of the cursor line, shown after the end of the line
+    Blame,
     // 行内折叠。跨行折叠也都转换成行内折叠。跨行折叠会转成2个PhantomText
     LineFoldedRang {
         next_line:      Option<usize>,
@@ -48,7 +48,7 @@ use floem::{
     unit::{PxPctAuto, PxPctAuto::Auto},
     views::{
         Decorators, VirtualVector, clip, container, drag_resize_window_area,
-        dyn_stack, dyn_view, empty, label, rich_text,
+        dyn_container, dyn_stack, dyn_view, empty, label, rich_text,
         scroll::{PropagatePointerWheel, VerticalScrollAsHorizontal, scroll},
         stack, tab, text, tooltip, v_stack, virtual_stack,
     },
@@ -68,6 +68,7 @@ use lapce_core::{
 use lapce_rpc::{
     RpcMessage,
     core::{CoreMessage, CoreNotification},
+    dap_types::DebugRequest,
     file::PathObject,
 };
 use log::{error, trace};
@@ -86,7 +87,7 @@ use crate::{
     db::LapceDb,
     editor::{
         location::{EditorLocation, EditorPosition},
-        view::{editor_container_view, editor_diff_header},
+        view::{editor_container_view, editor_diff_header, merge_editor_header},
     },
     editor_tab::{
         EditorTabChildId, EditorTabChildSimple, EditorTabDraging,
@@ -1332,9 +1333,33 @@ fn editor_tab_content(
                     // create_rw_signal();
                     let right_editor = diff_editor_data.right.clone();
                     // create_rw_signal();
+                    let merge = diff_editor_data.merge;
+                    // the result editor of a merge editor has the focus
+                    let result_focused = move |tracked: bool| {
+                        if tracked {
+                            merge.with(|merge| {
+                                merge.as_ref().is_some_and(|m| m.focus_result.get())
+                            })
+                        } else {
+                            merge.with_untracked(|merge| {
+                                merge
+                                    .as_ref()
+                                    .is_some_and(|m| m.focus_result.get_untracked())
+                            })
+                        }
+                    };
+                    let unfocus_result = move || {
+                        merge.with_untracked(|merge| {
+                            if let Some(merge) = merge {
+                                merge.focus_result.set(false);
+                            }
+                        });
+                    };
                     v_stack((
                         editor_diff_header(config, right_editor.clone())
                             .debug_name("editor_diff_header"),
+                        merge_editor_header(window_tab_data.clone(), merge)
+                            .debug_name("merge_editor_header"),
                         stack((
                             container(
                                 editor_container_view(
@@ -1342,6 +1367,7 @@ fn editor_tab_content(
                                     workspace.clone(),
                                     move |track| {
                                         is_active(track)
+                                            && !result_focused(track)
                                             && if track {
                                                 !focus_right.get()
                                             } else {
@@ -1353,6 +1379,7 @@ fn editor_tab_content(
                                 .debug_name("Left Editor"),
                             )
                             .on_event_cont(EventListener::PointerDown, move |_| {
+                                unfocus_result();
                                 focus_right.set(false);
                             })
                             .style(move |s| {
@@ -1364,12 +1391,52 @@ fn editor_tab_content(
                                         config.with_color(LapceColor::LAPCE_BORDER),
                                     )
                             }),
+                            // the base our and their versions changed, shown
+                            // between them by a merge editor
+                            dyn_container(move || merge.get(), {
+                                let window_tab_data = window_tab_data.clone();
+                                let workspace = workspace.clone();
+                                move |merge| {
+                                    let Some(merge) = merge else {
+                                        return empty().into_any();
+                                    };
+                                    let base_scroll_to = merge.base.scroll_to;
+                                    create_effect(move |_| {
+                                        let right_viewport = right_viewport.get();
+                                        base_scroll_to.set(Some(
+                                            right_viewport.origin().to_vec2(),
+                                        ));
+                                    });
+                                    editor_container_view(
+                                        window_tab_data.clone(),
+                                        workspace.clone(),
+                                        |_| false,
+                                        merge.base,
+                                    )
+                                    .debug_name("Base Editor")
+                                    .into_any()
+                                }
+                            })
+                            .style(move |s| {
+                                s.height_full()
+                                    .flex_grow(1.0)
+                                    .flex_basis(0.0)
+                                    .border_right(1.0)
+                                    .border_color(
+                                        config.with_color(LapceColor::LAPCE_BORDER),
+                                    )
+                                    .apply_if(
+                                        merge.with(|merge| merge.is_none()),
+                                        |s| s.hide(),
+                                    )
+                            }),
                             container(
                                 editor_container_view(
                                     window_tab_data.clone(),
                                     workspace.clone(),
                                     move |track| {
                                         is_active(track)
+                                            && !result_focused(track)
                                             && if track {
                                                 focus_right.get()
                                             } else {
@@ -1381,6 +1448,7 @@ fn editor_tab_content(
                                 .debug_name("Right Editor"),
                             )
                             .on_event_cont(EventListener::PointerDown, move |_| {
+                                unfocus_result();
                                 focus_right.set(true);
                             })
                             .style(|s| {
@@ -1390,7 +1458,54 @@ fn editor_tab_content(
                                  *     &diff_editor_data.right
                                  * ) */
                         ))
-                        .style(|s: Style| s.size_full()),
+                        .style(|s: Style| {
+                            s.width_full().flex_grow(1.0).flex_basis(0.0)
+                        }),
+                        // the working copy file the conflicts are resolved in,
+                        // below the three versions of a merge editor
+                        dyn_container(move || merge.get(), {
+                            let window_tab_data = window_tab_data.clone();
+                            let workspace = workspace.clone();
+                            move |merge| {
+                                let Some(merge) = merge else {
+                                    return empty().into_any();
+                                };
+                                let focus_result = merge.focus_result;
+                                editor_container_view(
+                                    window_tab_data.clone(),
+                                    workspace.clone(),
+                                    move |track| {
+                                        is_active(track)
+                                            && if track {
+                                                focus_result.get()
+                                            } else {
+                                                focus_result.get_untracked()
+                                            }
+                                    },
+                                    merge.result_editor,
+                                )
+                                .on_event_cont(
+                                    EventListener::PointerDown,
+                                    move |_| {
+                                        focus_result.set(true);
+                                    },
+                                )
+                                .debug_name("Result Editor")
+                                .into_any()
+                            }
+                        })
+                        .style(move |s| {
+                            s.width_full()
+                                .flex_grow(1.0)
+                                .flex_basis(0.0)
+                                .border_top(1.0)
+                                .border_color(
+                                    config.with_color(LapceColor::LAPCE_BORDER),
+                                )
+                                .apply_if(merge.with(|merge| merge.is_none()), |s| {
+                                    s.hide()
+                                })
+                        }),
                     ))
                     .into_any()
                 } else {
@@ -2559,11 +2674,15 @@ fn palette_item(
         } => {
             let mode = *mode;
             let text = format!("{mode} {}", run_config.name);
-            let hint = format!(
-                "{} {}",
-                run_config.program,
-                run_config.args.clone().unwrap_or_default().join(" ")
-            );
+            let hint = if run_config.request == DebugRequest::Attach {
+                run_config.attach_target()
+            } else {
+                format!(
+                    "{} {}",
+                    run_config.program,
+                    run_config.args.clone().unwrap_or_default().join(" ")
+                )
+            };
             let text_indices: Vec<usize> = item
                 .indices
                 .iter()
@@ -2672,7 +2791,9 @@ fn palette_item(
         | PaletteItemContent::LineEnding { .. }
         | PaletteItemContent::ColorTheme { .. }
         | PaletteItemContent::SCMReference { .. }
+        | PaletteItemContent::BreakpointField { .. }
         | PaletteItemContent::TerminalProfile { .. }
+        | PaletteItemContent::DebugProcess { .. }
         | PaletteItemContent::IconTheme { .. } => {
             let text = item.filter_text;
             let indices = item.indices;
@@ -2712,28 +2833,38 @@ fn palette_item(
 }
 
 fn palette_input(window_tab_data: WindowWorkspaceData) -> impl View {
-    let input_str = window_tab_data.palette.input_str;
-    let status = window_tab_data.palette.status.read_only();
+    let palette = window_tab_data.palette.clone();
+    let input_str = palette.input_str;
+    let status = palette.status.read_only();
     let config = window_tab_data.common.config;
 
-    let input = text_input(input_str)
-        .placeholder(window_tab_data.palette.placeholder_text().to_owned())
-        .style(|s| s.width_full())
-        .debug_name("Pallete Input")
-        .on_event(EventListener::KeyDown, move |event| {
-            if let Event::KeyDown(_key_event) = event {
-                window_tab_data.key_down(_key_event);
-                EventPropagation::Stop
-            } else {
-                EventPropagation::Continue
-            }
-        });
-    let id = input.id();
-    create_effect(move |_| {
-        if status.get() == PaletteStatus::Started {
-            id.request_focus()
-        }
-    });
+    // rebuilt for the placeholder to follow the palette kind
+    let input = dyn_container(
+        move || palette.input_placeholder(),
+        move |placeholder| {
+            let window_tab_data = window_tab_data.clone();
+            let input = text_input(input_str)
+                .placeholder(placeholder)
+                .style(|s| s.width_full())
+                .debug_name("Pallete Input")
+                .on_event(EventListener::KeyDown, move |event| {
+                    if let Event::KeyDown(_key_event) = event {
+                        window_tab_data.key_down(_key_event);
+                        EventPropagation::Stop
+                    } else {
+                        EventPropagation::Continue
+                    }
+                });
+            let id = input.id();
+            create_effect(move |_| {
+                if status.get() == PaletteStatus::Started {
+                    id.request_focus()
+                }
+            });
+            input.into_any()
+        },
+    )
+    .style(|s| s.width_full());
 
     container(input).style(move |s| {
         let (caret_color, bg) = config.signal(|config| {
@@ -3138,28 +3269,52 @@ fn hover(window_tab_data: WindowWorkspaceData) -> impl View {
     let config = window_tab_data.common.config;
     let id = AtomicU64::new(0);
     let layout_rect = window_tab_data.common.hover.layout_rect;
+    let workbench_command = window_tab_data.common.workbench_command;
 
-    scroll(
-        dyn_stack(
-            move || hover_data.content.get(),
-            move |_| id.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
-            move |content| match content {
-                MarkdownContent::Text(text_layout) => container(
-                    rich_text(move || text_layout.clone())
-                        .style(|s| s.max_width(600.0)),
-                )
-                .style(|s| s.max_width_full()),
-                MarkdownContent::Image { .. } => container(empty()),
-                MarkdownContent::Separator => container(empty().style(move |s| {
-                    s.width_full()
-                        .margin_vert(5.0)
-                        .height(1.0)
-                        .background(config.with_color(LapceColor::LAPCE_BORDER))
-                })),
-            },
+    stack((
+        label(|| "Pin".to_string())
+            .on_click_stop(move |_| {
+                workbench_command.send(LapceWorkbenchCommand::PinHover);
+            })
+            .style(move |s| {
+                s.absolute()
+                    .inset_right(6.0)
+                    .inset_top(2.0)
+                    .padding_horiz(4.0)
+                    .font_size(11.0)
+                    .border_radius(4.0)
+                    .color(config.with_color(LapceColor::EDITOR_DIM))
+                    .hover(|s| {
+                        s.cursor(CursorStyle::Pointer).background(
+                            config.with_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        )
+                    })
+                    .selectable(false)
+            }),
+        scroll(
+            dyn_stack(
+                move || hover_data.content.get(),
+                move |_| id.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+                move |content| match content {
+                    MarkdownContent::Text(text_layout, _) => container(
+                        rich_text(move || text_layout.clone())
+                            .style(|s| s.max_width(600.0)),
+                    )
+                    .style(|s| s.max_width_full()),
+                    MarkdownContent::Image { .. } => container(empty()),
+                    MarkdownContent::Separator => {
+                        container(empty().style(move |s| {
+                            s.width_full().margin_vert(5.0).height(1.0).background(
+                                config.with_color(LapceColor::LAPCE_BORDER),
+                            )
+                        }))
+                    },
+                },
+            )
+            .style(|s| s.flex_col().padding_horiz(10.0).padding_vert(5.0)),
         )
-        .style(|s| s.flex_col().padding_horiz(10.0).padding_vert(5.0)),
-    )
+        .style(|s| s.max_height_full().min_height(0.0)),
+    ))
     .on_resize(move |rect| {
         layout_rect.set(rect);
     })
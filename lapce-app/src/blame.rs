@@ -0,0 +1,221 @@
+use std::rc::Rc;
+
+use anyhow::Result;
+use doc::lines::buffer::rope_text::{RopeText, RopeTextRef};
+use lapce_rpc::source_control::{BlameHunk, CommitInfo};
+use lapce_xi_rope::{Rope, RopeDelta, Transformer};
+
+/// The commit that last changed each line of a document, `None` for lines
+/// that are not committed yet. Empty when the document isn't blamed.
+pub type LineBlame = Vec<Option<Rc<CommitInfo>>>;
+
+pub fn line_blame(hunks: Vec<BlameHunk>) -> LineBlame {
+    let mut blame = Vec::new();
+    for hunk in hunks {
+        let start = hunk.start_line as usize;
+        let end = start + hunk.lines as usize;
+        if blame.len() < end {
+            blame.resize(end, None);
+        }
+        let commit = hunk.commit.map(Rc::new);
+        blame[start..end].fill(commit);
+    }
+    blame
+}
+
+/// Move the blame of each line along with an edit, the same way breakpoints
+/// follow their line. Lines touched by the edit are no longer attributed to
+/// any commit, those whole lines were inserted above aren't touched.
+pub fn update_blame_by_rope_delta(
+    blame: &mut LineBlame,
+    delta: &RopeDelta,
+    old_text: &Rope,
+    new_text: &impl RopeText,
+) -> Result<()> {
+    if blame.is_empty() {
+        return Ok(());
+    }
+    let old_text = RopeTextRef::new(old_text);
+    let (iv, new_len) = delta.summary();
+    let first = old_text.line_of_offset(iv.start);
+    let mut last = old_text.line_of_offset(iv.end);
+    if last > first && old_text.offset_of_line(last)? == iv.end {
+        last -= 1;
+    }
+    let lines_inserted = iv.is_empty()
+        && new_len > 0
+        && old_text.offset_of_line(first)? == iv.start
+        && new_text.slice_to_cow(iv.start + new_len - 1..iv.start + new_len) == "\n";
+
+    let mut transformer = Transformer::new(delta);
+    let mut new_blame = vec![None; new_text.num_lines()];
+    for (line, commit) in blame.iter().enumerate() {
+        if !lines_inserted && (first..=last).contains(&line) {
+            continue;
+        }
+        let offset = old_text.offset_of_line(line)?;
+        let offset = transformer.transform(offset, lines_inserted);
+        let new_line = new_text.line_of_offset(offset);
+        // the line was joined with the previous one
+        if new_text.offset_of_line(new_line)? != offset {
+            continue;
+        }
+        if let Some(new_commit) = new_blame.get_mut(new_line) {
+            *new_commit = commit.clone();
+        }
+    }
+    *blame = new_blame;
+    Ok(())
+}
+
+/// The annotation shown at the end of the cursor line.
+pub fn blame_annotation(commit: Option<&CommitInfo>, now: i64) -> String {
+    match commit {
+        Some(commit) => format!(
+            "    {}, {} • {}",
+            commit.author,
+            relative_time(commit.time, now),
+            commit.summary
+        ),
+        None => "    Not Committed Yet".to_string(),
+    }
+}
+
+/// The text of the blame gutter for the first line of a commit's run of lines.
+pub fn blame_gutter_text(commit: Option<&CommitInfo>, now: i64) -> String {
+    match commit {
+        Some(commit) => {
+            format!("{} {}", relative_time(commit.time, now), commit.author)
+        },
+        None => "Not Committed Yet".to_string(),
+    }
+}
+
+pub fn relative_time(time: i64, now: i64) -> String {
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+    const MONTH: i64 = 30 * DAY;
+    const YEAR: i64 = 365 * DAY;
+
+    let secs = (now - time).max(0);
+    let (value, unit) = match secs {
+        0..MINUTE => return "just now".to_string(),
+        MINUTE..HOUR => (secs / MINUTE, "minute"),
+        HOUR..DAY => (secs / HOUR, "hour"),
+        DAY..MONTH => (secs / DAY, "day"),
+        MONTH..YEAR => (secs / MONTH, "month"),
+        _ => (secs / YEAR, "year"),
+    };
+    if value == 1 {
+        format!("1 {unit} ago")
+    } else {
+        format!("{value} {unit}s ago")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ops::Range, rc::Rc};
+
+    use doc::lines::buffer::rope_text::RopeTextRef;
+    use lapce_rpc::source_control::{BlameHunk, CommitInfo};
+    use lapce_xi_rope::{Delta, Interval, Rope};
+
+    use super::{LineBlame, line_blame, relative_time, update_blame_by_rope_delta};
+
+    fn commit(id: &str) -> Option<Rc<CommitInfo>> {
+        Some(Rc::new(CommitInfo {
+            id:      id.to_string(),
+            author:  "someone".to_string(),
+            time:    0,
+            summary: id.to_string(),
+        }))
+    }
+
+    /// The blame of `a\nb\nc`, one commit per line, after replacing `range`
+    /// with `new`, by commit id.
+    fn blame_after_edit(range: Range<usize>, new: &str) -> Vec<Option<String>> {
+        let old_text = Rope::from("a\nb\nc");
+        let mut blame: LineBlame = vec![commit("a"), commit("b"), commit("c")];
+        let delta = Delta::simple_edit(
+            Interval::new(range.start, range.end),
+            Rope::from(new),
+            old_text.len(),
+        );
+        let new_text = delta.apply(&old_text);
+        update_blame_by_rope_delta(
+            &mut blame,
+            &delta,
+            &old_text,
+            &RopeTextRef::new(&new_text),
+        )
+        .unwrap();
+        blame
+            .into_iter()
+            .map(|commit| commit.map(|commit| commit.id.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_update_blame_by_rope_delta() {
+        let ids = |ids: &[Option<&str>]| {
+            ids.iter()
+                .map(|id| id.map(str::to_string))
+                .collect::<Vec<_>>()
+        };
+        // a line inserted above
+        assert_eq!(
+            blame_after_edit(0..0, "x\n"),
+            ids(&[None, Some("a"), Some("b"), Some("c")])
+        );
+        assert_eq!(
+            blame_after_edit(2..2, "x\n"),
+            ids(&[Some("a"), None, Some("b"), Some("c")])
+        );
+        // a deleted line
+        assert_eq!(blame_after_edit(2..4, ""), ids(&[Some("a"), Some("c")]));
+        // a line joined with the previous one
+        assert_eq!(blame_after_edit(1..2, ""), ids(&[None, Some("c")]));
+        // a line edited
+        assert_eq!(
+            blame_after_edit(2..3, "B"),
+            ids(&[Some("a"), None, Some("c")])
+        );
+    }
+
+    #[test]
+    fn test_relative_time() {
+        assert_eq!(relative_time(100, 130), "just now");
+        assert_eq!(relative_time(0, 60), "1 minute ago");
+        assert_eq!(relative_time(0, 3 * 3600 + 5), "3 hours ago");
+        assert_eq!(relative_time(0, 2 * 86400), "2 days ago");
+        assert_eq!(relative_time(0, 400 * 86400), "1 year ago");
+        assert_eq!(relative_time(200, 100), "just now");
+    }
+
+    #[test]
+    fn test_line_blame() {
+        let commit = CommitInfo {
+            id:      "abc".to_string(),
+            author:  "someone".to_string(),
+            time:    0,
+            summary: "init".to_string(),
+        };
+        let blame = line_blame(vec![
+            BlameHunk {
+                start_line: 0,
+                lines:      2,
+                commit:     Some(commit.clone()),
+            },
+            BlameHunk {
+                start_line: 2,
+                lines:      1,
+                commit:     None,
+            },
+        ]);
+        assert_eq!(blame.len(), 3);
+        assert_eq!(blame[1].as_deref(), Some(&commit));
+        assert!(blame[2].is_none());
+    }
+}
@@ -1,7 +1,9 @@
 use std::rc::Rc;
 
 use lapce_core::debug::RunDebugMode;
-use lapce_rpc::dap_types::{ConfigSource, RunDebugConfig, RunDebugProgram};
+use lapce_rpc::dap_types::{
+    ConfigSource, DebugRequest, RunDebugConfig, RunDebugProgram,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -128,15 +130,19 @@ impl CodeLensData {
             Some(RunDebugConfig {
                 ty,
                 name: cargo_args.label,
+                request: DebugRequest::Launch,
                 program,
                 args: Some(cargo_args.args.cargo_args),
                 cwd: None,
                 env: None,
+                stop_on_entry: false,
                 prelaunch,
                 debug_command: None,
                 dap_id: Default::default(),
                 tracing_output,
                 config_source: ConfigSource::RustCodeLens,
+                pid: None,
+                process_name: None,
             })
         } else {
             log::error!("no args");
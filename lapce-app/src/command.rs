@@ -4,7 +4,8 @@ use doc::lines::{command::*, editor_command::Command};
 use floem::{ViewId, keyboard::Modifiers, menu::MenuItem, peniko::kurbo::Vec2};
 use indexmap::IndexMap;
 use lapce_core::{
-    debug::RunDebugMode,
+    debug::{BreakpointField, RunDebugMode},
+    doc::DocContent,
     id::*,
     main_split::{SplitDirection, SplitMoveDirection, TabCloseKind},
     workspace::LapceWorkspace,
@@ -23,6 +24,7 @@ use strum_macros::{Display, EnumIter, EnumMessage, EnumString, IntoStaticStr};
 
 use crate::{
     alert::AlertButton,
+    debug::{BreakpointLocation, DapVariableEdit},
     doc::Doc,
     editor::{
         DocSignal,
@@ -239,6 +241,18 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Go to Implementation")]
     GoToImplementation,
 
+    #[strum(serialize = "peek_definition")]
+    #[strum(message = "Peek Definition")]
+    PeekDefinition,
+
+    #[strum(serialize = "peek_references")]
+    #[strum(message = "Peek References")]
+    PeekReferences,
+
+    #[strum(serialize = "pin_hover")]
+    #[strum(message = "Pin Hover")]
+    PinHover,
+
     #[strum(serialize = "reveal_in_panel")]
     #[strum(message = "Reveal in Panel")]
     RevealInPanel,
@@ -247,6 +261,10 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Source Control: Open Remote File Url")]
     SourceControlOpenActiveFileRemoteUrl,
 
+    #[strum(serialize = "source_control_copy_active_file_remote_url")]
+    #[strum(message = "Source Control: Copy Permalink")]
+    SourceControlCopyActiveFileRemoteUrl,
+
     #[cfg(not(target_os = "macos"))]
     #[strum(serialize = "reveal_in_file_explorer")]
     #[strum(message = "Reveal in System File Explorer")]
@@ -265,6 +283,10 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Reveal Active File in File Explorer")]
     RevealActiveFileInFileExplorer,
 
+    #[strum(serialize = "file_explorer_toggle_only_changed")]
+    #[strum(message = "File Explorer: Toggle Show Only Changed Files")]
+    FileExplorerToggleOnlyChanged,
+
     #[strum(serialize = "open_ui_inspector")]
     #[strum(message = "Open Internal UI Inspector")]
     OpenUIInspector,
@@ -438,6 +460,10 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "palette.run_and_debug")]
     PaletteRunAndDebug,
 
+    #[strum(message = "Debug: Attach to Process")]
+    #[strum(serialize = "palette.debug_attach")]
+    PaletteDebugAttach,
+
     #[strum(message = "Source Control: Checkout")]
     #[strum(serialize = "palette.scm_references")]
     PaletteSCMReferences,
@@ -564,6 +590,154 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "source_control_discard_workspace_changes")]
     SourceControlDiscardWorkspaceChanges,
 
+    #[strum(message = "Source Control: Stage All Changes")]
+    #[strum(serialize = "source_control_stage_all")]
+    SourceControlStageAll,
+
+    #[strum(message = "Source Control: Unstage All Changes")]
+    #[strum(serialize = "source_control_unstage_all")]
+    SourceControlUnstageAll,
+
+    #[strum(message = "Source Control: Stage Hunk at Cursor")]
+    #[strum(serialize = "source_control_stage_hunk")]
+    SourceControlStageHunk,
+
+    #[strum(message = "Source Control: Unstage Hunk at Cursor")]
+    #[strum(serialize = "source_control_unstage_hunk")]
+    SourceControlUnstageHunk,
+
+    #[strum(message = "Source Control: Peek Change")]
+    #[strum(serialize = "source_control_peek_change")]
+    SourceControlPeekChange,
+
+    #[strum(message = "Source Control: Revert Change")]
+    #[strum(serialize = "source_control_revert_change")]
+    SourceControlRevertChange,
+
+    #[strum(message = "Source Control: Next Change")]
+    #[strum(serialize = "source_control_next_change")]
+    SourceControlNextChange,
+
+    #[strum(message = "Source Control: Previous Change")]
+    #[strum(serialize = "source_control_previous_change")]
+    SourceControlPreviousChange,
+
+    #[strum(message = "Source Control: Stage Selected Lines")]
+    #[strum(serialize = "source_control_stage_selected_lines")]
+    SourceControlStageSelectedLines,
+
+    #[strum(message = "Source Control: Unstage Selected Lines")]
+    #[strum(serialize = "source_control_unstage_selected_lines")]
+    SourceControlUnstageSelectedLines,
+
+    #[strum(message = "Source Control: Checkout (Stash Local Changes)...")]
+    #[strum(serialize = "source_control_checkout_auto_stash")]
+    SourceControlCheckoutAutoStash,
+
+    #[strum(message = "Source Control: Create Branch...")]
+    #[strum(serialize = "source_control_create_branch")]
+    SourceControlCreateBranch,
+
+    #[strum(message = "Source Control: Create Branch From...")]
+    #[strum(serialize = "source_control_create_branch_from")]
+    SourceControlCreateBranchFrom,
+
+    #[strum(message = "Source Control: Rename Branch...")]
+    #[strum(serialize = "source_control_rename_branch")]
+    SourceControlRenameBranch,
+
+    #[strum(message = "Source Control: Delete Branch...")]
+    #[strum(serialize = "source_control_delete_branch")]
+    SourceControlDeleteBranch,
+
+    #[strum(message = "Source Control: Merge Branch...")]
+    #[strum(serialize = "source_control_merge_branch")]
+    SourceControlMergeBranch,
+
+    #[strum(message = "Source Control: Merge Branch (Create Merge Commit)...")]
+    #[strum(serialize = "source_control_merge_branch_no_ff")]
+    SourceControlMergeBranchNoFf,
+
+    #[strum(message = "Source Control: Stash Changes")]
+    #[strum(serialize = "source_control_stash")]
+    SourceControlStash,
+
+    #[strum(message = "Source Control: Stash Changes (Include Untracked)")]
+    #[strum(serialize = "source_control_stash_include_untracked")]
+    SourceControlStashIncludeUntracked,
+
+    #[strum(message = "Source Control: Apply Latest Stash")]
+    #[strum(serialize = "source_control_stash_apply_latest")]
+    SourceControlStashApplyLatest,
+
+    #[strum(message = "Source Control: Pop Latest Stash")]
+    #[strum(serialize = "source_control_stash_pop_latest")]
+    SourceControlStashPopLatest,
+
+    #[strum(message = "Source Control: Fetch")]
+    #[strum(serialize = "source_control_fetch")]
+    SourceControlFetch,
+
+    #[strum(message = "Source Control: Pull")]
+    #[strum(serialize = "source_control_pull")]
+    SourceControlPull,
+
+    #[strum(message = "Source Control: Pull (Rebase)")]
+    #[strum(serialize = "source_control_pull_rebase")]
+    SourceControlPullRebase,
+
+    #[strum(message = "Source Control: Push")]
+    #[strum(serialize = "source_control_push")]
+    SourceControlPush,
+
+    #[strum(message = "Source Control: Open Merge Editor")]
+    #[strum(serialize = "source_control_open_merge_editor")]
+    SourceControlOpenMergeEditor,
+
+    #[strum(message = "Source Control: Show Git Log")]
+    #[strum(serialize = "source_control_show_git_log")]
+    SourceControlShowGitLog,
+
+    #[strum(message = "Source Control: Compare File with Ref...")]
+    #[strum(serialize = "source_control_compare_file_with")]
+    SourceControlCompareFileWith,
+
+    #[strum(message = "Source Control: Compare Working Tree with Ref...")]
+    #[strum(serialize = "source_control_compare_working_tree_with")]
+    SourceControlCompareWorkingTreeWith,
+
+    #[strum(message = "Source Control: Compare Refs...")]
+    #[strum(serialize = "source_control_compare_refs")]
+    SourceControlCompareRefs,
+
+    #[strum(message = "Source Control: Show Gutter Changes Against Ref...")]
+    #[strum(serialize = "source_control_set_gutter_base")]
+    SourceControlSetGutterBase,
+
+    #[strum(message = "Source Control: Show Gutter Changes Against HEAD")]
+    #[strum(serialize = "source_control_reset_gutter_base")]
+    SourceControlResetGutterBase,
+
+    #[strum(message = "Source Control: Apply Patch File...")]
+    #[strum(serialize = "source_control_apply_patch_file")]
+    SourceControlApplyPatchFile,
+
+    #[strum(message = "Source Control: Apply Patch from Clipboard")]
+    #[strum(serialize = "source_control_apply_patch_from_clipboard")]
+    SourceControlApplyPatchFromClipboard,
+
+    #[strum(message = "Source Control: Export Unstaged Changes as Patch...")]
+    #[strum(serialize = "source_control_export_unstaged_patch")]
+    SourceControlExportUnstagedPatch,
+
+    #[strum(message = "Source Control: Export Staged Changes as Patch...")]
+    #[strum(serialize = "source_control_export_staged_patch")]
+    SourceControlExportStagedPatch,
+
+    #[strum(message = "Source Control: Show File History")]
+    #[strum(serialize = "source_control_show_file_history")]
+    SourceControlShowFileHistory,
+
     #[strum(serialize = "export_current_theme_settings")]
     #[strum(message = "Export current settings to a theme file")]
     ExportCurrentThemeSettings,
@@ -692,6 +866,26 @@ pub enum InternalCommand {
     OpenFileChanges {
         path: PathBuf,
     },
+    /// Diff `path` between `commit` and its parent
+    OpenFileCommitChanges {
+        path:   PathBuf,
+        commit: String,
+    },
+    /// Diff two documents, each a file of the workspace or a file at some
+    /// revision
+    OpenRevisionDiff {
+        left:  DocContent,
+        right: DocContent,
+    },
+    /// Stage the hunk of `path` touching `line`
+    StageHunk {
+        path: PathBuf,
+        line: u32,
+    },
+    /// Resolve the conflicts of `path` in the merge editor
+    OpenMergeEditor {
+        path: PathBuf,
+    },
     ReloadFileExplorer,
     /// Test whether a file/directory can be created at that path
     TestPathCreation {
@@ -777,6 +971,11 @@ pub enum InternalCommand {
     ApplyWorkspaceEdit {
         edit: WorkspaceEdit,
     },
+    /// Show the edit in the workspace edit panel instead of applying it
+    PreviewWorkspaceEdit {
+        title: String,
+        edit:  WorkspaceEdit,
+    },
     RunAndDebug {
         mode:   RunDebugMode,
         config: RunDebugConfig,
@@ -880,6 +1079,25 @@ pub enum InternalCommand {
         doc:      DocSignal,
         line_num: usize,
     },
+    /// Set the condition, hit count or log message of the breakpoint on a
+    /// line
+    EditBreakPoint {
+        doc:      DocSignal,
+        line_num: usize,
+    },
+    SetBreakpointField {
+        location: BreakpointLocation,
+        field:    BreakpointField,
+        value:    String,
+    },
+    /// Type a new value for a variable of a stopped session
+    EditDapVariable {
+        edit: DapVariableEdit,
+    },
+    SetDapVariable {
+        edit:  DapVariableEdit,
+        value: String,
+    },
     ShowStatusMessage {
         message: String,
     },
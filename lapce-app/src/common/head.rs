@@ -358,7 +358,7 @@ impl<T: Clone + TabHead + 'static> Tabs<T> {
         }
     }
 
-    pub fn push_tab(&self, content: String, references: T) {
+    pub fn push_tab(&self, content: String, references: T) -> ViewId {
         let id = ViewId::new();
         let active = self.active;
         let config = self.config;
@@ -377,6 +377,25 @@ impl<T: Clone + TabHead + 'static> Tabs<T> {
             self.tabs.update(|x| x.push(tab));
             self.active.set(Some(id));
         });
+        id
+    }
+
+    pub fn get_content(&self, id: ViewId) -> Option<T> {
+        self.tabs.with_untracked(|x| {
+            x.iter()
+                .find(|x| x.id == id)
+                .map(|x| x.references.get_untracked())
+        })
+    }
+
+    /// Update the content of the tab `id`, if it has not been closed yet.
+    pub fn update_content(&self, id: ViewId, f: impl FnOnce(&mut T)) {
+        let references = self
+            .tabs
+            .with_untracked(|x| x.iter().find(|x| x.id == id).map(|x| x.references));
+        if let Some(references) = references {
+            references.update(f);
+        }
     }
 
     fn tabs(&self) -> impl IntoIterator<Item = (Tab<T>, CloseManager<T>)> + use<T> {
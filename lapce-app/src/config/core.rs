@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use structdesc::FieldNames;
 
@@ -21,4 +23,22 @@ pub struct CoreConfig {
         desc = "Enable auto-reload for the plugin when its configuration changes."
     )]
     pub auto_reload_plugin: bool,
+    #[field_names(desc = "Run the pre-commit and commit-msg hooks of the \
+                          repository when committing")]
+    pub git_commit_hooks: bool,
+    #[field_names(desc = "The permalink URL template of each git host, either \
+                          \"github\", \"gitlab\", \"gitea\", \"bitbucket\" or a \
+                          URL with {host}, {repo}, {commit}, {file}, {start} and \
+                          {end} in it")]
+    #[serde(default)]
+    pub git_remote_url_templates: HashMap<String, String>,
+    #[field_names(desc = "The path of lldb-dap (or lldb-vscode), used for the \
+                          \"lldb\" and \"lldb-dap\" debugger types. If empty, it \
+                          is searched for on PATH")]
+    #[serde(default)]
+    pub lldb_dap_path: String,
+    #[field_names(desc = "The path of gdb 14 or newer, used for the \"gdb\" \
+                          debugger type. If empty, it is searched for on PATH")]
+    #[serde(default)]
+    pub gdb_path: String,
 }
@@ -123,6 +123,24 @@ pub struct EditorConfig {
     #[field_names(desc = "Whether it should format the document on save (if \
                           there is an available formatter)")]
     pub format_on_save: bool,
+    #[field_names(desc = "The code action kinds requested and applied in order \
+                          before saving, such as `source.organizeImports` or \
+                          `source.fixAll`")]
+    pub code_actions_on_save: Vec<String>,
+    #[field_names(desc = "How long (in ms) to wait for each code action kind on \
+                          save before skipping it")]
+    pub code_actions_on_save_timeout: u64,
+    #[field_names(desc = "Whether renames and code actions editing several files \
+                          should be reviewed in a preview panel before they are \
+                          applied")]
+    pub preview_workspace_edits: bool,
+    #[field_names(
+        desc = "Show the git blame of the cursor line at the end of the line"
+    )]
+    pub show_inline_blame: bool,
+    #[field_names(desc = "Show the git blame of every line in a gutter next to \
+                          the line numbers")]
+    pub show_blame_gutter: bool,
 
     #[field_names(desc = "Whether newlines should be automatically converted to \
                           the current line ending")]
@@ -554,6 +554,10 @@ pub struct CoreConfigSignal {
     pub custom_titlebar: SignalManager<bool>,
     pub file_explorer_double_click: SignalManager<bool>,
     pub auto_reload_plugin: SignalManager<bool>,
+    pub git_commit_hooks: SignalManager<bool>,
+    pub git_remote_url_templates: SignalManager<HashMap<String, String>>,
+    pub lldb_dap_path: SignalManager<String>,
+    pub gdb_path: SignalManager<String>,
 }
 
 impl CoreConfigSignal {
@@ -568,6 +572,13 @@ impl CoreConfigSignal {
                 config.file_explorer_double_click,
             ),
             auto_reload_plugin: SignalManager::new(cx, config.auto_reload_plugin),
+            git_commit_hooks: SignalManager::new(cx, config.git_commit_hooks),
+            git_remote_url_templates: SignalManager::new(
+                cx,
+                config.git_remote_url_templates.clone(),
+            ),
+            lldb_dap_path: SignalManager::new(cx, config.lldb_dap_path.clone()),
+            gdb_path: SignalManager::new(cx, config.gdb_path.clone()),
         }
     }
 
@@ -583,6 +594,16 @@ impl CoreConfigSignal {
             .update_and_trigger_if_not_equal(config.file_explorer_double_click);
         self.auto_reload_plugin
             .update_and_trigger_if_not_equal(config.auto_reload_plugin);
+        self.git_commit_hooks
+            .update_and_trigger_if_not_equal(config.git_commit_hooks);
+        self.git_remote_url_templates
+            .update_and_trigger_if_not_equal(
+                config.git_remote_url_templates.clone(),
+            );
+        self.lldb_dap_path
+            .update_and_trigger_if_not_equal(config.lldb_dap_path.clone());
+        self.gdb_path
+            .update_and_trigger_if_not_equal(config.gdb_path.clone());
     }
 }
 
@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     path::{Path, PathBuf},
     sync::Arc,
 };
@@ -16,6 +17,7 @@ use crate::{
     app::{AppData, AppInfo},
     doc::DocInfo,
     local_task::{LocalNotification, LocalTaskRequester},
+    panel::implementation_view::ReferenceFilter,
     window::{WindowData, WindowInfo},
     window_workspace::WindowWorkspaceData,
 };
@@ -27,6 +29,8 @@ const WORKSPACE_FILES: &str = "workspace_files";
 const PANEL_ORDERS: &str = "panel_orders";
 const DISABLED_VOLTS: &str = "disabled_volts";
 const RECENT_WORKSPACES: &str = "recent_workspaces";
+const COMMIT_MESSAGES: &str = "commit_messages";
+const REFERENCE_FILTERS: &str = "reference_filters";
 
 pub enum SaveEvent {
     App(AppInfo),
@@ -36,6 +40,8 @@ pub enum SaveEvent {
     DisabledVolts(Vec<VoltID>),
     WorkspaceDisabledVolts(Arc<LapceWorkspace>, Vec<VoltID>),
     PanelOrder(PanelOrder),
+    CommitMessages(Arc<LapceWorkspace>, Vec<String>),
+    ReferenceFilter(Arc<LapceWorkspace>, PanelKind, ReferenceFilter),
 }
 
 #[derive(Clone)]
@@ -184,6 +190,85 @@ impl LapceDb {
         Ok(())
     }
 
+    /// The messages of the last commits made in the workspace, most recent
+    /// first.
+    pub fn get_commit_messages(
+        &self,
+        workspace: &LapceWorkspace,
+    ) -> Result<Vec<String>> {
+        let folder = self.workspace_folder.join(workspace_folder_name(workspace));
+        let messages = std::fs::read_to_string(folder.join(COMMIT_MESSAGES))?;
+        let messages: Vec<String> = serde_json::from_str(&messages)?;
+        Ok(messages)
+    }
+
+    pub fn save_commit_messages(
+        &self,
+        workspace: Arc<LapceWorkspace>,
+        messages: Vec<String>,
+        requester: &LocalTaskRequester,
+    ) {
+        requester.notification(LocalNotification::DbSaveEvent(
+            SaveEvent::CommitMessages(workspace, messages),
+        ));
+    }
+
+    pub(crate) fn insert_commit_messages(
+        &self,
+        workspace: &LapceWorkspace,
+        messages: &[String],
+    ) -> Result<()> {
+        let folder = self.workspace_folder.join(workspace_folder_name(workspace));
+        if let Err(err) = std::fs::create_dir_all(&folder) {
+            log::error!("{:?}", err);
+        }
+        let messages = serde_json::to_string_pretty(messages)?;
+        std::fs::write(folder.join(COMMIT_MESSAGES), messages)?;
+        Ok(())
+    }
+
+    /// The grouping and path exclusion of the references and implementations
+    /// panels of the workspace.
+    pub fn get_reference_filters(
+        &self,
+        workspace: &LapceWorkspace,
+    ) -> Result<HashMap<PanelKind, ReferenceFilter>> {
+        let folder = self.workspace_folder.join(workspace_folder_name(workspace));
+        let filters = std::fs::read_to_string(folder.join(REFERENCE_FILTERS))?;
+        let filters: HashMap<PanelKind, ReferenceFilter> =
+            serde_json::from_str(&filters)?;
+        Ok(filters)
+    }
+
+    pub fn save_reference_filter(
+        &self,
+        workspace: Arc<LapceWorkspace>,
+        kind: PanelKind,
+        filter: ReferenceFilter,
+        requester: &LocalTaskRequester,
+    ) {
+        requester.notification(LocalNotification::DbSaveEvent(
+            SaveEvent::ReferenceFilter(workspace, kind, filter),
+        ));
+    }
+
+    pub(crate) fn insert_reference_filter(
+        &self,
+        workspace: &LapceWorkspace,
+        kind: PanelKind,
+        filter: ReferenceFilter,
+    ) -> Result<()> {
+        let mut filters = self.get_reference_filters(workspace).unwrap_or_default();
+        filters.insert(kind, filter);
+        let folder = self.workspace_folder.join(workspace_folder_name(workspace));
+        if let Err(err) = std::fs::create_dir_all(&folder) {
+            log::error!("{:?}", err);
+        }
+        let filters = serde_json::to_string_pretty(&filters)?;
+        std::fs::write(folder.join(REFERENCE_FILTERS), filters)?;
+        Ok(())
+    }
+
     pub fn save_window_tab(
         &self,
         data: WindowWorkspaceData,
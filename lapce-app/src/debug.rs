@@ -11,17 +11,22 @@ use floem::{
     views::VirtualVector,
 };
 use lapce_core::debug::{
-    BreakpointAction, DapVariableViewdata, LapceBreakpoint, ScopeOrVar,
+    BreakpointAction, BreakpointField, DapVariableViewdata, LapceBreakpoint,
+    ScopeOrVar,
 };
 use lapce_rpc::{
+    RpcError,
     dap_types::{
-        self, DapId, SourceBreakpoint, StackFrame, Stopped, ThreadId, Variable,
+        self, DapId, DebuggerCapabilities, ExceptionBreakpointsFilter,
+        GlobalBreakpoints, ProcessInfo, SourceBreakpoint, StackFrame, Stopped,
+        ThreadId, Variable,
     },
     proxy::{ProxyResponse, ProxyRpcHandler},
     terminal::TermId,
 };
 use lapce_xi_rope::{Rope, RopeDelta, Transformer};
 use log::error;
+use lsp_types::MessageType;
 
 use crate::{
     command::InternalCommand,
@@ -29,6 +34,14 @@ use crate::{
     window_workspace::CommonData,
 };
 
+/// Where a breakpoint being edited is, or is to be added
+#[derive(Clone, Debug, PartialEq)]
+pub struct BreakpointLocation {
+    pub path:   PathBuf,
+    pub line:   usize,
+    pub offset: usize,
+}
+
 #[derive(Clone, Copy)]
 pub struct BreakPoints {
     pub breakpoints: RwSignal<BTreeMap<PathBuf, BTreeMap<usize, LapceBreakpoint>>>,
@@ -64,15 +77,7 @@ impl BreakPoints {
                 if let std::collections::btree_map::Entry::Vacant(e) =
                     breakpoints.entry(line)
                 {
-                    e.insert(LapceBreakpoint {
-                        id: None,
-                        verified: false,
-                        message: None,
-                        line,
-                        offset,
-                        dap_line: None,
-                        active: true,
-                    });
+                    e.insert(LapceBreakpoint::new(line, offset));
                 } else {
                     breakpoints.remove(&line);
                 }
@@ -93,15 +98,7 @@ impl BreakPoints {
                 if let std::collections::btree_map::Entry::Vacant(e) =
                     breakpoints.entry(line)
                 {
-                    e.insert(LapceBreakpoint {
-                        id: None,
-                        verified: false,
-                        message: None,
-                        line,
-                        offset,
-                        dap_line: None,
-                        active: true,
-                    });
+                    e.insert(LapceBreakpoint::new(line, offset));
                 } else {
                     let mut toggle_active = false;
                     if let Some(breakpint) = breakpoints.get_mut(&line)
@@ -136,6 +133,36 @@ impl BreakPoints {
             .unwrap()
     }
 
+    pub fn edit_by_path_line_offset(
+        &self,
+        path: &Path,
+        line: usize,
+        offset: usize,
+        field: BreakpointField,
+        value: &str,
+    ) -> BTreeMap<usize, LapceBreakpoint> {
+        self.breakpoints
+            .try_update(|breakpoints| {
+                let breakpoints = breakpoints.entry(path.to_path_buf()).or_default();
+                let breakpoint = breakpoints
+                    .entry(line)
+                    .or_insert_with(|| LapceBreakpoint::new(line, offset));
+                breakpoint.set_field(field, value);
+                breakpoint.verified = false;
+                breakpoints.clone()
+            })
+            .unwrap()
+    }
+
+    pub fn get_by_path_line_untracked(
+        &self,
+        path: &Path,
+        line: usize,
+    ) -> Option<LapceBreakpoint> {
+        self.breakpoints
+            .with_untracked(|x| x.get(path).and_then(|x| x.get(&line)).cloned())
+    }
+
     pub fn remove_by_path_line(
         &self,
         path: &Path,
@@ -256,19 +283,8 @@ impl BreakPoints {
                     path.to_path_buf(),
                     breakpoints
                         .iter()
-                        .filter_map(|(_, b)| {
-                            if b.active {
-                                Some(SourceBreakpoint {
-                                    line:          b.line + 1,
-                                    column:        None,
-                                    condition:     None,
-                                    hit_condition: None,
-                                    log_message:   None,
-                                })
-                            } else {
-                                None
-                            }
-                        })
+                        .filter(|(_, b)| b.active)
+                        .map(|(_, b)| b.source_breakpoint())
                         .collect(),
                 )
             })
@@ -293,9 +309,16 @@ impl BreakPoints {
 
 #[derive(Clone)]
 pub struct RunDebugData {
-    pub active_term: RwSignal<Option<TermId>>,
-    pub daps:        RwSignal<im::HashMap<DapId, DapData>>,
-    pub breakpoints: BreakPoints,
+    pub active_term:        RwSignal<Option<TermId>>,
+    pub daps:               RwSignal<im::HashMap<DapId, DapData>>,
+    pub breakpoints:        BreakPoints,
+    /// The watch expressions, evaluated whenever a session stops
+    pub watches:            RwSignal<Vec<String>>,
+    pub global_breakpoints: RwSignal<GlobalBreakpoints>,
+    /// The exception filters of the debuggers started, by debugger type,
+    /// kept with the workspace for them to be set before a launch
+    pub exception_filters:
+        RwSignal<BTreeMap<String, Vec<ExceptionBreakpointsFilter>>>,
 }
 
 impl RunDebugData {
@@ -308,8 +331,30 @@ impl RunDebugData {
             active_term,
             daps,
             breakpoints,
+            watches: cx.create_rw_signal(Vec::new()),
+            global_breakpoints: cx.create_rw_signal(GlobalBreakpoints::default()),
+            exception_filters: cx.create_rw_signal(BTreeMap::new()),
         }
     }
+
+    /// The exception filters of every debugger started, once each.
+    pub fn known_exception_filters(&self) -> Vec<ExceptionBreakpointsFilter> {
+        self.exception_filters.with(known_exception_filters)
+    }
+}
+
+/// The filters of `by_debugger`, those of the same id listed once as their
+/// settings are shared.
+fn known_exception_filters(
+    by_debugger: &BTreeMap<String, Vec<ExceptionBreakpointsFilter>>,
+) -> Vec<ExceptionBreakpointsFilter> {
+    let mut ids = HashSet::new();
+    by_debugger
+        .values()
+        .flatten()
+        .filter(|filter| ids.insert(filter.filter.clone()))
+        .cloned()
+        .collect()
 }
 
 #[derive(Clone, PartialEq)]
@@ -319,6 +364,24 @@ pub struct StackTraceData {
     pub frames_shown: usize,
 }
 
+/// The trees of variables a session keeps in the debug panel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DapVariableTree {
+    Variables,
+    Watch,
+    Console,
+}
+
+/// A variable whose value is being edited
+#[derive(Clone, Debug)]
+pub struct DapVariableEdit {
+    pub dap_id:   DapId,
+    pub tree:     DapVariableTree,
+    /// The references of the variables it is in, the last being its parent
+    pub parent:   Vec<usize>,
+    pub variable: Variable,
+}
+
 #[derive(Clone, Default)]
 pub struct DapVariable {
     pub item:                    ScopeOrVar,
@@ -338,6 +401,12 @@ pub struct DapData {
     pub stack_traces: RwSignal<BTreeMap<ThreadId, StackTraceData>>,
     pub variables_id: RwSignal<usize>,
     pub variables:    RwSignal<DapVariable>,
+    /// The frame expressions are evaluated in, the top frame of the stopped
+    /// thread unless another one is picked
+    pub frame_id:     RwSignal<Option<usize>>,
+    pub watches:      RwSignal<DapVariable>,
+    pub console:      RwSignal<DapVariable>,
+    pub capabilities: RwSignal<Option<DebuggerCapabilities>>,
     pub breakline:    Memo<Option<(usize, PathBuf)>>,
     pub common:       Rc<CommonData>,
 }
@@ -383,16 +452,11 @@ impl DapData {
             thread_id,
             stack_traces,
             variables_id: cx.create_rw_signal(0),
-            variables: cx.create_rw_signal(DapVariable {
-                item:                    ScopeOrVar::Scope(
-                    dap_types::Scope::default(),
-                ),
-                parent:                  Vec::new(),
-                expanded:                true,
-                read:                    true,
-                children:                Vec::new(),
-                children_expanded_count: 0,
-            }),
+            variables: cx.create_rw_signal(DapVariable::root(Vec::new())),
+            frame_id: cx.create_rw_signal(None),
+            watches: cx.create_rw_signal(DapVariable::root(Vec::new())),
+            console: cx.create_rw_signal(DapVariable::root(Vec::new())),
+            capabilities: cx.create_rw_signal(None),
             breakline,
             common,
         }
@@ -411,6 +475,12 @@ impl DapData {
         });
 
         let main_thread_id = self.thread_id.get_untracked();
+        self.frame_id.set(
+            main_thread_id
+                .and_then(|thread_id| stack_traces.get(&thread_id))
+                .and_then(|frames| frames.first())
+                .map(|frame| frame.id),
+        );
         let mut current_stack_traces = self.stack_traces.get_untracked();
         current_stack_traces.retain(|t, _| stack_traces.contains_key(t));
         for (thread_id, frames) in stack_traces {
@@ -482,11 +552,26 @@ impl DapData {
         });
     }
 
-    pub fn toggle_expand(&self, parent: Vec<usize>, reference: usize) {
+    pub fn tree(&self, tree: DapVariableTree) -> RwSignal<DapVariable> {
+        match tree {
+            DapVariableTree::Variables => self.variables,
+            DapVariableTree::Watch => self.watches,
+            DapVariableTree::Console => self.console,
+        }
+    }
+
+    /// Expand or collapse a variable of the tree in `root`, which is the
+    /// scopes, the watches or the console.
+    pub fn toggle_expand(
+        &self,
+        root: RwSignal<DapVariable>,
+        parent: Vec<usize>,
+        reference: usize,
+    ) {
         self.variables_id.update(|id| {
             *id += 1;
         });
-        self.variables.update(|variables| {
+        root.update(|variables| {
             if let Some(var) = variables.get_var_mut(&parent, reference) {
                 if var.expanded {
                     var.expanded = false;
@@ -495,7 +580,7 @@ impl DapData {
                     var.expanded = true;
                     if !var.read {
                         var.read = true;
-                        self.read_var_children(&parent, reference);
+                        self.read_var_children(root, &parent, reference);
                     } else {
                         variables.update_count_recursive(&parent, reference);
                     }
@@ -504,8 +589,175 @@ impl DapData {
         });
     }
 
-    fn read_var_children(&self, parent: &[usize], reference: usize) {
-        let root = self.variables;
+    /// Evaluate `expression` in the selected frame. A failure is given as
+    /// the value, as a debug console shows it.
+    fn evaluate(
+        &self,
+        expression: String,
+        context: &str,
+        f: impl FnOnce(DapVariable) + 'static,
+    ) {
+        let name = expression.clone();
+        let send = create_ext_action(
+            self.common.scope,
+            move |result: Result<ProxyResponse, RpcError>| {
+                let var = match result {
+                    Ok(ProxyResponse::DapEvaluateResponse { result }) => {
+                        DapVariable::evaluated(
+                            name,
+                            result.result,
+                            result.ty,
+                            result.variables_reference,
+                        )
+                    },
+                    Ok(_) => return,
+                    Err(err) => DapVariable::evaluated(name, err.message, None, 0),
+                };
+                f(var);
+            },
+        );
+        self.common.proxy.proxy_rpc.dap_evaluate(
+            self.dap_id,
+            expression,
+            self.frame_id.get_untracked(),
+            context,
+            move |(_, result)| {
+                send(result);
+            },
+        );
+    }
+
+    /// Evaluate the watch expressions again, e.g. after stopping or picking
+    /// another frame.
+    pub fn evaluate_watches(&self, expressions: Vec<String>) {
+        let watches = self.watches;
+        watches.set(DapVariable::watch_placeholders(&expressions));
+        for (i, expression) in expressions.into_iter().enumerate() {
+            self.evaluate(expression, "watch", move |var| {
+                watches.update(|root| {
+                    if let Some(watch) = root.children.get_mut(i)
+                        && watch.item.name() == var.item.name()
+                    {
+                        *watch = var;
+                    }
+                    root.update_children_count();
+                });
+            });
+        }
+    }
+
+    /// Evaluate an expression typed in the debug console, adding the result
+    /// to it.
+    pub fn evaluate_in_console(&self, expression: String) {
+        let console = self.console;
+        self.evaluate(expression, "repl", move |var| {
+            console.update(|root| {
+                root.children.push(var);
+                root.update_children_count();
+            });
+        });
+    }
+
+    /// Change the value of a variable with `setExpression` when the debugger
+    /// supports it, else with `setVariable`. `f` is run once it is changed.
+    pub fn set_value(
+        &self,
+        edit: DapVariableEdit,
+        value: String,
+        f: impl FnOnce() + 'static,
+    ) {
+        let (set_expression, set_variable) = self.capabilities.with_untracked(|c| {
+            c.as_ref()
+                .map(|c| {
+                    (
+                        c.supports_set_expression.unwrap_or(false),
+                        c.supports_set_variable.unwrap_or(false),
+                    )
+                })
+                .unwrap_or_default()
+        });
+        let DapVariableEdit {
+            tree,
+            parent,
+            variable,
+            ..
+        } = edit;
+        let name = variable.name.clone();
+        let expression = variable.evaluate_name.filter(|_| set_expression);
+        let parent_reference = parent.last().copied().filter(|_| set_variable);
+        if expression.is_none() && parent_reference.is_none() {
+            self.common.show_popup_message(
+                format!("Can't set {name}"),
+                MessageType::ERROR,
+                "The debugger doesn't support changing this value".to_string(),
+            );
+            return;
+        }
+
+        let dap = self.clone();
+        let send = create_ext_action(
+            self.common.scope,
+            move |result: Result<ProxyResponse, RpcError>| {
+                let (value, ty, reference) = match result {
+                    Ok(ProxyResponse::DapSetVariableResponse { result }) => {
+                        (result.value, result.ty, result.variables_reference)
+                    },
+                    Ok(ProxyResponse::DapSetExpressionResponse { result }) => {
+                        (result.value, result.ty, result.variables_reference)
+                    },
+                    Ok(_) => return,
+                    Err(err) => {
+                        dap.common.show_popup_message(
+                            format!("Can't set {name}"),
+                            MessageType::ERROR,
+                            err.message,
+                        );
+                        return;
+                    },
+                };
+                let root = dap.tree(tree);
+                dap.variables_id.update(|id| {
+                    *id += 1;
+                });
+                let read = root.try_update(|root| {
+                    root.set_value(&parent, &name, value, ty, reference)
+                });
+                if read.flatten().unwrap_or(false) {
+                    dap.read_var_children(root, &parent, reference);
+                }
+                f();
+            },
+        );
+        let proxy_rpc = &self.common.proxy.proxy_rpc;
+        if let Some(expression) = expression {
+            proxy_rpc.dap_set_expression(
+                self.dap_id,
+                expression,
+                value,
+                self.frame_id.get_untracked(),
+                move |(_, result)| {
+                    send(result);
+                },
+            );
+        } else if let Some(reference) = parent_reference {
+            proxy_rpc.dap_set_variable(
+                self.dap_id,
+                reference,
+                variable.name,
+                value,
+                move |(_, result)| {
+                    send(result);
+                },
+            );
+        }
+    }
+
+    fn read_var_children(
+        &self,
+        root: RwSignal<DapVariable>,
+        parent: &[usize],
+        reference: usize,
+    ) {
         let parent = parent.to_vec();
         let variables_id = self.variables_id;
 
@@ -570,6 +822,70 @@ impl VirtualVector<DapVariableViewdata> for DapVariable {
 }
 
 impl DapVariable {
+    /// The invisible root of a tree, always expanded
+    pub fn root(children: Vec<DapVariable>) -> Self {
+        let mut root = DapVariable {
+            item: ScopeOrVar::Scope(dap_types::Scope::default()),
+            parent: Vec::new(),
+            expanded: true,
+            read: true,
+            children,
+            children_expanded_count: 0,
+        };
+        root.update_children_count();
+        root
+    }
+
+    /// The result of evaluating `expression`, its children read when it is
+    /// expanded.
+    pub fn evaluated(
+        expression: String,
+        value: String,
+        ty: Option<String>,
+        reference: usize,
+    ) -> Self {
+        DapVariable {
+            item:                    ScopeOrVar::Var(Variable {
+                name: expression.clone(),
+                value,
+                ty,
+                evaluate_name: Some(expression),
+                variables_reference: reference,
+                ..Default::default()
+            }),
+            parent:                  Vec::new(),
+            expanded:                false,
+            read:                    false,
+            children:                Vec::new(),
+            children_expanded_count: 0,
+        }
+    }
+
+    /// The watch expressions without values, for when nothing is stopped.
+    pub fn watch_placeholders(expressions: &[String]) -> Self {
+        DapVariable::root(
+            expressions
+                .iter()
+                .map(|expression| {
+                    DapVariable::evaluated(
+                        expression.clone(),
+                        String::new(),
+                        None,
+                        0,
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    pub fn update_children_count(&mut self) {
+        self.children_expanded_count = self
+            .children
+            .iter()
+            .map(|item| item.children_expanded_count + 1)
+            .sum::<usize>();
+    }
+
     pub fn append_view_slice(
         &self,
         view_items: &mut Vec<DapVariableViewdata>,
@@ -592,6 +908,7 @@ impl DapVariable {
                 parent: self.parent.clone(),
                 expanded: self.expanded,
                 level,
+                index: current,
             });
         }
 
@@ -611,19 +928,55 @@ impl DapVariable {
         parent: &[usize],
         reference: usize,
     ) -> Option<&mut DapVariable> {
-        let parent = if parent.is_empty() {
-            self
+        self.get_parent_mut(parent)?
+            .children
+            .iter_mut()
+            .find(|c| c.item.reference() == reference)
+    }
+
+    fn get_parent_mut(&mut self, parent: &[usize]) -> Option<&mut DapVariable> {
+        if parent.is_empty() {
+            Some(self)
         } else {
             parent.iter().try_fold(self, |item, parent| {
                 item.children
                     .iter_mut()
                     .find(|c| c.item.reference() == *parent)
-            })?
-        };
-        parent
+            })
+        }
+    }
+
+    /// Set the value of the variable `name` in `parent`, dropping its
+    /// children. Returns whether they should be read again, which is when it
+    /// is still expanded.
+    pub fn set_value(
+        &mut self,
+        parent: &[usize],
+        name: &str,
+        value: String,
+        ty: Option<String>,
+        reference: usize,
+    ) -> Option<bool> {
+        let var = self
+            .get_parent_mut(parent)?
             .children
             .iter_mut()
-            .find(|c| c.item.reference() == reference)
+            .find(|c| c.item.name() == name)?;
+        let ScopeOrVar::Var(item) = &mut var.item else {
+            return None;
+        };
+        item.value = value;
+        if ty.is_some() {
+            item.ty = ty;
+        }
+        item.variables_reference = reference;
+        var.children.clear();
+        var.children_expanded_count = 0;
+        var.expanded = var.expanded && reference > 0;
+        var.read = false;
+        let read = var.expanded;
+        self.update_count_recursive(parent, reference);
+        Some(read)
     }
 
     pub fn update_count_recursive(&mut self, parent: &[usize], reference: usize) {
@@ -678,23 +1031,22 @@ pub fn update_breakpoints(
             breakpoints.add_or_remove_by_path_line_offset(path, line, offset),
             path,
         ),
+        BreakpointAction::Edit {
+            path,
+            line,
+            offset,
+            field,
+            value,
+        } => (
+            breakpoints.edit_by_path_line_offset(path, line, offset, field, value),
+            path,
+        ),
     };
 
     let source_breakpoints: Vec<SourceBreakpoint> = path_breakpoints
         .iter()
-        .filter_map(|(_, b)| {
-            if b.active {
-                Some(SourceBreakpoint {
-                    line:          b.line + 1,
-                    column:        None,
-                    condition:     None,
-                    hit_condition: None,
-                    log_message:   None,
-                })
-            } else {
-                None
-            }
-        })
+        .filter(|(_, b)| b.active)
+        .map(|(_, b)| b.source_breakpoint())
         .collect();
     let daps: Vec<DapId> =
         daps.with_untracked(|daps| daps.keys().cloned().collect());
@@ -707,11 +1059,101 @@ pub fn update_breakpoints(
     }
 }
 
+/// Find the most recently started process with the given name, matching
+/// either the process name or the file name of its executable.
+pub fn find_process<'a>(
+    processes: &'a [ProcessInfo],
+    name: &str,
+) -> Option<&'a ProcessInfo> {
+    processes
+        .iter()
+        .filter(|process| {
+            process.name == name
+                || process
+                    .command_line
+                    .split(' ')
+                    .next()
+                    .and_then(|program| Path::new(program).file_name())
+                    .is_some_and(|program| program == name)
+        })
+        .max_by_key(|process| (process.start_time, process.pid))
+}
+
+/// The command run in the terminal of an attached session. It only waits for
+/// the process to exit, so that the session shows up like a launched one.
+pub fn attach_wait_command(pid: u32) -> Vec<String> {
+    if cfg!(windows) {
+        vec![
+            "powershell".to_string(),
+            "-Command".to_string(),
+            format!("Wait-Process -Id {pid}"),
+        ]
+    } else {
+        vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            format!(
+                "echo Attached to process {pid}; while [ -e /proc/{pid} ] || kill \
+                 -0 {pid} 2>/dev/null; do sleep 1; done"
+            ),
+        ]
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use lapce_rpc::dap_types::{Scope, Variable};
+    use std::collections::BTreeMap;
+
+    use lapce_rpc::dap_types::{
+        ExceptionBreakpointsFilter, ProcessInfo, Scope, Variable,
+    };
 
-    use super::{DapVariable, ScopeOrVar};
+    use super::{DapVariable, ScopeOrVar, find_process, known_exception_filters};
+
+    #[test]
+    fn test_known_exception_filters() {
+        let filter = |id: &str| ExceptionBreakpointsFilter {
+            filter:                id.to_string(),
+            label:                 id.to_string(),
+            description:           None,
+            default:               None,
+            supports_condition:    None,
+            condition_description: None,
+        };
+        let by_debugger = BTreeMap::from([
+            (
+                "lldb".to_string(),
+                vec![filter("cpp_throw"), filter("rust_panic")],
+            ),
+            ("gdb".to_string(), vec![filter("rust_panic")]),
+        ]);
+        let ids = known_exception_filters(&by_debugger)
+            .into_iter()
+            .map(|filter| filter.filter)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["rust_panic", "cpp_throw"]);
+    }
+
+    #[test]
+    fn test_find_process() {
+        let process =
+            |pid: u32, name: &str, command_line: &str, start_time| ProcessInfo {
+                pid,
+                name: name.to_string(),
+                command_line: command_line.to_string(),
+                start_time,
+            };
+        // pids wrap around, the newest process can have the lowest one
+        let processes = vec![
+            process(10, "server", "/usr/bin/server --port 80", 500),
+            process(20, "tokio-runtime-w", "/home/dev/target/debug/server", 900),
+            process(30, "server", "/usr/bin/server --port 81", 100),
+            process(40, "bash", "bash", 50),
+        ];
+        assert_eq!(find_process(&processes, "server").map(|p| p.pid), Some(20));
+        assert_eq!(find_process(&processes, "bash").map(|p| p.pid), Some(40));
+        assert_eq!(find_process(&processes, "target"), None);
+    }
 
     #[test]
     fn test_update_count() {
@@ -830,5 +1272,12 @@ mod tests {
         let var = root.get_var_mut(&[], 0).unwrap();
         assert_eq!(var.children_expanded_count, 4);
         assert_eq!(root.children_expanded_count, 11);
+
+        let read = root.set_value(&[0], "", "[]".to_string(), None, 0);
+        assert_eq!(read, Some(false));
+        let var = root.get_var_mut(&[], 0).unwrap();
+        assert_eq!(var.children_expanded_count, 2);
+        assert_eq!(var.children[0].item.value(), Some("[]"));
+        assert_eq!(root.children_expanded_count, 9);
     }
 }
@@ -43,7 +43,10 @@ use itertools::Itertools;
 use lapce_core::{
     debug::RunDebugConfigs, doc::DocContent, id::Id, workspace::LapceWorkspace,
 };
-use lapce_rpc::{buffer::BufferId, plugin::PluginId, proxy::ProxyResponse};
+use lapce_rpc::{
+    buffer::BufferId, plugin::PluginId, proxy::ProxyResponse,
+    source_control::FILE_NOT_IN_REVISION,
+};
 use lapce_xi_rope::{Interval, Rope, RopeDelta, spans::SpansBuilder};
 use log::{debug, error};
 use lsp_types::{CodeLens, Diagnostic, DocumentSymbolResponse};
@@ -51,6 +54,7 @@ use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 
 use crate::{
+    blame::{LineBlame, line_blame, update_blame_by_rope_delta},
     command::InternalCommand,
     editor::{
         floem_editor::CommonAction,
@@ -123,6 +127,12 @@ pub struct Doc {
     /// control.
     histories:        RwSignal<im::HashMap<String, DocumentHistory>>,
     pub head_changes: RwSignal<im::Vector<DiffLines>>,
+    /// The commit that last changed each line, kept in sync with unsaved
+    /// edits.
+    pub blame:        RwSignal<LineBlame>,
+    /// Set when the content or `HEAD` changed since the blame was retrieved,
+    /// it is retrieved again once the document is shown
+    pub blame_stale:  RwSignal<bool>,
 
     /// A cache for the sticky headers which maps a line to the lines it should
     /// show in the header.
@@ -199,6 +209,8 @@ impl Doc {
             loaded: cx.create_rw_signal(DocStatus::default()),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
+            blame: cx.create_rw_signal(Vec::new()),
+            blame_stale: cx.create_rw_signal(true),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
             find_result: FindResult::new(cx),
             // preedit: PreeditData::new(cx),
@@ -276,6 +288,8 @@ impl Doc {
             content: cx.create_rw_signal(content),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
+            blame: cx.create_rw_signal(Vec::new()),
+            blame_stale: cx.create_rw_signal(true),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
             loaded: cx.create_rw_signal(DocStatus::Ok { loaded: true }),
             find_result: FindResult::new(cx),
@@ -364,6 +378,8 @@ impl Doc {
             loaded: cx.create_rw_signal(DocStatus::default()),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
+            blame: cx.create_rw_signal(Vec::new()),
+            blame_stale: cx.create_rw_signal(true),
             find_result: FindResult::new(cx),
             // preedit: PreeditData::new(cx),
             common,
@@ -477,6 +493,7 @@ impl Doc {
             self.loaded.set(DocStatus::Ok { loaded: true });
             self.on_update(None);
             self.retrieve_head();
+            self.blame_stale.set(true);
         });
     }
 
@@ -492,6 +509,7 @@ impl Doc {
             })
             .remove(0);
         self.apply_deltas(&[delta]);
+        self.blame_stale.set(true);
     }
 
     pub fn handle_file_changed(&self, content: Rope) {
@@ -579,6 +597,7 @@ impl Doc {
                     // self.apply_deltas_for_lines(delta);
                     self.update_find_result(delta);
                     self.update_breakpoints(delta, &path, &inval.old_text);
+                    self.update_blame(delta, &inval.old_text);
                 }
             });
         }
@@ -1092,6 +1111,24 @@ impl Doc {
         }
     }
 
+    fn update_blame(&self, delta: &RopeDelta, old_text: &Rope) {
+        if self.blame.with_untracked(|blame| blame.is_empty()) {
+            return;
+        }
+        self.lines.with_untracked(|lines| {
+            self.blame.update(|blame| {
+                if let Err(err) = update_blame_by_rope_delta(
+                    blame,
+                    delta,
+                    old_text,
+                    lines.buffer(),
+                ) {
+                    error!("{err:?}");
+                }
+            });
+        });
+    }
+
     // /// Update the completion lens position after an edit so that it appears in
     // the correct place. pub fn update_completion_lens(&self, delta:
     // &RopeDelta) {     self.lines.update(|x| x.update_completion_lens(delta));
@@ -1245,39 +1282,151 @@ impl Doc {
         self.head_changes
     }
 
-    /// Retrieve the `head` version of the buffer
+    /// The text the gutter changes are against, once retrieved
+    pub fn head_text(&self) -> Option<Rope> {
+        self.histories.with_untracked(|histories| {
+            histories
+                .get("head")
+                .map(|history| history.buffer.text().clone())
+        })
+    }
+
+    /// Retrieve the `head` version of the buffer, or its version at the ref
+    /// the gutter changes are shown against
     pub fn retrieve_head(&self) {
         if let DocContent::File { path, .. } = self.content.get_untracked() {
             let histories = self.histories;
+            let diff_base = self.common.diff_base.get_untracked();
 
             let send = {
                 let path = path.clone();
                 let doc = self.clone();
+                let has_base = diff_base.is_some();
                 create_ext_action(self.scope, move |result| {
-                    if let Ok(ProxyResponse::BufferHeadResponse {
-                        content, ..
-                    }) = result
-                    {
-                        let hisotry = DocumentHistory::new(
-                            path.clone(),
-                            "head".to_string(),
-                            &content,
-                        );
-                        histories.update(|histories| {
-                            histories.insert("head".to_string(), hisotry);
-                        });
+                    let content = match result {
+                        Ok(ProxyResponse::BufferHeadResponse {
+                            content, ..
+                        })
+                        | Ok(ProxyResponse::GitGetFileAtRevisionResponse {
+                            content,
+                        }) => content,
+                        // the file didn't exist at the base, so all of it
+                        // is added
+                        Err(err) if has_base && err.code == FILE_NOT_IN_REVISION => {
+                            String::new()
+                        },
+                        // the previous base is kept
+                        Err(err) if has_base => {
+                            error!("{err}");
+                            return;
+                        },
+                        _ => return,
+                    };
+                    let hisotry = DocumentHistory::new(
+                        path.clone(),
+                        "head".to_string(),
+                        &content,
+                    );
+                    histories.update(|histories| {
+                        histories.insert("head".to_string(), hisotry);
+                    });
 
-                        doc.trigger_head_change();
-                    }
+                    doc.trigger_head_change();
                 })
             };
 
-            let path = path.clone();
-            let proxy = self.common.proxy.clone();
-            proxy.proxy_rpc.get_buffer_head(path, move |(_, result)| {
+            let proxy_rpc = &self.common.proxy.proxy_rpc;
+            match diff_base {
+                Some(base) => {
+                    proxy_rpc.git_get_file_at_revision(
+                        path,
+                        base,
+                        move |(_, result)| {
+                            send(result);
+                        },
+                    );
+                },
+                None => {
+                    proxy_rpc.get_buffer_head(path, move |(_, result)| {
+                        send(result);
+                    });
+                },
+            }
+        }
+    }
+
+    /// Load the content of a history document from source control.
+    pub fn retrieve_history(&self) {
+        let DocContent::History(history) = self.content.get_untracked() else {
+            return;
+        };
+        let send = {
+            let doc = self.clone();
+            create_ext_action(self.scope, move |result| match result {
+                Ok(ProxyResponse::BufferHeadResponse { content, .. })
+                | Ok(ProxyResponse::GitGetFileAtRevisionResponse { content }) => {
+                    doc.init_content(Rope::from(content));
+                },
+                Ok(_) => {},
+                Err(err) => {
+                    // e.g. the file didn't exist at that revision
+                    debug!("{err:?}");
+                    doc.init_content(Rope::from(""));
+                },
+            })
+        };
+        let proxy_rpc = &self.common.proxy.proxy_rpc;
+        if history.version == "head" {
+            proxy_rpc.get_buffer_head(history.path, move |(_, result)| {
                 send(result);
             });
+        } else {
+            proxy_rpc.git_get_file_at_revision(
+                history.path,
+                history.version,
+                move |(_, result)| {
+                    send(result);
+                },
+            );
+        }
+    }
+
+    /// Retrieve the git blame of the buffer, if it is shown anywhere
+    pub fn retrieve_blame(&self) {
+        let DocContent::File { path, .. } = self.content.get_untracked() else {
+            return;
+        };
+        if !self.common.config.with_untracked(|config| {
+            config.editor.show_inline_blame || config.editor.show_blame_gutter
+        }) {
+            return;
         }
+        self.blame_stale.set(false);
+        let rev = self.rev();
+        let send = {
+            let doc = self.clone();
+            create_ext_action(self.scope, move |result| match result {
+                // when edited since, the lines moved; the previous blame
+                // follows the edits until the next save blames it again
+                Ok(ProxyResponse::GitBlameResponse { blame }) => {
+                    if doc.rev() == rev {
+                        doc.blame.set(line_blame(blame));
+                    }
+                },
+                Ok(_) => {},
+                Err(err) => {
+                    // not in a repository, or not tracked yet
+                    debug!("{err:?}");
+                    doc.blame.set(Vec::new());
+                },
+            })
+        };
+        self.common
+            .proxy
+            .proxy_rpc
+            .git_blame(path, move |(_, result)| {
+                send(result);
+            });
     }
 
     pub fn trigger_head_change(&self) {
@@ -1354,6 +1503,7 @@ impl Doc {
                 Ok(_) => {
                     // lines.try_update(|x| x.set_pristine(rev));
                     doc.buffer_edit(EditBuffer::SetPristine(rev));
+                    doc.blame_stale.set(true);
                     after_action();
                     doc.update_run_debug_config_after_save();
                 },
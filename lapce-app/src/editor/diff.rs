@@ -1,4 +1,4 @@
-use std::{rc::Rc, sync::atomic};
+use std::{path::PathBuf, rc::Rc, sync::atomic};
 
 use doc::{
     EditorViewKind,
@@ -17,7 +17,7 @@ use lapce_core::{
 };
 use log::error;
 
-use super::{EditorData, view::count_rect};
+use super::{EditorData, merge::MergeEditorData, view::count_rect};
 use crate::{
     doc::{Doc, DocStatus},
     local_task::{LocalRequest, LocalResponse},
@@ -42,6 +42,8 @@ pub struct DiffEditorData {
     pub focus_right:           RwSignal<bool>,
     /// used when open diff file
     pub jump_by_changes_index: RwSignal<Option<usize>>,
+    /// Set when the diff editor is the merge editor of a file with conflicts
+    pub merge:                 RwSignal<Option<MergeEditorData>>,
     common:                    Rc<CommonData>,
 }
 
@@ -92,6 +94,7 @@ impl DiffEditorData {
             focus_right: cx.create_rw_signal(true),
             common,
             jump_by_changes_index: cx.create_rw_signal(Some(0)),
+            merge: cx.create_rw_signal(None),
         };
 
         data.listen_diff_changes();
@@ -126,6 +129,19 @@ impl DiffEditorData {
                 .unwrap()
         });
 
+        let merge = self.merge.get_untracked().and_then(|merge| {
+            let [base, result_editor] =
+                [&merge.base, &merge.result_editor].map(|editor_data| {
+                    editors.make_copy(
+                        editor_data.id(),
+                        cx,
+                        None,
+                        Some((editor_tab_id, diff_editor_id)),
+                    )
+                });
+            Some(merge.copy(cx, base?, result_editor?))
+        });
+
         let diff_editor = DiffEditorData {
             scope: cx,
             id: diff_editor_id,
@@ -135,12 +151,65 @@ impl DiffEditorData {
             right,
             common: self.common.clone(),
             jump_by_changes_index: cx.create_rw_signal(Some(0)),
+            merge: cx.create_rw_signal(merge),
         };
 
         diff_editor.listen_diff_changes();
         diff_editor
     }
 
+    /// Turn this into the merge editor of `path`, showing `base` between our
+    /// and their versions and resolving the conflicts in `result`.
+    pub fn set_merge(
+        &self,
+        path: PathBuf,
+        base: Rc<Doc>,
+        result: Rc<Doc>,
+        editors: Editors,
+    ) {
+        let [base, result] = [base, result].map(|doc| {
+            editors.make_from_doc(
+                self.scope,
+                doc,
+                None,
+                Some((self.editor_tab_id.get_untracked(), self.id)),
+                self.common.clone(),
+                EditorViewKind::Normal,
+            )
+        });
+        self.merge
+            .set(Some(MergeEditorData::new(self.scope, path, base, result)));
+    }
+
+    /// The editor which has the focus in this diff editor.
+    pub fn focused_editor(&self, tracked: bool) -> EditorData {
+        let merge = if tracked {
+            self.merge.get()
+        } else {
+            self.merge.get_untracked()
+        };
+        if let Some(merge) = merge {
+            let focus_result = if tracked {
+                merge.focus_result.get()
+            } else {
+                merge.focus_result.get_untracked()
+            };
+            if focus_result {
+                return merge.result_editor;
+            }
+        }
+        let focus_right = if tracked {
+            self.focus_right.get()
+        } else {
+            self.focus_right.get_untracked()
+        };
+        if focus_right {
+            self.right.clone()
+        } else {
+            self.left.clone()
+        }
+    }
+
     fn listen_diff_changes(&self) {
         let cx = self.scope;
 
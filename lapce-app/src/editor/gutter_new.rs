@@ -1,15 +1,24 @@
 pub mod view;
 
-use std::hash::{Hash, Hasher};
+use std::{
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
 
 use doc::lines::{buffer::rope_text::RopeText, screen_lines::VisualLineInfo};
 use floem::{
     peniko::Color,
     prelude::{SignalGet, SignalWith},
 };
+use lapce_rpc::source_control::CommitInfo;
 
 use crate::{
-    config::color::LapceColor, editor::EditorData,
+    blame::blame_gutter_text,
+    config::color::LapceColor,
+    editor::{
+        EditorData,
+        hunk::{ChangeKind, change_hunks},
+    },
     window_workspace::WindowWorkspaceData,
 };
 
@@ -95,45 +104,26 @@ pub fn gutter_data(
                         } else if let Some(breakpoint) =
                             breakpoints.get(&text.folded_line.origin_line_start)
                         {
-                            if breakpoint.verified {
-                                GutterData {
-                                    origin_line_start: Some(
-                                        text.folded_line.origin_line_start,
-                                    ),
-                                    paint_point_y: text.folded_line_y,
-                                    marker: GutterMarker::BreakpointVerified,
-                                    style_color,
-                                    style_width: width,
-                                    style_font_size,
-                                    style_font_family: font_family.1.clone(),
-                                    is_current_line,
-                                }
-                            } else if breakpoint.active {
-                                GutterData {
-                                    origin_line_start: Some(
-                                        text.folded_line.origin_line_start,
-                                    ),
-                                    paint_point_y: text.folded_line_y,
-                                    marker: GutterMarker::Breakpoint,
-                                    style_color,
-                                    style_width: width,
-                                    style_font_size,
-                                    style_font_family: font_family.1.clone(),
-                                    is_current_line,
-                                }
+                            let marker = if !breakpoint.active {
+                                GutterMarker::BreakpointInactive
+                            } else if breakpoint.is_logpoint() {
+                                GutterMarker::Logpoint
+                            } else if breakpoint.verified {
+                                GutterMarker::BreakpointVerified
                             } else {
-                                GutterData {
-                                    origin_line_start: Some(
-                                        text.folded_line.origin_line_start,
-                                    ),
-                                    paint_point_y: text.folded_line_y,
-                                    marker: GutterMarker::BreakpointInactive,
-                                    style_color,
-                                    style_width: width,
-                                    style_font_size,
-                                    style_font_family: font_family.1.clone(),
-                                    is_current_line,
-                                }
+                                GutterMarker::Breakpoint
+                            };
+                            GutterData {
+                                origin_line_start: Some(
+                                    text.folded_line.origin_line_start,
+                                ),
+                                paint_point_y: text.folded_line_y,
+                                marker,
+                                style_color,
+                                style_width: width,
+                                style_font_size,
+                                style_font_family: font_family.1.clone(),
+                                is_current_line,
                             }
                         } else {
                             GutterData {
@@ -169,6 +159,99 @@ pub fn gutter_data(
     })
 }
 
+/// The blame of the visible lines. Only the first line of each run of lines
+/// from the same commit has a text.
+pub fn blame_gutter_data(e_data: &EditorData) -> Vec<BlameGutterData> {
+    let config = e_data.common.config;
+    if !config
+        .signal(|config| config.editor.show_blame_gutter.signal())
+        .get()
+    {
+        return Vec::new();
+    }
+    let doc = e_data.doc_signal().get();
+    let now = chrono::Utc::now().timestamp();
+    let screen_lines = e_data.screen_lines.read_only();
+    doc.blame.with(|blame| {
+        screen_lines.with(|screen_lines| {
+            screen_lines
+                .visual_lines
+                .iter()
+                .enumerate()
+                .filter_map(|(index, vl_info)| {
+                    let VisualLineInfo::OriginText { text } = vl_info else {
+                        return None;
+                    };
+                    let line = text.folded_line.origin_line_start;
+                    let commit = blame.get(line)?;
+                    let id = |commit: &Option<Rc<CommitInfo>>| {
+                        commit.as_ref().map(|commit| commit.id.clone())
+                    };
+                    let same_as_previous = index > 0
+                        && line > 0
+                        && blame.get(line - 1).map(id) == Some(id(commit));
+                    let blame_text = if same_as_previous {
+                        String::new()
+                    } else {
+                        blame_gutter_text(commit.as_deref(), now)
+                    };
+                    Some(BlameGutterData {
+                        origin_line_start: line,
+                        paint_point_y:     text.folded_line_y,
+                        text:              blame_text,
+                    })
+                })
+                .collect()
+        })
+    })
+}
+
+/// The change markers of the visible lines, a removal marked at the top of
+/// the line after it.
+pub fn change_gutter_data(e_data: &EditorData) -> Vec<ChangeGutterData> {
+    let doc = e_data.doc_signal().get();
+    let last_line = doc.lines.with_untracked(|x| x.buffer().last_line());
+    let hunks = doc.head_changes().with(|changes| change_hunks(changes));
+    if hunks.is_empty() {
+        return Vec::new();
+    }
+    let screen_lines = e_data.screen_lines.read_only();
+    screen_lines.with(|screen_lines| {
+        screen_lines
+            .visual_lines
+            .iter()
+            .filter_map(|vl_info| {
+                let VisualLineInfo::OriginText { text } = vl_info else {
+                    return None;
+                };
+                let line = text.folded_line.origin_line_start;
+                let hunk = hunks
+                    .iter()
+                    .find(|hunk| hunk.is_marked_on(line, last_line))?;
+                Some(ChangeGutterData {
+                    origin_line_start: line,
+                    paint_point_y:     text.folded_line_y,
+                    kind:              hunk.kind(),
+                })
+            })
+            .collect()
+    })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChangeGutterData {
+    pub origin_line_start: usize,
+    pub paint_point_y:     f64,
+    pub kind:              ChangeKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlameGutterData {
+    pub origin_line_start: usize,
+    pub paint_point_y:     f64,
+    pub text:              String,
+}
+
 #[derive(Clone, Debug)]
 pub struct GutterData {
     origin_line_start: Option<usize>,
@@ -215,6 +298,7 @@ pub enum GutterMarker {
     Breakpoint,
     BreakpointInactive,
     BreakpointVerified, // CodeLenAndBreakPoint,
+    Logpoint,
 }
 
 #[derive(Debug, Clone, Hash, Copy, Eq, PartialEq)]
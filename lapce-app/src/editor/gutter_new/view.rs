@@ -3,7 +3,7 @@ use floem::{
     prelude::{Decorators, SignalGet, Svg, clip, container, palette, static_label},
     style::{CursorStyle, StyleValue},
     taffy::{AlignItems, JustifyContent},
-    views::{dyn_stack, svg},
+    views::{dyn_stack, empty, svg},
 };
 use lapce_core::icon::LapceIcons;
 
@@ -11,7 +11,11 @@ use crate::{
     config::{WithLapceConfig, color::LapceColor},
     editor::{
         DocSignal, EditorData,
-        gutter_new::{GutterData, GutterMarker, gutter_data},
+        gutter_new::{
+            BlameGutterData, ChangeGutterData, GutterData, GutterMarker,
+            blame_gutter_data, change_gutter_data, gutter_data,
+        },
+        hunk::ChangeKind,
     },
     window_workspace::WindowWorkspaceData,
 };
@@ -66,6 +70,19 @@ fn gutter_marker_breakpoint_inactive_svg_view(config: WithLapceConfig) -> Svg {
     )
 }
 
+fn gutter_marker_logpoint_svg_view(config: WithLapceConfig) -> Svg {
+    svg(move || config.with_ui_svg(LapceIcons::DEBUG_LOGPOINT)).style(move |s| {
+        let (icon_size, color) = config.signal(|config| {
+            (
+                config.ui.icon_size.signal(),
+                config.color(LapceColor::DEBUG_BREAKPOINT),
+            )
+        });
+        let size = icon_size.get() as f64;
+        s.size(size, size).color(color.get())
+    })
+}
+
 fn gutter_marker_code_len_svg_view(
     window_tab_data: WindowWorkspaceData,
     line: Option<usize>,
@@ -142,6 +159,124 @@ pub fn editor_gutter_new(
     .debug_name("editor_gutter")
 }
 
+/// The optional git blame column, left of the line numbers.
+pub fn editor_gutter_blame(e_data: EditorData) -> impl View {
+    let config = e_data.common.config;
+    let line_height = e_data.common.ui_line_height;
+    let e_data_click = e_data.clone();
+    dyn_stack(
+        move || blame_gutter_data(&e_data),
+        |data| {
+            (
+                data.origin_line_start,
+                data.paint_point_y.to_bits(),
+                data.text.clone(),
+            )
+        },
+        move |data| {
+            let BlameGutterData {
+                origin_line_start,
+                paint_point_y,
+                text,
+            } = data;
+            let editor = e_data_click.clone();
+            static_label(text)
+                .style(move |s| {
+                    let (font_size, color) = config.signal(|config| {
+                        (
+                            config.editor.font_size.signal(),
+                            config.color(LapceColor::EDITOR_DIM),
+                        )
+                    });
+                    s.absolute()
+                        .inset_top(paint_point_y)
+                        .height(line_height.get())
+                        .width_full()
+                        .padding_horiz(6.0)
+                        .font_size(font_size.get() as f32 - 1.0)
+                        .color(color.get())
+                        .items_center()
+                        .text_ellipsis()
+                        .hover(|s| s.cursor(CursorStyle::Pointer))
+                })
+                .on_click_stop(move |_| {
+                    editor.open_blame_commit(origin_line_start);
+                })
+        },
+    )
+    .style(move |s| {
+        let (show, font_size) = config.signal(|config| {
+            (
+                config.editor.show_blame_gutter.signal(),
+                config.editor.font_size.signal(),
+            )
+        });
+        s.width(font_size.get() as f32 * 14.0)
+            .height_full()
+            .background(config.with_color(LapceColor::PANEL_BACKGROUND))
+            .apply_if(!show.get(), |s| s.hide())
+    })
+    .debug_name("Blame Gutter")
+}
+
+/// The markers of the lines changed against `HEAD`, or the ref the changes
+/// are shown against. Clicking one peeks at the old text of its change.
+pub fn editor_gutter_changes(e_data: EditorData) -> impl View {
+    let config = e_data.common.config;
+    let line_height = e_data.common.ui_line_height;
+    let e_data_click = e_data.clone();
+    dyn_stack(
+        move || change_gutter_data(&e_data),
+        |data| {
+            (
+                data.origin_line_start,
+                data.paint_point_y.to_bits(),
+                data.kind,
+            )
+        },
+        move |data| {
+            let ChangeGutterData {
+                origin_line_start,
+                paint_point_y,
+                kind,
+            } = data;
+            let editor = e_data_click.clone();
+            let color = match kind {
+                ChangeKind::Added => LapceColor::SOURCE_CONTROL_ADDED,
+                ChangeKind::Modified => LapceColor::SOURCE_CONTROL_MODIFIED,
+                ChangeKind::Removed => LapceColor::SOURCE_CONTROL_REMOVED,
+            };
+            container(empty().style(move |s| {
+                let line_height = line_height.get();
+                s.width(3.0)
+                    .height(if kind == ChangeKind::Removed {
+                        line_height / 4.0
+                    } else {
+                        line_height
+                    })
+                    .background(config.with_color(color))
+            }))
+            .style(move |s| {
+                s.absolute()
+                    .inset_top(paint_point_y)
+                    .height(line_height.get())
+                    .width_full()
+                    .justify_center()
+                    .hover(|s| s.cursor(CursorStyle::Pointer))
+            })
+            .on_click_stop(move |_| {
+                editor.toggle_hunk_peek(origin_line_start);
+            })
+        },
+    )
+    .style(move |s| {
+        s.width(7.0)
+            .height_full()
+            .background(config.with_color(LapceColor::PANEL_BACKGROUND))
+    })
+    .debug_name("Change Gutter")
+}
+
 fn gutter_data_view(
     data: &GutterData,
     window_tab_data: &WindowWorkspaceData,
@@ -199,11 +334,13 @@ fn marker_view(
         GutterMarker::BreakpointInactive => {
             gutter_marker_breakpoint_inactive_svg_view(config)
         },
+        GutterMarker::Logpoint => gutter_marker_logpoint_svg_view(config),
         GutterMarker::CurrentDebugLine => {
             gutter_marker_debug_line_svg_view(window_tab_data)
         },
     };
     let origin_line_start = data.origin_line_start;
+    let window_tab_data_secondary_click = window_tab_data_click.clone();
     container(svg)
         .style(move |s| {
             let size = config.with_icon_size() as f64;
@@ -227,4 +364,15 @@ fn marker_view(
                 );
             }
         })
+        .on_secondary_click_stop(move |_| {
+            if let Some(line) = origin_line_start {
+                window_tab_data_secondary_click
+                    .common
+                    .internal_command
+                    .send(crate::command::InternalCommand::EditBreakPoint {
+                        doc:      doc_signal,
+                        line_num: line,
+                    });
+            }
+        })
 }
@@ -0,0 +1,326 @@
+use std::ops::Range;
+
+use doc::lines::{
+    buffer::{diff::DiffLines, rope_text::RopeText},
+    edit::EditType,
+    selection::Selection,
+};
+use floem::reactive::{SignalGet, SignalUpdate, SignalWith};
+use lapce_core::doc::DocContent;
+use lapce_xi_rope::Rope;
+
+use super::{EditorData, location::EditorPosition};
+use crate::command::InternalCommand;
+
+/// A run of changed lines, against the version of the file the gutter shows
+/// the changes against.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChangeHunk {
+    /// The lines of the old version, empty when lines were only added
+    pub old: Range<usize>,
+    /// The lines of the buffer, empty when lines were only removed
+    pub new: Range<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Removed,
+}
+
+impl ChangeHunk {
+    pub fn kind(&self) -> ChangeKind {
+        if self.old.is_empty() {
+            ChangeKind::Added
+        } else if self.new.is_empty() {
+            ChangeKind::Removed
+        } else {
+            ChangeKind::Modified
+        }
+    }
+
+    /// Whether the gutter marker of the hunk is on `line`. Removed lines are
+    /// marked on the line after them, or the last line at the end of the
+    /// buffer.
+    pub fn is_marked_on(&self, line: usize, last_line: usize) -> bool {
+        if self.new.is_empty() {
+            self.new.start.min(last_line) == line
+        } else {
+            self.new.contains(&line)
+        }
+    }
+}
+
+/// Group the line changes of a diff into hunks of consecutive changes.
+pub fn change_hunks<'a>(
+    changes: impl IntoIterator<Item = &'a DiffLines>,
+) -> Vec<ChangeHunk> {
+    let mut hunks = Vec::new();
+    let mut current: Option<ChangeHunk> = None;
+    let (mut old_line, mut new_line) = (0, 0);
+    for change in changes {
+        match change {
+            DiffLines::Both(info) => {
+                hunks.extend(current.take());
+                old_line = info.left.end;
+                new_line = info.right.end;
+            },
+            DiffLines::Left(range) => {
+                let hunk = current.get_or_insert(ChangeHunk {
+                    old: range.start..range.start,
+                    new: new_line..new_line,
+                });
+                hunk.old.end = range.end;
+                old_line = range.end;
+            },
+            DiffLines::Right(range) => {
+                let hunk = current.get_or_insert(ChangeHunk {
+                    old: old_line..old_line,
+                    new: range.start..range.start,
+                });
+                hunk.new.end = range.end;
+                new_line = range.end;
+            },
+        }
+    }
+    hunks.extend(current);
+    hunks
+}
+
+/// The text of `lines` in `text`, the last one ending with a line break
+/// only if it has one.
+pub fn lines_text(text: &Rope, lines: &Range<usize>) -> String {
+    let last_line = text.line_of_offset(text.len());
+    let offset = |line: usize| {
+        if line > last_line {
+            text.len()
+        } else {
+            text.offset_of_line(line)
+        }
+    };
+    text.slice_to_cow(offset(lines.start)..offset(lines.end))
+        .to_string()
+}
+
+impl EditorData {
+    pub fn change_hunks(&self) -> Vec<ChangeHunk> {
+        self.doc()
+            .head_changes()
+            .with_untracked(|changes| change_hunks(changes))
+    }
+
+    /// The hunk whose gutter marker is on `line`.
+    pub fn change_hunk_at(&self, line: usize) -> Option<ChangeHunk> {
+        let last_line = self.doc().lines.with_untracked(|x| x.buffer().last_line());
+        self.change_hunks()
+            .into_iter()
+            .find(|hunk| hunk.is_marked_on(line, last_line))
+    }
+
+    /// Show the old text of the hunk marked on `line` under it, or close it
+    /// when it is already shown.
+    pub fn toggle_hunk_peek(&self, line: usize) {
+        let Some(hunk) = self.change_hunk_at(line) else {
+            self.hunk_peek.set(None);
+            return;
+        };
+        if self.hunk_peek.get_untracked().as_ref() == Some(&hunk) {
+            self.hunk_peek.set(None);
+        } else {
+            self.hunk_peek.set(Some(hunk));
+        }
+    }
+
+    /// The peeked hunk and its old text, while the hunk is unchanged.
+    pub fn hunk_peek(&self) -> Option<(ChangeHunk, String)> {
+        let hunk = self.hunk_peek.get()?;
+        let doc = self.doc_signal().get();
+        if !doc
+            .head_changes()
+            .with(|changes| change_hunks(changes).contains(&hunk))
+        {
+            return None;
+        }
+        let head = doc.head_text()?;
+        let text = lines_text(&head, &hunk.old);
+        Some((hunk, text))
+    }
+
+    /// Move to the next hunk after the cursor, or the previous one, wrapping
+    /// around, and peek at it if a hunk is peeked at.
+    pub fn go_to_change_hunk(&self, previous: bool) {
+        let hunks = self.change_hunks();
+        if hunks.is_empty() {
+            self.common
+                .show_status_message("There are no changes".to_string());
+            return;
+        }
+        let doc = self.doc();
+        let offset = self.cursor().with_untracked(|cursor| cursor.offset());
+        let line = doc
+            .lines
+            .with_untracked(|x| x.buffer().line_of_offset(offset));
+        let hunk = if previous {
+            hunks
+                .iter()
+                .rev()
+                .find(|hunk| hunk.new.start < line)
+                .or(hunks.last())
+        } else {
+            hunks
+                .iter()
+                .find(|hunk| hunk.new.start > line)
+                .or(hunks.first())
+        };
+        let Some(hunk) = hunk.cloned() else {
+            return;
+        };
+        let last_line = doc.lines.with_untracked(|x| x.buffer().last_line());
+        if let Err(err) = self.go_to_position(
+            EditorPosition::Line(hunk.new.start.min(last_line)),
+            None,
+            None,
+        ) {
+            log::error!("{err}");
+        }
+        if self.hunk_peek.with_untracked(Option::is_some) {
+            self.hunk_peek.set(Some(hunk));
+        }
+    }
+
+    /// Whether the hunks can be reverted and staged, which is done against
+    /// the index, so not while the gutter shows the changes against a ref.
+    pub fn can_apply_change_hunks(&self) -> bool {
+        self.common.diff_base.with(Option::is_none)
+    }
+
+    /// Show why the hunks can't be reverted or staged, if they can't.
+    fn check_apply_change_hunks(&self) -> bool {
+        let can_apply = self.can_apply_change_hunks();
+        if !can_apply {
+            self.common.show_status_message(
+                "Show the gutter changes against HEAD to revert or stage them"
+                    .to_string(),
+            );
+        }
+        can_apply
+    }
+
+    /// Replace the lines of `hunk` with their old text. It is an ordinary
+    /// edit, so it can be undone.
+    pub fn revert_change_hunk(&self, hunk: &ChangeHunk) {
+        if !self.check_apply_change_hunks() {
+            return;
+        }
+        let doc = self.doc();
+        let Some(head) = doc.head_text() else {
+            return;
+        };
+        let old_text = lines_text(&head, &hunk.old);
+        let text = doc.text();
+        let last_line = text.line_of_offset(text.len());
+        let offset = |line: usize| {
+            if line > last_line {
+                text.len()
+            } else {
+                text.offset_of_line(line)
+            }
+        };
+        let (start, end) = (offset(hunk.new.start), offset(hunk.new.end));
+        doc.do_raw_edit(
+            &[(Selection::region(start, end), old_text.as_str())],
+            EditType::Other,
+        );
+        self.hunk_peek.set(None);
+    }
+
+    /// Stage the hunk, which must be saved first as the file is staged from
+    /// the disk.
+    pub fn stage_change_hunk(&self, hunk: &ChangeHunk) {
+        if !self.check_apply_change_hunks() {
+            return;
+        }
+        let doc = self.doc();
+        let DocContent::File { path, .. } = doc.content.get_untracked() else {
+            return;
+        };
+        if !doc.is_pristine() {
+            self.common
+                .show_status_message("Save the file before staging it".to_string());
+            return;
+        }
+        self.common
+            .internal_command
+            .send(InternalCommand::StageHunk {
+                path,
+                line: hunk.new.start as u32,
+            });
+        self.hunk_peek.set(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use doc::lines::buffer::diff::{DiffBothInfo, DiffLines};
+    use lapce_xi_rope::Rope;
+
+    use super::{ChangeHunk, ChangeKind, change_hunks, lines_text};
+
+    fn both(
+        left: std::ops::Range<usize>,
+        right: std::ops::Range<usize>,
+    ) -> DiffLines {
+        DiffLines::Both(DiffBothInfo {
+            left,
+            right,
+            skip: None,
+        })
+    }
+
+    #[test]
+    fn test_change_hunks() {
+        // `a b c d e` changed into `a B c e f`
+        let changes = [
+            both(0..1, 0..1),
+            DiffLines::Left(1..2),
+            DiffLines::Right(1..2),
+            both(2..3, 2..3),
+            DiffLines::Left(3..4),
+            both(4..5, 3..4),
+            DiffLines::Right(4..5),
+        ];
+        let hunks = change_hunks(&changes);
+        assert_eq!(
+            hunks,
+            vec![
+                ChangeHunk {
+                    old: 1..2,
+                    new: 1..2,
+                },
+                ChangeHunk {
+                    old: 3..4,
+                    new: 3..3,
+                },
+                ChangeHunk {
+                    old: 5..5,
+                    new: 4..5,
+                },
+            ]
+        );
+        assert_eq!(
+            hunks.iter().map(ChangeHunk::kind).collect::<Vec<_>>(),
+            vec![ChangeKind::Modified, ChangeKind::Removed, ChangeKind::Added]
+        );
+        assert!(hunks[1].is_marked_on(3, 4));
+        assert!(!hunks[1].is_marked_on(2, 4));
+    }
+
+    #[test]
+    fn test_lines_text() {
+        let text = Rope::from("a\nb\nc");
+        assert_eq!(lines_text(&text, &(1..2)), "b\n");
+        assert_eq!(lines_text(&text, &(1..3)), "b\nc");
+        assert_eq!(lines_text(&text, &(3..3)), "");
+    }
+}
@@ -0,0 +1,258 @@
+use std::{ops::Range, path::PathBuf, rc::Rc};
+
+use doc::lines::{edit::EditType, selection::Selection};
+use floem::reactive::{Memo, RwSignal, Scope, SignalGet, SignalUpdate, SignalWith};
+use lapce_xi_rope::Rope;
+use log::error;
+
+use super::{EditorData, location::EditorPosition};
+use crate::doc::Doc;
+
+/// A conflict left in a file by a merge, as the lines of its markers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConflictRegion {
+    /// The `<<<<<<<` line
+    pub start:  usize,
+    /// The `>>>>>>>` line
+    pub end:    usize,
+    pub ours:   Range<usize>,
+    /// Only written with the `diff3` conflict style
+    pub base:   Option<Range<usize>>,
+    pub theirs: Range<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictResolution {
+    Ours,
+    Theirs,
+    /// Ours followed by theirs
+    Both,
+}
+
+/// The conflicts marked in `text`. Markers that aren't closed are ignored.
+pub fn parse_conflicts(text: &str) -> Vec<ConflictRegion> {
+    let is_marker = |line: &str, marker: &str| {
+        line.strip_prefix(marker).is_some_and(|rest| {
+            rest.is_empty() || rest.starts_with([' ', '\r', '\n'])
+        })
+    };
+
+    let mut conflicts = Vec::new();
+    // the `<<<<<<<` line, then the `|||||||` and `=======` lines once seen
+    let mut open: Option<(usize, Option<usize>, Option<usize>)> = None;
+    for (line, content) in text.split_inclusive('\n').enumerate() {
+        if is_marker(content, "<<<<<<<") {
+            open = Some((line, None, None));
+            continue;
+        }
+        let Some((start, base, separator)) = open.as_mut() else {
+            continue;
+        };
+        if is_marker(content, "|||||||") && base.is_none() && separator.is_none() {
+            *base = Some(line);
+        } else if is_marker(content, "=======") && separator.is_none() {
+            *separator = Some(line);
+        } else if is_marker(content, ">>>>>>>")
+            && let Some(separator) = *separator
+        {
+            let start = *start;
+            let base = *base;
+            conflicts.push(ConflictRegion {
+                start,
+                end: line,
+                ours: start + 1..base.unwrap_or(separator),
+                base: base.map(|base| base + 1..separator),
+                theirs: separator + 1..line,
+            });
+            open = None;
+        }
+    }
+    conflicts
+}
+
+/// The text replacing the lines of `conflict`, markers included.
+pub fn resolution_text(
+    text: &Rope,
+    conflict: &ConflictRegion,
+    resolution: ConflictResolution,
+) -> String {
+    let lines = |range: &Range<usize>| {
+        text.slice_to_cow(
+            text.offset_of_line(range.start)..text.offset_of_line(range.end),
+        )
+        .to_string()
+    };
+    match resolution {
+        ConflictResolution::Ours => lines(&conflict.ours),
+        ConflictResolution::Theirs => lines(&conflict.theirs),
+        ConflictResolution::Both => lines(&conflict.ours) + &lines(&conflict.theirs),
+    }
+}
+
+/// What a diff editor shows when it is a merge editor: its left and right
+/// sides are our and their versions of the file, the base they both changed
+/// is shown between them, and the conflicts are resolved in the working copy
+/// file, shown below them.
+#[derive(Clone)]
+pub struct MergeEditorData {
+    pub path:          PathBuf,
+    pub base:          EditorData,
+    /// The editor of the working copy file, below the three versions
+    pub result_editor: EditorData,
+    /// The working copy file with the conflict markers
+    pub result:        Rc<Doc>,
+    pub conflicts:     Memo<Vec<ConflictRegion>>,
+    /// The index of the conflict the actions apply to
+    pub current:       RwSignal<usize>,
+    /// Whether the result editor has the focus rather than a side
+    pub focus_result:  RwSignal<bool>,
+}
+
+impl MergeEditorData {
+    pub fn new(
+        cx: Scope,
+        path: PathBuf,
+        base: EditorData,
+        result_editor: EditorData,
+    ) -> Self {
+        let result = result_editor.doc();
+        let conflicts = {
+            let result = result.clone();
+            cx.create_memo(move |_| {
+                let buffer = result.lines.with_untracked(|x| x.signal_buffer());
+                buffer.with(|b| parse_conflicts(&b.text().to_string()))
+            })
+        };
+        Self {
+            path,
+            base,
+            result_editor,
+            result,
+            conflicts,
+            current: cx.create_rw_signal(0),
+            focus_result: cx.create_rw_signal(false),
+        }
+    }
+
+    pub fn copy(
+        &self,
+        cx: Scope,
+        base: EditorData,
+        result_editor: EditorData,
+    ) -> Self {
+        let data = Self::new(cx, self.path.clone(), base, result_editor);
+        data.current.set(self.current.get_untracked());
+        data
+    }
+
+    /// The conflict the actions apply to, the last one once later ones were
+    /// resolved.
+    pub fn current_conflict(&self) -> Option<(usize, ConflictRegion)> {
+        let current = self.current.get();
+        self.conflicts.with(|conflicts| {
+            let index = current.min(conflicts.len().checked_sub(1)?);
+            Some((index, conflicts[index].clone()))
+        })
+    }
+
+    pub fn select(&self, previous: bool) {
+        let len = self.conflicts.with_untracked(|conflicts| conflicts.len());
+        if len == 0 {
+            return;
+        }
+        self.current.update(|current| {
+            let index = (*current).min(len - 1);
+            *current = if previous {
+                (index + len - 1) % len
+            } else {
+                (index + 1) % len
+            };
+        });
+        self.reveal_current();
+    }
+
+    /// Put the cursor of the result editor on the current conflict.
+    pub fn reveal_current(&self) {
+        let Some((_, conflict)) = self.current_conflict() else {
+            return;
+        };
+        if let Err(err) = self.result_editor.go_to_position(
+            EditorPosition::Line(conflict.start),
+            None,
+            None,
+        ) {
+            error!("{err}");
+        }
+    }
+
+    /// Replace the current conflict with the chosen side. It is an ordinary
+    /// edit of the working copy file, so it can be undone.
+    pub fn accept(&self, resolution: ConflictResolution) {
+        let Some((_, conflict)) = self.current_conflict() else {
+            return;
+        };
+        let text = self.result.text();
+        let replacement = resolution_text(&text, &conflict, resolution);
+        let start = text.offset_of_line(conflict.start);
+        let end = text.offset_of_line(conflict.end + 1);
+        self.result.do_raw_edit(
+            &[(Selection::region(start, end), replacement.as_str())],
+            EditType::Other,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_xi_rope::Rope;
+
+    use super::{
+        ConflictRegion, ConflictResolution, parse_conflicts, resolution_text,
+    };
+
+    const TEXT: &str = "a\n<<<<<<< HEAD\nours\n||||||| \
+                        base\nbase\n=======\ntheirs\nmore\n>>>>>>> \
+                        topic\nb\n<<<<<<< HEAD\n=======\nx\n>>>>>>> topic\n";
+
+    #[test]
+    fn test_parse_conflicts() {
+        assert_eq!(
+            parse_conflicts(TEXT),
+            vec![
+                ConflictRegion {
+                    start:  1,
+                    end:    8,
+                    ours:   2..3,
+                    base:   Some(4..5),
+                    theirs: 6..8,
+                },
+                ConflictRegion {
+                    start:  10,
+                    end:    13,
+                    ours:   11..11,
+                    base:   None,
+                    theirs: 12..13,
+                },
+            ]
+        );
+        assert!(parse_conflicts("<<<<<<< HEAD\na\n=======\nb\n").is_empty());
+    }
+
+    #[test]
+    fn test_resolution_text() {
+        let rope = Rope::from(TEXT);
+        let conflicts = parse_conflicts(TEXT);
+        assert_eq!(
+            resolution_text(&rope, &conflicts[0], ConflictResolution::Ours),
+            "ours\n"
+        );
+        assert_eq!(
+            resolution_text(&rope, &conflicts[0], ConflictResolution::Theirs),
+            "theirs\nmore\n"
+        );
+        assert_eq!(
+            resolution_text(&rope, &conflicts[1], ConflictResolution::Both),
+            "x\n"
+        );
+    }
+}
@@ -1,7 +1,8 @@
 use std::{
+    cell::RefCell,
     cmp::Ordering,
-    collections::{HashMap, HashSet},
-    path::PathBuf,
+    collections::{HashMap, HashSet, VecDeque},
+    path::{Path, PathBuf},
     rc::Rc,
     str::FromStr,
     sync::Arc,
@@ -62,10 +63,11 @@ use lapce_rpc::{RpcError, SnippetTextEdit, plugin::PluginId, proxy::ProxyRespons
 use lapce_xi_rope::{Rope, RopeDelta, Transformer};
 use log::{debug, error, info};
 use lsp_types::{
-    CodeActionOrCommand, CodeActionResponse, CodeLens, CompletionItem,
-    CompletionTextEdit, Diagnostic, DiagnosticSeverity, GotoDefinitionResponse,
-    HoverContents, InlineCompletionTriggerKind, InsertTextFormat, Location,
-    MarkedString, MarkupKind, Position, Range, TextEdit,
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionResponse, CodeLens,
+    CompletionItem, CompletionTextEdit, Diagnostic, DiagnosticSeverity,
+    GotoDefinitionResponse, HoverContents, InlineCompletionTriggerKind,
+    InsertTextFormat, Location, MarkedString, MarkupKind, Position, Range, TextEdit,
+    WorkspaceEdit,
 };
 use nucleo::Utf32Str;
 use serde_json::Value;
@@ -87,14 +89,18 @@ use crate::{
     keypress::{KeyPressFocus, condition::Condition},
     listener::Listener,
     lsp::path_from_url,
+    main_split::workspace_edits,
     markdown::{
         MarkdownContent, from_marked_string, from_plaintext, parse_markdown,
     },
     panel::{
         call_hierarchy_view::{CallHierarchyData, CallHierarchyItemData},
         document_symbol::MatchDocumentSymbol,
-        implementation_view::{init_implementation_root, map_to_location},
+        implementation_view::{
+            init_implementation_root, map_to_location, resolve_reference_details,
+        },
     },
+    peek::peek_line_after_delta,
     snippet::Snippet,
     window_workspace::{CommonData, Focus, WindowWorkspaceData},
 };
@@ -102,7 +108,9 @@ use crate::{
 pub mod diff;
 pub mod floem_editor;
 pub mod gutter;
+pub mod hunk;
 pub mod location;
+pub mod merge;
 pub mod view;
 
 pub mod gutter_new;
@@ -174,6 +182,11 @@ pub struct EditorData {
     pub sticky_header_info:   RwSignal<StickyHeaderInfo>,
     pub last_movement:        RwSignal<Movement>,
     pub auto_save_token:      RwSignal<TimerToken>,
+    /// The change whose old text is shown under it
+    pub hunk_peek:            RwSignal<Option<hunk::ChangeHunk>>,
+    /// Blank lines kept under a line for the peek view, as `(line, count)`,
+    /// the line moving along with the edits made in this editor
+    pub reserved_lines:       RwSignal<Option<(usize, usize)>>,
 }
 
 impl PartialEq for EditorData {
@@ -211,17 +224,19 @@ impl EditorData {
         let cursor = cx.create_rw_signal(cursor);
         let doc = cx.create_rw_signal(doc);
         let kind = cx.create_rw_signal(view_kind);
+        let reserved_lines = cx.create_rw_signal(None);
 
         cx.create_effect(move |_| {
             let lines = doc.with(|x| x.lines);
             let base = viewport_memo.get();
             let kind = kind.get();
+            let reserved = reserved_lines.get();
             let signal_paint_content =
                 lines.with_untracked(|x| x.signal_paint_content());
             signal_paint_content.track();
             let Some((screen_lines_val, folding_display_item_val, visual_lines_val)) = lines
                 .try_update(|x| {
-                    match x.compute_screen_lines_new(base, kind) {
+                    match x.compute_screen_lines_new(base, kind, reserved) {
                         Ok(rs) => {Some(rs)}
                         Err(err) => {
                             error!("{err}");
@@ -278,6 +293,8 @@ impl EditorData {
             last_movement: cx.create_rw_signal(Movement::Left),
             editor_id: EditorId::next(),
             auto_save_token: cx.create_rw_signal(TimerToken::INVALID),
+            hunk_peek: cx.create_rw_signal(None),
+            reserved_lines,
         }
     }
 
@@ -1685,10 +1702,10 @@ impl EditorData {
                 if let Ok(ProxyResponse::ReferencesResolveResponse { items }) =
                     result
                 {
-                    window_tab_data
-                        .main_split
-                        .references
+                    let tabs = window_tab_data.main_split.references;
+                    let id = tabs
                         .push_tab(symbol, init_implementation_root(items, scope));
+                    resolve_reference_details(&window_tab_data, tabs, id);
                     window_tab_data.show_panel(PanelKind::References);
                 }
             }
@@ -1751,10 +1768,10 @@ impl EditorData {
                 if let Ok(ProxyResponse::ReferencesResolveResponse { items }) =
                     result
                 {
-                    window_tab_data
-                        .main_split
-                        .implementations
+                    let tabs = window_tab_data.main_split.implementations;
+                    let id = tabs
                         .push_tab(symbol, init_implementation_root(items, scope));
+                    resolve_reference_details(&window_tab_data, tabs, id);
                     window_tab_data.show_panel(PanelKind::Implementation);
                 }
             }
@@ -1785,6 +1802,130 @@ impl EditorData {
         Ok(())
     }
 
+    pub fn peek_definition(
+        &self,
+        window_tab_data: WindowWorkspaceData,
+    ) -> Result<()> {
+        let Some((path, position, symbol)) = self.symbol_at_cursor()? else {
+            return Ok(());
+        };
+        let editor = self.clone();
+        let send = create_ext_action(self.scope, move |locations| {
+            editor.peek_locations(
+                window_tab_data,
+                format!("Definition of `{symbol}`"),
+                locations,
+            );
+        });
+        self.common.proxy.proxy_rpc.get_definition(
+            self.cursor().with_untracked(|c| c.offset()),
+            path,
+            position,
+            move |(_, result)| {
+                if let Ok(ProxyResponse::GetDefinitionResponse {
+                    definition, ..
+                }) = result
+                {
+                    let locations = match definition {
+                        GotoDefinitionResponse::Scalar(location) => vec![location],
+                        GotoDefinitionResponse::Array(locations) => locations,
+                        GotoDefinitionResponse::Link(location_links) => {
+                            location_links
+                                .into_iter()
+                                .map(|x| Location {
+                                    uri:   x.target_uri,
+                                    range: x.target_selection_range,
+                                })
+                                .collect()
+                        },
+                    };
+                    send(locations);
+                }
+            },
+        );
+        Ok(())
+    }
+
+    pub fn peek_references(
+        &self,
+        window_tab_data: WindowWorkspaceData,
+    ) -> Result<()> {
+        let Some((path, position, symbol)) = self.symbol_at_cursor()? else {
+            return Ok(());
+        };
+        let editor = self.clone();
+        let send = create_ext_action(self.scope, move |locations| {
+            editor.peek_locations(
+                window_tab_data,
+                format!("References of `{symbol}`"),
+                locations,
+            );
+        });
+        self.common.proxy.proxy_rpc.get_references(
+            path,
+            position,
+            move |(_, result)| {
+                if let Ok(ProxyResponse::GetReferencesResponse { references }) =
+                    result
+                {
+                    send(references);
+                }
+            },
+        );
+        Ok(())
+    }
+
+    /// The path of the document, the cursor position and the word under the
+    /// cursor, if the document is a loaded file.
+    fn symbol_at_cursor(&self) -> Result<Option<(PathBuf, Position, String)>> {
+        let doc = self.doc();
+        let path = match if doc.loaded() {
+            doc.content.with_untracked(|c| c.path().cloned())
+        } else {
+            None
+        } {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+
+        let offset = self.cursor().with_untracked(|c| c.offset());
+        let (position, symbol) = doc.lines.with_untracked(|b| {
+            let start_offset = b.buffer().prev_code_boundary(offset);
+            let end_offset = b.buffer().next_code_boundary(offset);
+            let position = b.buffer().offset_to_position(offset);
+            let symbol = b
+                .buffer()
+                .slice_to_cow(start_offset..end_offset)
+                .to_string();
+            (position, symbol)
+        });
+        Ok(Some((path, position?, symbol)))
+    }
+
+    /// Resolve the lines of `locations` and show them in the inline peek
+    /// anchored under the cursor.
+    fn peek_locations(
+        &self,
+        window_tab_data: WindowWorkspaceData,
+        title: String,
+        locations: Vec<Location>,
+    ) {
+        if locations.is_empty() {
+            window_tab_data.show_status_message("No results to peek".to_string());
+            return;
+        }
+        let editor = self.clone();
+        let send = create_ext_action(self.scope, move |(_, result)| {
+            if let Ok(ProxyResponse::ReferencesResolveResponse { items }) = result {
+                window_tab_data.peek_data.show(&editor, title, items);
+            }
+        });
+        self.common
+            .proxy
+            .proxy_rpc
+            .references_resolve(locations, send);
+    }
+
     fn scroll(&self, down: bool, count: usize, mods: Modifiers) {
         let top_offset = self.sticky_header_height.get_untracked();
         let viewport = self.viewport_untracked();
@@ -2233,7 +2374,7 @@ impl EditorData {
     }
 
     /// Check if there are completions that are being rendered
-    fn has_completions(&self) -> bool {
+    pub(crate) fn has_completions(&self) -> bool {
         self.common.completion.with_untracked(|completion| {
             completion.status != CompletionStatus::Inactive
                 && !completion.filtered_items.is_empty()
@@ -2682,14 +2823,32 @@ impl EditorData {
         // if !deltas.is_empty() && !self.confirmed.get_untracked() {
         //     self.confirmed.set(true);
         // }
-        for (_, delta, _) in deltas {
+        for (_, delta, inval) in deltas {
             // self.inactive_apply_delta(delta);
             self.update_snippet_offset(delta);
+            self.update_reserved_lines(delta, &inval.old_text);
             // self.update_breakpoints(delta);
         }
         // self.update_signature();
     }
 
+    /// Keep the lines blank for the peek under the line it was opened from.
+    fn update_reserved_lines(&self, delta: &RopeDelta, old_text: &Rope) {
+        let Some((line, count)) = self.reserved_lines.get_untracked() else {
+            return;
+        };
+        let new_line = self.doc().lines.with_untracked(|lines| {
+            peek_line_after_delta(line, delta, old_text, lines.buffer())
+        });
+        match new_line {
+            Ok(new_line) if new_line != line => {
+                self.reserved_lines.set(Some((new_line, count)));
+            },
+            Ok(_) => {},
+            Err(err) => error!("{err:?}"),
+        }
+    }
+
     fn update_snippet_offset(&self, delta: &RopeDelta) {
         if self.snippet.with_untracked(|s| s.is_some()) {
             self.snippet.update(|snippet| {
@@ -2954,13 +3113,19 @@ impl EditorData {
             return Ok(());
         }
 
-        let (normalize_line_endings, format_on_save) =
-            self.common.config.with_untracked(|config| {
-                (
-                    config.editor.normalize_line_endings,
-                    config.editor.format_on_save,
-                )
-            });
+        let (
+            normalize_line_endings,
+            format_on_save,
+            code_actions_on_save,
+            code_actions_on_save_timeout,
+        ) = self.common.config.with_untracked(|config| {
+            (
+                config.editor.normalize_line_endings,
+                config.editor.format_on_save,
+                config.editor.code_actions_on_save.clone(),
+                config.editor.code_actions_on_save_timeout,
+            )
+        });
 
         let DocContent::File { path, .. } = content else {
             return Ok(());
@@ -2978,8 +3143,192 @@ impl EditorData {
             error!("{:?}", err);
         }
 
-        let rev = doc.rev();
         let format_on_save = allow_formatting && format_on_save;
+        let code_actions_on_save = if allow_formatting {
+            code_actions_on_save
+                .into_iter()
+                .map(CodeActionKind::from)
+                .collect()
+        } else {
+            VecDeque::new()
+        };
+        let editor = self.clone();
+        let format_and_save = {
+            let path = path.clone();
+            Box::new(move || {
+                editor.format_and_save(path, format_on_save, after_action)
+            })
+        };
+        self.run_code_actions_on_save(
+            path,
+            code_actions_on_save,
+            Duration::from_millis(code_actions_on_save_timeout),
+            format_and_save,
+        );
+        Ok(())
+    }
+
+    /// Request the code actions of each kind in turn, applying the first
+    /// matching one before moving on to the next kind. A kind that does not
+    /// answer within `timeout` is skipped, so a slow language server can't
+    /// block the save.
+    fn run_code_actions_on_save(
+        &self,
+        path: PathBuf,
+        mut kinds: VecDeque<CodeActionKind>,
+        timeout: Duration,
+        then: Box<dyn FnOnce()>,
+    ) {
+        let Some(kind) = kinds.pop_front() else {
+            then();
+            return;
+        };
+        let doc = self.doc();
+        let rev = doc.rev();
+        let range = match doc
+            .lines
+            .with_untracked(|x| x.buffer().offset_to_position(x.buffer().len()))
+        {
+            Ok(end) => Range {
+                start: Position::new(0, 0),
+                end,
+            },
+            Err(err) => {
+                error!("{err:?}");
+                then();
+                return;
+            },
+        };
+
+        let next: Rc<RefCell<Option<Box<dyn FnOnce()>>>> = {
+            let editor = self.clone();
+            let path = path.clone();
+            Rc::new(RefCell::new(Some(Box::new(move || {
+                editor.run_code_actions_on_save(path, kinds, timeout, then)
+            }))))
+        };
+
+        {
+            let next = next.clone();
+            let common = self.common.clone();
+            let kind = kind.clone();
+            exec_after(timeout, move |_| {
+                if let Some(next) = next.borrow_mut().take() {
+                    common.show_status_message(format!(
+                        "code action {} on save timed out",
+                        kind.as_str()
+                    ));
+                    next();
+                }
+            });
+        }
+
+        let editor = self.clone();
+        let send = create_ext_action(self.scope, move |resp| {
+            if next.borrow().is_none() {
+                // timed out already
+                return;
+            }
+            if let Some((plugin_id, action)) = resp
+                && editor.doc().rev() == rev
+            {
+                editor.apply_code_action_on_save(plugin_id, action, next);
+            } else if let Some(next) = next.borrow_mut().take() {
+                next();
+            }
+        });
+        self.common.proxy.proxy_rpc.get_code_actions_of_kind(
+            path,
+            range,
+            vec![kind.clone()],
+            move |(_, result)| match result {
+                Ok(ProxyResponse::GetCodeActionsResponse { plugin_id, resp }) => {
+                    let action = select_code_action_of_kind(resp, &kind);
+                    send(action.map(|action| (plugin_id, action)));
+                },
+                Ok(_) => send(None),
+                Err(err) => {
+                    error!("{err:?}");
+                    send(None)
+                },
+            },
+        );
+    }
+
+    /// Apply the edit held by a code action, resolving it first if the server
+    /// left it out, then continue with `next` unless the timeout already did.
+    fn apply_code_action_on_save(
+        &self,
+        plugin_id: PluginId,
+        action: CodeAction,
+        next: Rc<RefCell<Option<Box<dyn FnOnce()>>>>,
+    ) {
+        if let Some(edit) = action.edit.as_ref() {
+            if let Some(next) = next.borrow_mut().take() {
+                self.apply_workspace_edit_on_save(edit);
+                next();
+            }
+            return;
+        }
+
+        let rev = self.doc().rev();
+        let editor = self.clone();
+        let send = create_ext_action(self.scope, move |edit| {
+            if let Some(next) = next.borrow_mut().take() {
+                if let Some(edit) = edit
+                    && editor.doc().rev() == rev
+                {
+                    editor.apply_workspace_edit_on_save(&edit);
+                }
+                next();
+            }
+        });
+        self.common.proxy.proxy_rpc.code_action_resolve(
+            action,
+            plugin_id,
+            move |(_, result)| match result {
+                Ok(ProxyResponse::CodeActionResolveResponse { item }) => {
+                    send(item.edit)
+                },
+                Ok(_) => send(None),
+                Err(err) => {
+                    error!("{err:?}");
+                    send(None)
+                },
+            },
+        );
+    }
+
+    /// Edits for the saved document are applied directly so they land before
+    /// the write, anything touching other files goes through the main split.
+    fn apply_workspace_edit_on_save(&self, edit: &WorkspaceEdit) {
+        let Some(path) = self.doc().content.get_untracked().path().cloned() else {
+            return;
+        };
+        let Some(mut edits) = workspace_edits(edit) else {
+            return;
+        };
+        if let Some(own_edits) = take_file_edits(&mut edits, &path)
+            && let Err(err) = self.do_text_edit(own_edits, true)
+        {
+            error!("{err}");
+        }
+        if !edits.is_empty() {
+            self.common
+                .internal_command
+                .send(InternalCommand::ApplyWorkspaceEdit {
+                    edit: WorkspaceEdit::new(edits),
+                });
+        }
+    }
+
+    fn format_and_save(
+        &self,
+        path: PathBuf,
+        format_on_save: bool,
+        after_action: impl FnOnce() + 'static,
+    ) {
+        let rev = self.doc().rev();
         if format_on_save {
             let editor = self.clone();
             let send = create_ext_action(self.scope, move |result| {
@@ -3004,7 +3353,6 @@ impl EditorData {
         } else {
             self.do_save(after_action);
         }
-        Ok(())
     }
 
     pub fn format(&self) {
@@ -3532,6 +3880,9 @@ impl EditorData {
                         },
                         ClickResult::MatchWithoutLocation
                         | ClickResult::MatchFolded => {},
+                        ClickResult::MatchBlame(line) => {
+                            self.open_blame_commit(line);
+                        },
                         ClickResult::MatchHint(location) => {
                             if control {
                                 let Ok(path) = location.uri.to_file_path() else {
@@ -3564,6 +3915,26 @@ impl EditorData {
         }
     }
 
+    /// Open the changes of the commit that last changed `line`.
+    pub fn open_blame_commit(&self, line: usize) {
+        let doc = self.doc();
+        let Some(path) = doc.content.with_untracked(|c| c.path().cloned()) else {
+            return;
+        };
+        let Some(commit) = doc
+            .blame
+            .with_untracked(|blame| blame.get(line).cloned().flatten())
+        else {
+            return;
+        };
+        self.common
+            .internal_command
+            .send(InternalCommand::OpenFileCommitChanges {
+                path,
+                commit: commit.id.clone(),
+            });
+    }
+
     fn result_of_left_click(&self, pos: Point) -> Option<ClickResult> {
         self.doc()
             .lines
@@ -4000,7 +4371,7 @@ impl EditorData {
         let doc = self.doc();
 
         if let Some(text) = self.find_most_serious_diag_by_offset(offset) {
-            let content = vec![MarkdownContent::Text(text)];
+            let content = vec![MarkdownContent::Text(text, Vec::new())];
             let hover_data = self.common.hover.clone();
             let editor_id = self.id();
             batch(|| {
@@ -4032,11 +4403,63 @@ impl EditorData {
     }
 
     fn get_hover_from_lsp(&self, offset: usize, path: PathBuf, position: Position) {
-        let config = self.common.config;
         let hover_data = self.common.hover.clone();
         let editor_id = self.id();
-        let directory = self.common.directory.clone();
         log::info!("get_hover_from_lsp position={position:?} {path:?}");
+        self.request_hover(path, position, move |content| {
+            batch(|| {
+                hover_data.content.set(content);
+                hover_data.offset.set(offset);
+                hover_data.editor_id.set(editor_id);
+                hover_data.active.set(true);
+            });
+        });
+    }
+
+    /// Refresh the pinned hover from the symbol at `offset`, once the cursor
+    /// has settled.
+    pub fn update_pinned_hover(&self, offset: usize) {
+        let pinned = self.common.hover.pinned.clone();
+        let editor = self.clone();
+        let token = exec_after(Duration::from_millis(300), move |token| {
+            if pinned.timer.get_untracked() != token {
+                return;
+            }
+            let doc = editor.doc();
+            let Some(path) = doc
+                .content
+                .with_untracked(|content| content.path().cloned())
+            else {
+                return;
+            };
+            let position = match doc
+                .lines
+                .with_untracked(|buffer| buffer.buffer().offset_to_position(offset))
+            {
+                Ok(rs) => rs,
+                Err(err) => {
+                    error!("{err:?}");
+                    return;
+                },
+            };
+            editor.request_hover(path, position, move |content| {
+                // Keep the last documentation when moving over whitespace
+                if !content.is_empty() {
+                    pinned.content.set(content);
+                }
+            });
+        });
+        self.common.hover.pinned.timer.set(token);
+    }
+
+    fn request_hover(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl FnOnce(Vec<MarkdownContent>) + 'static,
+    ) {
+        let config = self.common.config;
+        let directory = self.common.directory.clone();
         let send = create_ext_action(self.scope, move |resp| {
             if let Ok(ProxyResponse::HoverResponse { hover, .. }) = resp {
                 let (
@@ -4065,12 +4488,7 @@ impl EditorData {
                     markdown_blockquote,
                     editor_link,
                 );
-                batch(|| {
-                    hover_data.content.set(content);
-                    hover_data.offset.set(offset);
-                    hover_data.editor_id.set(editor_id);
-                    hover_data.active.set(true);
-                });
+                f(content);
             }
         });
         self.common
@@ -4897,6 +5315,45 @@ fn should_trigger_code_action_after_completion(
     None
 }
 
+/// Take the edits of the file at `path` out of `edits`.
+fn take_file_edits(
+    edits: &mut HashMap<lsp_types::Url, Vec<TextEdit>>,
+    path: &Path,
+) -> Option<Vec<TextEdit>> {
+    let url = edits
+        .keys()
+        .find(|url| url.to_file_path().ok().as_deref() == Some(path))?
+        .clone();
+    edits.remove(&url)
+}
+
+/// Pick the code action to run on save for `kind`: the preferred one if the
+/// server marks any, otherwise the first one of `kind` or a sub-kind of it.
+fn select_code_action_of_kind(
+    resp: CodeActionResponse,
+    kind: &CodeActionKind,
+) -> Option<CodeAction> {
+    let sub_kind_prefix = format!("{}.", kind.as_str());
+    let actions: Vec<CodeAction> = resp
+        .into_iter()
+        .filter_map(|action| match action {
+            CodeActionOrCommand::CodeAction(action)
+                if action.kind.as_ref().is_some_and(|x| {
+                    x == kind || x.as_str().starts_with(&sub_kind_prefix)
+                }) =>
+            {
+                Some(action)
+            },
+            _ => None,
+        })
+        .collect();
+    let index = actions
+        .iter()
+        .position(|x| x.is_preferred == Some(true))
+        .unwrap_or(0);
+    actions.into_iter().nth(index)
+}
+
 #[allow(clippy::too_many_arguments)]
 fn parse_hover_resp(
     hover: lsp_types::Hover,
@@ -4997,6 +5454,14 @@ fn file_menu(
             CommandKind::Workbench(LapceWorkbenchCommand::GoToImplementation)
                 .to_menu(lapce_command),
         )
+        .entry(
+            CommandKind::Workbench(LapceWorkbenchCommand::PeekDefinition)
+                .to_menu(lapce_command),
+        )
+        .entry(
+            CommandKind::Workbench(LapceWorkbenchCommand::PeekReferences)
+                .to_menu(lapce_command),
+        )
         .separator()
         .entry(CommandKind::Focus(FocusCommand::Rename).to_menu(lapce_command))
         .entry(
@@ -5010,6 +5475,12 @@ fn file_menu(
             )
             .to_menu(lapce_command),
         )
+        .entry(
+            CommandKind::Workbench(
+                LapceWorkbenchCommand::SourceControlCopyActiveFileRemoteUrl,
+            )
+            .to_menu(lapce_command),
+        )
         .entry(
             CommandKind::Workbench(LapceWorkbenchCommand::PaletteCommand)
                 .to_menu(lapce_command),
@@ -5084,6 +5555,12 @@ fn run_toml_menu(add_go_to: bool, lapce_command: Listener<LapceCommand>) -> Menu
         )
         .to_menu(lapce_command),
     );
+    menu = menu.entry(
+        CommandKind::Workbench(
+            LapceWorkbenchCommand::SourceControlCopyActiveFileRemoteUrl,
+        )
+        .to_menu(lapce_command),
+    );
     menu = menu.separator().entry(edit_menu(lapce_command));
     menu = menu.entry(
         CommandKind::Workbench(LapceWorkbenchCommand::AddRunDebugConfig)
@@ -5143,3 +5620,85 @@ fn edit_menu(lapce_command: Listener<LapceCommand>) -> Menu {
         .entry(CommandKind::Edit(EditCommand::ClipboardCopy).to_menu(lapce_command))
         .entry(CommandKind::Edit(EditCommand::ClipboardPaste).to_menu(lapce_command))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, path::Path};
+
+    use lsp_types::{
+        CodeAction, CodeActionKind, CodeActionOrCommand, Command, Position, Range,
+        TextEdit, Url,
+    };
+
+    use super::{select_code_action_of_kind, take_file_edits};
+
+    fn action(title: &str, kind: &str, is_preferred: bool) -> CodeActionOrCommand {
+        CodeActionOrCommand::CodeAction(CodeAction {
+            title: title.to_string(),
+            kind: Some(CodeActionKind::from(kind.to_string())),
+            is_preferred: Some(is_preferred),
+            ..Default::default()
+        })
+    }
+
+    fn selected(resp: Vec<CodeActionOrCommand>, kind: &str) -> Option<String> {
+        select_code_action_of_kind(resp, &CodeActionKind::from(kind.to_string()))
+            .map(|action| action.title)
+    }
+
+    #[test]
+    fn test_select_code_action_of_kind() {
+        let command = CodeActionOrCommand::Command(Command {
+            title:     "command".to_string(),
+            command:   "command".to_string(),
+            arguments: None,
+        });
+        let resp = vec![
+            command,
+            action("fix", "quickfix", true),
+            action("organize", "source.organizeImports", false),
+            action("fix all", "source.fixAll.eslint", false),
+            action("fix all preferred", "source.fixAll", true),
+        ];
+        assert_eq!(
+            selected(resp.clone(), "source.organizeImports").as_deref(),
+            Some("organize")
+        );
+        // sub-kinds match, the preferred action wins
+        assert_eq!(
+            selected(resp.clone(), "source.fixAll").as_deref(),
+            Some("fix all preferred")
+        );
+        assert_eq!(
+            selected(resp.clone(), "source").as_deref(),
+            Some("fix all preferred")
+        );
+        // a kind sharing a prefix isn't a sub-kind
+        assert_eq!(selected(resp.clone(), "source.fix"), None);
+        assert_eq!(selected(resp, "refactor"), None);
+    }
+
+    #[test]
+    fn test_take_file_edits() {
+        let edit = |text: &str| TextEdit {
+            range:    Range::new(Position::new(0, 0), Position::new(0, 0)),
+            new_text: text.to_string(),
+        };
+        let own = Url::parse("file:///ws/src/main.rs").unwrap();
+        let other = Url::parse("file:///ws/src/lib.rs").unwrap();
+        let mut edits = HashMap::from([
+            (own, vec![edit("use a;\n")]),
+            (other.clone(), vec![edit("use b;\n")]),
+        ]);
+
+        assert_eq!(
+            take_file_edits(&mut edits, Path::new("/ws/src/main.rs")),
+            Some(vec![edit("use a;\n")])
+        );
+        assert_eq!(edits.keys().collect::<Vec<_>>(), vec![&other]);
+        assert_eq!(
+            take_file_edits(&mut edits, Path::new("/ws/src/main.rs")),
+            None
+        );
+    }
+}
@@ -17,7 +17,7 @@ use doc::{
         layout::TextLayout,
         line::LineTy,
         line_ending::LineEnding,
-        screen_lines::{DiffSectionKind, ScreenLines},
+        screen_lines::{DiffSectionKind, ScreenLines, VisualLineInfo},
         selection::SelRegion,
         style::{
             CurrentLineColor, CursorSurroundingLines, DocumentHighlightColor,
@@ -50,23 +50,33 @@ use floem::{
     taffy::prelude::NodeId,
     text::{AttrsList, FamilyOwned},
     views::{
-        Decorators, container, dyn_stack, empty, label,
+        Decorators, clip, container, dyn_container, dyn_stack, empty, label,
         scroll::{PropagatePointerWheel, scroll},
         stack, svg, text_input,
     },
 };
 use lapce_core::{doc::DocContent, icon::LapceIcons, workspace::LapceWorkspace};
+use lapce_rpc::source_control::StageSelection;
 use lapce_xi_rope::find::CaseMatching;
 use log::error;
 
 use super::{DocSignal, EditorData, floem_editor::get_selection};
 use crate::{
+    alert::AlertButton,
     app::clickable_icon,
     command::InternalCommand,
     common_svg,
     config::{LapceConfig, WithLapceConfig, color::LapceColor, editor::WrapStyle},
-    editor::{floem_editor::paint_text, gutter_new::view::editor_gutter_new},
+    editor::{
+        floem_editor::paint_text,
+        gutter_new::view::{
+            editor_gutter_blame, editor_gutter_changes, editor_gutter_new,
+        },
+        hunk::{ChangeHunk, ChangeKind},
+        merge::{ConflictResolution, MergeEditorData},
+    },
     keypress::KeyPressFocus,
+    peek::PEEK_LINES,
     window_workspace::{CommonData, Focus, WindowWorkspaceData},
 };
 
@@ -1169,9 +1179,13 @@ pub fn editor_container_view(
     stack((
         editor_breadcrumbs(workspace, editor.clone(), config),
         stack((
+            editor_gutter_blame(editor.clone()),
             editor_gutter_new(window_tab_data.clone(), editor.clone()),
+            editor_gutter_changes(editor.clone()),
             editor_gutter_folding_range(window_tab_data.clone(), editor.clone()),
             editor_content(editor.clone(), debug_breakline, is_active),
+            hunk_peek_view(editor.clone()),
+            peek_view(window_tab_data.clone(), editor.clone()),
             empty().style(move |s| {
                 let sticky_header = config
                     .signal(|config| config.editor.sticky_header.signal())
@@ -1824,6 +1838,504 @@ pub fn editor_diff_header(
     view.style(|x| x.height(30.))
 }
 
+/// The old text of the change peeked at from the gutter, shown under its
+/// lines with the actions on it.
+fn hunk_peek_view(editor: EditorData) -> impl View {
+    let config = editor.common.config;
+    let line_height = editor.common.ui_line_height;
+    let peek = {
+        let editor = editor.clone();
+        editor.scope.create_memo(move |_| editor.hunk_peek())
+    };
+    // the top of the line under the change, or of the line a removal is
+    // marked on
+    let top = {
+        let editor = editor.clone();
+        editor.scope.create_memo(move |_| {
+            let (hunk, _) = peek.get()?;
+            let (line, below) = if hunk.new.is_empty() {
+                (hunk.new.start, false)
+            } else {
+                (hunk.new.end - 1, true)
+            };
+            editor.screen_lines.with(|screen_lines| {
+                screen_lines.visual_lines.iter().find_map(|vl_info| {
+                    let VisualLineInfo::OriginText { text } = vl_info else {
+                        return None;
+                    };
+                    (text.folded_line.origin_line_start == line).then(|| {
+                        text.folded_line_y
+                            + if below { line_height.get() } else { 0.0 }
+                    })
+                })
+            })
+        })
+    };
+
+    let can_apply = editor.clone();
+    let action = move |text: &'static str, on_click: Box<dyn Fn()>| {
+        let can_apply = can_apply.clone();
+        label(move || text.to_string())
+            .on_click_stop(move |_| on_click())
+            .style(move |s| {
+                s.apply_if(!can_apply.can_apply_change_hunks(), |s| s.hide())
+                    .margin_left(6.0)
+                    .padding_horiz(8.0)
+                    .border(1.0)
+                    .border_radius(4.0)
+                    .border_color(config.with_color(LapceColor::LAPCE_BORDER))
+                    .selectable(false)
+                    .hover(|s| {
+                        s.cursor(CursorStyle::Pointer).background(
+                            config.with_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        )
+                    })
+            })
+    };
+    let on_hunk = |editor: &EditorData, f: fn(&EditorData, &ChangeHunk)| {
+        let editor = editor.clone();
+        Box::new(move || {
+            if let Some((hunk, _)) = peek.get_untracked() {
+                f(&editor, &hunk);
+            }
+        }) as Box<dyn Fn()>
+    };
+    let (previous, next, close) = (editor.clone(), editor.clone(), editor.clone());
+
+    stack((
+        stack((
+            label(move || match peek.get().map(|(hunk, _)| hunk.kind()) {
+                Some(ChangeKind::Added) => "Added lines".to_string(),
+                Some(ChangeKind::Removed) => "Removed lines".to_string(),
+                _ => "Changed lines".to_string(),
+            })
+            .style(|s| s.padding_horiz(10.0).flex_grow(1.0).selectable(false)),
+            common_svg(config, None, LapceIcons::FOLD_UP)
+                .on_click_stop(move |_| previous.go_to_change_hunk(true))
+                .style(|s| s.padding_horiz(5.0)),
+            common_svg(config, None, LapceIcons::FOLD_DOWN)
+                .on_click_stop(move |_| next.go_to_change_hunk(false))
+                .style(|s| s.padding_horiz(5.0)),
+            action("Revert", on_hunk(&editor, EditorData::revert_change_hunk)),
+            action("Stage", on_hunk(&editor, EditorData::stage_change_hunk)),
+            common_svg(config, None, LapceIcons::CLOSE)
+                .on_click_stop(move |_| close.hunk_peek.set(None))
+                .style(|s| s.padding_horiz(5.0)),
+        ))
+        .style(move |s| {
+            s.items_center()
+                .width_full()
+                .height(line_height.get() + 6.0)
+                .border_bottom(1.0)
+                .border_color(config.with_color(LapceColor::LAPCE_BORDER))
+        }),
+        scroll(
+            label(move || {
+                peek.get()
+                    .map(|(_, text)| text.trim_end_matches('\n').to_string())
+                    .unwrap_or_default()
+            })
+            .style(move |s| {
+                let (font_family, font_size) = config.signal(|config| {
+                    (
+                        config.editor.font_family.signal(),
+                        config.editor.font_size.signal(),
+                    )
+                });
+                s.padding_horiz(10.0)
+                    .font_family(font_family.get().1)
+                    .font_size(font_size.get() as f32)
+                    .line_height(line_height.get() as f32 / font_size.get() as f32)
+            }),
+        )
+        .style(move |s| {
+            s.width_full()
+                .max_height(line_height.get() * 10.0)
+                .background(
+                    config
+                        .with_color(LapceColor::SOURCE_CONTROL_REMOVED)
+                        .multiply_alpha(0.1),
+                )
+                .apply_if(
+                    peek.with(|peek| {
+                        peek.as_ref().is_none_or(|(hunk, _)| hunk.old.is_empty())
+                    }),
+                    |s| s.hide(),
+                )
+        }),
+    ))
+    .style(move |s| {
+        let top = top.get();
+        s.absolute()
+            .flex_col()
+            .inset_top(top.unwrap_or_default())
+            .width_full()
+            .border_top(1.0)
+            .border_bottom(1.0)
+            .border_color(config.with_color(LapceColor::LAPCE_BORDER))
+            .background(config.with_color(LapceColor::EDITOR_BACKGROUND))
+            .apply_if(top.is_none(), |s| s.hide())
+    })
+    .debug_name("Hunk Peek")
+}
+
+/// The peek opened from `editor`, drawn over the lines kept blank for it under
+/// the line it was opened from.
+fn peek_view(window_tab_data: WindowWorkspaceData, editor: EditorData) -> impl View {
+    let source = window_tab_data.peek_data.source;
+    let editor_id = editor.id();
+    let shown = editor
+        .scope
+        .create_memo(move |_| source.get() == Some(editor_id));
+    let screen_lines = editor.screen_lines;
+    let line_height = editor
+        .scope
+        .create_memo(move |_| screen_lines.with(|x| x.line_height));
+    let reserved_lines = editor.reserved_lines;
+    let top = editor.scope.create_memo(move |_| {
+        if !shown.get() {
+            return None;
+        }
+        let (line, _) = reserved_lines.get()?;
+        let index = editor.visual_lines.with(|visual_lines| {
+            visual_lines.iter().find_map(|visual_line| {
+                let LineTy::OriginText {
+                    line_range_inclusive,
+                    ..
+                } = &visual_line.line_ty
+                else {
+                    return None;
+                };
+                line_range_inclusive
+                    .contains(&line)
+                    .then_some(visual_line.line_index)
+            })
+        })?;
+        Some((index + 1) as f64 * line_height.get() - editor.viewport.get().y0)
+    });
+
+    clip(
+        dyn_container(
+            move || shown.get(),
+            move |shown| {
+                if shown {
+                    peek_content(window_tab_data.clone()).into_any()
+                } else {
+                    empty().into_any()
+                }
+            },
+        )
+        .style(move |s| {
+            s.absolute()
+                .inset_top(top.get().unwrap_or_default())
+                .width_full()
+                .height(line_height.get() * PEEK_LINES as f64)
+        }),
+    )
+    .style(move |s| {
+        s.absolute()
+            .size_full()
+            .apply_if(top.get().is_none(), |s| s.hide())
+    })
+    .debug_name("Peek")
+}
+
+fn peek_content(window_tab_data: WindowWorkspaceData) -> impl View {
+    let peek_data = window_tab_data.peek_data.clone();
+    let workspace = window_tab_data.workspace.clone();
+    let config = window_tab_data.common.config;
+    let focus = window_tab_data.common.focus;
+    let (title, items, index) = (peek_data.title, peek_data.items, peek_data.index);
+    let workspace_path = workspace.path().cloned();
+    let open_data = peek_data.clone();
+    let close_data = peek_data.clone();
+    let select_data = peek_data.clone();
+    let (previous_data, next_data) = (peek_data.clone(), peek_data.clone());
+
+    let header = stack((
+        label(move || {
+            let len = items.with(|x| x.len());
+            format!("{} ({}/{len})", title.get(), index.get() + 1)
+        })
+        .style(|s| s.flex_grow(1.0).text_ellipsis().min_width(0.0)),
+        common_svg(config, None, LapceIcons::FOLD_UP)
+            .on_click_stop(move |_| previous_data.previous())
+            .style(|s| s.padding_horiz(5.0)),
+        common_svg(config, None, LapceIcons::FOLD_DOWN)
+            .on_click_stop(move |_| next_data.next())
+            .style(|s| s.padding_horiz(5.0)),
+        peek_button("Open", config, move || open_data.open_in_editor()),
+        peek_button("Close", config, move || close_data.close()),
+    ))
+    .style(move |s| {
+        s.items_center()
+            .width_pct(100.0)
+            .padding_horiz(10.0)
+            .gap(6.0)
+            .border_bottom(1.0)
+            .border_color(config.with_color(LapceColor::LAPCE_BORDER))
+            .background(config.with_color(LapceColor::PANEL_BACKGROUND))
+    });
+    let results =
+        scroll(
+            dyn_stack(
+                move || items.get().into_iter().enumerate(),
+                |(i, item)| (*i, item.path.clone(), item.position.line),
+                move |(i, item)| {
+                    let file_name = item
+                        .path
+                        .file_name()
+                        .map(|x| x.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let folder = workspace_path
+                        .as_ref()
+                        .and_then(|x| item.path.parent()?.strip_prefix(x).ok())
+                        .map(|x| x.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let line = item.position.line + 1;
+                    let content = item.content.trim().to_string();
+                    let select_data = select_data.clone();
+                    stack((
+                        stack((
+                            label(move || format!("{file_name}:{line}")),
+                            label(move || folder.clone()).style(move |s| {
+                                s.margin_left(6.0)
+                                    .text_ellipsis()
+                                    .min_width(0.0)
+                                    .color(config.with_color(LapceColor::EDITOR_DIM))
+                            }),
+                        ))
+                        .style(|s| s.items_center()),
+                        label(move || content.clone()).style(move |s| {
+                            s.text_ellipsis()
+                                .min_width(0.0)
+                                .color(config.with_color(LapceColor::EDITOR_DIM))
+                        }),
+                    ))
+                    .on_click_stop(move |_| select_data.select(i))
+                    .style(move |s| {
+                        s.flex_col()
+                            .width_pct(100.0)
+                            .padding_horiz(10.0)
+                            .padding_vert(2.0)
+                            .cursor(CursorStyle::Pointer)
+                            .apply_if(index.get() == i, |s| {
+                                s.background(config.with_color(
+                                    LapceColor::PANEL_CURRENT_BACKGROUND,
+                                ))
+                            })
+                            .hover(|s| {
+                                s.background(config.with_color(
+                                    LapceColor::PANEL_HOVERED_BACKGROUND,
+                                ))
+                            })
+                    })
+                },
+            )
+            .style(|s| s.flex_col().width_pct(100.0)),
+        )
+        .style(move |s| {
+            s.width(250.0)
+                .height_pct(100.0)
+                .border_left(1.0)
+                .border_color(config.with_color(LapceColor::LAPCE_BORDER))
+                .background(config.with_color(LapceColor::PANEL_BACKGROUND))
+        });
+
+    stack((
+        header,
+        stack((
+            container(editor_container_view(
+                window_tab_data.clone(),
+                workspace,
+                move |track| {
+                    if track {
+                        focus.get() == Focus::Peek
+                    } else {
+                        focus.get_untracked() == Focus::Peek
+                    }
+                },
+                peek_data.editor.clone(),
+            ))
+            .style(|s| s.flex_grow(1.0).height_pct(100.0).min_width(0.0)),
+            results,
+        ))
+        .style(|s| s.flex_grow(1.0).min_height(0.0).width_pct(100.0)),
+    ))
+    // clicking into the embedded editor moves the focus to it
+    .on_event_stop(EventListener::PointerDown, move |_| {
+        focus.set(Focus::Peek);
+    })
+    .on_event_stop(EventListener::PointerMove, |_| {})
+    .style(move |s| {
+        s.flex_col()
+            .size_full()
+            .border_top(1.0)
+            .border_bottom(1.0)
+            .border_color(config.with_color(LapceColor::LAPCE_BORDER))
+            .background(config.with_color(LapceColor::EDITOR_BACKGROUND))
+            .set(PropagatePointerWheel, false)
+    })
+}
+
+fn peek_button(
+    text: &'static str,
+    config: WithLapceConfig,
+    on_click: impl Fn() + 'static,
+) -> impl View {
+    label(move || text.to_string())
+        .on_click_stop(move |_| on_click())
+        .style(move |s| {
+            s.padding_horiz(6.0)
+                .border_radius(6.0)
+                .hover(|s| {
+                    s.cursor(CursorStyle::Pointer).background(
+                        config.with_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                    )
+                })
+                .selectable(false)
+        })
+}
+
+/// The conflict actions of a merge editor, applying to one conflict of the
+/// working copy file at a time.
+pub fn merge_editor_header(
+    window_tab_data: WindowWorkspaceData,
+    merge: RwSignal<Option<MergeEditorData>>,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    dyn_container(
+        move || merge.get(),
+        move |merge| {
+            let Some(merge) = merge else {
+                return empty().into_any();
+            };
+            let action = |text: &'static str, on_click: Box<dyn Fn()>| {
+                label(move || text.to_string())
+                    .on_click_stop(move |_| on_click())
+                    .style(move |s| {
+                        s.margin_left(6.0)
+                            .padding_horiz(8.0)
+                            .border(1.0)
+                            .border_radius(4.0)
+                            .border_color(
+                                config.with_color(LapceColor::LAPCE_BORDER),
+                            )
+                            .selectable(false)
+                            .hover(|s| {
+                                s.cursor(CursorStyle::Pointer).background(
+                                    config.with_color(
+                                        LapceColor::PANEL_HOVERED_BACKGROUND,
+                                    ),
+                                )
+                            })
+                    })
+            };
+            let accept = |resolution: ConflictResolution| -> Box<dyn Fn()> {
+                let merge = merge.clone();
+                Box::new(move || {
+                    merge.accept(resolution);
+                    merge.reveal_current();
+                })
+            };
+            // the result editor below the versions takes the focus
+            let edit_result: Box<dyn Fn()> = {
+                let merge = merge.clone();
+                Box::new(move || {
+                    merge.focus_result.set(true);
+                    merge.reveal_current();
+                })
+            };
+            let mark_resolved: Box<dyn Fn()> = {
+                let merge = merge.clone();
+                let source_control = window_tab_data.source_control.clone();
+                let internal_command = window_tab_data.common.internal_command;
+                Box::new(move || {
+                    let resolve = {
+                        let result = merge.result.clone();
+                        let path = merge.path.clone();
+                        let source_control = source_control.clone();
+                        move || {
+                            let stage = {
+                                let path = path.clone();
+                                let source_control = source_control.clone();
+                                move || {
+                                    source_control
+                                        .stage(StageSelection::Files(vec![path]))
+                                }
+                            };
+                            // the file is staged as it is on disk
+                            if result.is_pristine() {
+                                stage();
+                            } else {
+                                result.save(stage);
+                            }
+                        }
+                    };
+                    let left = merge.conflicts.with_untracked(|x| x.len());
+                    if left == 0 {
+                        resolve();
+                        return;
+                    }
+                    internal_command.send(InternalCommand::ShowAlert {
+                        title:   format!(
+                            "{} still has {left} conflict(s)",
+                            merge
+                                .path
+                                .file_name()
+                                .unwrap_or_default()
+                                .to_string_lossy()
+                        ),
+                        msg:     "Its conflict markers will be committed as they \
+                                  are."
+                            .to_string(),
+                        buttons: vec![AlertButton {
+                            text:   "Mark Resolved".to_string(),
+                            action: Rc::new(move || {
+                                internal_command.send(InternalCommand::HideAlert);
+                                resolve();
+                            }),
+                        }],
+                    });
+                })
+            };
+            let position = {
+                let merge = merge.clone();
+                move || {
+                    let len = merge.conflicts.with(|x| x.len());
+                    match merge.current_conflict() {
+                        Some((index, conflict)) => format!(
+                            "Conflict {} of {len}, line {}",
+                            index + 1,
+                            conflict.start + 1
+                        ),
+                        None => "No conflicts left".to_string(),
+                    }
+                }
+            };
+            let previous = merge.clone();
+            let next = merge.clone();
+
+            h_stack((
+                label(position).style(|s| s.padding_horiz(10.0).selectable(false)),
+                common_svg(config, None, LapceIcons::FOLD_UP)
+                    .on_click_stop(move |_| previous.select(true))
+                    .style(|s| s.padding_horiz(5.0)),
+                common_svg(config, None, LapceIcons::FOLD_DOWN)
+                    .on_click_stop(move |_| next.select(false))
+                    .style(|s| s.padding_horiz(5.0)),
+                action("Accept Ours", accept(ConflictResolution::Ours)),
+                action("Accept Theirs", accept(ConflictResolution::Theirs)),
+                action("Accept Both", accept(ConflictResolution::Both)),
+                action("Edit Result", edit_result),
+                action("Mark Resolved", mark_resolved),
+            ))
+            .style(|s| s.items_center().height(30.0))
+            .into_any()
+        },
+    )
+}
+
 fn editor_content(
     editor: EditorData,
     debug_breakline: Memo<Option<(usize, PathBuf)>>,
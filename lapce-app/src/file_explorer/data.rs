@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
     rc::Rc,
 };
@@ -47,13 +47,17 @@ enum RenamedPath {
 
 #[derive(Clone, Debug)]
 pub struct FileExplorerData {
-    pub root:           RwSignal<FileNodeItem>,
-    pub naming:         RwSignal<Naming>,
-    pub common:         Rc<CommonData>,
-    pub naming_str:     RwSignal<String>,
-    pub scroll_to_line: RwSignal<Option<f64>>,
-    left_diff_path:     RwSignal<Option<PathBuf>>,
-    pub select:         RwSignal<Option<FileNodeViewKind>>,
+    pub root:              RwSignal<FileNodeItem>,
+    pub naming:            RwSignal<Naming>,
+    pub common:            Rc<CommonData>,
+    pub naming_str:        RwSignal<String>,
+    pub scroll_to_line:    RwSignal<Option<f64>>,
+    left_diff_path:        RwSignal<Option<PathBuf>>,
+    pub select:            RwSignal<Option<FileNodeViewKind>>,
+    /// Whether only the files changed in git are listed
+    pub only_changed:      RwSignal<bool>,
+    /// The folders collapsed while only the changed files are listed
+    pub changed_collapsed: RwSignal<HashSet<PathBuf>>,
 }
 
 impl KeyPressFocus for FileExplorerData {
@@ -123,6 +127,7 @@ impl FileExplorerData {
             open:                false,
             children:            HashMap::new(),
             children_open_count: 0,
+            ignored:             false,
         });
         let naming = cx.create_rw_signal(Naming::None);
         let naming_str = cx.create_rw_signal(String::new());
@@ -135,6 +140,8 @@ impl FileExplorerData {
             left_diff_path: cx.create_rw_signal(None),
             select: cx.create_rw_signal(None),
             naming_str,
+            only_changed: cx.create_rw_signal(false),
+            changed_collapsed: cx.create_rw_signal(HashSet::new()),
         };
         if data.common.workspace.path().is_some() {
             // only fill in the child files if there is open folder
@@ -153,6 +160,15 @@ impl FileExplorerData {
     /// Toggle whether the directory is expanded or not.  
     /// Does nothing if the path does not exist or is not a directory.
     pub fn toggle_expand(&self, path: &Path) {
+        if self.only_changed.get_untracked() {
+            self.changed_collapsed.update(|collapsed| {
+                if !collapsed.remove(path) {
+                    collapsed.insert(path.to_path_buf());
+                }
+            });
+            return;
+        }
+
         let Some(read) = self
             .root
             .try_update(|root| {
@@ -265,9 +281,16 @@ impl FileExplorerData {
     /// Returns `true` if `path` exists in the file explorer tree and is a
     /// directory, `false` otherwise.
     fn is_dir(&self, path: &Path) -> bool {
-        self.root.with_untracked(|root| {
-            root.get_file_node(path).is_some_and(|node| node.is_dir)
-        })
+        // Folders listed as changed may not have been read yet
+        self.root
+            .with_untracked(|root| root.get_file_node(path).map(|node| node.is_dir))
+            .unwrap_or_else(|| path.is_dir())
+    }
+
+    /// List only the files changed in git, or every file again.
+    pub fn toggle_only_changed(&self) {
+        self.only_changed
+            .update(|only_changed| *only_changed = !*only_changed);
     }
 
     /// The current path that we're renaming to / creating or duplicating a node
@@ -420,6 +443,10 @@ impl FileExplorerData {
     }
 
     pub fn reveal_in_file_tree(&self, path: PathBuf) {
+        // The file is revealed in the tree of every file
+        if self.only_changed.get_untracked() {
+            self.only_changed.set(false);
+        }
         let done = self
             .root
             .try_update(|root| {
@@ -1,3 +1,8 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
 use floem::views::VirtualVector;
 use lapce_rpc::file::{FileNodeItem, FileNodeViewData, Naming};
 
@@ -42,3 +47,50 @@ impl VirtualVector<FileNodeViewData> for FileNodeVirtualList {
         view_items.into_iter()
     }
 }
+
+/// The tree of the changed files under `root`, with the folders open unless
+/// they were collapsed. `paths` lists the folders of the files too.
+pub fn changed_files_tree<'a>(
+    root: &Path,
+    paths: impl IntoIterator<Item = &'a PathBuf>,
+    collapsed: &HashSet<PathBuf>,
+) -> FileNodeItem {
+    let node = |path: &Path| FileNodeItem {
+        path:                path.to_path_buf(),
+        is_dir:              false,
+        read:                true,
+        open:                !collapsed.contains(path),
+        children:            HashMap::new(),
+        children_open_count: 0,
+        ignored:             false,
+    };
+    let mut tree = node(root);
+    tree.is_dir = true;
+
+    let mut paths: Vec<&PathBuf> = paths
+        .into_iter()
+        .filter(|path| path.starts_with(root) && path.as_path() != root)
+        .collect();
+    // Parents come before their children
+    paths.sort_by_key(|path| path.components().count());
+    for path in paths {
+        let Some(parent) = path.parent().and_then(|p| tree.get_file_node_mut(p))
+        else {
+            continue;
+        };
+        parent.is_dir = true;
+        parent.children.insert(path.clone(), node(path));
+    }
+
+    fn count(node: &mut FileNodeItem) -> usize {
+        let open_count = node
+            .children
+            .values_mut()
+            .map(|child| count(child) + 1)
+            .sum();
+        node.children_open_count = if node.open { open_count } else { 0 };
+        node.children_open_count
+    }
+    count(&mut tree);
+    tree
+}
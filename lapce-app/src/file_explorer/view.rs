@@ -21,12 +21,12 @@ use lapce_core::{
     icon::LapceIcons,
     panel::{PanelContainerPosition, PanelSection},
 };
-use lapce_rpc::{
-    file::{FileNodeViewData, FileNodeViewKind, Naming},
-    source_control::FileDiffKind,
-};
+use lapce_rpc::file::{FileNodeViewData, FileNodeViewKind, Naming};
 
-use super::{data::FileExplorerData, node::FileNodeVirtualList};
+use super::{
+    data::FileExplorerData,
+    node::{FileNodeVirtualList, changed_files_tree},
+};
 use crate::{
     app::clickable_icon,
     command::InternalCommand,
@@ -91,8 +91,8 @@ pub fn file_explorer_panel(
                 )
             },
         )
-        .add(
-            "File Explorer",
+        .add_with_header(
+            file_explorer_header(data.clone()),
             container(file_explorer_view(data, source_control))
                 .style(|s| s.size_full()),
             window_tab_data
@@ -103,6 +103,30 @@ pub fn file_explorer_panel(
         .debug_name("File Explorer Panel")
 }
 
+fn file_explorer_header(data: FileExplorerData) -> impl View {
+    let config = data.common.config;
+    let only_changed = data.only_changed;
+    stack((
+        label(|| "File Explorer".to_string())
+            .style(|s| s.flex_grow(1.0).selectable(false)),
+        clickable_icon(
+            || LapceIcons::SCM,
+            move || data.toggle_only_changed(),
+            move || only_changed.get(),
+            || false,
+            move || {
+                if only_changed.get() {
+                    "Show All Files"
+                } else {
+                    "Show Only Changed Files"
+                }
+            },
+            config,
+        ),
+    ))
+    .style(|s| s.flex_grow(1.0).items_center())
+}
+
 /// Initialize the file explorer's naming (renaming, creating, etc.) editor with
 /// the given path.
 fn initialize_naming_editor_with_path(data: &FileExplorerData, path: &Path) {
@@ -142,33 +166,52 @@ fn file_node_text_color(
     node: FileNodeViewData,
     source_control: SourceControlData,
 ) -> Color {
-    let diff = source_control.file_diffs.with(|file_diffs| {
-        let FileNodeViewKind::Path(path) = &node.kind else {
-            return None;
-        };
-
-        if node.is_dir {
-            file_diffs
-                .keys()
-                .find(|p| p.as_path().starts_with(path))
-                .map(|_| FileDiffKind::Modified)
-        } else {
-            file_diffs.get(path).map(|(diff, _)| diff.kind())
-        }
-    });
-
-    let color = match diff {
-        Some(FileDiffKind::Modified | FileDiffKind::Renamed) => {
-            LapceColor::SOURCE_CONTROL_MODIFIED
-        },
-        Some(FileDiffKind::Added) => LapceColor::SOURCE_CONTROL_ADDED,
-        Some(FileDiffKind::Deleted) => LapceColor::SOURCE_CONTROL_REMOVED,
+    let status = match &node.kind {
+        FileNodeViewKind::Path(path) => source_control
+            .file_statuses
+            .with(|statuses| statuses.get(path).copied()),
+        _ => None,
+    };
+    let color = match status {
+        Some(status) => status.color(),
+        None if node.ignored => LapceColor::PANEL_FOREGROUND_DIM,
         None => LapceColor::PANEL_FOREGROUND,
     };
 
     config.with_color(color)
 }
 
+/// The letter of the git state of a file, or a dot for a folder with changes.
+fn file_node_status_view(
+    config: WithLapceConfig,
+    node: &FileNodeViewData,
+    source_control: SourceControlData,
+) -> impl View {
+    let path = node.kind.path().map(Path::to_path_buf);
+    let is_dir = node.is_dir;
+    let status = move || {
+        let path = path.as_ref()?;
+        source_control
+            .file_statuses
+            .with(|statuses| statuses.get(path).copied())
+    };
+    label(move || match status() {
+        Some(_) if is_dir => "\u{2022}".to_string(),
+        Some(status) => status.badge().to_string(),
+        None => String::new(),
+    })
+    .style(move |s| {
+        let status = status();
+        s.apply_opt(status, |s, status| {
+            s.color(config.with_color(status.color()))
+        })
+        .apply_if(status.is_none(), |s| s.hide())
+        .flex_shrink(0.0)
+        .padding_left(6.0)
+        .selectable(false)
+    })
+}
+
 fn file_node_text_view(
     data: FileExplorerData,
     node: FileNodeViewData,
@@ -332,6 +375,8 @@ fn file_explorer_view(
     let naming = data.naming;
     let scroll_to_line = data.scroll_to_line;
     let select = data.select;
+    let only_changed = data.only_changed;
+    let changed_collapsed = data.changed_collapsed;
     let secondary_click_data = data.clone();
     let scroll_rect = create_rw_signal(Rect::ZERO);
 
@@ -339,7 +384,24 @@ fn file_explorer_view(
         virtual_stack(
             // VirtualDirection::Vertical,
             // VirtualItemSize::Fixed(Box::new(move || ui_line_height.get())),
-            move || FileNodeVirtualList::new(root.get(), data.naming.get()),
+            move || {
+                let naming = data.naming.get();
+                if only_changed.get() {
+                    let root_path = root.with(|root| root.path.clone());
+                    let tree = changed_collapsed.with(|collapsed| {
+                        source_control.file_statuses.with(|statuses| {
+                            changed_files_tree(
+                                &root_path,
+                                statuses.keys(),
+                                collapsed,
+                            )
+                        })
+                    });
+                    FileNodeVirtualList::new(tree, naming)
+                } else {
+                    FileNodeVirtualList::new(root.get(), naming)
+                }
+            },
             move |node| (node.kind.clone(), node.is_dir, node.open, node.level),
             move |node| {
                 let level = node.level;
@@ -426,7 +488,9 @@ fn file_explorer_view(
                                 })
                         })
                     },
-                    file_node_text_view(data, node, source_control.clone()),
+                    file_node_text_view(data, node.clone(), source_control.clone())
+                        .style(|s| s.flex_grow(1.0)),
+                    file_node_status_view(config, &node, source_control.clone()),
                 ))
                 .style({
                     let kind = kind.clone();
@@ -0,0 +1,157 @@
+use lapce_rpc::source_control::LogEntry;
+
+/// How the row of a commit is drawn in the graph of the log. Lanes are the
+/// columns the lines between commits run down in.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct GraphRow {
+    /// The lane of the commit's dot
+    pub lane:   usize,
+    /// The lines of the top half of the row, from a lane at its top edge to
+    /// a lane at its middle
+    pub top:    Vec<(usize, usize)>,
+    /// The lines of the bottom half of the row, from a lane at its middle to
+    /// a lane at its bottom edge
+    pub bottom: Vec<(usize, usize)>,
+    /// How many lanes the row spans
+    pub lanes:  usize,
+}
+
+/// Lay out the graph of `entries`, which are sorted so that children come
+/// before their parents. A commit continues the lane of its first child, and
+/// a branch or merge parent opens a new lane until the parent is reached.
+/// Rows only depend on the entries before them, so appending a page keeps
+/// the rows already laid out.
+pub fn graph_rows<'a>(
+    entries: impl IntoIterator<Item = &'a LogEntry>,
+) -> Vec<GraphRow> {
+    // the commit each lane runs down to
+    let mut lanes: Vec<Option<&str>> = Vec::new();
+    let mut rows = Vec::new();
+    for entry in entries {
+        let id = entry.commit.id.as_str();
+        let lane = match lanes.iter().position(|waiting| *waiting == Some(id)) {
+            Some(lane) => lane,
+            None => free_lane(&mut lanes),
+        };
+
+        let mut row = GraphRow {
+            lane,
+            ..Default::default()
+        };
+        for (index, waiting) in lanes.iter_mut().enumerate() {
+            match *waiting {
+                Some(waiting_id) if waiting_id == id => {
+                    row.top.push((index, lane));
+                    *waiting = None;
+                },
+                Some(_) => {
+                    row.top.push((index, index));
+                    row.bottom.push((index, index));
+                },
+                None => {},
+            }
+        }
+
+        for (index, parent) in entry.parents.iter().enumerate() {
+            let parent = parent.as_str();
+            let target =
+                match lanes.iter().position(|waiting| *waiting == Some(parent)) {
+                    Some(target) => target,
+                    None if index == 0 => {
+                        lanes[lane] = Some(parent);
+                        lane
+                    },
+                    None => {
+                        let target = free_lane(&mut lanes);
+                        lanes[target] = Some(parent);
+                        target
+                    },
+                };
+            row.bottom.push((lane, target));
+        }
+
+        while lanes.last() == Some(&None) {
+            lanes.pop();
+        }
+        row.lanes = row
+            .top
+            .iter()
+            .chain(row.bottom.iter())
+            .map(|(from, to)| (*from).max(*to) + 1)
+            .max()
+            .unwrap_or(0)
+            .max(lane + 1);
+        rows.push(row);
+    }
+    rows
+}
+
+fn free_lane(lanes: &mut Vec<Option<&str>>) -> usize {
+    match lanes.iter().position(Option::is_none) {
+        Some(lane) => lane,
+        None => {
+            lanes.push(None);
+            lanes.len() - 1
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_rpc::source_control::{CommitInfo, LogEntry};
+
+    use super::{GraphRow, graph_rows};
+
+    fn entry(id: &str, parents: &[&str]) -> LogEntry {
+        LogEntry {
+            commit:  CommitInfo {
+                id:      id.to_string(),
+                author:  String::new(),
+                time:    0,
+                summary: String::new(),
+            },
+            parents: parents.iter().map(|parent| parent.to_string()).collect(),
+            refs:    Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_graph_rows_merge() {
+        // m merges b into a, and c is the root both branch from
+        let entries = [
+            entry("m", &["a", "b"]),
+            entry("b", &["c"]),
+            entry("a", &["c"]),
+            entry("c", &[]),
+        ];
+        assert_eq!(
+            graph_rows(&entries),
+            vec![
+                GraphRow {
+                    lane:   0,
+                    top:    vec![],
+                    bottom: vec![(0, 0), (0, 1)],
+                    lanes:  2,
+                },
+                GraphRow {
+                    lane:   1,
+                    top:    vec![(0, 0), (1, 1)],
+                    bottom: vec![(0, 0), (1, 1)],
+                    lanes:  2,
+                },
+                GraphRow {
+                    lane:   0,
+                    top:    vec![(0, 0), (1, 1)],
+                    bottom: vec![(1, 1), (0, 1)],
+                    lanes:  2,
+                },
+                GraphRow {
+                    lane:   1,
+                    top:    vec![(1, 1)],
+                    bottom: vec![],
+                    lanes:  2,
+                },
+            ]
+        );
+    }
+}
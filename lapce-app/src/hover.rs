@@ -1,6 +1,7 @@
 use floem::{
+    action::TimerToken,
     peniko::kurbo::Rect,
-    reactive::{RwSignal, Scope},
+    reactive::{RwSignal, Scope, SignalGet, SignalUpdate, batch},
 };
 use lapce_core::id::EditorId;
 
@@ -13,6 +14,16 @@ pub struct HoverData {
     pub editor_id:   RwSignal<EditorId>,
     pub content:     RwSignal<Vec<MarkdownContent>>,
     pub layout_rect: RwSignal<Rect>,
+    pub pinned:      PinnedHoverData,
+}
+
+/// Hover content kept in the documentation panel
+#[derive(Clone)]
+pub struct PinnedHoverData {
+    pub content:       RwSignal<Vec<MarkdownContent>>,
+    /// Refresh the content from the hover at the cursor of the active editor
+    pub follow_cursor: RwSignal<bool>,
+    pub timer:         RwSignal<TimerToken>,
 }
 
 impl HoverData {
@@ -23,6 +34,19 @@ impl HoverData {
             content:     cx.create_rw_signal(Vec::new()),
             editor_id:   cx.create_rw_signal(EditorId::next()),
             layout_rect: cx.create_rw_signal(Rect::ZERO),
+            pinned:      PinnedHoverData {
+                content:       cx.create_rw_signal(Vec::new()),
+                follow_cursor: cx.create_rw_signal(false),
+                timer:         cx.create_rw_signal(TimerToken::INVALID),
+            },
         }
     }
+
+    /// Copy the current hover into the documentation panel.
+    pub fn pin(&self) {
+        batch(|| {
+            self.pinned.content.set(self.content.get_untracked());
+            self.active.set(false);
+        });
+    }
 }
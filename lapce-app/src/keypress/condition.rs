@@ -63,6 +63,8 @@ pub enum Condition {
     PanelFocus,
     #[strum(serialize = "rename_focus")]
     RenameFocus,
+    #[strum(serialize = "peek_focus")]
+    PeekFocus,
     #[strum(serialize = "search_active")]
     SearchActive,
     #[strum(serialize = "on_screen_find_active")]
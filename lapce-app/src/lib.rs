@@ -2,6 +2,7 @@
 pub mod about;
 pub mod alert;
 pub mod app;
+pub mod blame;
 pub mod code_action;
 pub mod code_lens;
 pub mod command;
@@ -16,6 +17,7 @@ pub mod editor_tab;
 pub mod file_explorer;
 pub mod find;
 pub mod focus_text;
+pub mod git_graph;
 pub mod global_search;
 pub mod history;
 pub mod hover;
@@ -31,6 +33,7 @@ pub mod main_split;
 pub mod markdown;
 pub mod palette;
 pub mod panel;
+pub mod peek;
 pub mod plugin;
 pub mod proxy;
 pub mod rename;
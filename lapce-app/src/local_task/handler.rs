@@ -456,5 +456,15 @@ async fn handle_notification_db_save_event(db: Arc<LapceDb>, event: SaveEvent) {
                 log::error!("{:?}", err);
             }
         },
+        SaveEvent::CommitMessages(workspace, messages) => {
+            if let Err(err) = db.insert_commit_messages(&workspace, &messages) {
+                log::error!("{:?}", err);
+            }
+        },
+        SaveEvent::ReferenceFilter(workspace, kind, filter) => {
+            if let Err(err) = db.insert_reference_filter(&workspace, kind, filter) {
+                log::error!("{:?}", err);
+            }
+        },
     }
 }
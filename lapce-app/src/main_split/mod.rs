@@ -4,6 +4,7 @@ use std::{
     collections::HashMap,
     path::{Path, PathBuf},
     rc::Rc,
+    time::Duration,
 };
 
 use anyhow::Result;
@@ -15,6 +16,7 @@ use doc::{
 };
 pub use editors::*;
 use floem::{
+    action::exec_after,
     ext_event::create_ext_action,
     file::{FileDialogOptions, FileInfo},
     file_action::save_as,
@@ -39,6 +41,7 @@ use lapce_rpc::{
     dap_types::RunDebugConfig,
     plugin::{PluginId, VoltID},
     proxy::ProxyResponse,
+    source_control::FILE_NOT_IN_REVISION,
 };
 use lapce_xi_rope::Rope;
 use log::{error, warn};
@@ -66,6 +69,7 @@ use crate::{
     keypress::{EventRef, KeyPressData, KeyPressHandle},
     panel::{
         call_hierarchy_view::CallHierarchyData, implementation_view::ReferencesRoot,
+        workspace_edit_view::WorkspaceEditPreviewData,
     },
     window_workspace::{CommonData, Focus, WindowWorkspaceData},
 };
@@ -168,6 +172,7 @@ pub struct MainSplitData {
     pub code_lens:         RwSignal<CodeLensData>,
     pub common:            Rc<CommonData>,
     pub hierarchy:         Tabs<CallHierarchyData>,
+    pub edit_preview:      RwSignal<Option<WorkspaceEditPreviewData>>,
 }
 
 impl std::fmt::Debug for MainSplitData {
@@ -195,6 +200,7 @@ impl MainSplitData {
         let references = Tabs::new(common.config, cx);
         let implementations = Tabs::new(common.config, cx);
         let hierarchy = Tabs::new(common.config, cx);
+        let edit_preview = cx.create_rw_signal(None);
         let current_location = cx.create_rw_signal(0);
         let diagnostics = cx.create_rw_signal(im::HashMap::new());
         let find_str = cx.create_rw_signal(String::new());
@@ -217,11 +223,7 @@ impl MainSplitData {
                     let diff_editor = diff_editors.with(|diff_editors| {
                         diff_editors.get(&diff_editor_id).cloned()
                     })?;
-                    if diff_editor.focus_right.get() {
-                        diff_editor.right
-                    } else {
-                        diff_editor.left
-                    }
+                    diff_editor.focused_editor(true)
                 },
                 _ => return None,
             };
@@ -258,9 +260,49 @@ impl MainSplitData {
             references,
             implementations,
             hierarchy,
+            edit_preview,
         }
     }
 
+    /// The documents of the active child of every editor tab, tracked.
+    pub fn visible_docs(&self) -> Vec<Rc<Doc>> {
+        let children: Vec<EditorTabChildId> = self.editor_tabs.with(|editor_tabs| {
+            editor_tabs
+                .values()
+                .filter_map(|editor_tab| {
+                    editor_tab.with(|editor_tab| {
+                        editor_tab
+                            .children
+                            .get(editor_tab.active)
+                            .map(|x| x.id().clone())
+                    })
+                })
+                .collect()
+        });
+        let mut docs = Vec::new();
+        for child in children {
+            match child {
+                EditorTabChildId::Editor(editor_id) => {
+                    if let Some(editor) = self.editors.editor(editor_id) {
+                        docs.push(editor.doc_signal().get());
+                    }
+                },
+                EditorTabChildId::DiffEditor(diff_editor_id) => {
+                    if let Some(diff_editor) =
+                        self.diff_editors.with(|diff_editors| {
+                            diff_editors.get(&diff_editor_id).cloned()
+                        })
+                    {
+                        docs.push(diff_editor.left.doc_signal().get());
+                        docs.push(diff_editor.right.doc_signal().get());
+                    }
+                },
+                _ => {},
+            }
+        }
+        docs
+    }
+
     pub fn key_down<'a>(
         &self,
         event: impl Into<EventRef<'a>>,
@@ -287,12 +329,8 @@ impl MainSplitData {
                     self.diff_editors.with_untracked(|diff_editors| {
                         diff_editors.get(&diff_editor_id).cloned()
                     })?;
-                let editor = if diff_editor.focus_right.get_untracked() {
-                    &diff_editor.right
-                } else {
-                    &diff_editor.left
-                };
-                let handle = keypress.key_down(event, editor);
+                let editor = diff_editor.focused_editor(false);
+                let handle = keypress.key_down(event, &editor);
                 Some(handle)
             },
             EditorTabChildId::Settings(_) => None,
@@ -614,27 +652,217 @@ impl MainSplitData {
             self.common.clone(),
         );
         let left = Rc::new(left);
+        left.retrieve_history();
 
+        self.get_editor_tab_child(
+            EditorTabChildSource::DiffEditor { left, right },
+            false,
+            false,
+        );
+    }
+
+    /// Open the changes `commit` made to `path`.
+    pub fn open_file_commit_changes(&self, path: PathBuf, commit: String) {
+        let [left, right] = [format!("{commit}^"), commit].map(|version| {
+            DocContent::History(DocHistory {
+                path: path.clone(),
+                version,
+            })
+        });
+        self.open_revision_diff(left, right);
+    }
+
+    /// Open a diff editor between two documents, each either a file of the
+    /// workspace or the content of a file at some revision.
+    pub fn open_revision_diff(&self, left: DocContent, right: DocContent) {
+        let [left, right] = [left, right].map(|content| match content {
+            DocContent::File { path, read_only } => {
+                self.get_doc(
+                    path.clone(),
+                    None,
+                    false,
+                    DocContent::File { path, read_only },
+                )
+                .0
+            },
+            content => {
+                let doc = Rc::new(Doc::new_history(
+                    self.scope,
+                    content,
+                    self.common.clone(),
+                ));
+                doc.retrieve_history();
+                doc
+            },
+        });
+
+        self.get_editor_tab_child(
+            EditorTabChildSource::DiffEditor { left, right },
+            false,
+            false,
+        );
+    }
+
+    /// Open the merge editor of a file with conflicts: our and their versions
+    /// side by side with the base, and the working copy file they are resolved
+    /// into below them.
+    pub fn open_merge_editor(&self, path: PathBuf) {
+        let [base, ours, theirs] = [1, 2, 3].map(|stage| {
+            Rc::new(Doc::new_history(
+                self.scope,
+                DocContent::History(DocHistory {
+                    path:    path.clone(),
+                    version: format!(":{stage}"),
+                }),
+                self.common.clone(),
+            ))
+        });
+        ours.retrieve_history();
+        theirs.retrieve_history();
+        // a file added on both sides has no base, which is shown empty then
         let send = {
-            let left = left.clone();
-            create_ext_action(self.scope, move |result| {
-                if let Ok(ProxyResponse::BufferHeadResponse { content, .. }) = result
-                {
-                    left.init_content(Rope::from(content));
-                }
+            let base = base.clone();
+            let common = self.common.clone();
+            let path = path.clone();
+            create_ext_action(self.scope, move |result| match result {
+                Ok(ProxyResponse::GitGetFileAtRevisionResponse { content }) => {
+                    base.init_content(Rope::from(content));
+                },
+                Ok(_) => {},
+                Err(err) if err.code == FILE_NOT_IN_REVISION => {
+                    base.init_content(Rope::from(""));
+                    let name =
+                        path.file_name().unwrap_or_default().to_string_lossy();
+                    common.show_popup_message(
+                        "Merge Editor".to_string(),
+                        MessageType::INFO,
+                        format!(
+                            "{name} was added on both sides, so there is no base \
+                             to compare with."
+                        ),
+                    );
+                },
+                Err(err) => {
+                    base.init_content(Rope::from(""));
+                    common.show_popup_message(
+                        "Merge Editor".to_string(),
+                        MessageType::ERROR,
+                        err.message,
+                    );
+                },
             })
         };
-        self.common
-            .proxy
-            .proxy_rpc
-            .get_buffer_head(path, move |(_, result)| {
+        self.common.proxy.proxy_rpc.git_get_file_at_revision(
+            path.clone(),
+            ":1".to_string(),
+            move |(_, result)| {
                 send(result);
-            });
+            },
+        );
+        let (result, _) = self.get_doc(
+            path.clone(),
+            None,
+            false,
+            DocContent::File {
+                path:      path.clone(),
+                read_only: false,
+            },
+        );
 
-        self.get_editor_tab_child(
-            EditorTabChildSource::DiffEditor { left, right },
+        let child = self.get_editor_tab_child(
+            EditorTabChildSource::DiffEditor {
+                left:  ours,
+                right: theirs,
+            },
+            false,
             false,
+        );
+        let EditorTabChildId::DiffEditor(diff_editor_id) = child else {
+            return;
+        };
+        let Some(diff_editor) = self.diff_editors.with_untracked(|diff_editors| {
+            diff_editors.get(&diff_editor_id).cloned()
+        }) else {
+            return;
+        };
+        if diff_editor.merge.with_untracked(|merge| merge.is_none()) {
+            diff_editor.set_merge(path, base, result, self.editors);
+        }
+    }
+
+    /// Replace the content of `path` with the content `revision_path` had at
+    /// `revision`. The change is an ordinary edit, so it can be undone and
+    /// isn't saved.
+    pub fn restore_file_revision(
+        &self,
+        path: PathBuf,
+        revision_path: PathBuf,
+        revision: String,
+    ) {
+        self.jump_to_location(
+            EditorLocation {
+                path:               path.clone(),
+                position:           None,
+                scroll_offset:      None,
+                ignore_unconfirmed: false,
+                same_editor_tab:    false,
+            },
+            None,
+        );
+        let (doc, _) = self.get_doc(
+            path.clone(),
+            None,
             false,
+            DocContent::File {
+                path:      path.clone(),
+                read_only: false,
+            },
+        );
+
+        let scope = self.scope;
+        let common = self.common.clone();
+        let send = create_ext_action(self.scope, move |result| match result {
+            Ok(ProxyResponse::GitGetFileAtRevisionResponse { content }) => {
+                let loaded = doc.loaded;
+                let doc = doc.clone();
+                // the file may still be loading when it was just opened, it's
+                // waited for in a scope of its own which is dropped once done
+                let cx = scope.create_child();
+                cx.create_effect(move |done| {
+                    if done == Some(true) {
+                        return true;
+                    }
+                    let done = match loaded.get() {
+                        crate::doc::DocStatus::Ok { loaded } => {
+                            if loaded {
+                                doc.reload(Rope::from(content.as_str()), false);
+                            }
+                            loaded
+                        },
+                        crate::doc::DocStatus::Err { .. } => true,
+                    };
+                    if done {
+                        // not while the effect is running
+                        exec_after(Duration::ZERO, move |_| cx.dispose());
+                    }
+                    done
+                });
+            },
+            Ok(_) => {},
+            Err(err) => {
+                common.show_popup_message(
+                    "Restore file failed".to_string(),
+                    MessageType::ERROR,
+                    err.message,
+                );
+            },
+        });
+        self.common.proxy.proxy_rpc.git_get_file_at_revision(
+            revision_path,
+            revision,
+            move |(_, result)| {
+                send(result);
+            },
         );
     }
 
@@ -2174,7 +2402,7 @@ impl MainSplitData {
             },
             CodeActionOrCommand::CodeAction(action) => {
                 if let Some(edit) = action.edit.as_ref() {
-                    self.apply_workspace_edit(edit);
+                    self.apply_code_action_edit(&action.title, edit);
                 } else {
                     self.resolve_code_action(plugin_id, action);
                 }
@@ -2189,8 +2417,9 @@ impl MainSplitData {
     /// Resolve a code action and apply its held workspace edit
     fn resolve_code_action(&self, plugin_id: PluginId, action: CodeAction) {
         let main_split = self.clone();
+        let title = action.title.clone();
         let send = create_ext_action(self.scope, move |edit| {
-            main_split.apply_workspace_edit(&edit);
+            main_split.apply_code_action_edit(&title, &edit);
         });
         self.common.proxy.proxy_rpc.code_action_resolve(
             action,
@@ -2205,6 +2434,25 @@ impl MainSplitData {
         );
     }
 
+    /// Code actions touching more than one file go through the preview panel
+    /// when `editor.preview-workspace-edits` is enabled.
+    fn apply_code_action_edit(&self, title: &str, edit: &WorkspaceEdit) {
+        let preview = self
+            .common
+            .config
+            .with_untracked(|x| x.editor.preview_workspace_edits);
+        if preview && workspace_edits(edit).is_some_and(|x| x.len() > 1) {
+            self.common.internal_command.send(
+                InternalCommand::PreviewWorkspaceEdit {
+                    title: title.to_string(),
+                    edit:  edit.clone(),
+                },
+            );
+        } else {
+            self.apply_workspace_edit(edit);
+        }
+    }
+
     /// Perform a workspace edit, which are from the LSP (such as code actions,
     /// or symbol renaming)
     pub fn apply_workspace_edit(&self, edit: &WorkspaceEdit) {
@@ -3258,29 +3506,10 @@ impl MainSplitData {
                 DocContent::Local => {
                     Rc::new(Doc::new_local(cx, common.clone(), None))
                 },
-                DocContent::History(history) => {
+                DocContent::History(_) => {
                     let doc = Doc::new_history(cx, content.clone(), common.clone());
                     let doc = Rc::new(doc);
-
-                    {
-                        let doc = doc.clone();
-                        let send = create_ext_action(cx, move |result| {
-                            if let Ok(ProxyResponse::BufferHeadResponse {
-                                content,
-                                ..
-                            }) = result
-                            {
-                                doc.init_content(Rope::from(content));
-                            }
-                        });
-                        common.proxy.proxy_rpc.get_buffer_head(
-                            history.path.clone(),
-                            move |(_, result)| {
-                                send(result);
-                            },
-                        );
-                    }
-
+                    doc.retrieve_history();
                     doc
                 },
                 DocContent::Scratch { name, .. } => {
@@ -3376,7 +3605,7 @@ impl MainSplitData {
     }
 }
 
-fn workspace_edits(edit: &WorkspaceEdit) -> Option<HashMap<Url, Vec<TextEdit>>> {
+pub fn workspace_edits(edit: &WorkspaceEdit) -> Option<HashMap<Url, Vec<TextEdit>>> {
     if let Some(changes) = edit.changes.as_ref() {
         return Some(changes.clone());
     }
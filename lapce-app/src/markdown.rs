@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::Range};
 
 use doc::{language::LapceLanguage, syntax::Syntax};
 use floem::{
@@ -13,10 +13,14 @@ use log::warn;
 use lsp_types::MarkedString;
 use pulldown_cmark::{CodeBlockKind, CowStr, Event, Options, Parser, Tag};
 use smallvec::SmallVec;
+/// The byte range of a link in the text of a `MarkdownContent::Text`, and
+/// where it leads.
+pub type MarkdownLink = (Range<usize>, String);
+
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone)]
 pub enum MarkdownContent {
-    Text(TextLayout),
+    Text(TextLayout, Vec<MarkdownLink>),
     Image { url: String, title: String },
     Separator,
 }
@@ -43,6 +47,7 @@ pub fn parse_markdown(
         .font_size(font_size)
         .line_height(LineHeightValue::Normal(line_height as f32));
     let mut attr_list = AttrsList::new(default_attrs);
+    let mut links = Vec::new();
 
     let mut builder_dirty = false;
 
@@ -98,6 +103,10 @@ pub fn parse_markdown(
                         add_newline = true;
                     }
 
+                    if let Tag::Link { dest_url, .. } = &tag {
+                        links.push((start_offset..pos, dest_url.to_string()));
+                    }
+
                     match &tag {
                         Tag::CodeBlock(kind) => {
                             let language =
@@ -132,7 +141,10 @@ pub fn parse_markdown(
                                     &current_text,
                                     attr_list,
                                 );
-                                res.push(MarkdownContent::Text(text_layout));
+                                res.push(MarkdownContent::Text(
+                                    text_layout,
+                                    std::mem::take(&mut links),
+                                ));
                                 attr_list = AttrsList::new(default_attrs);
                                 current_text.clear();
                                 pos = 0;
@@ -207,12 +219,20 @@ pub fn parse_markdown(
 
     if builder_dirty {
         let text_layout = TextLayout::new_with_text(&current_text, attr_list);
-        res.push(MarkdownContent::Text(text_layout));
+        res.push(MarkdownContent::Text(text_layout, links));
     }
 
     res
 }
 
+/// The url of the link at `index` of the text it was parsed with.
+pub fn link_at(links: &[MarkdownLink], index: usize) -> Option<&str> {
+    links
+        .iter()
+        .find(|(range, _)| range.contains(&index))
+        .map(|(_, url)| url.as_str())
+}
+
 fn attribute_for_tag<'a>(
     default_attrs: Attrs<'a>,
     tag: &Tag,
@@ -384,5 +404,22 @@ pub fn from_plaintext(
                 .line_height(LineHeightValue::Normal(line_height as f32)),
         ),
     );
-    vec![MarkdownContent::Text(text_layout)]
+    vec![MarkdownContent::Text(text_layout, Vec::new())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::link_at;
+
+    #[test]
+    fn test_link_at() {
+        let links = vec![
+            (4..9, "https://a.rs".to_string()),
+            (12..15, "https://b.rs".to_string()),
+        ];
+        assert_eq!(link_at(&links, 3), None);
+        assert_eq!(link_at(&links, 4), Some("https://a.rs"));
+        assert_eq!(link_at(&links, 9), None);
+        assert_eq!(link_at(&links, 14), Some("https://b.rs"));
+    }
 }
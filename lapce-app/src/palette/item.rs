@@ -2,7 +2,7 @@ use std::{path::PathBuf, sync::Arc};
 
 use doc::lines::line_ending::LineEnding;
 use lapce_core::{
-    debug::RunDebugMode,
+    debug::{BreakpointField, RunDebugMode},
     workspace::{LapceWorkspace, SshHost},
 };
 use lapce_rpc::dap_types::RunDebugConfig;
@@ -68,6 +68,10 @@ pub enum PaletteItemContent {
         mode:   RunDebugMode,
         config: RunDebugConfig,
     },
+    DebugProcess {
+        pid:  u32,
+        name: String,
+    },
     ColorTheme {
         name: String,
     },
@@ -83,6 +87,9 @@ pub enum PaletteItemContent {
     SCMReference {
         name: String,
     },
+    BreakpointField {
+        field: BreakpointField,
+    },
     TerminalProfile {
         name:    String,
         profile: lapce_rpc::terminal::TerminalProfile,
@@ -15,11 +15,21 @@ pub enum PaletteKind {
     #[cfg(windows)]
     WslHost,
     RunAndDebug,
+    /// Select a local process to attach the debugger to
+    AttachProcess,
     ColorTheme,
     IconTheme,
     Language,
     LineEnding,
     SCMReferences,
+    /// Type the name of a branch to create or rename
+    SCMBranchName,
+    /// Type a condition, hit count or log message for a breakpoint
+    BreakpointEdit,
+    /// Type a new value for a variable of a stopped session
+    DapVariableValue,
+    /// Answer a credential prompt of a git remote
+    GitCredential,
     TerminalProfile,
     DiffFiles,
     HelpAndFile,
@@ -40,11 +50,16 @@ impl PaletteKind {
             PaletteKind::Reference
             | PaletteKind::SshHost
             | PaletteKind::RunAndDebug
+            | PaletteKind::AttachProcess
             | PaletteKind::ColorTheme
             | PaletteKind::IconTheme
             | PaletteKind::Language
             | PaletteKind::LineEnding
             | PaletteKind::SCMReferences
+            | PaletteKind::SCMBranchName
+            | PaletteKind::BreakpointEdit
+            | PaletteKind::DapVariableValue
+            | PaletteKind::GitCredential
             | PaletteKind::HelpAndFile
             | PaletteKind::DiffFiles => "",
             #[cfg(windows)]
@@ -92,6 +107,9 @@ impl PaletteKind {
             PaletteKind::RunAndDebug => {
                 Some(LapceWorkbenchCommand::PaletteRunAndDebug)
             },
+            PaletteKind::AttachProcess => {
+                Some(LapceWorkbenchCommand::PaletteDebugAttach)
+            },
             PaletteKind::ColorTheme => Some(LapceWorkbenchCommand::ChangeColorTheme),
             PaletteKind::IconTheme => Some(LapceWorkbenchCommand::ChangeIconTheme),
             PaletteKind::Language => Some(LapceWorkbenchCommand::ChangeFileLanguage),
@@ -101,6 +119,12 @@ impl PaletteKind {
             PaletteKind::SCMReferences => {
                 Some(LapceWorkbenchCommand::PaletteSCMReferences)
             },
+            PaletteKind::SCMBranchName => None, // PaletteData::run_scm_branch_name
+            PaletteKind::BreakpointEdit => None, // PaletteData::run_breakpoint_edit
+            // PaletteData::run_dap_variable_value
+            PaletteKind::DapVariableValue => None,
+            // PaletteData::run_git_credential
+            PaletteKind::GitCredential => None,
             PaletteKind::TerminalProfile => None, // InternalCommand::NewTerminal
             PaletteKind::DiffFiles => Some(LapceWorkbenchCommand::DiffFiles),
         }
@@ -124,11 +148,16 @@ impl PaletteKind {
             PaletteKind::Reference
             | PaletteKind::SshHost
             | PaletteKind::RunAndDebug
+            | PaletteKind::AttachProcess
             | PaletteKind::ColorTheme
             | PaletteKind::IconTheme
             | PaletteKind::Language
             | PaletteKind::LineEnding
-            | PaletteKind::SCMReferences | PaletteKind::HelpAndFile
+            | PaletteKind::SCMReferences
+            | PaletteKind::SCMBranchName | PaletteKind::HelpAndFile
+            | PaletteKind::BreakpointEdit
+            | PaletteKind::DapVariableValue
+            | PaletteKind::GitCredential
             | PaletteKind::DiffFiles => input,
             PaletteKind::PaletteHelp
             | PaletteKind::Command
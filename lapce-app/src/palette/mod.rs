@@ -31,11 +31,16 @@ use itertools::Itertools;
 #[cfg(windows)]
 use lapce_core::workspace::WslHost;
 use lapce_core::{
-    debug::{RunDebugConfigs, RunDebugMode},
-    doc::DocContent,
+    debug::{BreakpointField, RunDebugConfigs, RunDebugMode},
+    doc::{DocContent, DocHistory},
     workspace::{LapceWorkspace, LapceWorkspaceType, SshHost},
 };
-use lapce_rpc::proxy::ProxyResponse;
+use lapce_rpc::{
+    RpcError,
+    core::{CoreResponse, REQUEST_DISMISSED},
+    dap_types::{ConfigSource, DebugRequest, ProcessInfo, RunDebugConfig},
+    proxy::ProxyResponse,
+};
 use log::{error, info};
 use lsp_types::{DocumentSymbol, DocumentSymbolResponse};
 use nucleo::Utf32Str;
@@ -51,6 +56,7 @@ use crate::{
         WindowCommand,
     },
     db::LapceDb,
+    debug::{BreakpointLocation, DapVariableEdit},
     editor::{
         EditorData,
         location::{EditorLocation, EditorPosition},
@@ -58,7 +64,9 @@ use crate::{
     keypress::{KeyPressData, KeyPressFocus, condition::Condition},
     lsp::path_from_url,
     main_split::MainSplitData,
-    source_control::SourceControlData,
+    source_control::{
+        BranchNameTarget, GitCredentialPrompt, ReferenceAction, SourceControlData,
+    },
     window_workspace::{CommonData, Focus},
 };
 
@@ -67,6 +75,9 @@ pub mod kind;
 
 pub const DEFAULT_RUN_TOML: &str = include_str!("../../../defaults/run.toml");
 
+/// What the input shows for each character of the answer to a secret prompt
+const SECRET_MASK: char = '•';
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum PaletteStatus {
     Inactive,
@@ -151,6 +162,13 @@ pub struct PaletteData {
     pub source_control:        SourceControlData,
     pub common:                Rc<CommonData>,
     left_diff_path:            RwSignal<Option<PathBuf>>,
+    scm_reference_action:      RwSignal<ReferenceAction>,
+    scm_branch_name:           RwSignal<Option<BranchNameTarget>>,
+    breakpoint_edit:           RwSignal<Option<BreakpointLocation>>,
+    dap_variable_edit:         RwSignal<Option<DapVariableEdit>>,
+    git_credential:            RwSignal<Option<GitCredentialPrompt>>,
+    /// The answer to a secret prompt, the input only showing it masked
+    secret_input:              RwSignal<String>,
     pub workspace_document_id: RwSignal<Option<u64>>,
     pub document_symbol:       DocumentSymbolInfo,
     pub run_result:            RwSignal<RunResult>,
@@ -213,6 +231,12 @@ impl PaletteData {
             source_control,
             common,
             left_diff_path,
+            scm_reference_action: cx.create_rw_signal(ReferenceAction::default()),
+            scm_branch_name: cx.create_rw_signal(None),
+            breakpoint_edit: cx.create_rw_signal(None),
+            dap_variable_edit: cx.create_rw_signal(None),
+            git_credential: cx.create_rw_signal(None),
+            secret_input: cx.create_rw_signal(String::new()),
             workspace_document_id: cx.create_rw_signal(None),
             document_symbol: cx.create_rw_signal(None),
             run_result: cx.create_rw_signal(RunResult::default()),
@@ -230,6 +254,28 @@ impl PaletteData {
             });
         }
 
+        {
+            let palette = palette.clone();
+            let input_str = palette.input_str;
+            // keep what is typed for a secret prompt out of the input
+            cx.create_effect(move |_| {
+                let input = input_str.get();
+                if !palette.git_credential.with_untracked(|prompt| {
+                    prompt.as_ref().is_some_and(|prompt| prompt.secret)
+                }) {
+                    return;
+                }
+                let secret = palette
+                    .secret_input
+                    .with_untracked(|secret| unmask_input(&input, secret));
+                let masked = SECRET_MASK.to_string().repeat(secret.chars().count());
+                palette.secret_input.set(secret);
+                if masked != input {
+                    input_str.set(masked);
+                }
+            });
+        }
+
         {
             let palette = palette.clone();
             let input_str = palette.input_str;
@@ -307,6 +353,9 @@ impl PaletteData {
 
     /// Start and focus the palette for the given kind.
     pub fn run(&self, kind: PaletteKind) {
+        if kind != PaletteKind::GitCredential {
+            self.dismiss_git_credential();
+        }
         self.run_result.update(|x| {
             x.update_id();
         });
@@ -323,12 +372,118 @@ impl PaletteData {
         // cursor.set_insert(Selection::caret(symbol.len())));
     }
 
+    /// Pick a ref to run `action` on.
+    pub fn run_scm_references(&self, action: ReferenceAction) {
+        self.scm_reference_action.set(action);
+        self.run(PaletteKind::SCMReferences);
+    }
+
+    /// Type a branch name for `target`.
+    pub fn run_scm_branch_name(&self, target: BranchNameTarget) {
+        self.scm_branch_name.set(Some(target));
+        self.run(PaletteKind::SCMBranchName);
+    }
+
+    /// Type a condition, hit count or log message for the breakpoint at
+    /// `location`.
+    pub fn run_breakpoint_edit(&self, location: BreakpointLocation) {
+        self.breakpoint_edit.set(Some(location));
+        self.run(PaletteKind::BreakpointEdit);
+    }
+
+    /// Type a new value for the variable of `edit`, starting from its
+    /// current one.
+    pub fn run_dap_variable_value(&self, edit: DapVariableEdit) {
+        let value = edit.variable.value.clone();
+        self.dap_variable_edit.set(Some(edit));
+        self.run(PaletteKind::DapVariableValue);
+        self.input_str.set(value);
+    }
+
+    /// Answer the credential prompt of a git remote, dismissing the one
+    /// still waiting for an answer.
+    pub fn run_git_credential(&self, prompt: GitCredentialPrompt) {
+        self.dismiss_git_credential();
+        self.git_credential.set(Some(prompt));
+        self.run(PaletteKind::GitCredential);
+    }
+
+    /// Let the proxy know the user won't answer the credential prompt.
+    fn dismiss_git_credential(&self) {
+        self.secret_input.set(String::new());
+        if let Some(prompt) = self.git_credential.try_update(Option::take).flatten()
+        {
+            self.common.proxy.core_rpc.handle_response(
+                prompt.id,
+                Err(RpcError {
+                    code:    REQUEST_DISMISSED,
+                    message: format!("{} dismissed", prompt.prompt.trim()),
+                }),
+            );
+        }
+    }
+
+    /// The placeholder of the palette input field, the prompt for a
+    /// credential.
+    pub fn input_placeholder(&self) -> String {
+        if self.kind.get() == Some(PaletteKind::GitCredential)
+            && let Some(prompt) = self.git_credential.get()
+        {
+            return prompt.prompt;
+        }
+        self.placeholder_text().to_string()
+    }
+
     /// Get the placeholder text to use in the palette input field.
     pub fn placeholder_text(&self) -> &'static str {
         match self.kind.get() {
             Some(PaletteKind::SshHost) => {
                 "Type [user@]host or select a previously connected workspace below"
             },
+            Some(PaletteKind::SCMReferences) => {
+                match self.scm_reference_action.get() {
+                    ReferenceAction::Checkout
+                    | ReferenceAction::CheckoutAutoStash => {
+                        "Select a ref to checkout"
+                    },
+                    ReferenceAction::CreateBranchFrom => {
+                        "Select a ref to create the branch from"
+                    },
+                    ReferenceAction::RenameBranch => "Select a branch to rename",
+                    ReferenceAction::DeleteBranch => "Select a branch to delete",
+                    ReferenceAction::Merge | ReferenceAction::MergeNoFf => {
+                        "Select a ref to merge into the current branch"
+                    },
+                    ReferenceAction::CompareFile(_)
+                    | ReferenceAction::CompareWorkspace => {
+                        "Select or type a ref to compare with"
+                    },
+                    ReferenceAction::CompareRefs { base: None } => {
+                        "Select or type the ref to compare from"
+                    },
+                    ReferenceAction::CompareRefs { base: Some(_) } => {
+                        "Select or type the ref to compare to"
+                    },
+                    ReferenceAction::GutterBase => {
+                        "Select or type a ref to show the gutter changes against"
+                    },
+                }
+            },
+            Some(PaletteKind::SCMBranchName) => match self.scm_branch_name.get() {
+                Some(BranchNameTarget::Rename { .. }) => {
+                    "Type the new name of the branch and press Enter"
+                },
+                _ => "Type the name of the new branch and press Enter",
+            },
+            Some(PaletteKind::BreakpointEdit) => {
+                "Type an expression, hit count or log message and pick what it is"
+            },
+            Some(PaletteKind::DapVariableValue) => {
+                "Type the new value of the variable and press Enter"
+            },
+            Some(PaletteKind::AttachProcess) => {
+                "Select a process or type a process id to attach the debugger to"
+            },
             Some(PaletteKind::DiffFiles) => {
                 if self.left_diff_path.with(Option::is_some) {
                     "Select right file"
@@ -357,7 +512,11 @@ impl PaletteData {
         //     return;
         // }
         let run_id = self.run_result.try_update(|x| x.update_id()).unwrap();
-        log::debug!("run_inner_by_input {} {:?} input={input}", run_id, kind);
+        if kind == PaletteKind::GitCredential {
+            log::debug!("run_inner_by_input {} {:?}", run_id, kind);
+        } else {
+            log::debug!("run_inner_by_input {} {:?} input={input}", run_id, kind);
+        }
         match kind {
             PaletteKind::PaletteHelp => self.get_palette_help(run_id),
             PaletteKind::DiffFiles => {
@@ -392,6 +551,9 @@ impl PaletteData {
             PaletteKind::RunAndDebug => {
                 self.get_run_configs(run_id, kind_input.to_string())?;
             },
+            PaletteKind::AttachProcess => {
+                self.get_processes(run_id);
+            },
             PaletteKind::ColorTheme => {
                 self.get_color_themes(run_id);
             },
@@ -407,6 +569,15 @@ impl PaletteData {
             PaletteKind::SCMReferences => {
                 self.get_scm_references(run_id);
             },
+            PaletteKind::SCMBranchName => {
+                self.items.set(Vector::new());
+            },
+            PaletteKind::BreakpointEdit => {
+                self.get_breakpoint_fields(run_id, kind_input);
+            },
+            PaletteKind::DapVariableValue | PaletteKind::GitCredential => {
+                self.update_rs(run_id, Vector::new());
+            },
             PaletteKind::TerminalProfile => self.get_terminal_profiles(run_id),
         }
         Ok(())
@@ -1042,6 +1213,29 @@ impl PaletteData {
             let executed_run_configs = self.executed_run_configs.borrow();
             let mut items = Vec::new();
             for config in &configs.configs {
+                if config.request == DebugRequest::Attach {
+                    if config.ty.is_some() {
+                        items.push((
+                            executed_run_configs
+                                .get(&(RunDebugMode::Debug, config.name.clone())),
+                            PaletteItem {
+                                content: PaletteItemContent::RunAndDebug {
+                                    mode:   RunDebugMode::Debug,
+                                    config: config.clone(),
+                                },
+                                filter_text: format!(
+                                    "Debug {} {}",
+                                    config.name,
+                                    config.attach_target()
+                                ),
+                                score: 0,
+                                indices: vec![],
+                                run_id,
+                            },
+                        ));
+                    }
+                    continue;
+                }
                 items.push((
                     executed_run_configs
                         .get(&(RunDebugMode::Run, config.name.clone())),
@@ -1096,6 +1290,50 @@ impl PaletteData {
         }
     }
 
+    /// Initialize the palette with the processes running on the machine of
+    /// the proxy.
+    fn get_processes(&self, run_id: u64) {
+        let data = self.clone();
+        let send = create_ext_action(
+            self.common.scope,
+            move |processes: Vec<ProcessInfo>| {
+                let items = processes
+                    .into_iter()
+                    .rev()
+                    .map(|process| PaletteItem {
+                        filter_text: format!(
+                            "{}  {}",
+                            process.pid, process.command_line
+                        ),
+                        content: PaletteItemContent::DebugProcess {
+                            pid:  process.pid,
+                            name: process.name,
+                        },
+                        score: 0,
+                        indices: Vec::new(),
+                        run_id,
+                    })
+                    .collect::<im::Vector<_>>();
+                let input_str = data.input_str.get_untracked();
+                data.filter_items(
+                    run_id,
+                    PaletteKind::AttachProcess.get_input(&input_str),
+                    items,
+                );
+            },
+        );
+        self.common
+            .proxy
+            .proxy_rpc
+            .list_processes(move |(_, result)| {
+                if let Ok(ProxyResponse::ListProcessesResponse { processes }) =
+                    result
+                {
+                    send(processes);
+                }
+            });
+    }
+
     fn get_color_themes(&self, run_id: u64) {
         let (items, name) = self.common.config.with_untracked(|config| {
             (
@@ -1199,6 +1437,15 @@ impl PaletteData {
                 run_id,
             });
         }
+        // only branches can be renamed or deleted
+        let tags = if matches!(
+            self.scm_reference_action.get_untracked(),
+            ReferenceAction::RenameBranch | ReferenceAction::DeleteBranch
+        ) {
+            im::Vector::new()
+        } else {
+            tags
+        };
         for refs in tags.into_iter() {
             items.push_back(PaletteItem {
                 content: PaletteItemContent::SCMReference {
@@ -1213,6 +1460,38 @@ impl PaletteData {
         self.items.set(items);
     }
 
+    /// What the input can be set as on the breakpoint being edited, an
+    /// empty input clearing what is set.
+    fn get_breakpoint_fields(&self, run_id: u64, input: &str) {
+        let breakpoint = self.breakpoint_edit.with_untracked(|location| {
+            location.as_ref().and_then(|location| {
+                self.common
+                    .breakpoints
+                    .get_by_path_line_untracked(&location.path, location.line)
+            })
+        });
+        let input = input.trim();
+        let items = BreakpointField::ALL
+            .iter()
+            .filter_map(|field| {
+                let current = breakpoint.as_ref().and_then(|b| b.field(*field));
+                let filter_text = match current {
+                    _ if !input.is_empty() => format!("{}: {input}", field.name()),
+                    Some(current) => format!("Clear {}: {current}", field.name()),
+                    None => return None,
+                };
+                Some(PaletteItem {
+                    content: PaletteItemContent::BreakpointField { field: *field },
+                    filter_text,
+                    score: 0,
+                    indices: Vec::new(),
+                    run_id,
+                })
+            })
+            .collect();
+        self.update_rs(run_id, items);
+    }
+
     fn get_terminal_profiles(&self, run_id: u64) {
         let profiles = self
             .common
@@ -1267,7 +1546,25 @@ impl PaletteData {
     fn select(&self) {
         let index = self.index.get_untracked();
         let items = self.run_result.get_untracked().rs;
+        // closing clears them
+        let kind = self.kind.get_untracked();
+        let input = self.input_str.get_untracked();
+        // closing dismisses it
+        let git_credential = self.git_credential.try_update(Option::take).flatten();
+        let secret = self.secret_input.try_update(std::mem::take);
         self.close();
+        if let Some(prompt) = git_credential {
+            let answer = if prompt.secret {
+                secret.unwrap_or_default()
+            } else {
+                input
+            };
+            self.common.proxy.core_rpc.handle_response(
+                prompt.id,
+                Ok(CoreResponse::GitCredentialResponse { answer }),
+            );
+            return;
+        }
         if let Some(item) = items.get(index) {
             match &item.content {
                 PaletteItemContent::PaletteHelp { cmd } => {
@@ -1279,7 +1576,7 @@ impl PaletteData {
                     self.common.lapce_command.send(cmd);
                 },
                 PaletteItemContent::File { full_path, .. } => {
-                    if self.kind.get_untracked() == Some(PaletteKind::DiffFiles) {
+                    if kind == Some(PaletteKind::DiffFiles) {
                         if let Some(left_path) =
                             self.left_diff_path.try_update(Option::take).flatten()
                         {
@@ -1408,6 +1705,9 @@ impl PaletteData {
                         },
                     );
                 },
+                PaletteItemContent::DebugProcess { pid, name } => {
+                    self.select_debug_process(*pid, name);
+                },
                 PaletteItemContent::ColorTheme { name } => self
                     .common
                     .internal_command
@@ -1461,14 +1761,10 @@ impl PaletteData {
                     // });
                 },
                 PaletteItemContent::SCMReference { name } => {
-                    self.common
-                        .lapce_command
-                        .send(crate::command::LapceCommand {
-                        kind: CommandKind::Workbench(
-                            crate::command::LapceWorkbenchCommand::CheckoutReference,
-                        ),
-                        data: Some(serde_json::json!(name.to_owned())),
-                    });
+                    self.select_scm_reference(name.to_owned());
+                },
+                PaletteItemContent::BreakpointField { field } => {
+                    self.select_breakpoint_field(*field, input);
                 },
                 PaletteItemContent::TerminalProfile { name: _, profile } => self
                     .common
@@ -1477,8 +1773,54 @@ impl PaletteData {
                         profile: Some(profile.to_owned()),
                     }),
             }
-        } else if self.kind.get_untracked() == Some(PaletteKind::SshHost) {
-            let input = self.input.with_untracked(|input| input.input.clone());
+        } else if kind == Some(PaletteKind::SCMReferences) {
+            // a commit, or any other revision git understands
+            let name = input.trim().to_string();
+            if name.is_empty() {
+                self.scm_reference_action.set(ReferenceAction::default());
+                return;
+            }
+            self.select_scm_reference(name);
+        } else if kind == Some(PaletteKind::SCMBranchName) {
+            let name = input.trim().to_string();
+            let Some(target) =
+                self.scm_branch_name.try_update(Option::take).flatten()
+            else {
+                return;
+            };
+            if name.is_empty() {
+                return;
+            }
+            match target {
+                BranchNameTarget::Create { start_point } => {
+                    self.source_control.create_branch(name, start_point);
+                },
+                BranchNameTarget::Rename { old_name } => {
+                    self.source_control.rename_branch(old_name, name);
+                },
+            }
+        } else if kind == Some(PaletteKind::BreakpointEdit) {
+            if !input.trim().is_empty() {
+                self.select_breakpoint_field(BreakpointField::Condition, input);
+            }
+        } else if kind == Some(PaletteKind::DapVariableValue) {
+            let value = input.trim().to_string();
+            let Some(edit) =
+                self.dap_variable_edit.try_update(Option::take).flatten()
+            else {
+                return;
+            };
+            if value.is_empty() {
+                return;
+            }
+            self.common
+                .internal_command
+                .send(InternalCommand::SetDapVariable { edit, value });
+        } else if kind == Some(PaletteKind::AttachProcess)
+            && let Ok(pid) = input.trim().parse::<u32>()
+        {
+            self.select_debug_process(pid, "");
+        } else if kind == Some(PaletteKind::SshHost) {
             let ssh = SshHost::from_string(&input);
             self.common.window_common.window_command.send(
                 WindowCommand::SetWorkspace {
@@ -1488,6 +1830,123 @@ impl PaletteData {
         }
     }
 
+    /// Attach the debugger to the process `pid`, with the lldb based adapter.
+    fn select_debug_process(&self, pid: u32, name: &str) {
+        let name = if name.is_empty() {
+            format!("Attach to {pid}")
+        } else {
+            format!("Attach to {name} ({pid})")
+        };
+        let config = RunDebugConfig {
+            ty: Some("lldb".to_string()),
+            name,
+            request: DebugRequest::Attach,
+            program: String::new(),
+            args: None,
+            cwd: self
+                .workspace
+                .path()
+                .map(|path| path.to_string_lossy().into_owned()),
+            env: None,
+            stop_on_entry: false,
+            prelaunch: None,
+            debug_command: None,
+            dap_id: Default::default(),
+            tracing_output: false,
+            config_source: ConfigSource::ProcessPicker,
+            pid: Some(pid),
+            process_name: None,
+        };
+        self.common
+            .internal_command
+            .send(InternalCommand::RunAndDebug {
+                mode: RunDebugMode::Debug,
+                config,
+            });
+    }
+
+    fn select_breakpoint_field(&self, field: BreakpointField, value: String) {
+        let Some(location) = self.breakpoint_edit.try_update(Option::take).flatten()
+        else {
+            return;
+        };
+        self.common
+            .internal_command
+            .send(InternalCommand::SetBreakpointField {
+                location,
+                field,
+                value,
+            });
+    }
+
+    /// Run the action the references palette was opened for on `name`.
+    fn select_scm_reference(&self, name: String) {
+        let action = self
+            .scm_reference_action
+            .try_update(std::mem::take)
+            .unwrap_or_default();
+        match action {
+            ReferenceAction::Checkout => {
+                self.common
+                    .lapce_command
+                    .send(crate::command::LapceCommand {
+                        kind: CommandKind::Workbench(
+                            crate::command::LapceWorkbenchCommand::CheckoutReference,
+                        ),
+                        data: Some(serde_json::json!(name)),
+                    });
+            },
+            ReferenceAction::CheckoutAutoStash => {
+                self.source_control.checkout(name, true);
+            },
+            ReferenceAction::CreateBranchFrom => {
+                self.run_scm_branch_name(BranchNameTarget::Create {
+                    start_point: Some(name),
+                });
+            },
+            ReferenceAction::RenameBranch => {
+                self.run_scm_branch_name(BranchNameTarget::Rename {
+                    old_name: name,
+                });
+            },
+            ReferenceAction::DeleteBranch => {
+                self.source_control.delete_branch(name, false);
+            },
+            ReferenceAction::Merge => {
+                self.source_control.merge(name, false);
+            },
+            ReferenceAction::MergeNoFf => {
+                self.source_control.merge(name, true);
+            },
+            ReferenceAction::CompareFile(path) => {
+                self.main_split.open_revision_diff(
+                    DocContent::History(DocHistory {
+                        path:    path.clone(),
+                        version: name,
+                    }),
+                    DocContent::File {
+                        path,
+                        read_only: false,
+                    },
+                );
+            },
+            ReferenceAction::CompareWorkspace => {
+                self.source_control.compare(name, None);
+            },
+            ReferenceAction::CompareRefs { base: None } => {
+                self.run_scm_references(ReferenceAction::CompareRefs {
+                    base: Some(name),
+                });
+            },
+            ReferenceAction::CompareRefs { base: Some(base) } => {
+                self.source_control.compare(base, Some(name));
+            },
+            ReferenceAction::GutterBase => {
+                self.source_control.set_gutter_base(Some(name));
+            },
+        }
+    }
+
     /// Update the preview for the currently active palette item, if it has one.
     fn preview(&self) {
         if self.status.get_untracked() == PaletteStatus::Inactive {
@@ -1537,6 +1996,7 @@ impl PaletteData {
                 PaletteItemContent::Command { .. } => {},
                 PaletteItemContent::Workspace { .. } => {},
                 PaletteItemContent::RunAndDebug { .. } => {},
+                PaletteItemContent::DebugProcess { .. } => {},
                 PaletteItemContent::SshHost { .. } => {},
                 #[cfg(windows)]
                 PaletteItemContent::WslHost { .. } => {},
@@ -1631,6 +2091,7 @@ impl PaletteData {
                         save: false,
                     }),
                 PaletteItemContent::SCMReference { .. } => {},
+                PaletteItemContent::BreakpointField { .. } => {},
                 PaletteItemContent::TerminalProfile { .. } => {},
             }
             self.has_preview.set(has_preview);
@@ -1649,11 +2110,16 @@ impl PaletteData {
         }
 
         self.left_diff_path.set(None);
+        self.scm_reference_action.set(ReferenceAction::default());
+        self.scm_branch_name.set(None);
+        self.breakpoint_edit.set(None);
+        self.dap_variable_edit.set(None);
         self.close();
     }
 
     /// Close the palette, reverting focus back to the workbench.
     fn close(&self) {
+        self.dismiss_git_credential();
         self.status.set(PaletteStatus::Inactive);
         if self.common.focus.get_untracked() == Focus::Palette {
             self.common.focus.set(Focus::Workbench);
@@ -1817,3 +2283,29 @@ impl KeyPressFocus for PaletteData {
         error!("todo receive_char");
     }
 }
+
+/// The secret typed so far, from the masked `input` which showed `secret`:
+/// the masks it starts with stand for the start of the secret, and what
+/// isn't masked was just typed.
+fn unmask_input(input: &str, secret: &str) -> String {
+    let kept = input.chars().take_while(|c| *c == SECRET_MASK).count();
+    secret
+        .chars()
+        .take(kept)
+        .chain(input.chars().skip(kept).filter(|c| *c != SECRET_MASK))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unmask_input;
+
+    #[test]
+    fn test_unmask_input() {
+        assert_eq!(unmask_input("p", ""), "p");
+        assert_eq!(unmask_input("••s", "pa"), "pas");
+        assert_eq!(unmask_input("•", "pa"), "p");
+        assert_eq!(unmask_input("••pasted", "pa"), "papasted");
+        assert_eq!(unmask_input("", "pa"), "");
+    }
+}
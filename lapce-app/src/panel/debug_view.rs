@@ -1,8 +1,11 @@
+use std::rc::Rc;
+
 use floem::{
     View,
-    event::EventListener,
+    event::{Event, EventListener},
+    keyboard::{Key, NamedKey},
     peniko::Color,
-    prelude::palette,
+    prelude::{palette, text_input},
     reactive::{RwSignal, SignalGet, SignalUpdate, SignalWith, create_rw_signal},
     style::CursorStyle,
     text::Style as FontStyle,
@@ -12,12 +15,12 @@ use floem::{
     },
 };
 use lapce_core::{
-    debug::{BreakpointAction, RunDebugMode},
+    debug::{BreakpointAction, RunDebugMode, ScopeOrVar},
     icon::LapceIcons,
-    panel::{PanelContainerPosition, PanelSection},
+    panel::{PanelContainerPosition, PanelKind, PanelSection},
 };
 use lapce_rpc::{
-    dap_types::{DapId, ThreadId},
+    dap_types::{DapId, FunctionBreakpoint, ThreadId},
     terminal::TermId,
 };
 
@@ -26,12 +29,15 @@ use crate::{
     app::clickable_icon,
     command::InternalCommand,
     config::{WithLapceConfig, color::LapceColor},
-    debug::{DapVariable, StackTraceData, update_breakpoints},
+    debug::{
+        BreakpointLocation, DapData, DapVariable, DapVariableEdit, DapVariableTree,
+        StackTraceData, update_breakpoints,
+    },
     editor::location::{EditorLocation, EditorPosition},
     listener::Listener,
     settings::checkbox,
     terminal::panel::TerminalPanelData,
-    window_workspace::WindowWorkspaceData,
+    window_workspace::{Focus, WindowWorkspaceData},
 };
 
 pub fn debug_panel(
@@ -54,6 +60,16 @@ pub fn debug_panel(
             variables_view(window_tab_data.clone()),
             window_tab_data.panel.section_open(PanelSection::Variable),
         )
+        .add(
+            "Watch",
+            watch_view(window_tab_data.clone()),
+            window_tab_data.panel.section_open(PanelSection::Watch),
+        )
+        .add(
+            "Debug Console",
+            console_view(window_tab_data.clone()),
+            window_tab_data.panel.section_open(PanelSection::Console),
+        )
         .add(
             "Stack Frames",
             debug_stack_traces(terminal.clone(), internal_command, config),
@@ -65,6 +81,22 @@ pub fn debug_panel(
             breakpoints_view(window_tab_data.clone()),
             window_tab_data.panel.section_open(PanelSection::Breakpoint),
         )
+        .add_height(
+            "Exception Breakpoints",
+            100.0,
+            exception_breakpoints_view(window_tab_data.clone()),
+            window_tab_data
+                .panel
+                .section_open(PanelSection::ExceptionBreakpoint),
+        )
+        .add_height(
+            "Function Breakpoints",
+            100.0,
+            function_breakpoints_view(window_tab_data.clone()),
+            window_tab_data
+                .panel
+                .section_open(PanelSection::FunctionBreakpoint),
+        )
         .build()
         .debug_name("Debug Panel")
 }
@@ -339,36 +371,190 @@ fn debug_processes(
 
 fn variables_view(window_tab_data: WindowWorkspaceData) -> impl View {
     let terminal = window_tab_data.terminal.clone();
-    let local_terminal = window_tab_data.terminal.clone();
+    variables_tree(
+        window_tab_data.terminal.clone(),
+        window_tab_data.common.config,
+        move || {
+            stopped_dap(&terminal, true)
+                .map(|dap| dap.variables.get())
+                .unwrap_or_default()
+        },
+        DapVariableTree::Variables,
+        None,
+    )
+    .debug_name("debug variables")
+}
+
+fn watch_view(window_tab_data: WindowWorkspaceData) -> impl View {
     let config = window_tab_data.common.config;
+    let focus = window_tab_data.common.focus;
+    let terminal = window_tab_data.terminal.clone();
+    let watches = terminal.debug.watches;
+    let input = create_rw_signal(String::new());
+    let tree = {
+        let terminal = terminal.clone();
+        move || match stopped_dap(&terminal, true) {
+            Some(dap) => dap.watches.get(),
+            None => watches.with(|watches| DapVariable::watch_placeholders(watches)),
+        }
+    };
+    let remove_terminal = terminal.clone();
+    stack((
+        variables_tree(
+            terminal.clone(),
+            config,
+            tree,
+            DapVariableTree::Watch,
+            Some(Rc::new(move |expression: &str| {
+                remove_terminal.remove_watch(expression)
+            })),
+        ),
+        expression_input(
+            input,
+            "Add a watch expression",
+            config,
+            focus,
+            move || {
+                let expression = input.get_untracked().trim().to_string();
+                if !expression.is_empty() {
+                    terminal.add_watch(expression);
+                }
+                input.set(String::new());
+            },
+        ),
+    ))
+    .style(|s| s.flex_col().size_full())
+    .debug_name("debug watch")
+}
+
+fn console_view(window_tab_data: WindowWorkspaceData) -> impl View {
+    let config = window_tab_data.common.config;
+    let focus = window_tab_data.common.focus;
+    let terminal = window_tab_data.terminal.clone();
+    let input = create_rw_signal(String::new());
+    let tree = {
+        let terminal = terminal.clone();
+        move || {
+            terminal
+                .get_active_dap(true)
+                .map(|dap| dap.console.get())
+                .unwrap_or_default()
+        }
+    };
+    let clear_terminal = terminal.clone();
+    stack((
+        variables_tree(
+            terminal.clone(),
+            config,
+            tree,
+            DapVariableTree::Console,
+            None,
+        ),
+        stack((
+            expression_input(
+                input,
+                "Evaluate an expression",
+                config,
+                focus,
+                move || {
+                    let expression = input.get_untracked().trim().to_string();
+                    if !expression.is_empty() {
+                        terminal.evaluate_in_console(expression);
+                    }
+                    input.set(String::new());
+                },
+            )
+            .style(|s| s.flex_grow(1.0)),
+            clickable_icon(
+                || LapceIcons::SEARCH_CLEAR,
+                move || {
+                    if let Some(dap) = clear_terminal.get_active_dap(false) {
+                        dap.console.set(DapVariable::root(Vec::new()));
+                    }
+                },
+                || false,
+                || false,
+                || "Clear Console",
+                config,
+            )
+            .style(|s| s.margin_right(6.0)),
+        ))
+        .style(|s| s.items_center().width_full()),
+    ))
+    .style(|s| s.flex_col().size_full())
+    .debug_name("debug console")
+}
+
+/// The active session if it is stopped, which is when its variables can be
+/// read.
+fn stopped_dap(terminal: &TerminalPanelData, tracked: bool) -> Option<DapData> {
+    let dap = terminal.get_active_dap(tracked)?;
+    let stopped = if tracked {
+        dap.stopped.get()
+    } else {
+        dap.stopped.get_untracked()
+    };
+    if !stopped {
+        return None;
+    }
+    let process_stopped = dap
+        .term_id
+        .and_then(|x| {
+            terminal.get_terminal(x).and_then(|t| {
+                t.data.with(|r| r.run_debug.as_ref().map(|r| r.stopped))
+            })
+        })
+        .unwrap_or(true);
+    (!process_stopped).then_some(dap)
+}
+
+/// A one line input, `on_enter` run when Enter is pressed.
+fn expression_input(
+    input: RwSignal<String>,
+    placeholder: &'static str,
+    config: WithLapceConfig,
+    focus: RwSignal<Focus>,
+    on_enter: impl Fn() + 'static,
+) -> impl View {
+    text_input(input)
+        .placeholder(placeholder)
+        .keyboard_navigable()
+        .on_event_cont(EventListener::PointerDown, move |_| {
+            focus.set(Focus::Panel(PanelKind::Debug));
+        })
+        .on_event_stop(EventListener::KeyDown, move |event: &Event| {
+            if let Event::KeyDown(key_event) = event
+                && let Key::Named(NamedKey::Enter) = key_event.key.logical_key
+            {
+                on_enter();
+            }
+        })
+        .style(move |s| {
+            s.width_full()
+                .min_width(0.0)
+                .margin(6.0)
+                .padding_horiz(6.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(config.with_color(LapceColor::LAPCE_BORDER))
+        })
+}
+
+/// A tree of variables of the active session, children read when a variable
+/// is expanded and its value edited when double clicked. `tree` is where it
+/// is kept in the session, and `on_remove` adds a remove button to the top
+/// level items.
+fn variables_tree(
+    terminal: TerminalPanelData,
+    config: WithLapceConfig,
+    root: impl Fn() -> DapVariable + 'static,
+    tree: DapVariableTree,
+    on_remove: Option<Rc<dyn Fn(&str)>>,
+) -> impl View {
     container(
         scroll(
             virtual_stack(
-                // VirtualDirection::Vertical,
-                // VirtualItemSize::Fixed(Box::new(move || ui_line_height.get())),
-                move || {
-                    let dap = terminal.get_active_dap(true);
-                    dap.map(|dap| {
-                        if !dap.stopped.get() {
-                            return DapVariable::default();
-                        }
-                        let process_stopped = dap
-                            .term_id
-                            .and_then(|x| {
-                                terminal.get_terminal(x).and_then(|t| {
-                                    t.data.with(|r| {
-                                        r.run_debug.as_ref().map(|r| r.stopped)
-                                    })
-                                })
-                            })
-                            .unwrap_or(true);
-                        if process_stopped {
-                            return DapVariable::default();
-                        }
-                        dap.variables.get()
-                    })
-                    .unwrap_or_default()
-                },
+                root,
                 |node| {
                     (
                         node.item.name().to_string(),
@@ -376,15 +562,23 @@ fn variables_view(window_tab_data: WindowWorkspaceData) -> impl View {
                         node.item.reference(),
                         node.expanded,
                         node.level,
+                        node.index,
                     )
                 },
                 move |node| {
-                    let local_terminal = local_terminal.clone();
+                    let terminal = terminal.clone();
+                    let edit_terminal = terminal.clone();
                     let level = node.level;
                     let reference = node.item.reference();
-                    let name = node.item.name();
+                    let name = node.item.name().to_string();
                     let ty = node.item.ty();
                     let type_exists = ty.map(|ty| !ty.is_empty()).unwrap_or(false);
+                    let value = node.item.value().unwrap_or("").to_string();
+                    let removable = level == 0 && on_remove.is_some();
+                    let edit_item = node.item.clone();
+                    let edit_parent = node.parent.clone();
+                    let on_remove = on_remove.clone();
+                    let remove_name = name.clone();
                     stack((
                         svg(move || {
                             let svg_str = match node.expanded {
@@ -418,34 +612,53 @@ fn variables_view(window_tab_data: WindowWorkspaceData) -> impl View {
                                     s.hide()
                                 })
                         }),
-                        text(format!(" = {}", node.item.value().unwrap_or("")))
-                            .style(move |s| s.apply_if(reference > 0, |s| s.hide())),
+                        text(format!(" = {value}")).style(move |s| {
+                            s.apply_if(reference > 0 || value.is_empty(), |s| {
+                                s.hide()
+                            })
+                        }),
+                        clickable_icon(
+                            || LapceIcons::CLOSE,
+                            move || {
+                                if let Some(on_remove) = on_remove.as_ref() {
+                                    on_remove(&remove_name);
+                                }
+                            },
+                            || false,
+                            || false,
+                            || "Remove",
+                            config,
+                        )
+                        .on_event_stop(EventListener::PointerDown, |_| {})
+                        .style(move |s| {
+                            s.margin_left(6.0).apply_if(!removable, |s| s.hide())
+                        }),
                     ))
                     .on_click_stop(move |_| {
-                        if reference > 0 {
-                            let dap = local_terminal.get_active_dap(false);
-                            if let Some(dap) = dap {
-                                let process_stopped = dap
-                                    .term_id
-                                    .and_then(|x| {
-                                        local_terminal.get_terminal(x).and_then(
-                                            |t| {
-                                                t.data.with(|r| {
-                                                    r.run_debug
-                                                        .as_ref()
-                                                        .map(|r| r.stopped)
-                                                })
-                                            },
-                                        )
-                                    })
-                                    .unwrap_or(true);
-                                if !process_stopped {
-                                    dap.toggle_expand(
-                                        node.parent.clone(),
-                                        reference,
-                                    );
-                                }
-                            }
+                        if reference > 0
+                            && let Some(dap) = stopped_dap(&terminal, false)
+                        {
+                            dap.toggle_expand(
+                                dap.tree(tree),
+                                node.parent.clone(),
+                                reference,
+                            );
+                        }
+                    })
+                    .on_double_click_stop(move |_| {
+                        if let ScopeOrVar::Var(variable) = &edit_item
+                            && let Some(dap) = stopped_dap(&edit_terminal, false)
+                        {
+                            edit_terminal.common.internal_command.send(
+                                InternalCommand::EditDapVariable {
+                                    edit: DapVariableEdit {
+                                        dap_id: dap.dap_id,
+                                        tree,
+                                        parent: edit_parent.clone(),
+                                        variable: variable.clone(),
+                                    },
+                                },
+                            );
                         }
                     })
                     .style(move |s| {
@@ -468,7 +681,6 @@ fn variables_view(window_tab_data: WindowWorkspaceData) -> impl View {
         .style(|s| s.absolute().size_full()),
     )
     .style(|s| s.width_full().line_height(1.6).flex_grow(1.0).flex_basis(0))
-    .debug_name("debug variables")
 }
 
 fn debug_stack_frames(
@@ -658,18 +870,29 @@ fn breakpoints_view(window_tab_data: WindowWorkspaceData) -> impl View {
     let workspace = window_tab_data.common.workspace.clone();
     let available_width = create_rw_signal(0.0);
     let internal_command = window_tab_data.common.internal_command;
+    let palette = window_tab_data.palette.clone();
     container(
         scroll(
             dyn_stack(
                 move || breakpoints.view_data(),
                 move |(path, breakpoint)| {
-                    (path.clone(), breakpoint.line, breakpoint.active)
+                    (
+                        path.clone(),
+                        breakpoint.line,
+                        breakpoint.active,
+                        breakpoint.description(),
+                    )
                 },
                 move |(path, breakpoint)| {
                     let line = breakpoint.line;
+                    let offset = breakpoint.offset;
                     let full_path = path.clone();
                     let full_path_for_jump = path.clone();
                     let full_path_for_close = path.clone();
+                    let full_path_for_edit = path.clone();
+                    let description = breakpoint.description();
+                    let description_empty = description.is_none();
+                    let palette = palette.clone();
                     let path = if let Some(workspace_path) = workspace.path() {
                         path.strip_prefix(workspace_path)
                             .unwrap_or(&full_path)
@@ -713,6 +936,21 @@ fn breakpoints_view(window_tab_data: WindowWorkspaceData) -> impl View {
                             config,
                         )
                         .on_event_stop(EventListener::PointerDown, |_| {}),
+                        clickable_icon(
+                            move || LapceIcons::DEBUG_BREAKPOINT_EDIT,
+                            move || {
+                                palette.run_breakpoint_edit(BreakpointLocation {
+                                    path: full_path_for_edit.clone(),
+                                    line,
+                                    offset,
+                                });
+                            },
+                            || false,
+                            || false,
+                            || "Edit Condition or Log Message",
+                            config,
+                        )
+                        .on_event_stop(EventListener::PointerDown, |_| {}),
                         checkbox(move || breakpoint.active, config)
                             .style(|s| {
                                 s.margin_right(6.0).cursor(CursorStyle::Pointer)
@@ -750,10 +988,20 @@ fn breakpoints_view(window_tab_data: WindowWorkspaceData) -> impl View {
                                         - size
                                         - 6.0
                                         - size
+                                        - 8.0
+                                        - size
                                         - 8.0,
                                 )
                             },
                         ),
+                        text(description.unwrap_or_default()).style(move |s| {
+                            s.text_ellipsis()
+                                .flex_shrink(1.0)
+                                .color(config.with_color(LapceColor::EDITOR_DIM))
+                                .min_width(0.0)
+                                .margin_left(6.0)
+                                .apply_if(description_empty, |s| s.hide())
+                        }),
                         text(folder).style(move |s| {
                             s.text_ellipsis()
                                 .flex_grow(1.0)
@@ -799,3 +1047,168 @@ fn breakpoints_view(window_tab_data: WindowWorkspaceData) -> impl View {
     .style(|s| s.size_pct(100.0, 100.0))
     .debug_name("debug breakpoints")
 }
+
+/// The exception filters of the last debugger started, with a condition for
+/// the ones supporting it.
+fn exception_breakpoints_view(window_tab_data: WindowWorkspaceData) -> impl View {
+    let config = window_tab_data.common.config;
+    let focus = window_tab_data.common.focus;
+    let terminal = window_tab_data.terminal.clone();
+    let debug = terminal.debug.clone();
+    let global_breakpoints = terminal.debug.global_breakpoints;
+    container(
+        scroll(
+            dyn_stack(
+                move || debug.known_exception_filters(),
+                |filter| filter.filter.clone(),
+                move |filter| {
+                    let toggle_terminal = terminal.clone();
+                    let condition_terminal = terminal.clone();
+                    let toggle_filter = filter.clone();
+                    let condition_filter = filter.clone();
+                    let supports_condition =
+                        filter.supports_condition.unwrap_or(false);
+                    let condition = create_rw_signal(
+                        global_breakpoints
+                            .with_untracked(|b| {
+                                b.exception_filter_condition(&filter.filter)
+                                    .map(|c| c.to_string())
+                            })
+                            .unwrap_or_default(),
+                    );
+                    let enabled = {
+                        let filter = filter.clone();
+                        move || {
+                            global_breakpoints
+                                .with(|b| b.exception_filter_enabled(&filter))
+                        }
+                    };
+                    stack((
+                        checkbox(enabled, config)
+                            .style(|s| {
+                                s.margin_right(6.0).cursor(CursorStyle::Pointer)
+                            })
+                            .on_click_stop(move |_| {
+                                toggle_terminal.update_global_breakpoints(|b| {
+                                    let setting =
+                                        b.exception_filter_mut(&toggle_filter);
+                                    setting.enabled = !setting.enabled;
+                                });
+                            }),
+                        text(filter.label.clone())
+                            .style(|s| s.text_ellipsis().min_width(0.0)),
+                        expression_input(
+                            condition,
+                            "Condition",
+                            config,
+                            focus,
+                            move || {
+                                let value =
+                                    condition.get_untracked().trim().to_string();
+                                condition_terminal.update_global_breakpoints(|b| {
+                                    b.exception_filter_mut(&condition_filter)
+                                        .condition =
+                                        (!value.is_empty()).then_some(value);
+                                });
+                            },
+                        )
+                        .style(move |s| {
+                            s.flex_grow(1.0)
+                                .apply_if(!supports_condition, |s| s.hide())
+                        }),
+                    ))
+                    .style(move |s| {
+                        s.items_center().padding_horiz(10.0).width_pct(100.0).hover(
+                            |s| {
+                                s.background(config.with_color(
+                                    LapceColor::PANEL_HOVERED_BACKGROUND,
+                                ))
+                            },
+                        )
+                    })
+                },
+            )
+            .style(|s| s.flex_col().line_height(1.6).width_pct(100.0)),
+        )
+        .style(|s| s.absolute().size_pct(100.0, 100.0)),
+    )
+    .style(|s| s.size_pct(100.0, 100.0))
+    .debug_name("debug exception breakpoints")
+}
+
+/// The functions to break in, by their name, e.g. `core::panicking::panic`.
+fn function_breakpoints_view(window_tab_data: WindowWorkspaceData) -> impl View {
+    let config = window_tab_data.common.config;
+    let focus = window_tab_data.common.focus;
+    let terminal = window_tab_data.terminal.clone();
+    let global_breakpoints = terminal.debug.global_breakpoints;
+    let input = create_rw_signal(String::new());
+    let remove_terminal = terminal.clone();
+    stack((
+        container(
+            scroll(
+                dyn_stack(
+                    move || global_breakpoints.with(|b| b.functions.clone()),
+                    |breakpoint| breakpoint.name.clone(),
+                    move |breakpoint| {
+                        let terminal = remove_terminal.clone();
+                        let name = breakpoint.name.clone();
+                        stack((
+                            clickable_icon(
+                                || LapceIcons::CLOSE,
+                                move || {
+                                    terminal.update_global_breakpoints(|b| {
+                                        b.functions.retain(|f| f.name != name);
+                                    });
+                                },
+                                || false,
+                                || false,
+                                || "Remove",
+                                config,
+                            )
+                            .on_event_stop(EventListener::PointerDown, |_| {}),
+                            text(breakpoint.name).style(|s| {
+                                s.text_ellipsis().min_width(0.0).margin_left(6.0)
+                            }),
+                        ))
+                        .style(move |s| {
+                            s.items_center()
+                                .padding_horiz(10.0)
+                                .width_pct(100.0)
+                                .hover(|s| {
+                                    s.background(config.with_color(
+                                        LapceColor::PANEL_HOVERED_BACKGROUND,
+                                    ))
+                                })
+                        })
+                    },
+                )
+                .style(|s| s.flex_col().line_height(1.6).width_pct(100.0)),
+            )
+            .style(|s| s.absolute().size_full()),
+        )
+        .style(|s| s.width_full().flex_grow(1.0).flex_basis(0)),
+        expression_input(
+            input,
+            "Add a function breakpoint",
+            config,
+            focus,
+            move || {
+                let name = input.get_untracked().trim().to_string();
+                if !name.is_empty() {
+                    terminal.update_global_breakpoints(|b| {
+                        if !b.functions.iter().any(|f| f.name == name) {
+                            b.functions.push(FunctionBreakpoint {
+                                name,
+                                ..Default::default()
+                            });
+                        }
+                    });
+                }
+                input.set(String::new());
+            },
+        ),
+    ))
+    .style(|s| s.flex_col().size_full())
+    .debug_name("debug function breakpoints")
+}
@@ -0,0 +1,148 @@
+use std::sync::atomic::AtomicU64;
+
+use floem::{
+    View,
+    event::{Event, EventListener},
+    reactive::{SignalGet, SignalUpdate, SignalWith, create_rw_signal},
+    style::CursorStyle,
+    text::TextLayout,
+    views::{
+        Decorators, container, dyn_stack, empty, label, rich_text, scroll, stack,
+    },
+};
+use lapce_core::panel::PanelContainerPosition;
+
+use super::workspace_edit_view::panel_button;
+use crate::{
+    command::InternalCommand,
+    config::color::LapceColor,
+    listener::Listener,
+    markdown::{MarkdownContent, MarkdownLink, link_at},
+    settings::checkbox,
+    window_workspace::WindowWorkspaceData,
+};
+
+/// The hover content pinned with `pin_hover`, optionally following the
+/// cursor of the active editor.
+pub fn documentation_panel(
+    window_tab_data: WindowWorkspaceData,
+    _position: PanelContainerPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let internal_command = window_tab_data.common.internal_command;
+    let pinned = window_tab_data.common.hover.pinned.clone();
+    let follow_cursor = pinned.follow_cursor;
+    let content = pinned.content;
+    let id = AtomicU64::new(0);
+
+    stack((
+        stack((
+            stack((
+                checkbox(move || follow_cursor.get(), config),
+                label(|| "Follow cursor".to_string()),
+            ))
+            .on_click_stop(move |_| follow_cursor.update(|x| *x = !*x))
+            .style(|s| {
+                s.items_center()
+                    .gap(6.0)
+                    .hover(|s| s.cursor(CursorStyle::Pointer))
+            }),
+            empty().style(|s| s.flex_grow(1.0)),
+            panel_button("Clear", config, move || {
+                content.set(Vec::new());
+            }),
+        ))
+        .style(move |s| {
+            s.items_center()
+                .width_pct(100.0)
+                .padding_horiz(10.0)
+                .padding_vert(6.0)
+                .border_bottom(1.0)
+                .border_color(config.with_color(LapceColor::LAPCE_BORDER))
+        }),
+        container(
+            scroll(
+                stack((
+                    label(|| {
+                        "Hover a symbol and run \"Pin Hover\", or turn on \"Follow \
+                         cursor\""
+                            .to_string()
+                    })
+                    .style(move |s| {
+                        s.color(config.with_color(LapceColor::EDITOR_DIM))
+                            .apply_if(!content.with(|x| x.is_empty()), |s| s.hide())
+                    }),
+                    dyn_stack(
+                        move || content.get(),
+                        move |_| {
+                            id.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+                        },
+                        move |content| match content {
+                            MarkdownContent::Text(text_layout, links) => container(
+                                linked_text(text_layout, links, internal_command),
+                            ),
+                            MarkdownContent::Image { .. } => container(empty()),
+                            MarkdownContent::Separator => {
+                                container(empty().style(move |s| {
+                                    s.width_full()
+                                        .margin_vert(5.0)
+                                        .height(1.0)
+                                        .background(
+                                            config.with_color(
+                                                LapceColor::LAPCE_BORDER,
+                                            ),
+                                        )
+                                }))
+                            },
+                        },
+                    )
+                    .style(|s| s.flex_col()),
+                ))
+                .style(|s| s.flex_col().padding_horiz(10.0).padding_vert(5.0)),
+            )
+            .style(|s| s.absolute().size_pct(100.0, 100.0)),
+        )
+        .style(|s| s.size_pct(100.0, 100.0)),
+    ))
+    .style(|s| s.flex_col().size_pct(100.0, 100.0))
+    .debug_name("Documentation Panel")
+}
+
+/// Rendered markdown whose links open in the browser when clicked.
+fn linked_text(
+    text_layout: TextLayout,
+    links: Vec<MarkdownLink>,
+    internal_command: Listener<InternalCommand>,
+) -> impl View {
+    let on_link = create_rw_signal(false);
+    let layout = text_layout.clone();
+    let link_at_point = move |event: &Event| {
+        let pos = match event {
+            Event::PointerMove(pointer_event) => pointer_event.pos,
+            Event::PointerUp(pointer_event) => pointer_event.pos,
+            _ => return None,
+        };
+        let hit = layout.hit_point(pos);
+        if !hit.is_inside {
+            return None;
+        }
+        link_at(&links, hit.index).map(str::to_string)
+    };
+    let link_at_move = link_at_point.clone();
+    rich_text(move || text_layout.clone())
+        .on_event_cont(EventListener::PointerMove, move |event| {
+            let is_link = link_at_move(event).is_some();
+            if on_link.get_untracked() != is_link {
+                on_link.set(is_link);
+            }
+        })
+        .on_event_cont(EventListener::PointerLeave, move |_| on_link.set(false))
+        .on_event_stop(EventListener::PointerUp, move |event| {
+            if let Some(uri) = link_at_point(event) {
+                internal_command.send(InternalCommand::OpenWebUri { uri });
+            }
+        })
+        .style(move |s| {
+            s.apply_if(on_link.get(), |s| s.cursor(CursorStyle::Pointer))
+        })
+}
@@ -0,0 +1,219 @@
+use doc::lines::{register::Clipboard, text::SystemClipboard};
+use floem::{
+    View,
+    action::show_context_menu,
+    event::{Event, EventListener},
+    menu::{Menu, MenuItem},
+    reactive::{SignalGet, SignalUpdate, SignalWith, create_rw_signal},
+    style::CursorStyle,
+    views::{Decorators, container, dyn_stack, label, scroll, stack},
+};
+use lapce_core::{
+    doc::{DocContent, DocHistory},
+    panel::PanelContainerPosition,
+};
+use lapce_rpc::source_control::FileHistoryEntry;
+
+use crate::{
+    blame::relative_time, config::color::LapceColor,
+    window_workspace::WindowWorkspaceData,
+};
+
+/// The commits that changed the active file. Clicking one opens the changes
+/// it made.
+pub fn file_history_panel(
+    window_tab_data: WindowWorkspaceData,
+    _position: PanelContainerPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let history = window_tab_data.source_control.file_history.clone();
+    let main_split = window_tab_data.main_split.clone();
+    let path = history.path;
+    let entries = history.entries;
+    let loading = history.loading;
+    let more = history.more;
+    let list_height = create_rw_signal(0.0);
+    let source_control = window_tab_data.source_control.clone();
+    let workspace = window_tab_data.common.workspace.clone();
+
+    let view_fn = move |entry: FileHistoryEntry| {
+        let FileHistoryEntry {
+            commit,
+            path,
+            previous_path,
+        } = entry;
+        let now = chrono::Utc::now().timestamp();
+        let detail = format!(
+            "{}, {} · {}",
+            commit.author,
+            relative_time(commit.time, now),
+            commit.id.get(..7).unwrap_or(&commit.id)
+        );
+        let summary = commit.summary.clone();
+        let id = commit.id.clone();
+        let old_path = previous_path.unwrap_or_else(|| path.clone());
+        let open_changes = {
+            let main_split = main_split.clone();
+            let (path, old_path, id) = (path.clone(), old_path.clone(), id.clone());
+            move || {
+                main_split.open_revision_diff(
+                    DocContent::History(DocHistory {
+                        path:    old_path.clone(),
+                        version: format!("{id}^"),
+                    }),
+                    DocContent::History(DocHistory {
+                        path:    path.clone(),
+                        version: id.clone(),
+                    }),
+                );
+            }
+        };
+        let main_split = main_split.clone();
+        let current_path = history.path;
+
+        stack((
+            label(move || summary.clone())
+                .style(|s| s.text_ellipsis().min_width(0.0).selectable(false)),
+            label(move || detail.clone()).style(move |s| {
+                s.text_ellipsis()
+                    .min_width(0.0)
+                    .selectable(false)
+                    .color(config.with_color(LapceColor::EDITOR_DIM))
+            }),
+        ))
+        .on_click_stop({
+            let open_changes = open_changes.clone();
+            move |_| open_changes()
+        })
+        .on_event_cont(EventListener::PointerDown, move |event| {
+            let Event::PointerDown(pointer_event) = event else {
+                return;
+            };
+            if !pointer_event.button.is_secondary() {
+                return;
+            }
+            // the file the history was listed for, which may since have been
+            // renamed in `path`
+            let Some(current_path) = current_path.get_untracked() else {
+                return;
+            };
+            let compare = {
+                let main_split = main_split.clone();
+                let (path, id, current_path) =
+                    (path.clone(), id.clone(), current_path.clone());
+                move || {
+                    main_split.open_revision_diff(
+                        DocContent::History(DocHistory {
+                            path:    path.clone(),
+                            version: id.clone(),
+                        }),
+                        DocContent::File {
+                            path:      current_path.clone(),
+                            read_only: false,
+                        },
+                    );
+                }
+            };
+            let restore = {
+                let main_split = main_split.clone();
+                let (path, id, current_path) =
+                    (path.clone(), id.clone(), current_path.clone());
+                move || {
+                    main_split.restore_file_revision(
+                        current_path.clone(),
+                        path.clone(),
+                        id.clone(),
+                    );
+                }
+            };
+            let copy = {
+                let id = id.clone();
+                move || {
+                    SystemClipboard::new().put_string(id.clone());
+                }
+            };
+            let open_changes = open_changes.clone();
+            let menu = Menu::new("")
+                .entry(MenuItem::new("Open Changes").action(open_changes))
+                .entry(MenuItem::new("Compare with Working Copy").action(compare))
+                .entry(MenuItem::new("Restore This Version").action(restore))
+                .separator()
+                .entry(MenuItem::new("Copy Commit Id").action(copy));
+            show_context_menu(menu, None);
+        })
+        .style(move |s| {
+            s.flex_col()
+                .width_pct(100.0)
+                .padding_horiz(10.0)
+                .padding_vert(4.0)
+                .cursor(CursorStyle::Pointer)
+                .hover(|s| {
+                    s.background(
+                        config.with_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                    )
+                })
+        })
+    };
+
+    stack((
+        label(move || {
+            path.get()
+                .map(|path| {
+                    let path = workspace
+                        .path()
+                        .and_then(|root| path.strip_prefix(root).ok())
+                        .unwrap_or(&path);
+                    path.to_string_lossy().to_string()
+                })
+                .unwrap_or_else(|| "Open a file to see its history".to_string())
+        })
+        .style(move |s| {
+            s.text_ellipsis()
+                .width_pct(100.0)
+                .padding_horiz(10.0)
+                .padding_vert(6.0)
+                .border_bottom(1.0)
+                .border_color(config.with_color(LapceColor::LAPCE_BORDER))
+        }),
+        label(move || {
+            if loading.get() {
+                "Loading...".to_string()
+            } else {
+                "No commits found".to_string()
+            }
+        })
+        .style(move |s| {
+            s.padding_horiz(10.0)
+                .padding_vert(5.0)
+                .color(config.with_color(LapceColor::EDITOR_DIM))
+                .apply_if(
+                    path.with(|x| x.is_none())
+                        || (!loading.get() && !entries.with(|x| x.is_empty())),
+                    |s| s.hide(),
+                )
+        }),
+        container(
+            scroll(
+                dyn_stack(
+                    move || entries.get(),
+                    |entry| (entry.commit.id.clone(), entry.path.clone()),
+                    view_fn,
+                )
+                .on_resize(move |rect| list_height.set(rect.height()))
+                .style(|s| s.flex_col().width_pct(100.0)),
+            )
+            .on_scroll(move |rect| {
+                // load the next page before reaching the end of the list
+                if more.get_untracked()
+                    && rect.y1 + rect.height() >= list_height.get_untracked()
+                {
+                    source_control.load_more_file_history();
+                }
+            })
+            .style(|s| s.absolute().size_pct(100.0, 100.0)),
+        )
+        .style(|s| s.size_pct(100.0, 100.0)),
+    ))
+    .style(|s| s.flex_col().size_pct(100.0, 100.0))
+    .debug_name("File History Panel")
+}
@@ -0,0 +1,553 @@
+use std::ops::Range;
+
+use doc::lines::{register::Clipboard, text::SystemClipboard};
+use floem::{
+    View, ViewId,
+    action::show_context_menu,
+    event::{Event, EventListener},
+    menu::{Menu, MenuItem},
+    peniko::kurbo::{BezPath, Circle, Point, Stroke},
+    reactive::{SignalGet, SignalUpdate, SignalWith},
+    style::CursorStyle,
+    views::{
+        Decorators, VirtualVector, container, dyn_stack, label, scroll, stack,
+        virtual_stack,
+    },
+};
+use lapce_core::{
+    doc::{DocContent, DocHistory},
+    icon::LapceIcons,
+    panel::PanelContainerPosition,
+};
+use lapce_rpc::source_control::{FileDiff, LogEntry, PatchSource};
+
+use crate::{
+    app::clickable_icon,
+    blame::relative_time,
+    config::{WithLapceConfig, color::LapceColor},
+    git_graph::{GraphRow, graph_rows},
+    source_control::BranchNameTarget,
+    window_workspace::WindowWorkspaceData,
+};
+
+const ROW_HEIGHT: f64 = 24.0;
+const LANE_WIDTH: f64 = 12.0;
+/// The colors the lanes of the graph cycle through
+const LANE_COLORS: [&str; 6] = [
+    LapceColor::TERMINAL_BLUE,
+    LapceColor::TERMINAL_GREEN,
+    LapceColor::TERMINAL_MAGENTA,
+    LapceColor::TERMINAL_YELLOW,
+    LapceColor::TERMINAL_CYAN,
+    LapceColor::TERMINAL_RED,
+];
+
+struct LogRows(Vec<(GraphRow, LogEntry)>);
+
+impl VirtualVector<(GraphRow, LogEntry)> for LogRows {
+    fn total_len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn slice(
+        &mut self,
+        range: Range<usize>,
+    ) -> impl Iterator<Item = (GraphRow, LogEntry)> {
+        self.0
+            .iter()
+            .skip(range.start)
+            .take(range.len())
+            .cloned()
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+/// The history of the repository as a graph of its branches and merges.
+/// Selecting a commit lists the files it changed, which open in a diff
+/// editor.
+pub fn git_log_panel(
+    window_tab_data: WindowWorkspaceData,
+    _position: PanelContainerPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let source_control = window_tab_data.source_control.clone();
+    let log = source_control.git_log.clone();
+    let entries = log.entries;
+    let more = log.more;
+    let loading = log.loading;
+    let path_filter = log.path_filter;
+    let selected = log.selected;
+    let workspace = window_tab_data.common.workspace.clone();
+
+    let view_fn = {
+        let source_control = source_control.clone();
+        let palette = window_tab_data.palette.clone();
+        move |(row, entry): (GraphRow, LogEntry)| {
+            let LogEntry { commit, refs, .. } = entry;
+            let now = chrono::Utc::now().timestamp();
+            let date = relative_time(commit.time, now);
+            let short_id = commit.id.get(..7).unwrap_or(&commit.id).to_string();
+            let summary = commit.summary.clone();
+            let author = commit.author.clone();
+            let id = commit.id.clone();
+            let ref_names = refs.clone();
+            let source_control = source_control.clone();
+            let palette = palette.clone();
+            let is_selected = {
+                let id = id.clone();
+                move || selected.with(|selected| selected.as_ref() == Some(&id))
+            };
+
+            stack((
+                graph_view(row, config).style(move |s| {
+                    s.apply_if(path_filter.with(|x| x.is_some()), |s| s.hide())
+                }),
+                dyn_stack(
+                    move || refs.clone(),
+                    |name| name.clone(),
+                    move |name| {
+                        label(move || name.clone()).style(move |s| {
+                            s.padding_horiz(4.0)
+                                .margin_right(4.0)
+                                .border(1.0)
+                                .border_radius(4.0)
+                                .border_color(
+                                    config.with_color(LapceColor::LAPCE_BORDER),
+                                )
+                                .color(config.with_color(LapceColor::EDITOR_FOCUS))
+                                .selectable(false)
+                        })
+                    },
+                ),
+                label(move || summary.clone()).style(|s| {
+                    s.text_ellipsis()
+                        .min_width(0.0)
+                        .flex_grow(1.0)
+                        .flex_basis(0.0)
+                        .selectable(false)
+                }),
+                label(move || author.clone()).style(move |s| {
+                    s.text_ellipsis()
+                        .max_width(150.0)
+                        .margin_left(10.0)
+                        .selectable(false)
+                        .color(config.with_color(LapceColor::EDITOR_DIM))
+                }),
+                label(move || date.clone()).style(move |s| {
+                    s.margin_left(10.0)
+                        .selectable(false)
+                        .color(config.with_color(LapceColor::EDITOR_DIM))
+                }),
+                label(move || short_id.clone()).style(move |s| {
+                    s.margin_left(10.0)
+                        .selectable(false)
+                        .color(config.with_color(LapceColor::EDITOR_DIM))
+                }),
+            ))
+            .on_click_stop({
+                let source_control = source_control.clone();
+                let id = id.clone();
+                move |_| source_control.select_log_commit(id.clone())
+            })
+            .on_event_cont(EventListener::PointerDown, move |event| {
+                let Event::PointerDown(pointer_event) = event else {
+                    return;
+                };
+                if !pointer_event.button.is_secondary() {
+                    return;
+                }
+                let mut menu = Menu::new("");
+                // the local branches pointing at the commit
+                let branches = source_control.branches.get_untracked();
+                for branch in ref_names.iter().filter(|name| branches.contains(name))
+                {
+                    let source_control = source_control.clone();
+                    let branch = branch.clone();
+                    menu = menu.entry(
+                        MenuItem::new(format!("Checkout {branch}")).action(
+                            move || source_control.checkout(branch.clone(), false),
+                        ),
+                    );
+                }
+                let checkout = {
+                    let source_control = source_control.clone();
+                    let id = id.clone();
+                    move || source_control.checkout(id.clone(), false)
+                };
+                let create_branch = {
+                    let palette = palette.clone();
+                    let id = id.clone();
+                    move || {
+                        palette.run_scm_branch_name(BranchNameTarget::Create {
+                            start_point: Some(id.clone()),
+                        })
+                    }
+                };
+                let cherry_pick = {
+                    let source_control = source_control.clone();
+                    let id = id.clone();
+                    move || source_control.apply_commit(id.clone(), false)
+                };
+                let revert = {
+                    let source_control = source_control.clone();
+                    let id = id.clone();
+                    move || source_control.apply_commit(id.clone(), true)
+                };
+                let compare = {
+                    let source_control = source_control.clone();
+                    let id = id.clone();
+                    move || source_control.compare(id.clone(), None)
+                };
+                let gutter_base = {
+                    let source_control = source_control.clone();
+                    let id = id.clone();
+                    move || source_control.set_gutter_base(Some(id.clone()))
+                };
+                let export_patch = {
+                    let source_control = source_control.clone();
+                    let id = id.clone();
+                    move || {
+                        source_control.export_patch(PatchSource::Commit(id.clone()))
+                    }
+                };
+                let copy = {
+                    let id = id.clone();
+                    move || {
+                        SystemClipboard::new().put_string(id.clone());
+                    }
+                };
+                let menu = menu
+                    .entry(MenuItem::new("Checkout Commit").action(checkout))
+                    .entry(
+                        MenuItem::new("Create Branch Here...").action(create_branch),
+                    )
+                    .separator()
+                    .entry(MenuItem::new("Cherry-Pick").action(cherry_pick))
+                    .entry(MenuItem::new("Revert").action(revert))
+                    .separator()
+                    .entry(
+                        MenuItem::new("Compare with Working Tree").action(compare),
+                    )
+                    .entry(
+                        MenuItem::new("Show Gutter Changes Against This Commit")
+                            .action(gutter_base),
+                    )
+                    .entry(MenuItem::new("Export as Patch...").action(export_patch))
+                    .separator()
+                    .entry(MenuItem::new("Copy SHA").action(copy));
+                show_context_menu(menu, None);
+            })
+            .style(move |s| {
+                s.items_center()
+                    .width_pct(100.0)
+                    .height(ROW_HEIGHT)
+                    .padding_horiz(10.0)
+                    .cursor(CursorStyle::Pointer)
+                    .apply_if(is_selected(), |s| {
+                        s.background(
+                            config.with_color(LapceColor::PANEL_CURRENT_BACKGROUND),
+                        )
+                    })
+                    .hover(|s| {
+                        s.background(
+                            config.with_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        )
+                    })
+            })
+        }
+    };
+
+    let header = {
+        let source_control = source_control.clone();
+        let main_split = window_tab_data.main_split.clone();
+        stack((
+            label(move || {
+                path_filter
+                    .get()
+                    .map(|path| {
+                        let path = workspace
+                            .path()
+                            .and_then(|root| path.strip_prefix(root).ok())
+                            .unwrap_or(&path);
+                        format!("Commits changing {}", path.display())
+                    })
+                    .unwrap_or_else(|| "All branches".to_string())
+            })
+            .style(|s| s.text_ellipsis().min_width(0.0).flex_grow(1.0)),
+            clickable_icon(
+                || LapceIcons::FILE,
+                {
+                    let source_control = source_control.clone();
+                    move || {
+                        let path = if path_filter.get_untracked().is_some() {
+                            None
+                        } else {
+                            let Some(path) = main_split
+                                .active_editor
+                                .get_untracked()
+                                .and_then(|editor| {
+                                    editor
+                                        .doc()
+                                        .content
+                                        .get_untracked()
+                                        .path()
+                                        .cloned()
+                                })
+                            else {
+                                return;
+                            };
+                            Some(path)
+                        };
+                        path_filter.set(path);
+                        source_control.load_git_log(false);
+                    }
+                },
+                move || path_filter.with(|x| x.is_some()),
+                || false,
+                || "Only Commits Changing the Active File",
+                config,
+            ),
+            clickable_icon(
+                || LapceIcons::DEBUG_RESTART,
+                move || source_control.load_git_log(false),
+                || false,
+                || false,
+                || "Refresh",
+                config,
+            ),
+        ))
+        .style(move |s| {
+            s.items_center()
+                .width_pct(100.0)
+                .padding_horiz(10.0)
+                .padding_vert(4.0)
+                .border_bottom(1.0)
+                .border_color(config.with_color(LapceColor::LAPCE_BORDER))
+        })
+    };
+
+    let commits = {
+        let source_control = source_control.clone();
+        scroll(
+            virtual_stack(
+                move || {
+                    let entries = entries.get();
+                    let rows = if path_filter.with(|x| x.is_none()) {
+                        graph_rows(entries.iter())
+                    } else {
+                        // the parents of the commits aren't all listed
+                        vec![GraphRow::default(); entries.len()]
+                    };
+                    LogRows(rows.into_iter().zip(entries).collect())
+                },
+                |(row, entry)| (entry.commit.id.clone(), row.clone()),
+                view_fn,
+            )
+            .style(|s| s.flex_col().width_pct(100.0)),
+        )
+        .on_scroll(move |rect| {
+            // load the next page before reaching the end of the list
+            let len = entries.with_untracked(|entries| entries.len());
+            if more.get_untracked()
+                && rect.y1 + ROW_HEIGHT * 20.0 >= len as f64 * ROW_HEIGHT
+            {
+                source_control.load_git_log(true);
+            }
+        })
+        .style(|s| s.absolute().size_pct(100.0, 100.0))
+    };
+
+    stack((
+        header,
+        label(move || {
+            if loading.get() {
+                "Loading...".to_string()
+            } else {
+                "No commits found".to_string()
+            }
+        })
+        .style(move |s| {
+            s.padding_horiz(10.0)
+                .padding_vert(5.0)
+                .color(config.with_color(LapceColor::EDITOR_DIM))
+                .apply_if(!entries.with(|x| x.is_empty()), |s| s.hide())
+        }),
+        stack((
+            container(commits)
+                .style(|s| s.flex_grow(1.0).flex_basis(0.0).height_pct(100.0)),
+            commit_files_view(window_tab_data.clone()).style(move |s| {
+                s.width(300.0)
+                    .height_pct(100.0)
+                    .border_left(1.0)
+                    .border_color(config.with_color(LapceColor::LAPCE_BORDER))
+                    .apply_if(selected.with(|x| x.is_none()), |s| s.hide())
+            }),
+        ))
+        .style(|s| s.flex_grow(1.0).flex_basis(0.0).width_pct(100.0)),
+    ))
+    .style(|s| s.flex_col().size_pct(100.0, 100.0))
+    .debug_name("Git Log Panel")
+}
+
+/// The selected commit and the files it changed.
+fn commit_files_view(window_tab_data: WindowWorkspaceData) -> impl View {
+    let config = window_tab_data.common.config;
+    let log = window_tab_data.source_control.git_log.clone();
+    let main_split = window_tab_data.main_split.clone();
+    let workspace = window_tab_data.common.workspace.clone();
+    let selected = log.selected;
+    let entries = log.entries;
+    let files = log.selected_files;
+
+    let title = move || {
+        let id = selected.get()?;
+        entries.with(|entries| {
+            let entry = entries.iter().find(|entry| entry.commit.id == id)?;
+            Some(format!(
+                "{}\n{} · {}",
+                entry.commit.summary,
+                entry.commit.author,
+                entry.commit.id.get(..7).unwrap_or(&entry.commit.id)
+            ))
+        })
+    };
+
+    let file_view = move |diff: FileDiff| {
+        let full_path = diff.path().clone();
+        let path = workspace
+            .path()
+            .and_then(|root| full_path.strip_prefix(root).ok())
+            .unwrap_or(&full_path)
+            .to_string_lossy()
+            .to_string();
+        let color = match &diff {
+            FileDiff::Modified(_) | FileDiff::Renamed(_, _) => {
+                LapceColor::SOURCE_CONTROL_MODIFIED
+            },
+            FileDiff::Added(_) => LapceColor::SOURCE_CONTROL_ADDED,
+            FileDiff::Deleted(_) => LapceColor::SOURCE_CONTROL_REMOVED,
+        };
+        let old_path = match &diff {
+            FileDiff::Renamed(_, old) => old.clone(),
+            _ => full_path.clone(),
+        };
+        let main_split = main_split.clone();
+        label(move || path.clone())
+            .on_click_stop(move |_| {
+                let Some(id) = selected.get_untracked() else {
+                    return;
+                };
+                main_split.open_revision_diff(
+                    DocContent::History(DocHistory {
+                        path:    old_path.clone(),
+                        version: format!("{id}^"),
+                    }),
+                    DocContent::History(DocHistory {
+                        path:    full_path.clone(),
+                        version: id,
+                    }),
+                );
+            })
+            .style(move |s| {
+                s.text_ellipsis()
+                    .width_pct(100.0)
+                    .padding_horiz(10.0)
+                    .selectable(false)
+                    .color(config.with_color(color))
+                    .hover(|s| {
+                        s.cursor(CursorStyle::Pointer).background(
+                            config.with_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        )
+                    })
+            })
+    };
+
+    stack((
+        label(move || title().unwrap_or_default()).style(move |s| {
+            s.width_pct(100.0)
+                .padding_horiz(10.0)
+                .padding_vert(4.0)
+                .border_bottom(1.0)
+                .border_color(config.with_color(LapceColor::LAPCE_BORDER))
+        }),
+        container(
+            scroll(
+                dyn_stack(move || files.get(), |diff| diff.clone(), file_view)
+                    .style(|s| s.flex_col().width_pct(100.0)),
+            )
+            .style(|s| s.absolute().size_pct(100.0, 100.0)),
+        )
+        .style(|s| s.flex_grow(1.0).flex_basis(0.0).width_pct(100.0)),
+    ))
+    .style(|s| s.flex_col())
+}
+
+fn graph_view(row: GraphRow, config: WithLapceConfig) -> GraphView {
+    let width = row.lanes as f32 * LANE_WIDTH as f32;
+    GraphView {
+        id: ViewId::new(),
+        row,
+        config,
+    }
+    .style(move |s| s.min_width(width).width(width).height(ROW_HEIGHT))
+}
+
+/// Draws the lines and the dot of a commit in the graph of the log.
+struct GraphView {
+    id:     ViewId,
+    row:    GraphRow,
+    config: WithLapceConfig,
+}
+
+impl View for GraphView {
+    fn id(&self) -> ViewId {
+        self.id
+    }
+
+    fn paint(&mut self, cx: &mut floem::context::PaintCx) {
+        let height = self
+            .id
+            .get_layout()
+            .map(|layout| layout.size.height as f64)
+            .unwrap_or(ROW_HEIGHT);
+        let middle = height / 2.0;
+        let x = |lane: usize| LANE_WIDTH * (lane as f64 + 0.5);
+        let color = |lane: usize| {
+            self.config
+                .with_color(LANE_COLORS[lane % LANE_COLORS.len()])
+        };
+        let stroke = Stroke::new(1.5);
+
+        // lines merging into the commit keep the color of the lane they come
+        // from, and lines leaving it take the color of the lane they go to
+        let lines = self
+            .row
+            .top
+            .iter()
+            .map(|(from, to)| ((*from, 0.0), (*to, middle), *from))
+            .chain(
+                self.row
+                    .bottom
+                    .iter()
+                    .map(|(from, to)| ((*from, middle), (*to, height), *to)),
+            );
+        for ((from, y0), (to, y1), lane) in lines {
+            let (p0, p1) = (Point::new(x(from), y0), Point::new(x(to), y1));
+            let mut path = BezPath::new();
+            path.move_to(p0);
+            if from == to {
+                path.line_to(p1);
+            } else {
+                let y = (y0 + y1) / 2.0;
+                path.curve_to(Point::new(p0.x, y), Point::new(p1.x, y), p1);
+            }
+            cx.stroke(&path, color(lane), &stroke);
+        }
+        cx.fill(
+            &Circle::new(Point::new(x(self.row.lane), middle), 3.5),
+            color(self.row.lane),
+            0.0,
+        );
+    }
+}
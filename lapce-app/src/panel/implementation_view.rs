@@ -1,26 +1,48 @@
-use std::{ops::AddAssign, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    ops::AddAssign,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
+use doc::lines::{register::Clipboard, text::SystemClipboard};
 use floem::{
     IntoView, View, ViewId,
-    reactive::{RwSignal, Scope, SignalGet, SignalUpdate},
+    event::EventListener,
+    ext_event::create_ext_action,
+    prelude::text_input,
+    reactive::{
+        RwSignal, Scope, SignalGet, SignalUpdate, create_effect, create_rw_signal,
+        use_context,
+    },
     style::CursorStyle,
     views::{
         Decorators, VirtualVector, container, label, scroll, stack, svg,
         virtual_stack,
     },
 };
+use globset::{Glob, GlobMatcher};
 use im::HashMap;
 use itertools::Itertools;
-use lapce_core::{icon::LapceIcons, panel::PanelContainerPosition};
-use lapce_rpc::file_line::FileLine;
-use lsp_types::{Location, SymbolKind, request::GotoImplementationResponse};
+use lapce_core::{
+    icon::LapceIcons,
+    panel::{PanelContainerPosition, PanelKind},
+};
+use lapce_rpc::{file_line::FileLine, proxy::ProxyResponse};
+use lsp_types::{
+    DocumentHighlight, DocumentHighlightKind, DocumentSymbol,
+    DocumentSymbolResponse, Location, Position, Range, SymbolKind,
+    request::GotoImplementationResponse,
+};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     command::InternalCommand,
-    common::{TabHead, common_tab_header},
-    config::color::LapceColor,
+    common::{TabHead, Tabs, common_tab_header},
+    config::{WithLapceConfig, color::LapceColor},
+    db::LapceDb,
     editor::location::EditorLocation,
-    window_workspace::WindowWorkspaceData,
+    window_workspace::{Focus, WindowWorkspaceData},
 };
 
 pub fn implementation_panel(
@@ -32,34 +54,176 @@ pub fn implementation_panel(
             window_tab_data.clone(),
             window_tab_data.main_split.implementations,
         ),
-        common_reference_panel(window_tab_data.clone(), _position, move || {
-            window_tab_data
-                .main_split
-                .implementations
-                .get_active_content()
-                .unwrap_or_default()
-        })
+        common_reference_panel(
+            window_tab_data.clone(),
+            PanelKind::Implementation,
+            move || {
+                window_tab_data
+                    .main_split
+                    .implementations
+                    .get_active_content()
+                    .unwrap_or_default()
+            },
+        )
         .debug_name("implementation tabs"),
     ))
     .style(|x| x.flex_col().width_full())
     .debug_name("implementation panel")
 }
+
+/// The toolbar above the references: grouping, path exclusion and export.
+fn reference_toolbar(
+    window_tab_data: WindowWorkspaceData,
+    kind: PanelKind,
+    group: RwSignal<ReferenceGroup>,
+    exclude: RwSignal<String>,
+    on_export: impl Fn() + 'static,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let focus = window_tab_data.common.focus;
+    stack((
+        toolbar_button(
+            move || match group.get() {
+                ReferenceGroup::File => "Group: File".to_string(),
+                ReferenceGroup::Symbol => "Group: Symbol".to_string(),
+            },
+            config,
+            move || {
+                group.update(|x| {
+                    *x = match x {
+                        ReferenceGroup::File => ReferenceGroup::Symbol,
+                        ReferenceGroup::Symbol => ReferenceGroup::File,
+                    }
+                })
+            },
+        ),
+        text_input(exclude)
+            .placeholder("Exclude paths, e.g. tests/**")
+            .keyboard_navigable()
+            .on_event_cont(EventListener::PointerDown, move |_| {
+                focus.set(Focus::Panel(kind));
+            })
+            .style(move |s| {
+                s.flex_grow(1.0)
+                    .min_width(0.0)
+                    .padding_horiz(6.0)
+                    .border(1.0)
+                    .border_radius(6.0)
+                    .border_color(config.with_color(LapceColor::LAPCE_BORDER))
+            }),
+        toolbar_button(|| "Copy".to_string(), config, on_export),
+    ))
+    .style(move |s| {
+        s.items_center()
+            .width_pct(100.0)
+            .padding_horiz(6.0)
+            .padding_vert(4.0)
+            .gap(6.0)
+            .border_bottom(1.0)
+            .border_color(config.with_color(LapceColor::LAPCE_BORDER))
+    })
+}
+
+fn toolbar_button(
+    text: impl Fn() -> String + 'static,
+    config: WithLapceConfig,
+    on_click: impl Fn() + 'static,
+) -> impl View {
+    label(text)
+        .on_click_stop(move |_| on_click())
+        .style(move |s| {
+            s.padding_horiz(6.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(config.with_color(LapceColor::LAPCE_BORDER))
+                .hover(|s| {
+                    s.cursor(CursorStyle::Pointer).background(
+                        config.with_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                    )
+                })
+                .selectable(false)
+        })
+}
+
+/// `path` relative to `workspace`, as it is shown and filtered.
+fn relative_path<'a>(path: &'a Path, workspace: Option<&Path>) -> &'a Path {
+    workspace
+        .and_then(|x| path.strip_prefix(x).ok())
+        .unwrap_or(path)
+}
+
+/// Compile the exclusion glob, ignoring it while it is empty or invalid.
+fn exclude_matcher(glob: &str) -> Option<GlobMatcher> {
+    let glob = glob.trim();
+    if glob.is_empty() {
+        return None;
+    }
+    Glob::new(glob).ok().map(|x| x.compile_matcher())
+}
+
 pub fn common_reference_panel(
     window_tab_data: WindowWorkspaceData,
-    _position: PanelContainerPosition,
-    each_fn: impl Fn() -> ReferencesRoot + 'static,
+    kind: PanelKind,
+    each_fn: impl Fn() -> ReferencesRoot + 'static + Clone,
 ) -> impl View {
     let config = window_tab_data.common.config;
     let ui_line_height = window_tab_data.common.ui_line_height;
-    scroll(
+    let db: Arc<LapceDb> = use_context().unwrap();
+    let workspace = window_tab_data.workspace.clone();
+    let filter = db
+        .get_reference_filters(&workspace)
+        .ok()
+        .and_then(|mut filters| filters.remove(&kind))
+        .unwrap_or_default();
+    let group = create_rw_signal(filter.group);
+    let exclude = create_rw_signal(filter.exclude);
+    let local_task = window_tab_data.common.local_task.clone();
+    create_effect(move |saved| {
+        let filter = ReferenceFilter {
+            group:   group.get(),
+            exclude: exclude.get(),
+        };
+        // the first run only reads what was saved
+        if saved.is_some() {
+            db.save_reference_filter(workspace.clone(), kind, filter, &local_task);
+        }
+    });
+    let export_fn = each_fn.clone();
+    let export_data = window_tab_data.clone();
+    let toolbar = reference_toolbar(
+        window_tab_data.clone(),
+        kind,
+        group,
+        exclude,
+        move || {
+            let workspace = export_data.workspace.path().cloned();
+            let root = export_fn().grouped(
+                group.get_untracked(),
+                exclude_matcher(&exclude.get_untracked()).as_ref(),
+                workspace.as_deref(),
+            );
+            let text = root.export(workspace.as_deref());
+            let count = text.lines().count();
+            SystemClipboard::new().put_string(text);
+            export_data.show_status_message(format!("Copied {count} references"));
+        },
+    );
+    let workspace_path = window_tab_data.workspace.path().cloned();
+    let list = scroll(
         virtual_stack(
             // VirtualDirection::Vertical,
             // VirtualItemSize::Fixed(Box::new(move || ui_line_height.get())),
-            each_fn,
+            move || {
+                each_fn().grouped(
+                    group.get(),
+                    exclude_matcher(&exclude.get()).as_ref(),
+                    workspace_path.as_deref(),
+                )
+            },
             move |(_, _, data)| data.view_id(),
             move |(_, level, rw_data)| {
                 match rw_data {
-                    ReferenceLocation::File { path, open, .. } => stack((
+                    ReferenceLocation::File { path, symbol, open, .. } => stack((
                         container(
                             svg(move || {
                                 let svg_str = match open.get() {
@@ -114,7 +278,10 @@ pub fn common_reference_panel(
                                 .color(color.get()
                                 )
                         }),
-                        label(move || format!("{path:?}", ))
+                        label(move || match &symbol {
+                            Some(symbol) => format!("{symbol}  {}", path.display()),
+                            None => format!("{}", path.display()),
+                        })
                             .style(move |s| {
                                 s.margin_left(6.0).color(
                                     config.with_color(LapceColor::EDITOR_DIM),
@@ -134,27 +301,47 @@ pub fn common_reference_panel(
                                 .cursor(CursorStyle::Pointer)
                             })
                     }),
-                    ReferenceLocation::Line { file_line, .. } => stack((container(
-                        label(move || format!("{} {}", file_line.position.line + 1, file_line.content))
-                            .style(move |s| {
-                                s.margin_left(6.0).color(
-                                    config.with_color(LapceColor::EDITOR_DIM),
-                                )
-                            })
-                            .into_any(),
-                    )
-                    .style(move |s| {
-                        s.padding_right(5.0)
-                            .height(ui_line_height.get())
-                            .padding_left((level * 20) as f32)
-                            .items_center()
-                            .hover(|s| {
-                                s.background(
-                                    config.with_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                    ReferenceLocation::Line { file_line, kind, .. } => stack((
+                        container(
+                            label(move || format!("{} {}", file_line.position.line + 1, file_line.content))
+                                .style(move |s| {
+                                    s.margin_left(6.0).color(
+                                        config.with_color(LapceColor::EDITOR_DIM),
+                                    )
+                                })
+                                .into_any(),
+                        )
+                        .style(move |s| {
+                            s.padding_right(5.0)
+                                .height(ui_line_height.get())
+                                .padding_left((level * 20) as f32)
+                                .items_center()
+                                .hover(|s| {
+                                    s.background(
+                                        config.with_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                                    )
+                                    .cursor(CursorStyle::Pointer)
+                                })
+                        }),
+                        label(move || match kind {
+                            Some(DocumentHighlightKind::WRITE) => "write".to_string(),
+                            _ => "read".to_string(),
+                        })
+                        .style(move |s| {
+                            let color = if kind == Some(DocumentHighlightKind::WRITE) {
+                                LapceColor::LAPCE_WARN
+                            } else {
+                                LapceColor::EDITOR_DIM
+                            };
+                            s.margin_left(6.0)
+                                .color(config.with_color(color))
+                                .apply_if(
+                                    kind != Some(DocumentHighlightKind::READ)
+                                        && kind != Some(DocumentHighlightKind::WRITE),
+                                    |s| s.hide(),
                                 )
-                                .cursor(CursorStyle::Pointer)
-                            })
-                    }),))
+                        }),
+                    ))
                     .on_click_stop({
                         let window_tab_data = window_tab_data.clone();
                         let position = file_line.position;
@@ -193,7 +380,8 @@ pub fn common_reference_panel(
         )
         .style(|s| s.flex_col().absolute().min_width_full()),
     )
-        .style(|s| s.size_full())
+        .style(|s| s.size_full());
+    stack((toolbar, list)).style(|s| s.flex_col().size_full())
 }
 
 pub fn map_to_location(resp: Option<GotoImplementationResponse>) -> Vec<Location> {
@@ -215,54 +403,281 @@ pub fn map_to_location(resp: Option<GotoImplementationResponse>) -> Vec<Location
     }
 }
 
+/// The maximum number of files for which read/write access and containing
+/// symbols are requested after a query.
+const MAX_DETAIL_FILES: usize = 100;
+
 pub fn init_implementation_root(
     items: Vec<FileLine>,
     scope: Scope,
 ) -> ReferencesRoot {
-    let mut refs_map: HashMap<PathBuf, HashMap<u32, Reference>> = HashMap::new();
-    for item in items {
-        let entry = refs_map.entry(item.path.clone()).or_default();
-        (*entry).insert(
-            item.position.line,
-            Reference::Line {
-                location: ReferenceLocation::Line {
-                    file_line: item,
-                    view_id:   ViewId::new(),
-                },
-            },
+    let mut items: Vec<ReferenceItem> = items
+        .into_iter()
+        .map(|file_line| ReferenceItem {
+            file_line,
+            kind: None,
+            symbol: None,
+            view_id: ViewId::new(),
+        })
+        .collect();
+    items.sort_by(|x, y| {
+        (&x.file_line.path, x.file_line.position)
+            .cmp(&(&y.file_line.path, y.file_line.position))
+    });
+    items.dedup_by(|x, y| {
+        x.file_line.path == y.file_line.path
+            && x.file_line.position.line == y.file_line.position.line
+    });
+    let mut root = ReferencesRoot {
+        items,
+        ..Default::default()
+    };
+    root.init_groups(scope);
+    root
+}
+
+/// Ask the servers for the read/write access of every reference, through
+/// document highlights, and for the document symbol containing it. The
+/// results are merged into the tab `id` as they arrive.
+pub fn resolve_reference_details(
+    window_tab_data: &WindowWorkspaceData,
+    tabs: Tabs<ReferencesRoot>,
+    id: ViewId,
+) {
+    let Some(root) = tabs.get_content(id) else {
+        return;
+    };
+    let scope = window_tab_data.scope;
+    let proxy = window_tab_data.common.proxy.proxy_rpc.clone();
+    let files: Vec<(PathBuf, Position)> = root
+        .items
+        .iter()
+        .map(|x| (x.file_line.path.clone(), x.file_line.position))
+        .dedup_by(|x, y| x.0 == y.0)
+        .take(MAX_DETAIL_FILES)
+        .collect();
+    for (path, position) in files {
+        let highlight_path = path.clone();
+        proxy.document_highlight(
+            path.clone(),
+            position,
+            create_ext_action(scope, move |(_, result)| {
+                if let Ok(ProxyResponse::DocumentHighlightResponse {
+                    items: Some(items),
+                }) = result
+                {
+                    tabs.update_content(id, |root| {
+                        root.set_kinds(&highlight_path, &items)
+                    });
+                }
+            }),
+        );
+        let symbol_path = path.clone();
+        proxy.get_document_symbols(
+            path,
+            create_ext_action(scope, move |(_, result)| {
+                if let Ok(ProxyResponse::GetDocumentSymbols { resp }) = result {
+                    tabs.update_content(id, |root| {
+                        root.set_symbols(&symbol_path, &resp, scope)
+                    });
+                }
+            }),
         );
     }
+}
 
-    let mut refs = Vec::new();
-    for (path, items) in refs_map {
-        let open = scope.create_rw_signal(true);
-        let children = items
-            .into_iter()
-            .sorted_by(|x, y| x.0.cmp(&y.0))
-            .map(|x| x.1)
-            .collect();
-        let ref_item = Reference::File {
-            location: ReferenceLocation::File {
-                open,
-                path,
-                view_id: ViewId::new(),
-            },
-            children,
-            open,
-        };
-        refs.push(ref_item);
+/// The name of the innermost symbol whose range contains `line`, joined with
+/// the names of its parents, e.g. `Foo::bar`.
+fn containing_symbol(resp: &DocumentSymbolResponse, line: u32) -> Option<String> {
+    fn contains(range: &Range, line: u32) -> bool {
+        range.start.line <= line && line <= range.end.line
     }
-    ReferencesRoot { children: refs }
+    fn nested(symbols: &[DocumentSymbol], line: u32) -> Option<Vec<&str>> {
+        let symbol = symbols.iter().find(|x| contains(&x.range, line))?;
+        let mut names = vec![symbol.name.as_str()];
+        if let Some(children) = symbol.children.as_deref()
+            && let Some(inner) = nested(children, line)
+        {
+            names.extend(inner);
+        }
+        Some(names)
+    }
+    match resp {
+        DocumentSymbolResponse::Nested(symbols) => {
+            nested(symbols, line).map(|x| x.join("::"))
+        },
+        DocumentSymbolResponse::Flat(symbols) => symbols
+            .iter()
+            .filter(|x| contains(&x.location.range, line))
+            .min_by_key(|x| x.location.range.end.line - x.location.range.start.line)
+            .map(|x| match &x.container_name {
+                Some(container) if !container.is_empty() => {
+                    format!("{container}::{}", x.name)
+                },
+                _ => x.name.clone(),
+            }),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ReferenceGroup {
+    #[default]
+    File,
+    Symbol,
+}
+
+/// How the results of a references or implementations panel are shown, kept
+/// per workspace.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ReferenceFilter {
+    pub group:   ReferenceGroup,
+    /// A glob of the paths to leave out, relative to the workspace
+    pub exclude: String,
 }
 
+#[derive(Clone)]
+pub struct ReferenceItem {
+    pub file_line: FileLine,
+    /// Read or write access, when the server reports it
+    pub kind:      Option<DocumentHighlightKind>,
+    /// The document symbol containing the reference
+    pub symbol:    Option<String>,
+    view_id:       ViewId,
+}
+
+/// The group a reference is listed under: its file, and the containing
+/// symbol when grouping by symbol.
+type GroupKey = (PathBuf, Option<String>);
+
 #[derive(Clone, Default)]
 pub struct ReferencesRoot {
+    pub(crate) items:    Vec<ReferenceItem>,
+    groups:              HashMap<GroupKey, (RwSignal<bool>, ViewId)>,
     pub(crate) children: Vec<Reference>,
 }
 
 impl TabHead for ReferencesRoot {}
 
 impl ReferencesRoot {
+    fn init_groups(&mut self, scope: Scope) {
+        for item in &self.items {
+            let keys = [
+                (item.file_line.path.clone(), None),
+                (item.file_line.path.clone(), item.symbol.clone()),
+            ];
+            for key in keys {
+                self.groups.entry(key).or_insert_with(|| {
+                    (scope.create_rw_signal(true), ViewId::new())
+                });
+            }
+        }
+    }
+
+    fn set_kinds(&mut self, path: &Path, highlights: &[DocumentHighlight]) {
+        for item in &mut self.items {
+            if item.file_line.path != path {
+                continue;
+            }
+            if let Some(highlight) = highlights
+                .iter()
+                .find(|x| x.range.start == item.file_line.position)
+            {
+                item.kind = highlight.kind;
+            }
+        }
+    }
+
+    fn set_symbols(
+        &mut self,
+        path: &Path,
+        resp: &DocumentSymbolResponse,
+        scope: Scope,
+    ) {
+        for item in &mut self.items {
+            if item.file_line.path == path {
+                item.symbol = containing_symbol(resp, item.file_line.position.line);
+            }
+        }
+        self.init_groups(scope);
+    }
+
+    /// The tree to display: the references grouped by `group`, without the
+    /// ones whose path relative to `workspace` matches `exclude`.
+    pub fn grouped(
+        &self,
+        group: ReferenceGroup,
+        exclude: Option<&GlobMatcher>,
+        workspace: Option<&Path>,
+    ) -> Self {
+        let mut groups: BTreeMap<GroupKey, Vec<Reference>> = BTreeMap::new();
+        for item in &self.items {
+            if exclude.is_some_and(|x| {
+                x.is_match(relative_path(&item.file_line.path, workspace))
+            }) {
+                continue;
+            }
+            let symbol = match group {
+                ReferenceGroup::File => None,
+                ReferenceGroup::Symbol => item.symbol.clone(),
+            };
+            groups
+                .entry((item.file_line.path.clone(), symbol))
+                .or_default()
+                .push(Reference::Line {
+                    location: ReferenceLocation::Line {
+                        file_line: item.file_line.clone(),
+                        kind:      item.kind,
+                        view_id:   item.view_id,
+                    },
+                });
+        }
+        let children = groups
+            .into_iter()
+            .filter_map(|(key, children)| {
+                let (open, view_id) = *self.groups.get(&key)?;
+                let (path, symbol) = key;
+                Some(Reference::File {
+                    location: ReferenceLocation::File {
+                        path,
+                        symbol,
+                        open,
+                        view_id,
+                    },
+                    open,
+                    children,
+                })
+            })
+            .collect();
+        Self {
+            items: Vec::new(),
+            groups: HashMap::new(),
+            children,
+        }
+    }
+
+    /// One `file:line: text` line per displayed reference, with paths
+    /// relative to `workspace`.
+    pub fn export(&self, workspace: Option<&Path>) -> String {
+        let mut lines = Vec::new();
+        for child in &self.children {
+            let Reference::File { children, .. } = child else {
+                continue;
+            };
+            for child in children {
+                if let ReferenceLocation::Line { file_line, .. } = child.location() {
+                    let path = relative_path(&file_line.path, workspace);
+                    lines.push(format!(
+                        "{}:{}: {}",
+                        path.display(),
+                        file_line.position.line + 1,
+                        file_line.content.trim()
+                    ));
+                }
+            }
+        }
+        lines.join("\n")
+    }
+
     pub fn total(&self) -> usize {
         let mut total = 0;
         for child in &self.children {
@@ -324,11 +739,13 @@ pub enum Reference {
 pub enum ReferenceLocation {
     File {
         path:    PathBuf,
+        symbol:  Option<String>,
         open:    RwSignal<bool>,
         view_id: ViewId,
     },
     Line {
         file_line: FileLine,
+        kind:      Option<DocumentHighlightKind>,
         view_id:   ViewId,
     },
 }
@@ -403,3 +820,68 @@ impl Reference {
         children
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use lsp_types::{
+        DocumentSymbol, DocumentSymbolResponse, Position, Range, SymbolKind,
+    };
+
+    use super::{containing_symbol, exclude_matcher, relative_path};
+
+    #[allow(deprecated)]
+    fn symbol(
+        name: &str,
+        start: u32,
+        end: u32,
+        children: Option<Vec<DocumentSymbol>>,
+    ) -> DocumentSymbol {
+        let range = Range::new(Position::new(start, 0), Position::new(end, 0));
+        DocumentSymbol {
+            name: name.to_string(),
+            detail: None,
+            kind: SymbolKind::FUNCTION,
+            tags: None,
+            deprecated: None,
+            range,
+            selection_range: range,
+            children,
+        }
+    }
+
+    #[test]
+    fn test_containing_symbol() {
+        let resp = DocumentSymbolResponse::Nested(vec![
+            symbol("main", 0, 4, None),
+            symbol(
+                "Foo",
+                6,
+                20,
+                Some(vec![
+                    symbol("new", 7, 10, None),
+                    symbol("bar", 12, 18, None),
+                ]),
+            ),
+        ]);
+        assert_eq!(containing_symbol(&resp, 2).as_deref(), Some("main"));
+        assert_eq!(containing_symbol(&resp, 14).as_deref(), Some("Foo::bar"));
+        assert_eq!(containing_symbol(&resp, 19).as_deref(), Some("Foo"));
+        assert_eq!(containing_symbol(&resp, 5), None);
+    }
+
+    #[test]
+    fn test_exclude_relative_path() {
+        let workspace = Some(Path::new("/ws"));
+        let excluded = |glob: &str, path: &str| {
+            exclude_matcher(glob).is_some_and(|x| {
+                x.is_match(relative_path(Path::new(path), workspace))
+            })
+        };
+        assert!(excluded("tests/**", "/ws/tests/a.rs"));
+        assert!(!excluded("tests/**", "/ws/src/tests.rs"));
+        assert!(excluded("**/tests/**", "/ws/crate/tests/a.rs"));
+        assert!(!excluded(" ", "/ws/tests/a.rs"));
+    }
+}
@@ -2,6 +2,9 @@ pub mod call_hierarchy_view;
 pub mod data;
 pub mod debug_view;
 pub mod document_symbol;
+pub mod documentation_view;
+pub mod file_history_view;
+pub mod git_log_view;
 pub mod global_search_view;
 pub mod implementation_view;
 pub mod plugin_view;
@@ -12,3 +15,4 @@ mod rust_build_panel;
 pub mod source_control_view;
 pub mod terminal_view;
 pub mod view;
+pub mod workspace_edit_view;
@@ -2,7 +2,7 @@ use floem::{
     View,
     views::{Decorators, stack},
 };
-use lapce_core::panel::PanelContainerPosition;
+use lapce_core::panel::{PanelContainerPosition, PanelKind};
 
 use crate::{
     common::common_tab_header, panel::implementation_view::common_reference_panel,
@@ -18,13 +18,17 @@ pub fn references_panel(
             window_tab_data.clone(),
             window_tab_data.main_split.references,
         ),
-        common_reference_panel(window_tab_data.clone(), _position, move || {
-            window_tab_data
-                .main_split
-                .references
-                .get_active_content()
-                .unwrap_or_default()
-        })
+        common_reference_panel(
+            window_tab_data.clone(),
+            PanelKind::References,
+            move || {
+                window_tab_data
+                    .main_split
+                    .references
+                    .get_active_content()
+                    .unwrap_or_default()
+            },
+        )
         .debug_name("references panel"),
     ))
     .style(|x| x.flex_col().width_full().height_full())
@@ -8,24 +8,33 @@ use floem::{
     kurbo::Affine,
     menu::{Menu, MenuItem},
     peniko::kurbo::Rect,
-    reactive::{Scope, SignalGet, SignalTrack, SignalUpdate, SignalWith},
+    reactive::{
+        RwSignal, Scope, SignalGet, SignalTrack, SignalUpdate, SignalWith,
+        create_rw_signal,
+    },
     style::{CursorStyle, Style},
-    views::{Decorators, container, dyn_stack, label, scroll, stack, svg, text},
+    views::{
+        Decorators, container, dyn_stack, empty, label, scroll, stack, svg, text,
+    },
 };
 use lapce_core::{
+    doc::{DocContent, DocHistory},
     icon::LapceIcons,
     panel::{PanelContainerPosition, PanelKind, PanelSection},
 };
-use lapce_rpc::source_control::FileDiff;
+use lapce_rpc::source_control::{
+    FileDiff, PatchFile, PatchHunk, StageSelection, StashEntry,
+};
 use log::error;
 
 use super::view::foldable_panel_section;
 use crate::{
+    app::clickable_icon,
     command::{CommandKind, InternalCommand, LapceCommand, LapceWorkbenchCommand},
-    config::color::LapceColor,
+    config::{WithLapceConfig, color::LapceColor},
     editor::view::editor_view,
     settings::checkbox,
-    source_control::SourceControlData,
+    source_control::{CompareData, PatchData, SourceControlData},
     window_workspace::{Focus, WindowWorkspaceData},
 };
 pub fn source_control_panel(
@@ -197,45 +206,193 @@ pub fn source_control_panel(
                     .border_color(caret_color.get())
                     .background(bg.get())
             }),
+            commit_options_view(source_control.clone()),
             {
                 let source_control = source_control.clone();
-                label(|| "Commit".to_string())
-                    .on_click_stop(move |_| {
-                        source_control.commit();
-                    })
-                    .style(move |s| {
-                        let (caret_color, bg, abg) = config.signal(|config| {
-                            (
-                                config.color(LapceColor::LAPCE_BORDER),
-                                config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
-                                config.color(
-                                    LapceColor::PANEL_HOVERED_ACTIVE_BACKGROUND,
-                                ),
-                            )
-                        });
-                        s.margin_top(10.0)
-                            .line_height(1.6)
-                            .width_pct(100.0)
-                            .justify_center()
-                            .border(1.0)
-                            .border_radius(6.0)
-                            .border_color(caret_color.get())
-                            .hover(|s| {
-                                s.cursor(CursorStyle::Pointer).background(bg.get())
-                            })
-                            .active(|s| s.background(abg.get()))
-                            .selectable(false)
-                    })
+                let amend = source_control.amend;
+                label(move || {
+                    if amend.get() {
+                        "Amend Last Commit".to_string()
+                    } else {
+                        "Commit".to_string()
+                    }
+                })
+                .on_click_stop(move |_| {
+                    source_control.commit();
+                })
+                .style(move |s| {
+                    let (caret_color, bg, abg) = config.signal(|config| {
+                        (
+                            config.color(LapceColor::LAPCE_BORDER),
+                            config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                            config
+                                .color(LapceColor::PANEL_HOVERED_ACTIVE_BACKGROUND),
+                        )
+                    });
+                    s.margin_top(10.0)
+                        .line_height(1.6)
+                        .width_pct(100.0)
+                        .justify_center()
+                        .border(1.0)
+                        .border_radius(6.0)
+                        .border_color(caret_color.get())
+                        .hover(|s| {
+                            s.cursor(CursorStyle::Pointer).background(bg.get())
+                        })
+                        .active(|s| s.background(abg.get()))
+                        .selectable(false)
+                })
             },
         ))
         .style(|s| s.flex_col().width_pct(100.0).padding(10.0)),
-        foldable_panel_section(
-            text("Changes"),
-            file_diffs_view(source_control, scope),
-            window_tab_data.panel.section_open(PanelSection::Changes),
-            config,
-        )
-        .style(|s| s.flex_col().size_pct(100.0, 100.0)),
+        {
+            let open = window_tab_data
+                .panel
+                .section_open(PanelSection::MergeChanges);
+            let conflicts = source_control.conflicts;
+            let internal_command = source_control.common.internal_command;
+            foldable_panel_section(
+                section_header(
+                    "Merge Changes",
+                    LapceIcons::SPLIT_HORIZONTAL,
+                    "Open Merge Editor",
+                    move || {
+                        if let Some(path) =
+                            conflicts.with_untracked(|x| x.front().cloned())
+                        {
+                            internal_command
+                                .send(InternalCommand::OpenMergeEditor { path });
+                        }
+                    },
+                    config,
+                ),
+                conflicts_view(source_control.clone()),
+                open,
+                config,
+            )
+            .style(move |s| {
+                section_style(s, open.get())
+                    .apply_if(conflicts.with(|x| x.is_empty()), |s| s.hide())
+            })
+        },
+        {
+            let open = window_tab_data
+                .panel
+                .section_open(PanelSection::StagedChanges);
+            let staged_diffs = source_control.staged_diffs;
+            let source_control = source_control.clone();
+            foldable_panel_section(
+                section_header(
+                    "Staged Changes",
+                    LapceIcons::SCM_CHANGE_REMOVE,
+                    "Unstage All Changes",
+                    {
+                        let source_control = source_control.clone();
+                        move || source_control.unstage_all()
+                    },
+                    config,
+                ),
+                file_diffs_view(source_control, true, scope),
+                open,
+                config,
+            )
+            .style(move |s| {
+                section_style(s, open.get())
+                    .apply_if(staged_diffs.with(|x| x.is_empty()), |s| s.hide())
+            })
+        },
+        {
+            let open = window_tab_data.panel.section_open(PanelSection::Changes);
+            let source_control = source_control.clone();
+            foldable_panel_section(
+                section_header(
+                    "Changes",
+                    LapceIcons::SCM_CHANGE_ADD,
+                    "Stage All Changes",
+                    {
+                        let source_control = source_control.clone();
+                        move || source_control.stage_all()
+                    },
+                    config,
+                ),
+                file_diffs_view(source_control, false, scope),
+                open,
+                config,
+            )
+            .style(move |s| section_style(s, open.get()))
+        },
+        {
+            let open = window_tab_data.panel.section_open(PanelSection::Stashes);
+            let stashes = source_control.stashes;
+            let source_control = source_control.clone();
+            foldable_panel_section(
+                section_header(
+                    "Stashes",
+                    LapceIcons::ADD,
+                    "Stash Changes",
+                    {
+                        let source_control = source_control.clone();
+                        move || source_control.stash_save(false)
+                    },
+                    config,
+                ),
+                stashes_view(source_control),
+                open,
+                config,
+            )
+            .style(move |s| {
+                section_style(s, open.get())
+                    .apply_if(stashes.with(|x| x.is_empty()), |s| s.hide())
+            })
+        },
+        {
+            let open = window_tab_data.panel.section_open(PanelSection::Compare);
+            let base = source_control.compare.base;
+            let source_control = source_control.clone();
+            foldable_panel_section(
+                section_header(
+                    "Compare",
+                    LapceIcons::CLOSE,
+                    "Close Comparison",
+                    {
+                        let source_control = source_control.clone();
+                        move || source_control.close_compare()
+                    },
+                    config,
+                ),
+                compare_view(source_control),
+                open,
+                config,
+            )
+            .style(move |s| {
+                section_style(s, open.get())
+                    .apply_if(base.with(Option::is_none), |s| s.hide())
+            })
+        },
+        {
+            let open = window_tab_data.panel.section_open(PanelSection::Patch);
+            let name = source_control.patch.name;
+            let source_control = source_control.clone();
+            foldable_panel_section(
+                section_header(
+                    "Patch",
+                    LapceIcons::CLOSE,
+                    "Close Patch",
+                    {
+                        let source_control = source_control.clone();
+                        move || source_control.close_patch()
+                    },
+                    config,
+                ),
+                patch_view(source_control),
+                open,
+                config,
+            )
+            .style(move |s| {
+                section_style(s, open.get())
+                    .apply_if(name.with(Option::is_none), |s| s.hide())
+            })
+        },
     ))
     .on_event_stop(EventListener::PointerDown, move |_| {
         if focus.get_untracked() != Focus::Panel(PanelKind::SourceControl) {
@@ -246,8 +403,110 @@ pub fn source_control_panel(
     .debug_name("Source Control Panel")
 }
 
-fn file_diffs_view(source_control: SourceControlData, scope: Scope) -> impl View {
-    let file_diffs = source_control.file_diffs;
+/// The toggles for how the next commit is made, and the recent commit
+/// messages to pick from.
+fn commit_options_view(source_control: SourceControlData) -> impl View {
+    let config = source_control.common.config;
+    let amend = source_control.amend;
+    let sign_off = source_control.sign_off;
+    let commit_messages = source_control.commit_messages;
+
+    let toggle = move |text: &'static str,
+                       checked: RwSignal<bool>,
+                       on_toggle: Box<dyn Fn(bool)>| {
+        stack((
+            checkbox(move || checked.get(), config),
+            label(move || text.to_string()),
+        ))
+        .on_click_stop(move |_| on_toggle(!checked.get_untracked()))
+        .style(|s| {
+            s.items_center()
+                .gap(6.0)
+                .margin_right(12.0)
+                .selectable(false)
+                .hover(|s| s.cursor(CursorStyle::Pointer))
+        })
+    };
+
+    stack((
+        toggle("Amend", amend, {
+            let source_control = source_control.clone();
+            Box::new(move |checked| source_control.set_amend(checked))
+        }),
+        toggle(
+            "Sign Off",
+            sign_off,
+            Box::new(move |checked| sign_off.set(checked)),
+        ),
+        empty().style(|s| s.flex_grow(1.0)),
+        clickable_icon(
+            || LapceIcons::FILE_HISTORY,
+            move || {
+                let mut menu = Menu::new("");
+                for message in commit_messages.get_untracked() {
+                    let summary = message.lines().next().unwrap_or_default();
+                    let summary = if summary.chars().count() > 60 {
+                        format!("{}…", summary.chars().take(60).collect::<String>())
+                    } else {
+                        summary.to_string()
+                    };
+                    let source_control = source_control.clone();
+                    menu = menu.entry(MenuItem::new(summary).action(move || {
+                        source_control.set_commit_message(&message)
+                    }));
+                }
+                show_context_menu(menu, None);
+            },
+            || false,
+            move || commit_messages.with(|x| x.is_empty()),
+            || "Recent Commit Messages",
+            config,
+        ),
+    ))
+    .style(|s| s.items_center().width_pct(100.0).margin_top(6.0))
+}
+
+fn section_header(
+    title: &'static str,
+    icon: &'static str,
+    tooltip: &'static str,
+    on_click: impl Fn() + 'static,
+    config: WithLapceConfig,
+) -> impl View {
+    stack((
+        text(title).style(|s| s.flex_grow(1.0)),
+        clickable_icon(
+            move || icon,
+            on_click,
+            || false,
+            || false,
+            move || tooltip,
+            config,
+        ),
+    ))
+    .style(|s| s.flex_grow(1.0).items_center())
+}
+
+/// Sections share the height left by the commit message, a folded one only
+/// keeps its header.
+fn section_style(s: Style, open: bool) -> Style {
+    s.flex_col()
+        .width_pct(100.0)
+        .min_height(0.0)
+        .apply_if(open, |s| s.flex_grow(1.0).flex_basis(0.0))
+}
+
+/// The files of the index with `staged`, otherwise of the working copy.
+fn file_diffs_view(
+    source_control: SourceControlData,
+    staged: bool,
+    scope: Scope,
+) -> impl View {
+    let file_diffs = if staged {
+        source_control.staged_diffs
+    } else {
+        source_control.file_diffs
+    };
     let config = source_control.common.config;
     let workspace = source_control.common.workspace.clone();
     let panel_rect = scope.create_rw_signal(Rect::ZERO);
@@ -255,10 +514,14 @@ fn file_diffs_view(source_control: SourceControlData, scope: Scope) -> impl View
     let lapce_command = source_control.common.lapce_command;
     let internal_command = source_control.common.internal_command;
 
-    let view_fn = move |(path, (diff, checked)): (PathBuf, (FileDiff, bool))| {
+    let unchecked = source_control.unchecked;
+    let view_fn = move |(path, diff): (PathBuf, FileDiff)| {
         let diff_for_style = diff.clone();
         let full_path = path.clone();
         let diff_for_menu = diff.clone();
+        let diff_for_stage = diff.clone();
+        let source_control_for_menu = source_control.clone();
+        let source_control = source_control.clone();
         let path_for_click = full_path.clone();
 
         let path = if let Some(workspace_path) = workspace.path() {
@@ -279,16 +542,56 @@ fn file_diffs_view(source_control: SourceControlData, scope: Scope) -> impl View
             .unwrap_or("")
             .to_string();
         let style_path = path.clone();
+        let checked_path = full_path.clone();
+        let toggled_path = full_path.clone();
         stack((
-            checkbox(move || checked, config)
-                .style(|s| s.hover(|s| s.cursor(CursorStyle::Pointer)))
-                .on_click_stop(move |_| {
-                    file_diffs.update(|diffs| {
-                        if let Some((_, checked)) = diffs.get_mut(&full_path) {
-                            *checked = !*checked;
-                        }
-                    });
-                }),
+            // the files committed when nothing is staged
+            checkbox(
+                move || {
+                    unchecked.with(|unchecked| !unchecked.contains(&checked_path))
+                },
+                config,
+            )
+            .style(move |s| {
+                s.margin_right(6.0)
+                    .apply_if(staged, |s| s.hide())
+                    .hover(|s| s.cursor(CursorStyle::Pointer))
+            })
+            .on_click_stop(move |_| {
+                unchecked.update(|unchecked| {
+                    if !unchecked.remove(&toggled_path) {
+                        unchecked.insert(toggled_path.clone());
+                    }
+                });
+            }),
+            clickable_icon(
+                move || {
+                    if staged {
+                        LapceIcons::SCM_CHANGE_REMOVE
+                    } else {
+                        LapceIcons::SCM_CHANGE_ADD
+                    }
+                },
+                move || {
+                    let files =
+                        diff_for_stage.paths().into_iter().cloned().collect();
+                    if staged {
+                        source_control.unstage(StageSelection::Files(files));
+                    } else {
+                        source_control.stage(StageSelection::Files(files));
+                    }
+                },
+                || false,
+                || false,
+                move || {
+                    if staged {
+                        "Unstage Changes"
+                    } else {
+                        "Stage Changes"
+                    }
+                },
+                config,
+            ),
             svg(move || config.with_file_svg(&path).0).style(move |s| {
                 let (size, file_svg) = config.signal(|config| {
                     (config.ui.icon_size.signal(), config.icon_theme.signal())
@@ -302,7 +605,9 @@ fn file_diffs_view(source_control: SourceControlData, scope: Scope) -> impl View
             }),
             label(move || file_name.clone()).style(move |s| {
                 let size = config.with_icon_size() as f32;
+                let checkbox_width = if staged { 0.0 } else { size + 6.0 };
                 let max_width = panel_width.get() as f32
+                    - checkbox_width
                     - 10.0
                     - size
                     - 6.0
@@ -365,6 +670,16 @@ fn file_diffs_view(source_control: SourceControlData, scope: Scope) -> impl View
         })
         .on_event_cont(EventListener::PointerDown, move |event| {
             let diff_for_menu = diff_for_menu.clone();
+            let files: Vec<PathBuf> =
+                diff_for_menu.paths().into_iter().cloned().collect();
+            let source_control = source_control_for_menu.clone();
+            let stage = move || {
+                if staged {
+                    source_control.unstage(StageSelection::Files(files.clone()));
+                } else {
+                    source_control.stage(StageSelection::Files(files.clone()));
+                }
+            };
 
             let discard = move || {
                 lapce_command.send(LapceCommand {
@@ -378,8 +693,14 @@ fn file_diffs_view(source_control: SourceControlData, scope: Scope) -> impl View
             if let Event::PointerDown(pointer_event) = event
                 && pointer_event.button.is_secondary()
             {
-                let menu = Menu::new("")
-                    .entry(MenuItem::new("Discard Changes").action(discard));
+                let menu = if staged {
+                    Menu::new("")
+                        .entry(MenuItem::new("Unstage Changes").action(stage))
+                } else {
+                    Menu::new("")
+                        .entry(MenuItem::new("Stage Changes").action(stage))
+                        .entry(MenuItem::new("Discard Changes").action(discard))
+                };
                 show_context_menu(menu, None);
             }
         })
@@ -403,9 +724,7 @@ fn file_diffs_view(source_control: SourceControlData, scope: Scope) -> impl View
         scroll({
             dyn_stack(
                 move || file_diffs.get(),
-                |(path, (diff, checked))| {
-                    (path.to_path_buf(), diff.clone(), *checked)
-                },
+                |(path, diff)| (path.to_path_buf(), diff.clone()),
                 view_fn,
             )
             .style(|s| s.line_height(1.6).flex_col().width_pct(100.0))
@@ -417,3 +736,517 @@ fn file_diffs_view(source_control: SourceControlData, scope: Scope) -> impl View
     })
     .style(|s| s.size_pct(100.0, 100.0))
 }
+
+/// The files with conflicts, opening in the merge editor.
+fn conflicts_view(source_control: SourceControlData) -> impl View {
+    let config = source_control.common.config;
+    let workspace = source_control.common.workspace.clone();
+    let internal_command = source_control.common.internal_command;
+    let conflicts = source_control.conflicts;
+
+    let view_fn = move |full_path: PathBuf| {
+        let path = workspace
+            .path()
+            .and_then(|root| full_path.strip_prefix(root).ok())
+            .unwrap_or(&full_path)
+            .to_path_buf();
+        let file_name = path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_string();
+        let folder = path
+            .parent()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_string();
+        let source_control = source_control.clone();
+        let path_for_click = full_path.clone();
+
+        stack((
+            svg(move || config.with_file_svg(&path).0).style(move |s| {
+                let size = config.with_icon_size() as f32;
+                s.min_width(size).size(size, size).margin_right(6.0)
+            }),
+            label(move || file_name.clone()).style(move |s| {
+                s.text_ellipsis()
+                    .margin_right(6.0)
+                    .selectable(false)
+                    .color(config.with_color(LapceColor::SOURCE_CONTROL_MODIFIED))
+            }),
+            label(move || folder.clone()).style(move |s| {
+                s.text_ellipsis()
+                    .flex_grow(1.0)
+                    .flex_basis(0.0)
+                    .color(config.with_color(LapceColor::EDITOR_DIM))
+                    .min_width(0.0)
+                    .selectable(false)
+            }),
+        ))
+        .on_click_stop(move |_| {
+            internal_command.send(InternalCommand::OpenMergeEditor {
+                path: path_for_click.clone(),
+            });
+        })
+        .on_event_cont(EventListener::PointerDown, move |event| {
+            let Event::PointerDown(pointer_event) = event else {
+                return;
+            };
+            if !pointer_event.button.is_secondary() {
+                return;
+            }
+            let open = {
+                let path = full_path.clone();
+                move || {
+                    internal_command.send(InternalCommand::OpenMergeEditor {
+                        path: path.clone(),
+                    });
+                }
+            };
+            let resolve = {
+                let source_control = source_control.clone();
+                let path = full_path.clone();
+                move || {
+                    source_control.stage(StageSelection::Files(vec![path.clone()]));
+                }
+            };
+            let menu = Menu::new("")
+                .entry(MenuItem::new("Open Merge Editor").action(open))
+                .entry(MenuItem::new("Mark Resolved").action(resolve));
+            show_context_menu(menu, None);
+        })
+        .style(move |s| {
+            s.items_center()
+                .width_pct(100.0)
+                .padding_horiz(10.0)
+                .cursor(CursorStyle::Pointer)
+                .hover(|s| {
+                    s.background(
+                        config.with_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                    )
+                })
+        })
+    };
+
+    container({
+        scroll({
+            dyn_stack(move || conflicts.get(), |path| path.clone(), view_fn)
+                .style(|s| s.line_height(1.6).flex_col().width_pct(100.0))
+        })
+        .style(|s| s.absolute().size_pct(100.0, 100.0))
+    })
+    .style(|s| s.size_pct(100.0, 100.0))
+}
+
+/// The stashes, each one expanding to the files it changed, which are listed
+/// once it is first expanded.
+fn stashes_view(source_control: SourceControlData) -> impl View {
+    let config = source_control.common.config;
+    let workspace = source_control.common.workspace.clone();
+    let internal_command = source_control.common.internal_command;
+    let stashes = source_control.stashes;
+
+    let view_fn = move |stash: StashEntry| {
+        let expanded = create_rw_signal(false);
+        let files = create_rw_signal(None);
+        let StashEntry { id, message, .. } = stash;
+        let source_control = source_control.clone();
+        let workspace = workspace.clone();
+
+        let file_view = move |(diff, commit): (FileDiff, String)| {
+            let full_path = diff.path().clone();
+            let path = workspace
+                .path()
+                .and_then(|root| full_path.strip_prefix(root).ok())
+                .unwrap_or(&full_path)
+                .to_string_lossy()
+                .to_string();
+            let color = match &diff {
+                FileDiff::Modified(_) | FileDiff::Renamed(_, _) => {
+                    LapceColor::SOURCE_CONTROL_MODIFIED
+                },
+                FileDiff::Added(_) => LapceColor::SOURCE_CONTROL_ADDED,
+                FileDiff::Deleted(_) => LapceColor::SOURCE_CONTROL_REMOVED,
+            };
+            label(move || path.clone())
+                .on_click_stop(move |_| {
+                    internal_command.send(InternalCommand::OpenFileCommitChanges {
+                        path:   full_path.clone(),
+                        commit: commit.clone(),
+                    });
+                })
+                .style(move |s| {
+                    s.text_ellipsis()
+                        .width_pct(100.0)
+                        .padding_left(10.0 + config.with_icon_size() as f32 + 6.0)
+                        .padding_right(10.0)
+                        .selectable(false)
+                        .color(config.with_color(color))
+                        .hover(|s| {
+                            s.cursor(CursorStyle::Pointer).background(
+                                config.with_color(
+                                    LapceColor::PANEL_HOVERED_BACKGROUND,
+                                ),
+                            )
+                        })
+                })
+        };
+
+        stack((
+            stack((
+                svg(move || {
+                    config.with_ui_svg(if expanded.get() {
+                        LapceIcons::ITEM_OPENED
+                    } else {
+                        LapceIcons::ITEM_CLOSED
+                    })
+                })
+                .style(move |s| {
+                    let size = config.with_icon_size() as f32;
+                    s.min_width(size)
+                        .size(size, size)
+                        .margin_right(6.0)
+                        .color(config.with_color(LapceColor::LAPCE_ICON_ACTIVE))
+                }),
+                label(move || message.clone())
+                    .style(|s| s.text_ellipsis().min_width(0.0).selectable(false)),
+            ))
+            .on_click_stop({
+                let source_control = source_control.clone();
+                let id = id.clone();
+                move |_| {
+                    expanded.update(|x| *x = !*x);
+                    if expanded.get_untracked()
+                        && files.with_untracked(|x| x.is_none())
+                    {
+                        source_control.load_stash_files(id.clone(), files);
+                    }
+                }
+            })
+            .on_event_cont(EventListener::PointerDown, move |event| {
+                let Event::PointerDown(pointer_event) = event else {
+                    return;
+                };
+                if !pointer_event.button.is_secondary() {
+                    return;
+                }
+                let apply = {
+                    let source_control = source_control.clone();
+                    let id = id.clone();
+                    move || source_control.stash_apply(id.clone())
+                };
+                let pop = {
+                    let source_control = source_control.clone();
+                    let id = id.clone();
+                    move || source_control.stash_pop(id.clone())
+                };
+                let drop_stash = {
+                    let source_control = source_control.clone();
+                    let id = id.clone();
+                    move || source_control.stash_drop(id.clone())
+                };
+                let menu = Menu::new("")
+                    .entry(MenuItem::new("Apply Stash").action(apply))
+                    .entry(MenuItem::new("Pop Stash").action(pop))
+                    .entry(MenuItem::new("Drop Stash").action(drop_stash));
+                show_context_menu(menu, None);
+            })
+            .style(move |s| {
+                s.items_center()
+                    .width_pct(100.0)
+                    .padding_horiz(10.0)
+                    .hover(|s| {
+                        s.cursor(CursorStyle::Pointer).background(
+                            config.with_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        )
+                    })
+            }),
+            dyn_stack(
+                move || files.get().unwrap_or_default(),
+                |file| file.clone(),
+                file_view,
+            )
+            .style(move |s| {
+                s.flex_col()
+                    .width_pct(100.0)
+                    .apply_if(!expanded.get(), |s| s.hide())
+            }),
+        ))
+        .style(|s| s.flex_col().width_pct(100.0))
+    };
+
+    container({
+        scroll({
+            dyn_stack(
+                move || stashes.get(),
+                |stash| (stash.index, stash.id.clone()),
+                view_fn,
+            )
+            .style(|s| s.line_height(1.6).flex_col().width_pct(100.0))
+        })
+        .style(|s| s.absolute().size_pct(100.0, 100.0))
+    })
+    .style(|s| s.size_pct(100.0, 100.0))
+}
+
+/// The files changed between the compared refs, each opening its diff.
+fn compare_view(source_control: SourceControlData) -> impl View {
+    let config = source_control.common.config;
+    let workspace = source_control.common.workspace.clone();
+    let internal_command = source_control.common.internal_command;
+    let CompareData {
+        base,
+        target,
+        files,
+        loading,
+    } = source_control.compare.clone();
+
+    let view_fn = move |diff: FileDiff| {
+        let full_path = diff.path().clone();
+        let path = workspace
+            .path()
+            .and_then(|root| full_path.strip_prefix(root).ok())
+            .unwrap_or(&full_path)
+            .to_string_lossy()
+            .to_string();
+        let color = match &diff {
+            FileDiff::Modified(_) | FileDiff::Renamed(_, _) => {
+                LapceColor::SOURCE_CONTROL_MODIFIED
+            },
+            FileDiff::Added(_) => LapceColor::SOURCE_CONTROL_ADDED,
+            FileDiff::Deleted(_) => LapceColor::SOURCE_CONTROL_REMOVED,
+        };
+        let old_path = match &diff {
+            FileDiff::Renamed(_, old_path) => old_path.clone(),
+            _ => full_path.clone(),
+        };
+        label(move || path.clone())
+            .on_click_stop(move |_| {
+                let Some(version) = base.get_untracked() else {
+                    return;
+                };
+                let left = DocContent::History(DocHistory {
+                    path: old_path.clone(),
+                    version,
+                });
+                let right = match target.get_untracked() {
+                    Some(version) => DocContent::History(DocHistory {
+                        path: full_path.clone(),
+                        version,
+                    }),
+                    None => DocContent::File {
+                        path:      full_path.clone(),
+                        read_only: false,
+                    },
+                };
+                internal_command
+                    .send(InternalCommand::OpenRevisionDiff { left, right });
+            })
+            .style(move |s| {
+                s.text_ellipsis()
+                    .width_pct(100.0)
+                    .padding_horiz(10.0)
+                    .selectable(false)
+                    .color(config.with_color(color))
+                    .hover(|s| {
+                        s.cursor(CursorStyle::Pointer).background(
+                            config.with_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        )
+                    })
+            })
+    };
+
+    stack((
+        label(move || {
+            let base = base.get().unwrap_or_default();
+            let target = target.get().unwrap_or_else(|| "Working Tree".to_string());
+            if loading.get() {
+                format!("{base} → {target}, loading...")
+            } else if files.with(|files| files.is_empty()) {
+                format!("{base} → {target}, no changes")
+            } else {
+                format!("{base} → {target}")
+            }
+        })
+        .style(move |s| {
+            s.text_ellipsis()
+                .width_pct(100.0)
+                .padding_horiz(10.0)
+                .color(config.with_color(LapceColor::EDITOR_DIM))
+        }),
+        container({
+            scroll({
+                dyn_stack(move || files.get(), |diff| diff.clone(), view_fn)
+                    .style(|s| s.flex_col().width_pct(100.0))
+            })
+            .style(|s| s.absolute().size_pct(100.0, 100.0))
+        })
+        .style(|s| s.size_pct(100.0, 100.0)),
+    ))
+    .style(|s| s.line_height(1.6).flex_col().size_pct(100.0, 100.0))
+}
+
+/// The files and hunks of the previewed patch, each hunk picked to be
+/// applied or not, and marked when it doesn't apply to the working copy.
+fn patch_view(source_control: SourceControlData) -> impl View {
+    let config = source_control.common.config;
+    let workspace = source_control.common.workspace.clone();
+    let PatchData {
+        name,
+        files,
+        selected,
+        loading,
+        ..
+    } = source_control.patch.clone();
+
+    let button = move |title: &'static str, on_click: Box<dyn Fn()>| {
+        label(move || title.to_string())
+            .on_click_stop(move |_| on_click())
+            .style(move |s| {
+                let (border, bg, abg) = config.signal(|config| {
+                    (
+                        config.color(LapceColor::LAPCE_BORDER),
+                        config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        config.color(LapceColor::PANEL_HOVERED_ACTIVE_BACKGROUND),
+                    )
+                });
+                s.padding_horiz(10.0)
+                    .margin_right(6.0)
+                    .border(1.0)
+                    .border_radius(6.0)
+                    .border_color(border.get())
+                    .hover(|s| s.cursor(CursorStyle::Pointer).background(bg.get()))
+                    .active(|s| s.background(abg.get()))
+                    .selectable(false)
+            })
+    };
+
+    let hunk_view = {
+        let source_control = source_control.clone();
+        move |(index, hunk): (usize, PatchHunk)| {
+            let source_control = source_control.clone();
+            let applies = hunk.applies;
+            stack((
+                checkbox(move || selected.with(|s| s.contains(&index)), config),
+                label(move || {
+                    if applies {
+                        hunk.header.clone()
+                    } else {
+                        format!("{} (does not apply)", hunk.header)
+                    }
+                })
+                .style(move |s| {
+                    s.text_ellipsis()
+                        .min_width(0.0)
+                        .padding_left(6.0)
+                        .selectable(false)
+                        .apply_if(!applies, |s| {
+                            s.color(config.with_color(LapceColor::LAPCE_ERROR))
+                        })
+                }),
+            ))
+            .on_click_stop(move |_| {
+                source_control.toggle_patch_hunk(index);
+            })
+            .style(move |s| {
+                s.items_center()
+                    .width_pct(100.0)
+                    .padding_left(20.0)
+                    .padding_right(10.0)
+                    .hover(|s| {
+                        s.cursor(CursorStyle::Pointer).background(
+                            config.with_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        )
+                    })
+            })
+        }
+    };
+
+    let file_view = move |(start, file): (usize, PatchFile)| {
+        let path = workspace
+            .path()
+            .and_then(|root| file.path.strip_prefix(root).ok())
+            .unwrap_or(&file.path)
+            .to_string_lossy()
+            .to_string();
+        let hunks: Vec<(usize, PatchHunk)> = file
+            .hunks
+            .into_iter()
+            .enumerate()
+            .map(|(i, hunk)| (start + i, hunk))
+            .collect();
+        stack((
+            label(move || path.clone()).style(|s| {
+                s.text_ellipsis()
+                    .width_pct(100.0)
+                    .padding_horiz(10.0)
+                    .selectable(false)
+            }),
+            dyn_stack(
+                move || hunks.clone(),
+                |(index, _)| *index,
+                hunk_view.clone(),
+            )
+            .style(|s| s.flex_col().width_pct(100.0)),
+        ))
+        .style(|s| s.flex_col().width_pct(100.0))
+    };
+
+    stack((
+        label(move || {
+            let name = name.get().unwrap_or_default();
+            if loading.get() && files.with(|files| files.is_empty()) {
+                return format!("{name}, loading...");
+            }
+            let (applies, total) = files.with(|files| {
+                let hunks = files.iter().flat_map(|file| &file.hunks);
+                (
+                    hunks.clone().filter(|hunk| hunk.applies).count(),
+                    hunks.count(),
+                )
+            });
+            format!("{name}, {applies} of {total} hunks apply")
+        })
+        .style(move |s| {
+            s.text_ellipsis()
+                .width_pct(100.0)
+                .padding_horiz(10.0)
+                .color(config.with_color(LapceColor::EDITOR_DIM))
+        }),
+        stack((
+            button("Apply Selected", {
+                let source_control = source_control.clone();
+                Box::new(move || source_control.apply_patch(false))
+            }),
+            button("Apply All", {
+                let source_control = source_control.clone();
+                Box::new(move || source_control.apply_patch(true))
+            }),
+        ))
+        .style(|s| s.padding_horiz(10.0).padding_vert(4.0)),
+        container({
+            scroll({
+                dyn_stack(
+                    move || {
+                        let mut start = 0;
+                        files
+                            .get()
+                            .into_iter()
+                            .map(|file| {
+                                let item = (start, file);
+                                start += item.1.hunks.len();
+                                item
+                            })
+                            .collect::<Vec<_>>()
+                    },
+                    |(start, file)| (*start, file.clone()),
+                    file_view,
+                )
+                .style(|s| s.flex_col().width_pct(100.0))
+            })
+            .style(|s| s.absolute().size_pct(100.0, 100.0))
+        })
+        .style(|s| s.size_pct(100.0, 100.0)),
+    ))
+    .style(|s| s.line_height(1.6).flex_col().size_pct(100.0, 100.0))
+}
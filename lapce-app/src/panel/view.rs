@@ -27,8 +27,11 @@ use crate::{
     file_explorer::view::file_explorer_panel,
     panel::{
         call_hierarchy_view::show_hierarchy_panel, data::PanelData,
-        document_symbol::symbol_panel, implementation_view::implementation_panel,
+        document_symbol::symbol_panel, documentation_view::documentation_panel,
+        file_history_view::file_history_panel, git_log_view::git_log_panel,
+        implementation_view::implementation_panel,
         references_view::references_panel,
+        workspace_edit_view::workspace_edit_panel,
     },
     window_workspace::{DragContent, WindowWorkspaceData},
 };
@@ -88,38 +91,52 @@ impl PanelBuilder {
     }
 
     fn add_general(
-        mut self,
+        self,
         name: &'static str,
         height: Option<PxPctAuto>,
         view: impl View + 'static,
         open: RwSignal<bool>,
         style: impl Fn(Style) -> Style + 'static,
     ) -> Self {
-        let position = self.position;
-        let view = foldable_panel_section(
+        self.add_general_header(
             text(name).style(move |s| s.selectable(false)),
+            height,
             view,
             open,
-            self.config,
+            style,
         )
-        .style(move |s| {
-            let s = s.width_full().flex_col();
-            // Use the manual height if given, otherwise if we're open behave flex,
-            // otherwise, do nothing so that there's no height
-            let s = if open.get() {
-                if let Some(height) = height {
-                    s.height(height)
+    }
+
+    fn add_general_header(
+        mut self,
+        header: impl View + 'static,
+        height: Option<PxPctAuto>,
+        view: impl View + 'static,
+        open: RwSignal<bool>,
+        style: impl Fn(Style) -> Style + 'static,
+    ) -> Self {
+        let position = self.position;
+        let view = foldable_panel_section(header, view, open, self.config).style(
+            move |s| {
+                let s = s.width_full().flex_col();
+                // Use the manual height if given, otherwise if we're open behave
+                // flex, otherwise, do nothing so that there's no
+                // height
+                let s = if open.get() {
+                    if let Some(height) = height {
+                        s.height(height)
+                    } else {
+                        s.flex_grow(1.0).flex_basis(0.0)
+                    }
+                } else if position.is_bottom() {
+                    s.flex_grow(0.3).flex_basis(0.0)
                 } else {
-                    s.flex_grow(1.0).flex_basis(0.0)
-                }
-            } else if position.is_bottom() {
-                s.flex_grow(0.3).flex_basis(0.0)
-            } else {
-                s
-            };
+                    s
+                };
 
-            style(s)
-        });
+                style(s)
+            },
+        );
         self.views.push(view.into_any());
         self
     }
@@ -134,6 +151,17 @@ impl PanelBuilder {
         self.add_general(name, None, view, open, std::convert::identity)
     }
 
+    /// Add a view to the panel with a custom header, such as a name with
+    /// buttons
+    pub fn add_with_header(
+        self,
+        header: impl View + 'static,
+        view: impl View + 'static,
+        open: RwSignal<bool>,
+    ) -> Self {
+        self.add_general_header(header, None, view, open, std::convert::identity)
+    }
+
     /// Add a view to the panel with a custom style applied to the overall
     /// header+section-content
     pub fn add_style(
@@ -405,6 +433,18 @@ fn panel_view_by_kind(
         PanelKind::Implementation => {
             implementation_panel(window_tab_data.clone(), position).into_any()
         },
+        PanelKind::WorkspaceEdit => {
+            workspace_edit_panel(window_tab_data.clone(), position).into_any()
+        },
+        PanelKind::Documentation => {
+            documentation_panel(window_tab_data.clone(), position).into_any()
+        },
+        PanelKind::FileHistory => {
+            file_history_panel(window_tab_data.clone(), position).into_any()
+        },
+        PanelKind::GitLog => {
+            git_log_panel(window_tab_data.clone(), position).into_any()
+        },
         PanelKind::Build => {
             empty().into_any()
             // build_panel(window_tab_data.clone(), position).into_any()
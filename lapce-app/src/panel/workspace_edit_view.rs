@@ -0,0 +1,545 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use floem::{
+    View, ViewId,
+    reactive::{RwSignal, Scope, SignalGet, SignalUpdate, SignalWith},
+    style::{CursorStyle, Style},
+    views::{
+        Decorators, container, dyn_stack, label, scroll, stack, stack_from_iter, svg,
+    },
+};
+use lapce_core::{
+    encoding::offset_utf16_to_utf8_str, panel::PanelContainerPosition,
+};
+use lapce_rpc::file_line::FileLine;
+use lsp_types::{
+    DocumentChangeOperation, DocumentChanges, OneOf, TextEdit, Url, WorkspaceEdit,
+};
+
+use crate::{
+    config::{WithLapceConfig, color::LapceColor},
+    settings::checkbox,
+    window_workspace::WindowWorkspaceData,
+};
+
+/// A `WorkspaceEdit` waiting to be reviewed before it is applied, grouped by
+/// file with a checkbox per edit.
+#[derive(Clone)]
+pub struct WorkspaceEditPreviewData {
+    pub title:      String,
+    pub files:      Vec<WorkspaceEditPreviewFile>,
+    /// The `document_changes` of the edit, when it creates, renames or
+    /// deletes files, whose order the text edits have to keep
+    pub operations: Option<Vec<DocumentChangeOperation>>,
+}
+
+#[derive(Clone)]
+pub struct WorkspaceEditPreviewFile {
+    pub url:     Url,
+    pub path:    PathBuf,
+    pub open:    RwSignal<bool>,
+    /// Whether the `.gitignore` files ignore the file, filled in once the
+    /// proxy answered
+    pub ignored: RwSignal<bool>,
+    pub edits:   Vec<WorkspaceEditPreviewItem>,
+    pub view_id: ViewId,
+}
+
+#[derive(Clone)]
+pub struct WorkspaceEditPreviewItem {
+    pub edit:     TextEdit,
+    pub checked:  RwSignal<bool>,
+    /// The current content of the lines touched by the edit
+    pub old_text: String,
+    /// What those lines look like after the edit
+    pub new_text: String,
+}
+
+impl WorkspaceEditPreviewData {
+    /// `lines` are the resolved lines touched by the edits, used to render
+    /// the before/after of every change.
+    pub fn new(
+        cx: Scope,
+        title: String,
+        edits: HashMap<Url, Vec<TextEdit>>,
+        operations: Option<Vec<DocumentChangeOperation>>,
+        lines: Vec<FileLine>,
+    ) -> Self {
+        let lines: HashMap<(PathBuf, u32), String> = lines
+            .into_iter()
+            .map(|x| ((x.path, x.position.line), x.content))
+            .collect();
+        let mut files: Vec<WorkspaceEditPreviewFile> = edits
+            .into_iter()
+            .filter_map(|(url, mut edits)| {
+                let path = url.to_file_path().ok()?;
+                edits.sort_by_key(|x| (x.range.start.line, x.range.start.character));
+                let edits = edits
+                    .into_iter()
+                    .map(|edit| {
+                        let old_lines: Vec<&str> = (edit.range.start.line
+                            ..=edit.range.end.line)
+                            .map(|line| {
+                                lines
+                                    .get(&(path.clone(), line))
+                                    .map(|x| x.trim_end_matches(['\r', '\n']))
+                                    .unwrap_or_default()
+                            })
+                            .collect();
+                        let (old_text, new_text) = preview_edit(&old_lines, &edit);
+                        WorkspaceEditPreviewItem {
+                            edit,
+                            checked: cx.create_rw_signal(true),
+                            old_text,
+                            new_text,
+                        }
+                    })
+                    .collect();
+                Some(WorkspaceEditPreviewFile {
+                    url,
+                    path,
+                    open: cx.create_rw_signal(true),
+                    ignored: cx.create_rw_signal(false),
+                    edits,
+                    view_id: ViewId::new(),
+                })
+            })
+            .collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Self {
+            title,
+            files,
+            operations,
+        }
+    }
+
+    /// The workspace edit made of the checked edits only, along with the
+    /// file operations of the original edit.
+    pub fn checked_edit(&self) -> WorkspaceEdit {
+        let changes: HashMap<Url, Vec<TextEdit>> = self
+            .files
+            .iter()
+            .filter_map(|file| {
+                let edits: Vec<TextEdit> = file
+                    .edits
+                    .iter()
+                    .filter(|x| x.checked.get_untracked())
+                    .map(|x| x.edit.clone())
+                    .collect();
+                if edits.is_empty() {
+                    None
+                } else {
+                    Some((file.url.clone(), edits))
+                }
+            })
+            .collect();
+        match &self.operations {
+            Some(operations) => WorkspaceEdit {
+                document_changes: Some(DocumentChanges::Operations(
+                    checked_operations(operations, &changes),
+                )),
+                ..Default::default()
+            },
+            None => WorkspaceEdit::new(changes),
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.files.iter().map(|x| x.edits.len()).sum()
+    }
+
+    pub fn set_ignored(&self, ignored: &[PathBuf]) {
+        for file in &self.files {
+            file.ignored.set(ignored.contains(&file.path));
+        }
+    }
+}
+
+/// Directories holding code copied from dependencies or generated by the
+/// build, rather than code of the workspace itself.
+const VENDORED_DIRS: &[&str] = &["vendor", "third_party", "node_modules"];
+const GENERATED_DIRS: &[&str] = &["target"];
+
+/// Why an edit of `path` is most likely unwanted: it lands in a dependency or
+/// in generated code.
+fn file_warning(
+    path: &Path,
+    workspace_path: Option<&Path>,
+    ignored: bool,
+) -> Option<&'static str> {
+    let Some(workspace_path) = workspace_path else {
+        return ignored.then_some("ignored by git");
+    };
+    let Ok(relative) = path.strip_prefix(workspace_path) else {
+        return Some("outside workspace");
+    };
+    let in_dirs = |dirs: &[&str]| {
+        relative.parent().is_some_and(|parent| {
+            parent
+                .components()
+                .any(|x| dirs.iter().any(|dir| x.as_os_str() == *dir))
+        })
+    };
+    if in_dirs(VENDORED_DIRS) {
+        Some("vendored")
+    } else if in_dirs(GENERATED_DIRS) {
+        Some("generated")
+    } else {
+        ignored.then_some("ignored by git")
+    }
+}
+
+/// Keep the file operations as they are, in their order, and the text edits
+/// found in `checked` only.
+fn checked_operations(
+    operations: &[DocumentChangeOperation],
+    checked: &HashMap<Url, Vec<TextEdit>>,
+) -> Vec<DocumentChangeOperation> {
+    operations
+        .iter()
+        .filter_map(|operation| match operation {
+            DocumentChangeOperation::Op(_) => Some(operation.clone()),
+            DocumentChangeOperation::Edit(edit) => {
+                let checked = checked.get(&edit.text_document.uri)?;
+                let mut edit = edit.clone();
+                edit.edits.retain(|x| {
+                    checked.contains(match x {
+                        OneOf::Left(x) => x,
+                        OneOf::Right(x) => &x.text_edit,
+                    })
+                });
+                (!edit.edits.is_empty())
+                    .then_some(DocumentChangeOperation::Edit(edit))
+            },
+        })
+        .collect()
+}
+
+/// The text of `old_lines`, the lines from the start to the end of `edit`,
+/// before and after `edit` is applied.
+fn preview_edit(old_lines: &[&str], edit: &TextEdit) -> (String, String) {
+    let first = old_lines.first().copied().unwrap_or_default();
+    let last = old_lines.last().copied().unwrap_or_default();
+    let start = offset_utf16_to_utf8_str(first, edit.range.start.character as usize)
+        .min(first.len());
+    let end = offset_utf16_to_utf8_str(last, edit.range.end.character as usize)
+        .min(last.len());
+    let end = if old_lines.len() > 1 {
+        end
+    } else {
+        end.max(start)
+    };
+    let new_text = format!("{}{}{}", &first[..start], edit.new_text, &last[end..]);
+    (old_lines.join("\n"), new_text)
+}
+
+pub fn workspace_edit_panel(
+    window_tab_data: WindowWorkspaceData,
+    _position: PanelContainerPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let preview = window_tab_data.main_split.edit_preview;
+    let workspace = window_tab_data.common.workspace.clone();
+    let apply_data = window_tab_data.clone();
+    let discard_data = window_tab_data.clone();
+
+    stack((
+        stack((
+            label(move || {
+                preview.with(|x| match x {
+                    Some(x) => format!(
+                        "{}: {} changes in {} files",
+                        x.title,
+                        x.total(),
+                        x.files.len()
+                    ),
+                    None => "No pending edits".to_string(),
+                })
+            })
+            .style(|s| s.flex_grow(1.0).text_ellipsis().min_width(0.0)),
+            panel_button("Apply", config, move || {
+                apply_data.apply_workspace_edit_preview();
+            }),
+            panel_button("Discard", config, move || {
+                discard_data.discard_workspace_edit_preview();
+            }),
+        ))
+        .style(move |s| {
+            s.items_center()
+                .width_pct(100.0)
+                .padding_horiz(10.0)
+                .padding_vert(6.0)
+                .gap(6.0)
+                .border_bottom(1.0)
+                .border_color(config.with_color(LapceColor::LAPCE_BORDER))
+        }),
+        container(
+            scroll(
+                dyn_stack(
+                    move || {
+                        preview.with(|x| {
+                            x.as_ref().map(|x| x.files.clone()).unwrap_or_default()
+                        })
+                    },
+                    |file| file.view_id,
+                    move |file| file_view(file, workspace.path().cloned(), config),
+                )
+                .style(|s| s.flex_col().line_height(1.6).min_width_pct(100.0)),
+            )
+            .style(|s| s.absolute().size_pct(100.0, 100.0)),
+        )
+        .style(|s| s.size_pct(100.0, 100.0)),
+    ))
+    .style(|s| s.flex_col().size_pct(100.0, 100.0))
+    .debug_name("Workspace Edit Panel")
+}
+
+fn file_view(
+    file: WorkspaceEditPreviewFile,
+    workspace_path: Option<PathBuf>,
+    config: WithLapceConfig,
+) -> impl View {
+    let open = file.open;
+    let edits = file.edits.clone();
+    let all_checked = {
+        let edits = edits.clone();
+        move || edits.iter().all(|x| x.checked.get())
+    };
+    let toggle_all = {
+        let edits = edits.clone();
+        let all_checked = all_checked.clone();
+        move || {
+            let checked = !all_checked();
+            for edit in &edits {
+                edit.checked.set(checked);
+            }
+        }
+    };
+    let display_path = workspace_path
+        .as_ref()
+        .and_then(|x| file.path.strip_prefix(x).ok())
+        .unwrap_or(&file.path)
+        .to_path_buf();
+    let ignored = file.ignored;
+    let warning_path = file.path.clone();
+    let warning = move || {
+        file_warning(&warning_path, workspace_path.as_deref(), ignored.get())
+    };
+    let icon_path = file.path.clone();
+    let style_path = file.path.clone();
+    let count = edits.len();
+
+    stack((
+        stack((
+            checkbox(all_checked, config)
+                .style(|s| s.hover(|s| s.cursor(CursorStyle::Pointer)))
+                .on_click_stop(move |_| toggle_all()),
+            svg(move || config.with_file_svg(&icon_path).0).style(move |s| {
+                let (size, file_svg) = config.signal(|config| {
+                    (config.ui.icon_size.signal(), config.icon_theme.signal())
+                });
+                let color = file_svg.with(|x| x.file_svg(&style_path).1);
+                let size = size.get() as f32;
+                s.min_width(size)
+                    .size(size, size)
+                    .margin(6.0)
+                    .apply_opt(color, Style::color)
+            }),
+            label(move || format!("{}", display_path.display())),
+            label(move || format!("{count}")).style(move |s| {
+                s.margin_left(6.0)
+                    .color(config.with_color(LapceColor::EDITOR_DIM))
+            }),
+            label({
+                let warning = warning.clone();
+                move || warning().unwrap_or_default().to_string()
+            })
+            .style(move |s| {
+                s.margin_left(6.0)
+                    .color(config.with_color(LapceColor::LAPCE_WARN))
+                    .apply_if(warning().is_none(), |s| s.hide())
+            }),
+        ))
+        .on_click_stop(move |_| open.update(|x| *x = !*x))
+        .style(move |s| {
+            s.items_center()
+                .padding_horiz(10.0)
+                .width_pct(100.0)
+                .cursor(CursorStyle::Pointer)
+                .hover(|s| {
+                    s.background(
+                        config.with_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                    )
+                })
+        }),
+        dyn_stack(
+            move || edits.clone().into_iter().enumerate(),
+            |(index, _)| *index,
+            move |(_, item)| edit_view(item, config),
+        )
+        .style(move |s| s.flex_col().apply_if(!open.get(), |s| s.hide())),
+    ))
+    .style(|s| s.flex_col().min_width_pct(100.0))
+}
+
+fn edit_view(item: WorkspaceEditPreviewItem, config: WithLapceConfig) -> impl View {
+    let checked = item.checked;
+    let start = item.edit.range.start.line as usize + 1;
+    let lines = |text: &str, sign: char, color: &'static str| {
+        text.split('\n')
+            .enumerate()
+            .map(|(i, line)| {
+                let line = format!("{:>5} {sign} {line}", start + i);
+                label(move || line.clone())
+                    .style(move |s| s.color(config.with_color(color)))
+            })
+            .collect::<Vec<_>>()
+    };
+    let old_lines = lines(&item.old_text, '-', LapceColor::SOURCE_CONTROL_REMOVED);
+    let new_lines = lines(&item.new_text, '+', LapceColor::SOURCE_CONTROL_ADDED);
+    stack((
+        checkbox(move || checked.get(), config)
+            .style(|s| s.hover(|s| s.cursor(CursorStyle::Pointer)))
+            .on_click_stop(move |_| checked.update(|x| *x = !*x)),
+        stack((
+            stack_from_iter(old_lines).style(|s| s.flex_col()),
+            stack_from_iter(new_lines).style(|s| s.flex_col()),
+        ))
+        .style(|s| s.flex_col().margin_left(6.0)),
+    ))
+    .style(move |s| {
+        s.items_center()
+            .padding_left(30.0)
+            .padding_right(10.0)
+            .hover(|s| {
+                s.background(config.with_color(LapceColor::PANEL_HOVERED_BACKGROUND))
+            })
+    })
+}
+
+pub(crate) fn panel_button(
+    text: &'static str,
+    config: WithLapceConfig,
+    on_click: impl Fn() + 'static,
+) -> impl View {
+    label(move || text.to_string())
+        .on_click_stop(move |_| on_click())
+        .style(move |s| {
+            let (border, bg, abg) = config.signal(|config| {
+                (
+                    config.color(LapceColor::LAPCE_BORDER),
+                    config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                    config.color(LapceColor::PANEL_HOVERED_ACTIVE_BACKGROUND),
+                )
+            });
+            s.padding_horiz(10.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(border.get())
+                .hover(|s| s.cursor(CursorStyle::Pointer).background(bg.get()))
+                .active(|s| s.background(abg.get()))
+                .selectable(false)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use lsp_types::{
+        CreateFile, DocumentChangeOperation, OneOf,
+        OptionalVersionedTextDocumentIdentifier, Position, Range, ResourceOp,
+        TextDocumentEdit, TextEdit, Url,
+    };
+
+    use super::{checked_operations, file_warning, preview_edit};
+
+    fn text_edit(line: u32, new_text: &str) -> TextEdit {
+        TextEdit {
+            range:    Range {
+                start: Position::new(line, 0),
+                end:   Position::new(line, 0),
+            },
+            new_text: new_text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_preview_edit() {
+        let edit = TextEdit {
+            range:    Range {
+                start: Position::new(3, 8),
+                end:   Position::new(3, 11),
+            },
+            new_text: "bar".to_string(),
+        };
+        assert_eq!(
+            preview_edit(&["let a = foo();"], &edit),
+            ("let a = foo();".to_string(), "let a = bar();".to_string())
+        );
+
+        let edit = TextEdit {
+            range:    Range {
+                start: Position::new(3, 4),
+                end:   Position::new(5, 5),
+            },
+            new_text: "b = 1;\nlet c".to_string(),
+        };
+        assert_eq!(
+            preview_edit(&["let a = foo();", "", "let b = 2;"], &edit),
+            (
+                "let a = foo();\n\nlet b = 2;".to_string(),
+                "let b = 1;\nlet c = 2;".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_checked_operations() {
+        let uri = Url::parse("file:///ws/new.rs").unwrap();
+        let create = DocumentChangeOperation::Op(ResourceOp::Create(CreateFile {
+            uri:           uri.clone(),
+            options:       None,
+            annotation_id: None,
+        }));
+        let edit = |edits: Vec<TextEdit>| {
+            DocumentChangeOperation::Edit(TextDocumentEdit {
+                text_document: OptionalVersionedTextDocumentIdentifier {
+                    uri:     uri.clone(),
+                    version: None,
+                },
+                edits:         edits.into_iter().map(OneOf::Left).collect(),
+            })
+        };
+        let operations = [
+            create.clone(),
+            edit(vec![text_edit(0, "a"), text_edit(1, "b")]),
+        ];
+
+        let checked = [(uri.clone(), vec![text_edit(1, "b")])].into();
+        assert_eq!(
+            checked_operations(&operations, &checked),
+            vec![create.clone(), edit(vec![text_edit(1, "b")])]
+        );
+        assert_eq!(
+            checked_operations(&operations, &Default::default()),
+            vec![create]
+        );
+    }
+
+    #[test]
+    fn test_file_warning() {
+        let workspace = Some(Path::new("/ws"));
+        let warning =
+            |path: &str, ignored| file_warning(Path::new(path), workspace, ignored);
+        assert_eq!(warning("/ws/src/main.rs", false), None);
+        assert_eq!(warning("/ws/src/main.rs", true), Some("ignored by git"));
+        assert_eq!(warning("/ws/vendor/a/lib.rs", false), Some("vendored"));
+        assert_eq!(warning("/ws/target/debug/out.rs", false), Some("generated"));
+        assert_eq!(warning("/ws/target.rs", false), None);
+        assert_eq!(warning("/cargo/a/lib.rs", false), Some("outside workspace"));
+    }
+}
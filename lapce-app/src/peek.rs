@@ -0,0 +1,279 @@
+use std::rc::Rc;
+
+use anyhow::Result;
+use doc::lines::{
+    buffer::rope_text::{RopeText, RopeTextRef},
+    command::FocusCommand,
+    editor_command::CommandExecuted,
+    mode::Mode,
+};
+use floem::{
+    keyboard::Modifiers,
+    reactive::{RwSignal, Scope, SignalGet, SignalUpdate, SignalWith, batch},
+};
+use lapce_core::{doc::DocContent, id::EditorId};
+use lapce_rpc::file_line::FileLine;
+use lapce_xi_rope::{Rope, RopeDelta, Transformer};
+use log::error;
+
+use crate::{
+    command::{CommandKind, InternalCommand, LapceCommand},
+    editor::{
+        EditorData,
+        location::{EditorLocation, EditorPosition},
+    },
+    keypress::{KeyPressFocus, condition::Condition},
+    main_split::MainSplitData,
+    window_workspace::{CommonData, Focus},
+};
+
+/// How many lines the peek takes in the editor it is shown in, its header
+/// included
+pub const PEEK_LINES: usize = 12;
+
+/// An embedded editor shown under a line of another editor, used to peek at
+/// definitions and references without leaving the current file. The lines it
+/// covers are kept blank in that editor.
+#[derive(Clone, Debug)]
+pub struct PeekData {
+    pub active:     RwSignal<bool>,
+    pub title:      RwSignal<String>,
+    /// The editor the peek was opened from, whose `reserved_lines` hold the
+    /// line it is shown under
+    pub source:     RwSignal<Option<EditorId>>,
+    pub items:      RwSignal<Vec<FileLine>>,
+    pub index:      RwSignal<usize>,
+    pub editor:     EditorData,
+    pub main_split: MainSplitData,
+    pub common:     Rc<CommonData>,
+}
+
+impl KeyPressFocus for PeekData {
+    fn get_mode(&self) -> Mode {
+        self.editor.get_mode()
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(condition, Condition::PeekFocus | Condition::ModalFocus)
+            || self.editor.check_condition(condition)
+    }
+
+    fn run_command(
+        &self,
+        command: &LapceCommand,
+        count: Option<usize>,
+        mods: Modifiers,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Focus(FocusCommand::ModalClose)
+                if !self.editor.has_completions() =>
+            {
+                self.close();
+                CommandExecuted::Yes
+            },
+            CommandKind::Focus(FocusCommand::PeekNext) => {
+                self.next();
+                CommandExecuted::Yes
+            },
+            CommandKind::Focus(FocusCommand::PeekPrevious) => {
+                self.previous();
+                CommandExecuted::Yes
+            },
+            _ => self.editor.run_command(command, count, mods),
+        }
+    }
+
+    fn expect_char(&self) -> bool {
+        self.editor.expect_char()
+    }
+
+    fn receive_char(&self, c: &str) {
+        self.editor.receive_char(c);
+    }
+}
+
+impl PeekData {
+    pub fn new(
+        cx: Scope,
+        main_split: MainSplitData,
+        common: Rc<CommonData>,
+    ) -> Self {
+        let editor = main_split.editors.make_local(cx, common.clone());
+        Self {
+            active: cx.create_rw_signal(false),
+            title: cx.create_rw_signal(String::new()),
+            source: cx.create_rw_signal(None),
+            items: cx.create_rw_signal(Vec::new()),
+            index: cx.create_rw_signal(0),
+            editor,
+            main_split,
+            common,
+        }
+    }
+
+    /// Open the peek under the cursor line of `source` with the resolved
+    /// locations.
+    pub fn show(&self, source: &EditorData, title: String, items: Vec<FileLine>) {
+        if items.is_empty() {
+            return;
+        }
+        let offset = source.cursor().with_untracked(|c| c.offset());
+        let line = source
+            .doc()
+            .lines
+            .with_untracked(|x| x.buffer().line_of_offset(offset));
+        batch(|| {
+            self.release_lines();
+            source.reserved_lines.set(Some((line, PEEK_LINES)));
+            self.title.set(title);
+            self.source.set(Some(source.id()));
+            self.items.set(items);
+            self.active.set(true);
+            self.common.focus.set(Focus::Peek);
+        });
+        self.select(0);
+    }
+
+    /// Show the result at `index` in the embedded editor.
+    pub fn select(&self, index: usize) {
+        let Some(item) = self.items.with_untracked(|x| x.get(index).cloned()) else {
+            return;
+        };
+        self.index.set(index);
+        let (doc, new_doc) = self.main_split.get_doc(
+            item.path.clone(),
+            None,
+            true,
+            DocContent::File {
+                path:      item.path.clone(),
+                read_only: false,
+            },
+        );
+        self.editor.update_doc(doc);
+        if let Err(err) = self.editor.go_to_location(
+            EditorLocation {
+                path:               item.path,
+                position:           Some(EditorPosition::Position(item.position)),
+                scroll_offset:      None,
+                ignore_unconfirmed: false,
+                same_editor_tab:    false,
+            },
+            new_doc,
+            None,
+            None,
+        ) {
+            error!("{err}");
+        }
+    }
+
+    pub fn next(&self) {
+        let len = self.items.with_untracked(|x| x.len());
+        if len > 0 {
+            self.select((self.index.get_untracked() + 1) % len);
+        }
+    }
+
+    pub fn previous(&self) {
+        let len = self.items.with_untracked(|x| x.len());
+        if len > 0 {
+            self.select((self.index.get_untracked() + len - 1) % len);
+        }
+    }
+
+    /// Open the current result in a regular editor and close the peek.
+    pub fn open_in_editor(&self) {
+        let offset = self.editor.cursor().with_untracked(|c| c.offset());
+        let path = self
+            .editor
+            .doc()
+            .content
+            .with_untracked(|content| content.path().cloned());
+        self.close();
+        if let Some(path) = path {
+            self.common
+                .internal_command
+                .send(InternalCommand::JumpToLocation {
+                    location: EditorLocation {
+                        path,
+                        position: Some(EditorPosition::Offset(offset)),
+                        scroll_offset: None,
+                        ignore_unconfirmed: false,
+                        same_editor_tab: false,
+                    },
+                });
+        }
+    }
+
+    pub fn close(&self) {
+        batch(|| {
+            self.release_lines();
+            self.active.set(false);
+            self.source.set(None);
+            if let Focus::Peek = self.common.focus.get_untracked() {
+                self.common.focus.set(Focus::Workbench);
+            }
+        });
+    }
+
+    /// Give the lines kept blank for the peek back to the editor it was
+    /// shown in.
+    fn release_lines(&self) {
+        if let Some(editor_id) = self.source.get_untracked()
+            && let Some(editor) = self.main_split.editors.editor_untracked(editor_id)
+        {
+            editor.reserved_lines.set(None);
+        }
+    }
+}
+
+/// Where `line` is after `delta`, for the peek to stay under the line it was
+/// opened from. The end of the line is followed, so that lines inserted
+/// above push it down while breaking the line keeps it in place.
+pub fn peek_line_after_delta(
+    line: usize,
+    delta: &RopeDelta,
+    old_text: &Rope,
+    new_text: &impl RopeText,
+) -> Result<usize> {
+    let offset = RopeTextRef::new(old_text).line_end_offset(line, true)?;
+    let offset = Transformer::new(delta).transform(offset, false);
+    Ok(new_text.line_of_offset(offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use doc::lines::buffer::rope_text::RopeTextRef;
+    use lapce_xi_rope::{Delta, Interval, Rope};
+
+    use super::peek_line_after_delta;
+
+    /// The line of `a\nb\nc` the peek shown under `b` is under after
+    /// replacing `range` with `new`.
+    fn line_after_edit(range: Range<usize>, new: &str) -> usize {
+        let old_text = Rope::from("a\nb\nc");
+        let delta = Delta::simple_edit(
+            Interval::new(range.start, range.end),
+            Rope::from(new),
+            old_text.len(),
+        );
+        let new_text = delta.apply(&old_text);
+        peek_line_after_delta(1, &delta, &old_text, &RopeTextRef::new(&new_text))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_peek_line_after_delta() {
+        // lines inserted above
+        assert_eq!(line_after_edit(0..0, "x\ny\n"), 3);
+        assert_eq!(line_after_edit(2..2, "x\n"), 2);
+        // the line above deleted
+        assert_eq!(line_after_edit(0..2, ""), 0);
+        // the line broken or typed at
+        assert_eq!(line_after_edit(3..3, "\n"), 1);
+        assert_eq!(line_after_edit(3..3, "bb"), 1);
+        // lines changed below
+        assert_eq!(line_after_edit(4..5, "x\ny\nz"), 1);
+    }
+}
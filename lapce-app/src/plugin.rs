@@ -932,7 +932,7 @@ pub fn plugin_info_view(plugin: PluginData, volt: VoltID) -> impl View {
                                     )
                                 },
                                 move |content| match content {
-                                    MarkdownContent::Text(text_layout) => container(
+                                    MarkdownContent::Text(text_layout, _) => container(
                                         rich_text(move || text_layout.clone())
                                             .style(|s| s.width_full()),
                                     )
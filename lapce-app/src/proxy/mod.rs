@@ -12,7 +12,8 @@ use lapce_core::{
 };
 use lapce_proxy::dispatch::Dispatcher;
 use lapce_rpc::{
-    core::{CoreHandler, CoreNotification, CoreRpcHandler},
+    RequestId,
+    core::{CoreHandler, CoreNotification, CoreRequest, CoreRpcHandler},
     plugin::VoltID,
     proxy::{ProxyRpcHandler, ProxyStatus, WorkspaceContext},
 };
@@ -26,7 +27,8 @@ mod ssh;
 mod wsl;
 
 pub struct Proxy {
-    pub tx: Sender<CoreNotification>,
+    pub tx:         Sender<CoreNotification>,
+    pub request_tx: Sender<(RequestId, CoreRequest)>,
 }
 
 #[derive(Clone)]
@@ -34,6 +36,8 @@ pub struct ProxyData {
     pub proxy_rpc:    ProxyRpcHandler,
     pub core_rpc:     CoreRpcHandler,
     pub notification: ReadSignal<Option<CoreNotification>>,
+    /// The requests waiting for an answer from the user
+    pub request:      ReadSignal<Option<(RequestId, CoreRequest)>>,
 }
 
 impl ProxyData {
@@ -114,12 +118,13 @@ pub fn new_proxy(
     }
 
     let (tx, rx) = std::sync::mpsc::channel();
+    let (request_tx, request_rx) = std::sync::mpsc::channel();
     {
         let core_rpc = core_rpc.clone();
         std::thread::Builder::new()
             .name("CoreRpcHandler".to_owned())
             .spawn(move || {
-                let mut proxy = Proxy { tx };
+                let mut proxy = Proxy { tx, request_tx };
                 core_rpc.mainloop(&mut proxy);
                 core_rpc.notification(CoreNotification::ProxyStatus {
                     status: ProxyStatus::Connected,
@@ -129,11 +134,13 @@ pub fn new_proxy(
     };
 
     let notification = create_signal_from_channel(rx);
+    let request = create_signal_from_channel(request_rx);
 
     ProxyData {
         proxy_rpc,
         core_rpc,
         notification,
+        request,
     }
 }
 
@@ -153,11 +160,10 @@ impl CoreHandler for Proxy {
         }
     }
 
-    fn handle_request(
-        &mut self,
-        _id: lapce_rpc::RequestId,
-        _rpc: lapce_rpc::core::CoreRequest,
-    ) {
+    fn handle_request(&mut self, id: RequestId, rpc: CoreRequest) {
+        if let Err(err) = self.request_tx.send((id, rpc)) {
+            log::error!("{:?}", err);
+        }
     }
 }
 
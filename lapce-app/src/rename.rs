@@ -122,10 +122,21 @@ impl RenameData {
             let path = self.path.get_untracked();
             let position = self.position.get_untracked();
             let internal_command = self.common.internal_command;
+            let preview = self
+                .common
+                .config
+                .with_untracked(|x| x.editor.preview_workspace_edits);
+            let title = format!("Rename to `{new_name}`");
             let send = create_ext_action(self.common.scope, move |result| {
                 if let Ok(ProxyResponse::Rename { edit }) = result {
-                    internal_command
-                        .send(InternalCommand::ApplyWorkspaceEdit { edit });
+                    if preview {
+                        internal_command.send(
+                            InternalCommand::PreviewWorkspaceEdit { title, edit },
+                        );
+                    } else {
+                        internal_command
+                            .send(InternalCommand::ApplyWorkspaceEdit { edit });
+                    }
                 }
             });
             self.common.proxy.proxy_rpc.rename(
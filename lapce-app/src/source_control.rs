@@ -1,30 +1,282 @@
-use std::{path::PathBuf, rc::Rc};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+};
 
 use doc::lines::{editor_command::CommandExecuted, mode::Mode};
 use floem::{
+    ext_event::create_ext_action,
+    file::{FileDialogOptions, FileInfo},
+    file_action::save_as,
     keyboard::Modifiers,
-    reactive::{RwSignal, Scope, SignalWith},
+    reactive::{
+        Memo, RwSignal, Scope, SignalGet, SignalUpdate, SignalWith, batch,
+        use_context,
+    },
 };
 use indexmap::IndexMap;
-use lapce_rpc::source_control::FileDiff;
+use lapce_rpc::{
+    RequestId, RpcError,
+    proxy::ProxyResponse,
+    source_control::{
+        CheckoutOutcome, CommitOptions, FileDiff, FileHistoryEntry, LogEntry,
+        MergeOutcome, PatchFile, PatchSource, PullOutcome, StageSelection,
+        StashEntry,
+    },
+};
+use lapce_xi_rope::Rope;
+use log::warn;
+use lsp_types::MessageType;
 
 use crate::{
-    command::CommandKind,
+    alert::AlertButton,
+    command::{CommandKind, InternalCommand},
+    config::color::LapceColor,
+    db::LapceDb,
     editor::EditorData,
     keypress::{KeyPressFocus, condition::Condition},
     main_split::Editors,
     window_workspace::CommonData,
 };
 
+/// What selecting a ref in the references palette does.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ReferenceAction {
+    #[default]
+    Checkout,
+    /// Checkout, stashing the local changes and applying them again after
+    CheckoutAutoStash,
+    CreateBranchFrom,
+    RenameBranch,
+    DeleteBranch,
+    Merge,
+    /// Merge with a merge commit even when a fast-forward is possible
+    MergeNoFf,
+    /// Open a diff editor of the file at the ref and in the working copy
+    CompareFile(PathBuf),
+    /// List the files changed between the ref and the working copy
+    CompareWorkspace,
+    /// List the files changed between two refs, picking the second once
+    /// `base` is picked
+    CompareRefs {
+        base: Option<String>,
+    },
+    /// Show the changes in the gutter against the ref instead of `HEAD`
+    GutterBase,
+}
+
+/// What the name typed in the branch name palette is for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BranchNameTarget {
+    Create { start_point: Option<String> },
+    Rename { old_name: String },
+}
+
+/// A username, password or passphrase a git remote needs, asked in the
+/// palette while the proxy waits for the answer.
+#[derive(Clone, Debug)]
+pub struct GitCredentialPrompt {
+    pub id:     RequestId,
+    pub prompt: String,
+    /// Whether the answer must not be shown while it is typed
+    pub secret: bool,
+}
+
+/// The commits that changed a file, listed in the file history panel.
+#[derive(Clone, Debug)]
+pub struct FileHistoryData {
+    /// The file whose history is listed
+    pub path:    RwSignal<Option<PathBuf>>,
+    pub entries: RwSignal<im::Vector<FileHistoryEntry>>,
+    /// Whether more commits follow the loaded ones
+    pub more:    RwSignal<bool>,
+    pub loading: RwSignal<bool>,
+    /// Counts the listings requested, so that the pages of a listing that
+    /// was replaced are dropped
+    request:     RwSignal<u64>,
+}
+
+/// How many of the last commit messages are kept.
+const COMMIT_MESSAGE_HISTORY: usize = 20;
+
+/// How many commits the git log panel loads at a time.
+const GIT_LOG_PAGE: usize = 200;
+
+/// How many commits the file history panel loads at a time.
+const FILE_HISTORY_PAGE: usize = 100;
+
+/// The commits listed in the git log panel, loaded a page at a time.
+#[derive(Clone, Debug)]
+pub struct GitLogData {
+    pub entries:        RwSignal<im::Vector<LogEntry>>,
+    /// Whether more commits follow the loaded ones
+    pub more:           RwSignal<bool>,
+    pub loading:        RwSignal<bool>,
+    /// Only list the commits changing this file or directory
+    pub path_filter:    RwSignal<Option<PathBuf>>,
+    /// The commit whose changed files are listed
+    pub selected:       RwSignal<Option<String>>,
+    pub selected_files: RwSignal<im::Vector<FileDiff>>,
+    /// Counts the listings requested, so that the pages of a listing that
+    /// was replaced are dropped
+    request:            RwSignal<u64>,
+}
+
+/// The files changed between a ref and another ref or the working copy,
+/// listed in the source control panel.
+#[derive(Clone, Debug)]
+pub struct CompareData {
+    /// The ref compared from, nothing is compared when `None`
+    pub base:    RwSignal<Option<String>>,
+    /// The ref compared to, the working copy when `None`
+    pub target:  RwSignal<Option<String>>,
+    pub files:   RwSignal<im::Vector<FileDiff>>,
+    pub loading: RwSignal<bool>,
+}
+
+/// A patch previewed in the source control panel before it is applied.
+#[derive(Clone, Debug)]
+pub struct PatchData {
+    /// Where the patch comes from, nothing is previewed when `None`
+    pub name:     RwSignal<Option<String>>,
+    pub text:     RwSignal<String>,
+    pub files:    RwSignal<im::Vector<PatchFile>>,
+    /// The indexes of the hunks to apply, counted across the files
+    pub selected: RwSignal<HashSet<usize>>,
+    pub loading:  RwSignal<bool>,
+}
+
+/// The git state of a file shown in the file explorer. A folder shows the
+/// greatest state of the files in it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GitFileStatus {
+    Untracked,
+    Added,
+    Deleted,
+    Modified,
+    Conflicted,
+}
+
+impl GitFileStatus {
+    /// The letter shown next to the file name.
+    pub fn badge(&self) -> &'static str {
+        match self {
+            GitFileStatus::Untracked => "U",
+            GitFileStatus::Added => "A",
+            GitFileStatus::Deleted => "D",
+            GitFileStatus::Modified => "M",
+            GitFileStatus::Conflicted => "C",
+        }
+    }
+
+    pub fn color(&self) -> &'static str {
+        match self {
+            GitFileStatus::Untracked | GitFileStatus::Added => {
+                LapceColor::SOURCE_CONTROL_ADDED
+            },
+            GitFileStatus::Deleted => LapceColor::SOURCE_CONTROL_REMOVED,
+            GitFileStatus::Modified => LapceColor::SOURCE_CONTROL_MODIFIED,
+            GitFileStatus::Conflicted => LapceColor::LAPCE_ERROR,
+        }
+    }
+}
+
+/// The git state of every changed file, and of the folders containing them
+/// up to `root`.
+pub fn git_file_statuses<'a>(
+    staged: impl IntoIterator<Item = &'a FileDiff>,
+    unstaged: impl IntoIterator<Item = &'a FileDiff>,
+    conflicts: impl IntoIterator<Item = &'a PathBuf>,
+    root: &Path,
+) -> HashMap<PathBuf, GitFileStatus> {
+    let mut files = HashMap::new();
+    let mut set = |path: &PathBuf, status: GitFileStatus| {
+        let current = files.entry(path.clone()).or_insert(status);
+        *current = (*current).max(status);
+    };
+    let mut staged_added = HashSet::new();
+    for diff in staged {
+        match diff {
+            FileDiff::Added(path) => {
+                staged_added.insert(path.clone());
+                set(path, GitFileStatus::Added);
+            },
+            FileDiff::Deleted(path) => set(path, GitFileStatus::Deleted),
+            FileDiff::Modified(path) | FileDiff::Renamed(path, _) => {
+                set(path, GitFileStatus::Modified)
+            },
+        }
+    }
+    for diff in unstaged {
+        match diff {
+            // A file added to the index stays "added" while it is edited
+            // further
+            FileDiff::Added(path) if !staged_added.contains(path) => {
+                set(path, GitFileStatus::Untracked)
+            },
+            FileDiff::Added(_) => {},
+            FileDiff::Deleted(path) => set(path, GitFileStatus::Deleted),
+            FileDiff::Modified(path) | FileDiff::Renamed(path, _) => {
+                if !staged_added.contains(path) {
+                    set(path, GitFileStatus::Modified)
+                }
+            },
+        }
+    }
+    for path in conflicts {
+        set(path, GitFileStatus::Conflicted);
+    }
+
+    let mut statuses = files.clone();
+    for (path, status) in files {
+        for folder in path.ancestors().skip(1) {
+            if !folder.starts_with(root) {
+                break;
+            }
+            let current = statuses.entry(folder.to_path_buf()).or_insert(status);
+            *current = (*current).max(status);
+        }
+    }
+    statuses
+}
+
 #[derive(Clone, Debug)]
 pub struct SourceControlData {
-    // VCS modified files & whether they should be included in the next commit
-    pub file_diffs: RwSignal<IndexMap<PathBuf, (FileDiff, bool)>>,
-    pub branch:     RwSignal<String>,
-    pub branches:   RwSignal<im::Vector<String>>,
-    pub tags:       RwSignal<im::Vector<String>>,
-    pub editor:     EditorData,
-    pub common:     Rc<CommonData>,
+    // VCS modified files that are not staged
+    pub file_diffs:      RwSignal<IndexMap<PathBuf, FileDiff>>,
+    // Files staged for the next commit
+    pub staged_diffs:    RwSignal<IndexMap<PathBuf, FileDiff>>,
+    /// The unstaged files left out of a commit made with nothing staged
+    pub unchecked:       RwSignal<HashSet<PathBuf>>,
+    pub branch:          RwSignal<String>,
+    /// The id of the commit checked out
+    pub head_commit:     RwSignal<Option<String>>,
+    pub branches:        RwSignal<im::Vector<String>>,
+    pub tags:            RwSignal<im::Vector<String>>,
+    pub stashes:         RwSignal<im::Vector<StashEntry>>,
+    /// Commits the current branch is ahead of and behind its upstream
+    pub ahead_behind:    RwSignal<Option<(usize, usize)>>,
+    /// Files left with conflicts, resolved in the merge editor
+    pub conflicts:       RwSignal<im::Vector<PathBuf>>,
+    /// The git state of the changed files and the folders containing them
+    pub file_statuses:   Memo<HashMap<PathBuf, GitFileStatus>>,
+    pub file_history:    FileHistoryData,
+    pub git_log:         GitLogData,
+    pub compare:         CompareData,
+    pub patch:           PatchData,
+    /// Whether committing replaces the last commit
+    pub amend:           RwSignal<bool>,
+    /// Whether commits get a `Signed-off-by` trailer
+    pub sign_off:        RwSignal<bool>,
+    /// The messages of the last commits, most recent first
+    pub commit_messages: RwSignal<im::Vector<String>>,
+    /// The message the commit box was last filled with, which is replaced
+    /// when switching to or from amending unless it was edited
+    default_message:     RwSignal<String>,
+    pub editor:          EditorData,
+    pub common:          Rc<CommonData>,
 }
 
 impl KeyPressFocus for SourceControlData {
@@ -62,11 +314,73 @@ impl KeyPressFocus for SourceControlData {
 
 impl SourceControlData {
     pub fn new(cx: Scope, editors: Editors, common: Rc<CommonData>) -> Self {
+        let db: Arc<LapceDb> = use_context().unwrap();
+        let commit_messages = db
+            .get_commit_messages(&common.workspace)
+            .unwrap_or_default();
+        let file_diffs = cx.create_rw_signal(IndexMap::new());
+        let staged_diffs = cx.create_rw_signal(IndexMap::new());
+        let conflicts = cx.create_rw_signal(im::Vector::new());
+        let root = common.workspace.path().cloned().unwrap_or_default();
+        let file_statuses = cx.create_memo(move |_| {
+            staged_diffs.with(|staged: &IndexMap<PathBuf, FileDiff>| {
+                file_diffs.with(|unstaged: &IndexMap<PathBuf, FileDiff>| {
+                    conflicts.with(|conflicts| {
+                        git_file_statuses(
+                            staged.values(),
+                            unstaged.values(),
+                            conflicts,
+                            &root,
+                        )
+                    })
+                })
+            })
+        });
         Self {
-            file_diffs: cx.create_rw_signal(IndexMap::new()),
+            file_diffs,
+            staged_diffs,
+            unchecked: cx.create_rw_signal(HashSet::new()),
             branch: cx.create_rw_signal("".to_string()),
+            head_commit: cx.create_rw_signal(None),
             branches: cx.create_rw_signal(im::Vector::new()),
             tags: cx.create_rw_signal(im::Vector::new()),
+            stashes: cx.create_rw_signal(im::Vector::new()),
+            ahead_behind: cx.create_rw_signal(None),
+            conflicts,
+            file_statuses,
+            file_history: FileHistoryData {
+                path:    cx.create_rw_signal(None),
+                entries: cx.create_rw_signal(im::Vector::new()),
+                more:    cx.create_rw_signal(false),
+                loading: cx.create_rw_signal(false),
+                request: cx.create_rw_signal(0),
+            },
+            git_log: GitLogData {
+                entries:        cx.create_rw_signal(im::Vector::new()),
+                more:           cx.create_rw_signal(false),
+                loading:        cx.create_rw_signal(false),
+                path_filter:    cx.create_rw_signal(None),
+                selected:       cx.create_rw_signal(None),
+                selected_files: cx.create_rw_signal(im::Vector::new()),
+                request:        cx.create_rw_signal(0),
+            },
+            compare: CompareData {
+                base:    cx.create_rw_signal(None),
+                target:  cx.create_rw_signal(None),
+                files:   cx.create_rw_signal(im::Vector::new()),
+                loading: cx.create_rw_signal(false),
+            },
+            patch: PatchData {
+                name:     cx.create_rw_signal(None),
+                text:     cx.create_rw_signal(String::new()),
+                files:    cx.create_rw_signal(im::Vector::new()),
+                selected: cx.create_rw_signal(HashSet::new()),
+                loading:  cx.create_rw_signal(false),
+            },
+            amend: cx.create_rw_signal(false),
+            sign_off: cx.create_rw_signal(false),
+            commit_messages: cx.create_rw_signal(commit_messages.into()),
+            default_message: cx.create_rw_signal(String::new()),
             editor: editors.make_local_with_name(
                 cx,
                 common.clone(),
@@ -76,36 +390,1041 @@ impl SourceControlData {
         }
     }
 
+    /// Commit the staged changes, or every checked change when nothing is
+    /// staged. When amending, the last commit is replaced even without
+    /// changes.
     pub fn commit(&self) {
-        let diffs: Vec<FileDiff> = self.file_diffs.with_untracked(|file_diffs| {
-            file_diffs
-                .iter()
-                .filter_map(
-                    |(_, (diff, checked))| {
-                        if *checked { Some(diff) } else { None }
-                    },
-                )
-                .cloned()
-                .collect()
+        let amend = self.amend.get_untracked();
+        let nothing_staged =
+            self.staged_diffs.with_untracked(|diffs| diffs.is_empty());
+        let changes: Vec<FileDiff> = self.unchecked.with_untracked(|unchecked| {
+            self.file_diffs.with_untracked(|diffs| {
+                diffs
+                    .iter()
+                    .filter(|(path, _)| !unchecked.contains(*path))
+                    .map(|(_, diff)| diff.clone())
+                    .collect()
+            })
         });
-        if diffs.is_empty() {
+        let Some(diffs) = commit_diffs(nothing_staged, changes, amend) else {
+            return;
+        };
+
+        let message = self.commit_message();
+        let message = message.trim().to_string();
+        if message.is_empty() {
             return;
         }
 
-        let message = self
-            .editor
+        let options = CommitOptions {
+            amend,
+            sign_off: self.sign_off.get_untracked(),
+            run_hooks: self
+                .common
+                .config
+                .with_untracked(|config| config.core.git_commit_hooks),
+        };
+        let data = self.clone();
+        let send = {
+            let message = message.clone();
+            create_ext_action(self.common.scope, move |result| match result {
+                Ok(ProxyResponse::GitCommitResponse {}) => {
+                    data.remember_commit_message(message.clone());
+                    data.amend.set(false);
+                    // keep what was typed while committing
+                    if data.commit_message().trim() == message {
+                        data.fill_commit_message(false, true);
+                    }
+                },
+                result => show_git_error(&data.common, "Git Commit failure", result),
+            })
+        };
+        self.common.proxy.proxy_rpc.git_commit(
+            message,
+            diffs,
+            options,
+            move |(_, result)| {
+                send(result);
+            },
+        );
+    }
+
+    pub fn commit_message(&self) -> String {
+        self.editor
             .doc()
             .lines
-            .with_untracked(|x| x.buffer().to_string());
-        let message = message.trim();
-        if message.is_empty() {
+            .with_untracked(|x| x.buffer().to_string())
+    }
+
+    pub fn set_commit_message(&self, message: &str) {
+        self.editor.doc().reload(Rope::from(message), true);
+        self.editor
+            .cursor()
+            .update(|cursor| cursor.set_offset(0, false, false));
+    }
+
+    /// Switch to or from amending the last commit, bringing in its message
+    /// unless another one was typed.
+    pub fn set_amend(&self, amend: bool) {
+        self.amend.set(amend);
+        self.fill_commit_message(amend, false);
+    }
+
+    /// Fill the commit box with the message of the last commit when
+    /// `amend` is set, or with the commit template. Unless `force` is set,
+    /// a message that was typed is kept.
+    pub fn fill_commit_message(&self, amend: bool, force: bool) {
+        let data = self.clone();
+        let send = create_ext_action(self.common.scope, move |result| {
+            let message = match result {
+                Ok(ProxyResponse::GitCommitMessageResponse { message }) => {
+                    message.unwrap_or_default()
+                },
+                Ok(_) => return,
+                Err(err) => {
+                    warn!("{}", err.message);
+                    String::new()
+                },
+            };
+            let current = data.commit_message();
+            if force
+                || current.trim().is_empty()
+                || data.default_message.with_untracked(|x| *x == current)
+            {
+                data.set_commit_message(&message);
+                data.default_message.set(message);
+            }
+        });
+        self.common
+            .proxy
+            .proxy_rpc
+            .git_commit_message(amend, move |(_, result)| {
+                send(result);
+            });
+    }
+
+    fn remember_commit_message(&self, message: String) {
+        self.commit_messages.update(|messages| {
+            messages.retain(|m| *m != message);
+            messages.push_front(message);
+            messages.truncate(COMMIT_MESSAGE_HISTORY);
+        });
+        let db: Arc<LapceDb> = use_context().unwrap();
+        db.save_commit_messages(
+            self.common.workspace.clone(),
+            self.commit_messages.get_untracked().into_iter().collect(),
+            &self.common.local_task,
+        );
+    }
+
+    pub fn stage(&self, selection: StageSelection) {
+        let common = self.common.clone();
+        let send = create_ext_action(self.common.scope, move |result| {
+            show_git_error(&common, "Git Stage failure", result);
+        });
+        self.common
+            .proxy
+            .proxy_rpc
+            .git_stage(selection, move |(_, result)| {
+                send(result);
+            });
+    }
+
+    pub fn unstage(&self, selection: StageSelection) {
+        let common = self.common.clone();
+        let send = create_ext_action(self.common.scope, move |result| {
+            show_git_error(&common, "Git Unstage failure", result);
+        });
+        self.common
+            .proxy
+            .proxy_rpc
+            .git_unstage(selection, move |(_, result)| {
+                send(result);
+            });
+    }
+
+    pub fn stage_all(&self) {
+        let files = Self::diff_paths(self.file_diffs);
+        if !files.is_empty() {
+            self.stage(StageSelection::Files(files));
+        }
+    }
+
+    pub fn unstage_all(&self) {
+        let files = Self::diff_paths(self.staged_diffs);
+        if !files.is_empty() {
+            self.unstage(StageSelection::Files(files));
+        }
+    }
+
+    /// Switch to `reference`. When local changes are in the way, offer to
+    /// stash them and apply them again after the checkout.
+    pub fn checkout(&self, reference: String, auto_stash: bool) {
+        let data = self.clone();
+        let send = {
+            let reference = reference.clone();
+            create_ext_action(self.common.scope, move |result| {
+                data.handle_checkout_result(reference, result);
+            })
+        };
+        self.common.proxy.proxy_rpc.git_checkout(
+            reference,
+            auto_stash,
+            move |(_, result)| {
+                send(result);
+            },
+        );
+    }
+
+    /// Create the branch `name` from `start_point`, or from `HEAD`, and check
+    /// it out.
+    pub fn create_branch(&self, name: String, start_point: Option<String>) {
+        let data = self.clone();
+        let send = {
+            let name = name.clone();
+            create_ext_action(self.common.scope, move |result| {
+                data.handle_checkout_result(name, result);
+            })
+        };
+        self.common.proxy.proxy_rpc.git_create_branch(
+            name,
+            start_point,
+            move |(_, result)| {
+                send(result);
+            },
+        );
+    }
+
+    fn handle_checkout_result(
+        &self,
+        reference: String,
+        result: Result<ProxyResponse, RpcError>,
+    ) {
+        match result {
+            Ok(ProxyResponse::GitCheckoutResponse { outcome }) => match outcome {
+                CheckoutOutcome::CheckedOut => {},
+                CheckoutOutcome::LocalChanges => {
+                    let data = self.clone();
+                    let internal_command = self.common.internal_command;
+                    internal_command.send(InternalCommand::ShowAlert {
+                        title:   format!(
+                            "Your local changes would be overwritten by checking \
+                             out {reference}"
+                        ),
+                        msg:     "Stash them to apply them again after the \
+                                  checkout."
+                            .to_string(),
+                        buttons: vec![AlertButton {
+                            text:   "Stash and Checkout".to_string(),
+                            action: Rc::new(move || {
+                                internal_command.send(InternalCommand::HideAlert);
+                                data.checkout(reference.clone(), true);
+                            }),
+                        }],
+                    });
+                },
+                CheckoutOutcome::StashConflicts => {
+                    self.common.show_popup_message(
+                        "Git Checkout".to_string(),
+                        MessageType::WARNING,
+                        format!(
+                            "Checked out {reference}, but the stashed changes \
+                             conflict with it. They are kept in the stash list."
+                        ),
+                    );
+                },
+            },
+            result => show_git_error(&self.common, "Git Checkout failure", result),
+        }
+    }
+
+    pub fn rename_branch(&self, old_name: String, new_name: String) {
+        let common = self.common.clone();
+        let send = create_ext_action(self.common.scope, move |result| {
+            show_git_error(&common, "Git Rename Branch failure", result);
+        });
+        self.common.proxy.proxy_rpc.git_rename_branch(
+            old_name,
+            new_name,
+            move |(_, result)| {
+                send(result);
+            },
+        );
+    }
+
+    /// Delete the local branch `name`, asking first when it isn't merged.
+    pub fn delete_branch(&self, name: String, force: bool) {
+        let data = self.clone();
+        let send = {
+            let name = name.clone();
+            create_ext_action(self.common.scope, move |result| match result {
+                Ok(ProxyResponse::GitDeleteBranchResponse { deleted: false }) => {
+                    let internal_command = data.common.internal_command;
+                    let data = data.clone();
+                    internal_command.send(InternalCommand::ShowAlert {
+                        title:   format!("The branch {name} is not fully merged"),
+                        msg:     "Its unmerged commits will be lost if you delete \
+                                  it."
+                        .to_string(),
+                        buttons: vec![AlertButton {
+                            text:   "Delete Anyway".to_string(),
+                            action: Rc::new(move || {
+                                internal_command.send(InternalCommand::HideAlert);
+                                data.delete_branch(name.clone(), true);
+                            }),
+                        }],
+                    });
+                },
+                result => {
+                    show_git_error(&data.common, "Git Delete Branch failure", result)
+                },
+            })
+        };
+        self.common.proxy.proxy_rpc.git_delete_branch(
+            name,
+            force,
+            move |(_, result)| {
+                send(result);
+            },
+        );
+    }
+
+    /// Merge `reference` into the current branch. With `no_ff` a merge commit
+    /// is created even when the branch could be fast-forwarded.
+    pub fn merge(&self, reference: String, no_ff: bool) {
+        let common = self.common.clone();
+        let send = {
+            let reference = reference.clone();
+            create_ext_action(self.common.scope, move |result| match result {
+                Ok(ProxyResponse::GitMergeResponse { outcome }) => match outcome {
+                    MergeOutcome::UpToDate => {
+                        common.show_status_message("Already up to date".to_string())
+                    },
+                    MergeOutcome::FastForward => common.show_status_message(
+                        format!("Fast-forwarded to {reference}"),
+                    ),
+                    MergeOutcome::Merged => {
+                        common.show_status_message(format!("Merged {reference}"))
+                    },
+                    MergeOutcome::Conflicts => common.show_popup_message(
+                        "Git Merge".to_string(),
+                        MessageType::WARNING,
+                        format!(
+                            "Merging {reference} stopped with conflicts. Resolve \
+                             them, then commit the result."
+                        ),
+                    ),
+                },
+                result => show_git_error(&common, "Git Merge failure", result),
+            })
+        };
+        self.common.proxy.proxy_rpc.git_merge(
+            reference,
+            no_ff,
+            move |(_, result)| {
+                send(result);
+            },
+        );
+    }
+
+    pub fn fetch(&self) {
+        let common = self.common.clone();
+        let send = create_ext_action(self.common.scope, move |result| {
+            show_git_error(&common, "Git Fetch failure", result);
+        });
+        self.common
+            .proxy
+            .proxy_rpc
+            .git_fetch(None, move |(_, result)| {
+                send(result);
+            });
+    }
+
+    /// Update the current branch from its upstream, either only
+    /// fast-forwarding or rebasing the local commits onto the upstream ones.
+    pub fn pull(&self, rebase: bool) {
+        let common = self.common.clone();
+        let send =
+            create_ext_action(self.common.scope, move |result| match result {
+                Ok(ProxyResponse::GitPullResponse { outcome }) => {
+                    common.show_status_message(
+                        match outcome {
+                            PullOutcome::UpToDate => "Already up to date",
+                            PullOutcome::FastForward => {
+                                "Fast-forwarded to the upstream"
+                            },
+                            PullOutcome::Rebased => "Rebased onto the upstream",
+                        }
+                        .to_string(),
+                    );
+                },
+                result => show_git_error(&common, "Git Pull failure", result),
+            });
+        self.common
+            .proxy
+            .proxy_rpc
+            .git_pull(rebase, move |(_, result)| {
+                send(result);
+            });
+    }
+
+    pub fn push(&self) {
+        let common = self.common.clone();
+        let send =
+            create_ext_action(self.common.scope, move |result| match result {
+                Ok(ProxyResponse::GitRemoteResponse {}) => {
+                    common.show_status_message("Pushed".to_string());
+                },
+                result => show_git_error(&common, "Git Push failure", result),
+            });
+        self.common.proxy.proxy_rpc.git_push(move |(_, result)| {
+            send(result);
+        });
+    }
+
+    /// Stash the changes of the working copy and the index.
+    pub fn stash_save(&self, include_untracked: bool) {
+        let common = self.common.clone();
+        let send = create_ext_action(self.common.scope, move |result| {
+            show_git_error(&common, "Git Stash failure", result);
+        });
+        self.common.proxy.proxy_rpc.git_stash_save(
+            include_untracked,
+            move |(_, result)| {
+                send(result);
+            },
+        );
+    }
+
+    /// The id of `stash@{0}`, the last stash saved.
+    pub fn latest_stash(&self) -> Option<String> {
+        self.stashes
+            .with_untracked(|stashes| stashes.front().map(|stash| stash.id.clone()))
+    }
+
+    pub fn stash_apply(&self, id: String) {
+        let common = self.common.clone();
+        let send = create_ext_action(self.common.scope, move |result| {
+            show_git_error(&common, "Git Stash Apply failure", result);
+        });
+        self.common
+            .proxy
+            .proxy_rpc
+            .git_stash_apply(id, move |(_, result)| {
+                send(result);
+            });
+    }
+
+    pub fn stash_pop(&self, id: String) {
+        let common = self.common.clone();
+        let send = create_ext_action(self.common.scope, move |result| {
+            show_git_error(&common, "Git Stash Pop failure", result);
+        });
+        self.common
+            .proxy
+            .proxy_rpc
+            .git_stash_pop(id, move |(_, result)| {
+                send(result);
+            });
+    }
+
+    /// Drop the stash `id` once confirmed, its changes are lost.
+    pub fn stash_drop(&self, id: String) {
+        let Some(stash) = self.stashes.with_untracked(|stashes| {
+            stashes.iter().find(|stash| stash.id == id).cloned()
+        }) else {
+            return;
+        };
+        let common = self.common.clone();
+        let internal_command = self.common.internal_command;
+        internal_command.send(InternalCommand::ShowAlert {
+            title:   format!("Drop stash@{{{}}}?", stash.index),
+            msg:     format!("\"{}\" will be lost.", stash.message),
+            buttons: vec![AlertButton {
+                text:   "Drop".to_string(),
+                action: Rc::new(move || {
+                    internal_command.send(InternalCommand::HideAlert);
+                    let common_for_result = common.clone();
+                    let send = create_ext_action(common.scope, move |result| {
+                        show_git_error(
+                            &common_for_result,
+                            "Git Stash Drop failure",
+                            result,
+                        );
+                    });
+                    // the stash is dropped wherever it is in the list by then
+                    common.proxy.proxy_rpc.git_stash_drop(
+                        stash.id.clone(),
+                        move |(_, result)| {
+                            send(result);
+                        },
+                    );
+                }),
+            }],
+        });
+    }
+
+    /// List the files the stash `id` changed in `files`, the untracked ones
+    /// as added.
+    pub fn load_stash_files(
+        &self,
+        id: String,
+        files: RwSignal<Option<im::Vector<(FileDiff, String)>>>,
+    ) {
+        let send = {
+            let id = id.clone();
+            create_ext_action(self.common.scope, move |result| match result {
+                Ok(ProxyResponse::GitStashFilesResponse {
+                    files: changed,
+                    untracked,
+                }) => {
+                    // the untracked files are in the third parent, which has
+                    // no parent of its own
+                    let untracked_commit = format!("{id}^3");
+                    files.set(Some(
+                        changed
+                            .into_iter()
+                            .map(|diff| (diff, id.clone()))
+                            .chain(untracked.into_iter().map(|path| {
+                                (FileDiff::Added(path), untracked_commit.clone())
+                            }))
+                            .collect(),
+                    ));
+                },
+                Ok(_) => {},
+                Err(err) => {
+                    warn!("{}", err.message);
+                    files.set(Some(im::Vector::new()));
+                },
+            })
+        };
+        self.common
+            .proxy
+            .proxy_rpc
+            .git_stash_files(id, move |(_, result)| {
+                send(result);
+            });
+    }
+
+    /// List the commits that changed `path` in the file history panel.
+    pub fn load_file_history(&self, path: PathBuf) {
+        self.file_history.path.set(Some(path.clone()));
+        self.load_file_history_page(path, None);
+    }
+
+    /// Load the page of the file history after the listed commits.
+    pub fn load_more_file_history(&self) {
+        let history = &self.file_history;
+        if !history.more.get_untracked() || history.loading.get_untracked() {
             return;
         }
+        let Some(last) = history.entries.with_untracked(|x| x.last().cloned())
+        else {
+            return;
+        };
+        // the older commits have the file where the last one had it before
+        let path = last.previous_path.unwrap_or(last.path);
+        self.load_file_history_page(path, Some(last.commit.id));
+    }
+
+    fn load_file_history_page(&self, path: PathBuf, from: Option<String>) {
+        let history = self.file_history.clone();
+        let more = from.is_some();
+        let request = history.request.get_untracked() + 1;
+        batch(|| {
+            history.request.set(request);
+            history.loading.set(true);
+        });
+        let send = create_ext_action(self.common.scope, move |result| {
+            // a newer request was made, possibly for another file
+            if history.request.get_untracked() != request {
+                return;
+            }
+            history.loading.set(false);
+            match result {
+                Ok(ProxyResponse::GitFileHistoryResponse {
+                    history: entries,
+                    more: has_more,
+                }) => {
+                    batch(|| {
+                        if more {
+                            history.entries.update(|x| x.extend(entries));
+                        } else {
+                            history.entries.set(entries.into());
+                        }
+                        history.more.set(has_more);
+                    });
+                },
+                Ok(_) => {},
+                Err(err) => {
+                    warn!("{}", err.message);
+                    if !more {
+                        history.entries.set(im::Vector::new());
+                    }
+                    history.more.set(false);
+                },
+            }
+        });
+        self.common.proxy.proxy_rpc.git_file_history(
+            path,
+            from,
+            FILE_HISTORY_PAGE,
+            move |(_, result)| {
+                send(result);
+            },
+        );
+    }
+
+    /// List the first page of the git log again, or load the page after the
+    /// listed commits when `more` is set.
+    pub fn load_git_log(&self, more: bool) {
+        let log = self.git_log.clone();
+        let from = if more {
+            let last = log.entries.with_untracked(|entries| {
+                entries.last().map(|e| e.commit.id.clone())
+            });
+            if last.is_none() || log.loading.get_untracked() {
+                return;
+            }
+            last
+        } else {
+            None
+        };
+        let request = log.request.get_untracked() + 1;
+        batch(|| {
+            log.request.set(request);
+            log.loading.set(true);
+        });
+        let send = create_ext_action(self.common.scope, move |result| {
+            if log.request.get_untracked() != request {
+                return;
+            }
+            log.loading.set(false);
+            match result {
+                Ok(ProxyResponse::GitLogResponse {
+                    entries,
+                    more: has_more,
+                }) => {
+                    batch(|| {
+                        if more {
+                            log.entries.update(|log| log.extend(entries));
+                        } else {
+                            log.entries.set(entries.into());
+                        }
+                        log.more.set(has_more);
+                    });
+                },
+                Ok(_) => {},
+                Err(err) => {
+                    warn!("{}", err.message);
+                },
+            }
+        });
+        self.common.proxy.proxy_rpc.git_log(
+            from,
+            GIT_LOG_PAGE,
+            self.git_log.path_filter.get_untracked(),
+            move |(_, result)| {
+                send(result);
+            },
+        );
+    }
 
-        self.editor.reset();
+    /// Select `commit` in the git log and list the files it changed.
+    pub fn select_log_commit(&self, commit: String) {
+        let log = self.git_log.clone();
+        batch(|| {
+            log.selected.set(Some(commit.clone()));
+            log.selected_files.set(im::Vector::new());
+        });
+        let send = {
+            let commit = commit.clone();
+            create_ext_action(self.common.scope, move |result| {
+                if log.selected.get_untracked().as_ref() != Some(&commit) {
+                    return;
+                }
+                match result {
+                    Ok(ProxyResponse::GitCommitFilesResponse { files }) => {
+                        log.selected_files.set(files.into());
+                    },
+                    Ok(_) => {},
+                    Err(err) => {
+                        warn!("{}", err.message);
+                    },
+                }
+            })
+        };
         self.common
             .proxy
             .proxy_rpc
-            .git_commit(message.to_string(), diffs);
+            .git_commit_files(commit, move |(_, result)| {
+                send(result);
+            });
+    }
+
+    /// List the files changed from `base` to `target`, or to the working copy
+    /// without a `target`.
+    pub fn compare(&self, base: String, target: Option<String>) {
+        batch(|| {
+            self.compare.base.set(Some(base));
+            self.compare.target.set(target);
+            self.compare.files.set(im::Vector::new());
+        });
+        self.refresh_compare();
+    }
+
+    pub fn refresh_compare(&self) {
+        let compare = self.compare.clone();
+        let Some(base) = compare.base.get_untracked() else {
+            return;
+        };
+        let target = compare.target.get_untracked();
+        compare.loading.set(true);
+        let common = self.common.clone();
+        let send = {
+            let (base, target) = (base.clone(), target.clone());
+            create_ext_action(self.common.scope, move |result| {
+                // compared with something else since
+                if compare.base.get_untracked().as_ref() != Some(&base)
+                    || compare.target.get_untracked() != target
+                {
+                    return;
+                }
+                compare.loading.set(false);
+                match result {
+                    Ok(ProxyResponse::GitCompareResponse { files }) => {
+                        compare.files.set(files.into());
+                    },
+                    Ok(_) => {},
+                    Err(err) => {
+                        compare.base.set(None);
+                        show_git_error(&common, "Git Compare failure", Err(err));
+                    },
+                }
+            })
+        };
+        self.common
+            .proxy
+            .proxy_rpc
+            .git_compare(base, target, move |(_, result)| {
+                send(result);
+            });
+    }
+
+    pub fn close_compare(&self) {
+        batch(|| {
+            self.compare.base.set(None);
+            self.compare.target.set(None);
+            self.compare.files.set(im::Vector::new());
+        });
+    }
+
+    /// Show the changes in the gutter against `base`, or `HEAD` when `None`.
+    pub fn set_gutter_base(&self, base: Option<String>) {
+        let message = match &base {
+            Some(base) => format!("Showing the gutter changes against {base}"),
+            None => "Showing the gutter changes against HEAD".to_string(),
+        };
+        self.common.diff_base.set(base);
+        self.common.show_status_message(message);
+    }
+
+    /// Apply the changes of `commit` on top of `HEAD`, or their reverse with
+    /// `revert`, committing the result unless it conflicts.
+    pub fn apply_commit(&self, commit: String, revert: bool) {
+        let common = self.common.clone();
+        let short = commit.get(..7).unwrap_or(&commit).to_string();
+        let (title, done) = if revert {
+            ("Git Revert", "Reverted")
+        } else {
+            ("Git Cherry-Pick", "Cherry-picked")
+        };
+        let send =
+            create_ext_action(self.common.scope, move |result| match result {
+                Ok(ProxyResponse::GitApplyCommitResponse { conflicts: false }) => {
+                    common.show_status_message(format!("{done} {short}"));
+                },
+                Ok(ProxyResponse::GitApplyCommitResponse { conflicts: true }) => {
+                    common.show_popup_message(
+                        title.to_string(),
+                        MessageType::WARNING,
+                        format!(
+                            "Applying {short} stopped with conflicts. Resolve \
+                             them, then commit the result."
+                        ),
+                    );
+                },
+                result => {
+                    show_git_error(&common, &format!("{title} failure"), result)
+                },
+            });
+        if revert {
+            self.common
+                .proxy
+                .proxy_rpc
+                .git_revert(commit, move |(_, result)| {
+                    send(result);
+                });
+        } else {
+            self.common.proxy.proxy_rpc.git_cherry_pick(
+                commit,
+                move |(_, result)| {
+                    send(result);
+                },
+            );
+        }
+    }
+
+    /// Preview `text` as a patch, with the hunks that apply picked.
+    pub fn preview_patch(&self, name: String, text: String) {
+        batch(|| {
+            self.patch.name.set(Some(name));
+            self.patch.text.set(text);
+            self.patch.files.set(im::Vector::new());
+            self.patch.selected.set(HashSet::new());
+        });
+        self.refresh_patch(true);
+    }
+
+    /// Check the previewed patch against the working copy again. The hunks
+    /// that no longer apply are unpicked, and the ones that apply are picked
+    /// with `select`.
+    pub fn refresh_patch(&self, select: bool) {
+        let patch = self.patch.clone();
+        if patch.name.with_untracked(Option::is_none) {
+            return;
+        }
+        let text = patch.text.get_untracked();
+        patch.loading.set(true);
+        let common = self.common.clone();
+        let send = {
+            let text = text.clone();
+            create_ext_action(self.common.scope, move |result| {
+                // another patch is previewed since
+                if patch.text.with_untracked(|t| t != &text) {
+                    return;
+                }
+                patch.loading.set(false);
+                match result {
+                    Ok(ProxyResponse::GitPreviewPatchResponse { files }) => {
+                        let applies: HashSet<usize> = files
+                            .iter()
+                            .flat_map(|file| &file.hunks)
+                            .enumerate()
+                            .filter(|(_, hunk)| hunk.applies)
+                            .map(|(i, _)| i)
+                            .collect();
+                        batch(|| {
+                            patch.selected.update(|selected| {
+                                if select {
+                                    *selected = applies;
+                                } else {
+                                    selected.retain(|i| applies.contains(i));
+                                }
+                            });
+                            patch.files.set(files.into());
+                        });
+                    },
+                    Ok(_) => {},
+                    Err(err) => {
+                        patch.name.set(None);
+                        show_git_error(&common, "Git Patch failure", Err(err));
+                    },
+                }
+            })
+        };
+        self.common
+            .proxy
+            .proxy_rpc
+            .git_preview_patch(text, move |(_, result)| {
+                send(result);
+            });
+    }
+
+    pub fn toggle_patch_hunk(&self, index: usize) {
+        self.patch.selected.update(|selected| {
+            if !selected.remove(&index) {
+                selected.insert(index);
+            }
+        });
+    }
+
+    /// Apply the whole previewed patch, or only the picked hunks.
+    pub fn apply_patch(&self, all: bool) {
+        let hunks = if all {
+            None
+        } else {
+            let mut hunks: Vec<usize> =
+                self.patch.selected.get_untracked().into_iter().collect();
+            if hunks.is_empty() {
+                return;
+            }
+            hunks.sort();
+            Some(hunks)
+        };
+        let source_control = self.clone();
+        let send =
+            create_ext_action(self.common.scope, move |result| match result {
+                Ok(ProxyResponse::GitApplyPatchResponse {}) => {
+                    source_control
+                        .common
+                        .show_status_message("Applied the patch".to_string());
+                    if all {
+                        source_control.close_patch();
+                    }
+                },
+                result => show_git_error(
+                    &source_control.common,
+                    "Git Apply Patch failure",
+                    result,
+                ),
+            });
+        self.common.proxy.proxy_rpc.git_apply_patch(
+            self.patch.text.get_untracked(),
+            hunks,
+            move |(_, result)| {
+                send(result);
+            },
+        );
+    }
+
+    pub fn close_patch(&self) {
+        batch(|| {
+            self.patch.name.set(None);
+            self.patch.text.set(String::new());
+            self.patch.files.set(im::Vector::new());
+            self.patch.selected.set(HashSet::new());
+        });
+    }
+
+    /// Save the changes of `source` as a patch file picked in a dialog.
+    pub fn export_patch(&self, source: PatchSource) {
+        let common = self.common.clone();
+        let file_name = match &source {
+            PatchSource::Unstaged => "unstaged.patch".to_string(),
+            PatchSource::Staged => "staged.patch".to_string(),
+            PatchSource::Commit(commit) => {
+                format!("{}.patch", commit.get(..7).unwrap_or(commit))
+            },
+        };
+        let send =
+            create_ext_action(self.common.scope, move |result| match result {
+                Ok(ProxyResponse::GitCreatePatchResponse { patch }) => {
+                    let common = common.clone();
+                    let options =
+                        FileDialogOptions::new().default_name(file_name.clone());
+                    save_as(options, move |file: Option<FileInfo>| {
+                        let Some(path) = file.and_then(|mut file| file.path.pop())
+                        else {
+                            return;
+                        };
+                        match std::fs::write(&path, &patch) {
+                            Ok(()) => common.show_status_message(format!(
+                                "Saved the patch to {}",
+                                path.display()
+                            )),
+                            Err(err) => common.show_popup_message(
+                                "Git Export Patch failure".to_string(),
+                                MessageType::ERROR,
+                                err.to_string(),
+                            ),
+                        }
+                    });
+                },
+                result => {
+                    show_git_error(&common, "Git Export Patch failure", result)
+                },
+            });
+        self.common
+            .proxy
+            .proxy_rpc
+            .git_create_patch(source, move |(_, result)| {
+                send(result);
+            });
+    }
+
+    /// Every path touched by `diffs`, including where renamed files come
+    /// from.
+    fn diff_paths(diffs: RwSignal<IndexMap<PathBuf, FileDiff>>) -> Vec<PathBuf> {
+        diffs.with_untracked(|diffs| {
+            diffs.values().flat_map(FileDiff::paths).cloned().collect()
+        })
+    }
+}
+
+fn show_git_error(
+    common: &CommonData,
+    title: &str,
+    result: Result<ProxyResponse, RpcError>,
+) {
+    if let Err(err) = result {
+        common.show_popup_message(
+            title.to_string(),
+            MessageType::ERROR,
+            err.message,
+        );
+    }
+}
+
+/// The changes to stage before committing: all of them when nothing is
+/// staged, unless amending, which then only rewords the last commit. `None`
+/// when there is nothing to commit.
+fn commit_diffs(
+    nothing_staged: bool,
+    changes: Vec<FileDiff>,
+    amend: bool,
+) -> Option<Vec<FileDiff>> {
+    if !nothing_staged || amend {
+        Some(Vec::new())
+    } else {
+        (!changes.is_empty()).then_some(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use lapce_rpc::source_control::FileDiff;
+
+    use super::{GitFileStatus, commit_diffs, git_file_statuses};
+
+    #[test]
+    fn test_commit_diffs() {
+        let changes = vec![FileDiff::Modified(PathBuf::from("/ws/a"))];
+        assert_eq!(
+            commit_diffs(true, changes.clone(), false),
+            Some(changes.clone())
+        );
+        assert_eq!(commit_diffs(true, Vec::new(), false), None);
+        assert_eq!(
+            commit_diffs(false, changes.clone(), false),
+            Some(Vec::new())
+        );
+        // amending with nothing staged rewords the commit without them
+        assert_eq!(commit_diffs(true, changes, true), Some(Vec::new()));
+        assert_eq!(commit_diffs(true, Vec::new(), true), Some(Vec::new()));
+    }
+
+    #[test]
+    fn test_git_file_statuses() {
+        let root = Path::new("/ws");
+        let staged = [FileDiff::Added(PathBuf::from("/ws/src/new.rs"))];
+        let unstaged = [
+            FileDiff::Modified(PathBuf::from("/ws/src/new.rs")),
+            FileDiff::Added(PathBuf::from("/ws/notes.txt")),
+            FileDiff::Deleted(PathBuf::from("/ws/src/old/gone.rs")),
+        ];
+        let conflicts = [PathBuf::from("/ws/src/old/lib.rs")];
+        let statuses = git_file_statuses(&staged, &unstaged, &conflicts, root);
+
+        let status = |path: &str| statuses.get(Path::new(path)).copied();
+        assert_eq!(status("/ws/src/new.rs"), Some(GitFileStatus::Added));
+        assert_eq!(status("/ws/notes.txt"), Some(GitFileStatus::Untracked));
+        assert_eq!(status("/ws/src/old/gone.rs"), Some(GitFileStatus::Deleted));
+        assert_eq!(status("/ws/src/old"), Some(GitFileStatus::Conflicted));
+        assert_eq!(status("/ws/src"), Some(GitFileStatus::Conflicted));
+        assert_eq!(status("/ws"), Some(GitFileStatus::Conflicted));
+        assert_eq!(status("/"), None);
     }
 }
@@ -1,7 +1,9 @@
 use doc::lines::mode::{Mode, VisualMode};
 use floem::{
     View,
+    action::show_context_menu,
     event::EventPropagation,
+    menu::{Menu, MenuItem},
     reactive::{Memo, RwSignal, SignalGet, SignalUpdate, SignalWith, create_memo},
     style::{AlignItems, CursorStyle, Display, FlexWrap},
     views::{Decorators, label, stack, svg},
@@ -56,16 +58,29 @@ pub fn status(
     });
     let branch = source_control.branch;
     let file_diffs = source_control.file_diffs;
+    let staged_diffs = source_control.staged_diffs;
+    let ahead_behind = source_control.ahead_behind;
     let branch = move || {
-        format!(
+        let mut branch = format!(
             "{}{}",
             branch.get(),
-            if file_diffs.with(|diffs| diffs.is_empty()) {
+            if file_diffs.with(|diffs| diffs.is_empty())
+                && staged_diffs.with(|diffs| diffs.is_empty())
+            {
                 ""
             } else {
                 "*"
             }
-        )
+        );
+        if let Some((ahead, behind)) = ahead_behind.get() {
+            if behind > 0 {
+                branch.push_str(&format!(" {behind}↓"));
+            }
+            if ahead > 0 {
+                branch.push_str(&format!(" {ahead}↑"));
+            }
+        }
+        branch
     };
 
     let progresses = window_tab_data.progresses;
@@ -163,8 +178,7 @@ pub fn status(
                 floem::event::EventListener::PointerUp,
                 move |_| {
                     if pointer_down.get() {
-                        workbench_command
-                            .send(LapceWorkbenchCommand::PaletteSCMReferences);
+                        show_context_menu(branch_menu(workbench_command), None);
                     }
                     pointer_down.set(false);
                     EventPropagation::Continue
@@ -498,3 +512,53 @@ fn status_text<S: std::fmt::Display + 'static>(
             .selectable(false)
     })
 }
+
+/// The branch actions opened from the branch item.
+fn branch_menu(workbench_command: Listener<LapceWorkbenchCommand>) -> Menu {
+    let item = |text: &str, cmd: LapceWorkbenchCommand| {
+        MenuItem::new(text).action(move || workbench_command.send(cmd.clone()))
+    };
+    Menu::new("")
+        .entry(item(
+            "Checkout...",
+            LapceWorkbenchCommand::PaletteSCMReferences,
+        ))
+        .entry(item(
+            "Checkout (Stash Local Changes)...",
+            LapceWorkbenchCommand::SourceControlCheckoutAutoStash,
+        ))
+        .separator()
+        .entry(item(
+            "Create Branch...",
+            LapceWorkbenchCommand::SourceControlCreateBranch,
+        ))
+        .entry(item(
+            "Create Branch From...",
+            LapceWorkbenchCommand::SourceControlCreateBranchFrom,
+        ))
+        .entry(item(
+            "Rename Branch...",
+            LapceWorkbenchCommand::SourceControlRenameBranch,
+        ))
+        .entry(item(
+            "Delete Branch...",
+            LapceWorkbenchCommand::SourceControlDeleteBranch,
+        ))
+        .separator()
+        .entry(item(
+            "Merge Branch...",
+            LapceWorkbenchCommand::SourceControlMergeBranch,
+        ))
+        .entry(item(
+            "Merge Branch (Create Merge Commit)...",
+            LapceWorkbenchCommand::SourceControlMergeBranchNoFf,
+        ))
+        .separator()
+        .entry(item("Fetch", LapceWorkbenchCommand::SourceControlFetch))
+        .entry(item("Pull", LapceWorkbenchCommand::SourceControlPull))
+        .entry(item(
+            "Pull (Rebase)",
+            LapceWorkbenchCommand::SourceControlPullRebase,
+        ))
+        .entry(item("Push", LapceWorkbenchCommand::SourceControlPush))
+}
@@ -15,7 +15,8 @@ use lapce_core::{
 };
 use lapce_rpc::{
     dap_types::{
-        self, DapId, RunDebugConfig, StackFrame, Stopped, ThreadId, Variable,
+        self, DapId, DebuggerPaths, GlobalBreakpoints, RunDebugConfig, StackFrame,
+        Stopped, ThreadId, Variable,
     },
     proxy::ProxyResponse,
     terminal::{TermId, TerminalProfile},
@@ -25,7 +26,7 @@ use serde::{Deserialize, Serialize};
 
 use super::data::TerminalData;
 use crate::{
-    debug::{DapData, DapVariable, RunDebugData},
+    debug::{DapData, DapVariable, DapVariableEdit, RunDebugData},
     keypress::{EventRef, KeyPressData, KeyPressFocus, KeyPressHandle},
     main_split::MainSplitData,
     window_workspace::{CommonData, Focus},
@@ -459,10 +460,7 @@ impl TerminalPanelData {
                     if run_debug.mode == RunDebugMode::Debug {
                         self.update_executable(&mut run_debug, &terminal);
 
-                        self.common.proxy.proxy_rpc.dap_start(
-                            run_debug.config,
-                            self.common.source_breakpoints(),
-                        )
+                        self.dap_start(run_debug.config)
                     } else {
                         terminal.new_process(Some(run_debug));
                     }
@@ -623,8 +621,12 @@ impl TerminalPanelData {
     pub fn set_process_id(&self, term_id: &TermId, process_id: Option<u32>) {
         if let Some(terminal) = self.get_terminal(*term_id) {
             terminal.data.with_untracked(|x| {
+                // a terminal waiting for a known process, of an attached
+                // session or from gdb, wasn't requested by the debugger, so
+                // it isn't waiting for the process id
                 if let Some(run_debug) = x.run_debug.as_ref()
                     && run_debug.config.debug_command.is_some()
+                    && run_debug.config.pid.is_none()
                 {
                     let dap_id = run_debug.config.dap_id;
                     self.common
@@ -643,6 +645,7 @@ impl TerminalPanelData {
             .with_untracked(|daps| daps.get(dap_id).cloned());
         if let Some(dap) = dap {
             dap.thread_id.set(None);
+            dap.frame_id.set(None);
             dap.stopped.set(false);
         }
     }
@@ -660,10 +663,60 @@ impl TerminalPanelData {
             .with_untracked(|daps| daps.get(dap_id).cloned());
         if let Some(dap) = dap {
             dap.stopped(self.cx, stopped, stack_frames, variables);
+            dap.evaluate_watches(self.debug.watches.get_untracked());
         }
         floem::action::focus_window();
     }
 
+    pub fn add_watch(&self, expression: String) {
+        self.debug
+            .watches
+            .update(|watches| watches.push(expression));
+        self.evaluate_watches();
+    }
+
+    pub fn remove_watch(&self, expression: &str) {
+        self.debug.watches.update(|watches| {
+            if let Some(i) = watches.iter().position(|x| x == expression) {
+                watches.remove(i);
+            }
+        });
+        self.evaluate_watches();
+    }
+
+    fn evaluate_watches(&self) {
+        if let Some(dap) = self.get_active_dap(false)
+            && dap.stopped.get_untracked()
+        {
+            dap.evaluate_watches(self.debug.watches.get_untracked());
+        }
+    }
+
+    /// Evaluate an expression typed in the debug console of the active
+    /// session.
+    pub fn evaluate_in_console(&self, expression: String) {
+        if let Some(dap) = self.get_active_dap(false) {
+            dap.evaluate_in_console(expression);
+        }
+    }
+
+    /// Change the value of a variable, the watches evaluated again as they
+    /// may depend on it.
+    pub fn set_dap_variable(&self, edit: DapVariableEdit, value: String) {
+        let Some(dap) = self
+            .debug
+            .daps
+            .with_untracked(|daps| daps.get(&edit.dap_id).cloned())
+        else {
+            return;
+        };
+        let watches = self.debug.watches;
+        let evaluated = dap.clone();
+        dap.set_value(edit, value, move || {
+            evaluated.evaluate_watches(watches.get_untracked());
+        });
+    }
+
     pub fn dap_continue(&self, term_id: TermId) -> Option<()> {
         let terminal = self.get_terminal(term_id)?;
         let dap_id = terminal
@@ -678,11 +731,54 @@ impl TerminalPanelData {
         Some(())
     }
 
+    /// The built-in debugger programs configured by the user, sent along when
+    /// starting or restarting a session.
+    pub fn debugger_paths(&self) -> DebuggerPaths {
+        self.common.config.with_untracked(|config| DebuggerPaths {
+            lldb_dap: config.core.lldb_dap_path.clone(),
+            gdb:      config.core.gdb_path.clone(),
+        })
+    }
+
+    /// The type of the debugger of the session of `dap`, e.g. `lldb`.
+    pub fn debugger_type(&self, dap: &DapData) -> Option<String> {
+        let terminal = self.get_terminal(dap.term_id?)?;
+        terminal.data.with_untracked(|x| {
+            x.run_debug
+                .as_ref()
+                .and_then(|run_debug| run_debug.config.ty.clone())
+        })
+    }
+
     pub fn dap_start(&self, config: RunDebugConfig) {
-        self.common
-            .proxy
-            .proxy_rpc
-            .dap_start(config, self.common.source_breakpoints());
+        self.common.proxy.proxy_rpc.dap_start(
+            config,
+            self.common.source_breakpoints(),
+            self.debug.global_breakpoints.get_untracked(),
+            self.debugger_paths(),
+        );
+    }
+
+    pub fn dap_restart(&self, config: RunDebugConfig) {
+        self.common.proxy.proxy_rpc.dap_restart(
+            config,
+            self.common.source_breakpoints(),
+            self.debug.global_breakpoints.get_untracked(),
+            self.debugger_paths(),
+        );
+    }
+
+    /// Change the exception filters or function breakpoints, sending them to
+    /// every session.
+    pub fn update_global_breakpoints(&self, f: impl FnOnce(&mut GlobalBreakpoints)) {
+        self.debug.global_breakpoints.update(f);
+        let breakpoints = self.debug.global_breakpoints.get_untracked();
+        for dap_id in self.debug.daps.get_untracked().keys() {
+            self.common
+                .proxy
+                .proxy_rpc
+                .dap_set_global_breakpoints(*dap_id, breakpoints.clone());
+        }
     }
 
     pub fn dap_pause(&self, term_id: TermId) -> Option<()> {
@@ -777,6 +873,8 @@ impl TerminalPanelData {
 
     pub fn dap_frame_scopes(&self, dap_id: DapId, frame_id: usize) {
         if let Some(dap) = self.debug.daps.get_untracked().get(&dap_id) {
+            dap.frame_id.set(Some(frame_id));
+            dap.evaluate_watches(self.debug.watches.get_untracked());
             let variables = dap.variables;
             let send = create_ext_action(self.common.scope, move |result| {
                 if let Ok(ProxyResponse::DapGetScopesResponse { scopes }) = result {
@@ -11,14 +11,18 @@ use alacritty_terminal::vte::ansi::Handler;
 use anyhow::{Result, anyhow, bail};
 use cozy_floem::views::{panel::DocStyle, tree_with_panel::data::TreePanelData};
 use doc::lines::{
-    buffer::rope_text::RopeText, command::FocusCommand,
-    editor_command::CommandExecuted, mode::Mode, register::Register,
+    buffer::rope_text::RopeText,
+    command::FocusCommand,
+    editor_command::CommandExecuted,
+    mode::Mode,
+    register::{Clipboard, Register},
+    text::SystemClipboard,
 };
 use floem::{
     ViewId,
     action::{TimerToken, exec_after},
     ext_event::create_ext_action,
-    file::FileDialogOptions,
+    file::{FileDialogOptions, FileSpec},
     file_action::open_file,
     keyboard::Modifiers,
     kurbo::Size,
@@ -44,27 +48,32 @@ use lapce_core::{
     workspace::{LapceWorkspace, LapceWorkspaceType, WorkspaceInfo},
 };
 use lapce_rpc::{
-    RpcError,
-    core::CoreNotification,
-    dap_types::{ConfigSource, RunDebugConfig, SourceBreakpoint},
+    RequestId, RpcError,
+    core::{CoreNotification, CoreRequest},
+    dap_types::{
+        ConfigSource, DebugRequest, ProcessInfo, RunDebugConfig, SourceBreakpoint,
+    },
     file::{Naming, PathObject},
+    file_line::FileLine,
     plugin::PluginId,
     proxy::{ProxyResponse, ProxyStatus},
-    source_control::FileDiff,
+    source_control::{FileDiff, PatchSource, StageSelection},
     terminal::TermId,
 };
 use lapce_xi_rope::Rope;
 use log::{debug, error, trace, warn};
 use lsp_types::{
-    CodeActionOrCommand, CodeLens, Diagnostic, DiagnosticSeverity, MessageType,
-    NumberOrString, ProgressParams, ProgressToken, ShowMessageParams,
-    WorkDoneProgress, WorkDoneProgressBegin, WorkDoneProgressEnd,
+    CodeActionOrCommand, CodeLens, Diagnostic, DiagnosticSeverity, DocumentChanges,
+    Location, MessageType, NumberOrString, Position, ProgressParams, ProgressToken,
+    Range, ShowMessageParams, WorkDoneProgress, WorkDoneProgressBegin,
+    WorkDoneProgressEnd, WorkspaceEdit,
 };
 use serde_json::Value;
 
 use crate::{
     about::AboutData,
     alert::{AlertBoxData, AlertButton},
+    blame::blame_annotation,
     code_action::{CodeActionData, CodeActionStatus},
     command::{
         CommandKind, InternalCommand, LapceCommand, LapceWorkbenchCommand,
@@ -74,7 +83,10 @@ use crate::{
     completion::{CompletionData, CompletionStatus},
     config::{LapceConfig, WithLapceConfig},
     db::LapceDb,
-    debug::{BreakPoints, DapData, update_breakpoints},
+    debug::{
+        BreakPoints, BreakpointLocation, DapData, attach_wait_command, find_process,
+        update_breakpoints,
+    },
     doc::Doc,
     editor::location::{EditorLocation, EditorPosition},
     editor_tab::EditorTabChildId,
@@ -87,16 +99,20 @@ use crate::{
     listener::Listener,
     local_task::LocalTaskRequester,
     lsp::path_from_url,
-    main_split::{MainSplitData, SplitData},
+    main_split::{MainSplitData, SplitData, workspace_edits},
     palette::{DEFAULT_RUN_TOML, PaletteData, PaletteStatus, kind::PaletteKind},
     panel::{
         call_hierarchy_view::CallHierarchyItemData, data::PanelData,
         document_symbol::MatchDocumentSymbol,
+        workspace_edit_view::WorkspaceEditPreviewData,
     },
+    peek::PeekData,
     plugin::PluginData,
     proxy::{ProxyData, new_proxy},
     rename::RenameData,
-    source_control::SourceControlData,
+    source_control::{
+        BranchNameTarget, GitCredentialPrompt, ReferenceAction, SourceControlData,
+    },
     terminal::panel::TerminalPanelData,
     window::{CursorBlink, WindowCommonData},
 };
@@ -169,6 +185,7 @@ pub enum Focus {
     Palette,
     CodeAction,
     Rename,
+    Peek,
     AboutPopup,
     Panel(PanelKind),
 }
@@ -229,6 +246,8 @@ pub struct CommonData {
     pub inspect_info:          RwSignal<String>,
     pub run_debug_configs:     RwSignal<RunDebugConfigs>,
     pub code_len_selected:     RwSignal<Option<Id>>,
+    /// The ref the gutter changes are shown against, `HEAD` when `None`
+    pub diff_base:             RwSignal<Option<String>>,
 }
 
 impl std::fmt::Debug for CommonData {
@@ -306,6 +325,7 @@ pub struct WindowWorkspaceData {
     pub code_lens:                 RwSignal<Option<ViewId>>,
     pub source_control:            SourceControlData,
     pub rename:                    RenameData,
+    pub peek_data:                 PeekData,
     pub global_search:             GlobalSearchData,
     pub about_data:                AboutData,
     pub alert_data:                AlertBoxData,
@@ -544,6 +564,7 @@ impl WindowWorkspaceData {
             inspect_info: cx.create_rw_signal(String::new()),
             run_debug_configs: cx.create_rw_signal(RunDebugConfigs::default()),
             code_len_selected: cx.create_rw_signal(None),
+            diff_base: cx.create_rw_signal(None),
         });
 
         let main_split = MainSplitData::new(cx, common.clone());
@@ -551,6 +572,7 @@ impl WindowWorkspaceData {
             cx.create_rw_signal(CodeActionData::new(cx, common.clone()));
         let source_control =
             SourceControlData::new(cx, main_split.editors, common.clone());
+        source_control.fill_commit_message(false, false);
         let file_explorer = FileExplorerData::new(cx, common.clone());
 
         if let Some(info) = workspace_info.as_ref() {
@@ -658,9 +680,19 @@ impl WindowWorkspaceData {
                     })
                     .collect(),
             );
+            terminal
+                .debug
+                .global_breakpoints
+                .set(workspace_info.global_breakpoints.clone());
+            let exception_filters = workspace_info.exception_filters.clone();
+            terminal
+                .debug
+                .exception_filters
+                .set(exception_filters.into_iter().collect());
         }
 
         let rename = RenameData::new(cx, common.clone());
+        let peek_data = PeekData::new(cx, main_split.clone(), common.clone());
         let global_search = GlobalSearchData::new(cx, main_split.clone());
 
         let plugin = PluginData::new(
@@ -706,6 +738,7 @@ impl WindowWorkspaceData {
             source_control,
             plugin,
             rename,
+            peek_data,
             global_search,
             about_data,
             alert_data,
@@ -742,6 +775,137 @@ impl WindowWorkspaceData {
             });
         }
 
+        {
+            let pinned = window_tab_data.common.hover.pinned.clone();
+            let active_editor = window_tab_data.main_split.active_editor;
+            let panel = window_tab_data.panel.clone();
+            cx.create_effect(move |_| {
+                if !pinned.follow_cursor.get()
+                    || !panel.is_panel_visible(&PanelKind::Documentation)
+                {
+                    return;
+                }
+                if let Some(editor) = active_editor.get() {
+                    let offset = editor.cursor().with(|c| c.offset());
+                    editor.update_pinned_hover(offset);
+                }
+            });
+        }
+
+        {
+            // the file history panel lists the history of the active file
+            let active_editor = window_tab_data.main_split.active_editor;
+            let panel = window_tab_data.panel.clone();
+            let source_control = window_tab_data.source_control.clone();
+            cx.create_effect(move |_| {
+                if !panel.is_panel_visible(&PanelKind::FileHistory) {
+                    return;
+                }
+                let Some(editor) = active_editor.get() else {
+                    return;
+                };
+                let content = editor.doc_signal().get().content.get();
+                if let DocContent::File { path, .. } = content
+                    && source_control.file_history.path.get_untracked().as_ref()
+                        != Some(&path)
+                {
+                    source_control.load_file_history(path);
+                }
+            });
+        }
+
+        {
+            // the git log is listed when its panel is shown
+            let panel = window_tab_data.panel.clone();
+            let source_control = window_tab_data.source_control.clone();
+            cx.create_effect(move |_| {
+                panel.panels.track();
+                panel.styles.track();
+                if panel.is_panel_visible(&PanelKind::GitLog)
+                    && source_control
+                        .git_log
+                        .entries
+                        .with_untracked(|entries| entries.is_empty())
+                    && !source_control.git_log.loading.get_untracked()
+                {
+                    source_control.load_git_log(false);
+                }
+            });
+        }
+
+        {
+            // the gutter changes are retrieved again against the new base
+            let diff_base = window_tab_data.common.diff_base;
+            let docs = window_tab_data.main_split.docs;
+            cx.create_effect(move |last: Option<Option<String>>| {
+                let base = diff_base.get();
+                if last.is_some_and(|last| last != base) {
+                    for (_, doc) in docs.get_untracked() {
+                        doc.retrieve_head();
+                    }
+                }
+                base
+            });
+        }
+
+        {
+            // the blame annotation follows the cursor of the active editor
+            let active_editor = window_tab_data.main_split.active_editor;
+            let config = window_tab_data.common.config;
+            cx.create_effect(move |last_doc: Option<Option<Rc<Doc>>>| {
+                let show = config
+                    .signal(|config| config.editor.show_inline_blame.signal())
+                    .get();
+                let editor = active_editor.get();
+                let doc = editor.as_ref().map(|editor| editor.doc_signal().get());
+                if let Some(Some(last_doc)) = last_doc
+                    && doc.as_ref().is_none_or(|doc| !Rc::ptr_eq(doc, &last_doc))
+                {
+                    last_doc.lines.update(|x| x.clear_blame_lens());
+                }
+                let (editor, doc) = (editor?, doc?);
+                let offset = editor.cursor().with(|c| c.offset());
+                let line = doc
+                    .lines
+                    .with_untracked(|x| x.buffer().line_of_offset(offset));
+                let annotation = doc.blame.with(|blame| {
+                    blame.get(line).map(|commit| {
+                        blame_annotation(
+                            commit.as_deref(),
+                            chrono::Utc::now().timestamp(),
+                        )
+                    })
+                });
+                doc.lines.update(|x| match annotation {
+                    Some(annotation) if show => x.set_blame_lens(line, annotation),
+                    _ => x.clear_blame_lens(),
+                });
+                Some(doc)
+            });
+        }
+
+        {
+            // only the documents shown are blamed, once their content or
+            // `HEAD` changed since
+            let main_split = window_tab_data.main_split.clone();
+            let config = window_tab_data.common.config;
+            cx.create_effect(move |_| {
+                let (inline, gutter) = config.signal(|config| {
+                    (
+                        config.editor.show_inline_blame.signal(),
+                        config.editor.show_blame_gutter.signal(),
+                    )
+                });
+                if inline.get() || gutter.get() {
+                    for doc in main_split.visible_docs() {
+                        if doc.blame_stale.get() {
+                            doc.retrieve_blame();
+                        }
+                    }
+                }
+            });
+        }
+
         {
             let window_tab_data = window_tab_data.clone();
             window_tab_data.common.lapce_command.listen(move |cmd| {
@@ -780,6 +944,16 @@ impl WindowWorkspaceData {
             });
         }
 
+        {
+            let window_tab_data = window_tab_data.clone();
+            let request = window_tab_data.proxy.request;
+            cx.create_effect(move |_| {
+                if let Some((id, rpc)) = request.get() {
+                    window_tab_data.handle_core_request(id, rpc);
+                }
+            });
+        }
+
         Ok(window_tab_data)
     }
 
@@ -1002,6 +1176,9 @@ impl WindowWorkspaceData {
                             open_uri(path);
                         }
                     }
+            FileExplorerToggleOnlyChanged => {
+                        self.file_explorer.toggle_only_changed();
+                    }
             SaveAll => {
                         self.main_split.editors.with_editors_untracked(|editors| {
                             let mut paths = HashSet::new();
@@ -1282,8 +1459,11 @@ impl WindowWorkspaceData {
             PaletteRunAndDebug => {
                         self.palette.run(PaletteKind::RunAndDebug);
                     }
+            PaletteDebugAttach => {
+                        self.palette.run(PaletteKind::AttachProcess);
+                    }
             PaletteSCMReferences => {
-                        self.palette.run(PaletteKind::SCMReferences);
+                        self.palette.run_scm_references(ReferenceAction::Checkout);
                     }
             ChangeColorTheme => {
                         self.palette.run(PaletteKind::ColorTheme);
@@ -1461,7 +1641,7 @@ impl WindowWorkspaceData {
             CheckoutReference => match data {
                         Some(reference) => {
                             if let Some(reference) = reference.as_str() {
-                                self.proxy.proxy_rpc.git_checkout(reference.to_string());
+                                self.source_control.checkout(reference.to_string(), false);
                             }
                         }
                         None => error!("No ref provided"),
@@ -1500,6 +1680,204 @@ impl WindowWorkspaceData {
             SourceControlDiscardWorkspaceChanges => {
                         // TODO:
                     }
+            SourceControlStageAll => {
+                        self.source_control.stage_all();
+                    }
+            SourceControlUnstageAll => {
+                        self.source_control.unstage_all();
+                    }
+            SourceControlStageHunk => {
+                        if let Some((path, line, _)) = self.active_editor_git_lines("staging it") {
+                            self.source_control.stage(StageSelection::Hunk { path, line });
+                        }
+                    }
+            SourceControlUnstageHunk => {
+                        if let Some((path, line, _)) = self.active_editor_git_lines("unstaging it") {
+                            self.source_control.unstage(StageSelection::Hunk { path, line });
+                        }
+                    }
+            SourceControlPeekChange => {
+                        if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                            let line = editor.doc().lines.with_untracked(|x| {
+                                x.buffer().line_of_offset(editor.cursor().with_untracked(|c| c.offset()))
+                            });
+                            editor.toggle_hunk_peek(line);
+                        }
+                    }
+            SourceControlRevertChange => {
+                        if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                            let line = editor.doc().lines.with_untracked(|x| {
+                                x.buffer().line_of_offset(editor.cursor().with_untracked(|c| c.offset()))
+                            });
+                            if let Some(hunk) = editor.change_hunk_at(line) {
+                                editor.revert_change_hunk(&hunk);
+                            }
+                        }
+                    }
+            SourceControlNextChange => {
+                        if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                            editor.go_to_change_hunk(false);
+                        }
+                    }
+            SourceControlPreviousChange => {
+                        if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                            editor.go_to_change_hunk(true);
+                        }
+                    }
+            SourceControlStageSelectedLines => {
+                        if let Some((path, start, end)) = self.active_editor_git_lines("staging it") {
+                            self.source_control.stage(StageSelection::Lines { path, start, end });
+                        }
+                    }
+            SourceControlUnstageSelectedLines => {
+                        if let Some((path, start, end)) = self.active_editor_git_lines("unstaging it") {
+                            self.source_control.unstage(StageSelection::Lines { path, start, end });
+                        }
+                    }
+            SourceControlCheckoutAutoStash => {
+                        self.palette.run_scm_references(ReferenceAction::CheckoutAutoStash);
+                    }
+            SourceControlCreateBranch => {
+                        self.palette.run_scm_branch_name(BranchNameTarget::Create {
+                            start_point: None,
+                        });
+                    }
+            SourceControlCreateBranchFrom => {
+                        self.palette.run_scm_references(ReferenceAction::CreateBranchFrom);
+                    }
+            SourceControlRenameBranch => {
+                        self.palette.run_scm_references(ReferenceAction::RenameBranch);
+                    }
+            SourceControlDeleteBranch => {
+                        self.palette.run_scm_references(ReferenceAction::DeleteBranch);
+                    }
+            SourceControlMergeBranch => {
+                        self.palette.run_scm_references(ReferenceAction::Merge);
+                    }
+            SourceControlMergeBranchNoFf => {
+                        self.palette.run_scm_references(ReferenceAction::MergeNoFf);
+                    }
+            SourceControlStash => {
+                        self.source_control.stash_save(false);
+                    }
+            SourceControlStashIncludeUntracked => {
+                        self.source_control.stash_save(true);
+                    }
+            SourceControlStashApplyLatest => {
+                        if let Some(id) = self.source_control.latest_stash() {
+                            self.source_control.stash_apply(id);
+                        }
+                    }
+            SourceControlStashPopLatest => {
+                        if let Some(id) = self.source_control.latest_stash() {
+                            self.source_control.stash_pop(id);
+                        }
+                    }
+            SourceControlFetch => {
+                        self.source_control.fetch();
+                    }
+            SourceControlPull => {
+                        self.source_control.pull(false);
+                    }
+            SourceControlPullRebase => {
+                        self.source_control.pull(true);
+                    }
+            SourceControlPush => {
+                        self.source_control.push();
+                    }
+            SourceControlOpenMergeEditor => {
+                        if let Some(editor) = self.main_split.active_editor.get_untracked()
+                            && let DocContent::File { path, .. } =
+                                editor.doc().content.get_untracked()
+                        {
+                            self.main_split.open_merge_editor(path);
+                        }
+                    }
+            SourceControlShowGitLog => {
+                        self.show_panel(PanelKind::GitLog);
+                    }
+            SourceControlCompareFileWith => {
+                        if let Some(editor) = self.main_split.active_editor.get_untracked()
+                            && let DocContent::File { path, .. } =
+                                editor.doc().content.get_untracked()
+                        {
+                            self.palette
+                                .run_scm_references(ReferenceAction::CompareFile(path));
+                        }
+                    }
+            SourceControlCompareWorkingTreeWith => {
+                        self.palette.run_scm_references(ReferenceAction::CompareWorkspace);
+                    }
+            SourceControlCompareRefs => {
+                        self.palette
+                            .run_scm_references(ReferenceAction::CompareRefs { base: None });
+                    }
+            SourceControlSetGutterBase => {
+                        self.palette.run_scm_references(ReferenceAction::GutterBase);
+                    }
+            SourceControlResetGutterBase => {
+                        self.source_control.set_gutter_base(None);
+                    }
+            SourceControlApplyPatchFile => {
+                        let source_control = self.source_control.clone();
+                        let panel = self.panel.clone();
+                        let options = FileDialogOptions::new().allowed_types(vec![FileSpec {
+                            name: "Patch",
+                            extensions: &["patch", "diff"],
+                        }]);
+                        open_file(options, move |file| {
+                            let Some(path) = file.and_then(|mut file| file.path.pop()) else {
+                                return;
+                            };
+                            match std::fs::read_to_string(&path) {
+                                Ok(text) => {
+                                    let name = path
+                                        .file_name()
+                                        .map(|name| name.to_string_lossy().to_string())
+                                        .unwrap_or_default();
+                                    panel.show_panel(&PanelKind::SourceControl);
+                                    source_control.preview_patch(name, text);
+                                }
+                                Err(err) => {
+                                    source_control.common.show_popup_message(
+                                        "Git Patch failure".to_string(),
+                                        MessageType::ERROR,
+                                        err.to_string(),
+                                    );
+                                }
+                            }
+                        });
+                    }
+            SourceControlApplyPatchFromClipboard => {
+                        let mut clipboard = SystemClipboard::new();
+                        match clipboard.get_string() {
+                            Some(text) if !text.trim().is_empty() => {
+                                self.show_panel(PanelKind::SourceControl);
+                                self.source_control
+                                    .preview_patch("Clipboard".to_string(), text);
+                            }
+                            _ => {
+                                self.common.show_status_message(
+                                    "The clipboard is empty".to_string(),
+                                );
+                            }
+                        }
+                    }
+            SourceControlExportUnstagedPatch => {
+                        self.source_control.export_patch(PatchSource::Unstaged);
+                    }
+            SourceControlExportStagedPatch => {
+                        self.source_control.export_patch(PatchSource::Staged);
+                    }
+            SourceControlShowFileHistory => {
+                        self.show_panel(PanelKind::FileHistory);
+                        if let Some(editor) = self.main_split.active_editor.get_untracked()
+                            && let DocContent::File { path, .. } =
+                                editor.doc().content.get_untracked()
+                        {
+                            self.source_control.load_file_history(path);
+                        }
+                    }
             ShowAbout => {
                         self.about_data.open();
                     }
@@ -1635,23 +2013,18 @@ impl WindowWorkspaceData {
                         }
                     }
             SourceControlOpenActiveFileRemoteUrl => {
-                        if let Some(editor_data) =
-                            self.main_split.active_editor.get_untracked() && let DocContent::File { path, .. } = editor_data.doc().content.get_untracked() {
-                                let offset = editor_data.cursor().with_untracked(|c| c.offset());
-                                let line = editor_data.doc()
-                                    .lines.with_untracked(|x| x.buffer().line_of_offset(offset));
-                                self.common.proxy.proxy_rpc.git_get_remote_file_url(
-                                    path,
-                                    create_ext_action(self.scope, move |(_, result)| {
-                                        if let Ok(ProxyResponse::GitGetRemoteFileUrl {
-                                                      file_url
-                                                  }) = result
-                                        && let Err(err) = open::that(format!("{file_url}#L{line}",)) {
-                                                error!("Failed to open file in github: {err}",  );
-                                        }
-                                    }),
-                                );
-                        }
+                        self.active_editor_remote_url(|file_url| {
+                            if let Err(err) = open::that(&file_url) {
+                                error!("Failed to open {file_url}: {err}");
+                            }
+                        });
+                    }
+            SourceControlCopyActiveFileRemoteUrl => {
+                        let common = self.common.clone();
+                        self.active_editor_remote_url(move |file_url| {
+                            SystemClipboard::new().put_string(file_url.clone());
+                            common.show_status_message(format!("Copied {file_url}"));
+                        });
                     }
             RevealInFileExplorer => {
                         if let Some(editor_data) =
@@ -1696,6 +2069,26 @@ impl WindowWorkspaceData {
                             editor_data.go_to_implementation(self.clone())?;
                         }
                     }
+            PeekDefinition => {
+                        if let Some(editor_data) =
+                            self.main_split.active_editor.get_untracked()
+                        {
+                            editor_data.peek_definition(self.clone())?;
+                        }
+                    }
+            PeekReferences => {
+                        if let Some(editor_data) =
+                            self.main_split.active_editor.get_untracked()
+                        {
+                            editor_data.peek_references(self.clone())?;
+                        }
+                    }
+            PinHover => {
+                        if self.common.hover.active.get_untracked() {
+                            self.common.hover.pin();
+                            self.show_panel(PanelKind::Documentation);
+                        }
+                    }
             RunInTerminal => {
                         if let Some(editor_data) =
                             self.main_split.active_editor.get_untracked()
@@ -1714,15 +2107,19 @@ impl WindowWorkspaceData {
                                 let config = RunDebugConfig {
                                     ty: None,
                                     name,
+                                    request: DebugRequest::Launch,
                                     program,
                                     args,
                                     cwd: None,
                                     env: None,
+                                    stop_on_entry: false,
                                     prelaunch: None,
                                     debug_command: None,
                                     dap_id: Default::default(),
                                     tracing_output: false,
                                     config_source: ConfigSource::RunInTerminal,
+                                    pid: None,
+                                    process_name: None,
                                 };
                                 self.common
                                     .internal_command
@@ -1903,6 +2300,20 @@ impl WindowWorkspaceData {
             InternalCommand::OpenFileChanges { path } => {
                                         self.main_split.open_file_changes(path);
                                     }
+            InternalCommand::OpenFileCommitChanges { path, commit } => {
+                                        self.main_split
+                                            .open_file_commit_changes(path, commit);
+                                    }
+            InternalCommand::OpenRevisionDiff { left, right } => {
+                                        self.main_split.open_revision_diff(left, right);
+                                    }
+            InternalCommand::StageHunk { path, line } => {
+                                        self.source_control
+                                            .stage(StageSelection::Hunk { path, line });
+                                    }
+            InternalCommand::OpenMergeEditor { path } => {
+                                        self.main_split.open_merge_editor(path);
+                                    }
             InternalCommand::ReloadFileExplorer => {
                                         self.file_explorer.reload();
                                     }
@@ -2135,6 +2546,9 @@ impl WindowWorkspaceData {
             InternalCommand::ApplyWorkspaceEdit { edit } => {
                                         self.main_split.apply_workspace_edit(&edit);
                                     }
+            InternalCommand::PreviewWorkspaceEdit { title, edit } => {
+                                        self.preview_workspace_edit(title, edit);
+                                    }
             InternalCommand::SaveJumpLocation {
                                         path,
                                         offset,
@@ -2366,6 +2780,38 @@ impl WindowWorkspaceData {
                                 update_breakpoints(daps, proxy, breakpoints, lapce_core::debug::BreakpointAction::AddOrRemove { path, line: line_num, offset  });
                                 }
                             },
+            InternalCommand::EditBreakPoint { doc, line_num } => {
+                                let (offset, content) = doc.with_untracked(|x| {
+                                    (x.lines.with_untracked(|x| x.buffer().offset_of_line(line_num)),
+                                    x.content.get_untracked())
+                                });
+                                let offset = offset?;
+                                if let Some(path) = content.path() {
+                                    self.palette.run_breakpoint_edit(BreakpointLocation {
+                                        path: path.clone(),
+                                        line: line_num,
+                                        offset,
+                                    });
+                                }
+                            },
+            InternalCommand::SetBreakpointField { location, field, value } => {
+                                let breakpoints = self.common.breakpoints;
+                                let proxy = self.common.proxy.proxy_rpc.clone();
+                                let daps = self.terminal.debug.daps;
+                                update_breakpoints(daps, proxy, breakpoints, lapce_core::debug::BreakpointAction::Edit {
+                                    path: &location.path,
+                                    line: location.line,
+                                    offset: location.offset,
+                                    field,
+                                    value: &value,
+                                });
+                            },
+            InternalCommand::EditDapVariable { edit } => {
+                                self.palette.run_dap_variable_value(edit);
+                            },
+            InternalCommand::SetDapVariable { edit, value } => {
+                                self.terminal.set_dap_variable(edit, value);
+                            },
             InternalCommand::ShowStatusMessage { message } => self.show_status_message(message),
             InternalCommand::JumpToMaybeRelativeLocation { location } => {
                 let path = location.relative_path.clone();
@@ -2405,29 +2851,68 @@ impl WindowWorkspaceData {
                 self.common.proxy_status.set(Some(status.to_owned()));
             },
             CoreNotification::DiffInfo { diff } => {
+                let head_changed = self.source_control.head_commit.get_untracked()
+                    != diff.head_commit;
+                // commits were made, checked out or fetched
+                let log_changed = head_changed
+                    || self.source_control.ahead_behind.get_untracked()
+                        != diff.ahead_behind
+                    || self.source_control.branches.with_untracked(|branches| {
+                        !branches.iter().eq(diff.branches.iter())
+                    });
                 self.source_control.branch.set(diff.head.clone());
+                self.source_control
+                    .head_commit
+                    .set(diff.head_commit.clone());
                 self.source_control
                     .branches
                     .set(diff.branches.iter().cloned().collect());
                 self.source_control
                     .tags
                     .set(diff.tags.iter().cloned().collect());
-                self.source_control.file_diffs.update(|file_diffs| {
-                    *file_diffs = diff
-                        .diffs
+                self.source_control
+                    .stashes
+                    .set(diff.stashes.iter().cloned().collect());
+                self.source_control.ahead_behind.set(diff.ahead_behind);
+                self.source_control
+                    .conflicts
+                    .set(diff.conflicts.iter().cloned().collect());
+                self.source_control.file_diffs.set(
+                    diff.diffs
                         .iter()
-                        .cloned()
-                        .map(|diff| {
-                            let checked =
-                                file_diffs.get(diff.path()).is_none_or(|(_, c)| *c);
-                            (diff.path().clone(), (diff, checked))
-                        })
-                        .collect();
-                });
+                        .map(|diff| (diff.path().clone(), diff.clone()))
+                        .collect(),
+                );
+                self.source_control.staged_diffs.set(
+                    diff.staged
+                        .iter()
+                        .map(|diff| (diff.path().clone(), diff.clone()))
+                        .collect(),
+                );
 
                 let docs = self.main_split.docs.get_untracked();
                 for (_, doc) in docs {
                     doc.retrieve_head();
+                    if head_changed {
+                        doc.blame_stale.set(true);
+                    }
+                }
+                let compare = &self.source_control.compare;
+                if compare.base.with_untracked(Option::is_some)
+                    && compare.target.with_untracked(Option::is_none)
+                {
+                    // the working copy it is compared with changed
+                    self.source_control.refresh_compare();
+                }
+                // the hunks of the previewed patch may apply differently
+                self.source_control.refresh_patch(false);
+                if log_changed {
+                    if self.panel.is_panel_visible(&PanelKind::GitLog) {
+                        self.source_control.load_git_log(false);
+                    } else {
+                        // listed again when the panel is shown
+                        self.source_control.git_log.entries.set(im::Vector::new());
+                    }
                 }
             },
             CoreNotification::CompletionResponse {
@@ -2603,7 +3088,15 @@ impl WindowWorkspaceData {
             CoreNotification::TerminalLaunchFailed { term_id, error } => {
                 self.terminal.launch_failed(term_id, error);
             },
-            CoreNotification::DapRunInTerminal { config } => {
+            CoreNotification::DapRunInTerminal { config, pid } => {
+                let mut config = config.clone();
+                // a debugger which can't run the program in a terminal only
+                // tells its pid, so the terminal waits for the process
+                if config.debug_command.is_none()
+                    && let Some(pid) = *pid
+                {
+                    config.debug_command = Some(attach_wait_command(pid));
+                }
                 let dap_id = config.dap_id;
                 if let Some(dap_data) = self
                     .terminal
@@ -2615,7 +3108,7 @@ impl WindowWorkspaceData {
                         self.run_program_in_terminal(
                             cx,
                             &RunDebugMode::Debug,
-                            config,
+                            &config,
                             true,
                         );
                         return;
@@ -2667,6 +3160,30 @@ impl WindowWorkspaceData {
             CoreNotification::DapContinued { dap_id } => {
                 self.terminal.dap_continued(dap_id);
             },
+            CoreNotification::DapCapabilities {
+                dap_id,
+                capabilities,
+            } => {
+                let dap = self
+                    .terminal
+                    .debug
+                    .daps
+                    .with_untracked(|daps| daps.get(dap_id).cloned());
+                if let Some(dap) = dap {
+                    if let Some(filters) = capabilities
+                        .exception_breakpoint_filters
+                        .as_ref()
+                        .filter(|filters| !filters.is_empty())
+                        && let Some(ty) = self.terminal.debugger_type(&dap)
+                    {
+                        let known = self.terminal.debug.exception_filters;
+                        known.update(|known| {
+                            known.insert(ty, filters.clone());
+                        });
+                    }
+                    dap.capabilities.set(Some(capabilities.clone()));
+                }
+            },
             CoreNotification::DapBreakpointsResp {
                 path, breakpoints, ..
             } => {
@@ -2750,6 +3267,98 @@ impl WindowWorkspaceData {
         }
     }
 
+    /// Ask the user what the proxy is waiting for, the answer is sent back
+    /// by the palette.
+    fn handle_core_request(&self, id: RequestId, rpc: CoreRequest) {
+        match rpc {
+            CoreRequest::GitCredential { prompt, secret } => {
+                self.palette.run_git_credential(GitCredentialPrompt {
+                    id,
+                    prompt,
+                    secret,
+                });
+            },
+        }
+    }
+
+    /// A link to the selected lines of the active editor on the forge
+    /// hosting the repository, given to `f`. Lines of an unsaved file are
+    /// taken as they are.
+    fn active_editor_remote_url(&self, f: impl FnOnce(String) + 'static) {
+        let Some((path, start, end)) = self.active_editor_lines() else {
+            return;
+        };
+        let common = self.common.clone();
+        let send = create_ext_action(
+            self.scope,
+            move |result: Result<ProxyResponse, RpcError>| match result {
+                Ok(ProxyResponse::GitGetRemoteFileUrl { file_url }) => f(file_url),
+                Ok(_) => {},
+                Err(err) => {
+                    common.show_popup_message(
+                        "Git Permalink failure".to_string(),
+                        MessageType::ERROR,
+                        err.message,
+                    );
+                },
+            },
+        );
+        let templates = self
+            .common
+            .config
+            .with_untracked(|config| config.core.git_remote_url_templates.clone());
+        self.common.proxy.proxy_rpc.git_get_remote_file_url(
+            path,
+            Some((start, end)),
+            templates,
+            move |(_, result)| {
+                send(result);
+            },
+        );
+    }
+
+    /// The file of the active editor with the lines of its selection. Git
+    /// only sees what is on disk, so unsaved files are refused, which
+    /// `action` is shown for.
+    fn active_editor_git_lines(&self, action: &str) -> Option<(PathBuf, u32, u32)> {
+        let lines = self.active_editor_lines()?;
+        let editor = self.main_split.active_editor.get_untracked()?;
+        if !editor.doc().is_pristine() {
+            self.show_status_message(format!("Save the file before {action}"));
+            return None;
+        }
+        Some(lines)
+    }
+
+    /// The file of the active editor with the lines of its selection.
+    fn active_editor_lines(&self) -> Option<(PathBuf, u32, u32)> {
+        let editor = self.main_split.active_editor.get_untracked()?;
+        let doc = editor.doc();
+        let DocContent::File { path, .. } = doc.content.get_untracked() else {
+            return None;
+        };
+        let (start, end) = editor.cursor().with_untracked(|cursor| {
+            cursor
+                .get_selection()
+                .map(|(start, end)| (start.min(end), start.max(end)))
+                .unwrap_or((cursor.offset(), cursor.offset()))
+        });
+        let (start, end) = doc.lines.with_untracked(|x| {
+            let buffer = x.buffer();
+            // A selection ending at the start of a line doesn't include it
+            let end_line = buffer.line_of_offset(end);
+            let end_line = if end > start
+                && buffer.offset_of_line(end_line).ok() == Some(end)
+            {
+                end_line.saturating_sub(1)
+            } else {
+                end_line
+            };
+            (buffer.line_of_offset(start), end_line)
+        });
+        Some((path, start as u32, end as u32))
+    }
+
     pub fn show_status_message(&self, message: String) {
         let msg = WorkDoneProgressBegin {
             title:       message,
@@ -2794,6 +3403,7 @@ impl WindowWorkspaceData {
                 Some(keypress.key_down(event, &code_action))
             },
             Focus::Rename => Some(keypress.key_down(event, &self.rename)),
+            Focus::Peek => Some(keypress.key_down(event, &self.peek_data)),
             Focus::AboutPopup => Some(keypress.key_down(event, &self.about_data)),
             Focus::Panel(PanelKind::Terminal) => {
                 self.terminal.key_down(event, &keypress)
@@ -2831,10 +3441,17 @@ impl WindowWorkspaceData {
             .get(&self.main_split.root_split)
             .cloned()
             .unwrap();
+        let debug = &self.terminal.debug;
         WorkspaceInfo {
-            split:       main_split_data.get_untracked().split_info(self),
-            panel:       self.panel.panel_info(),
-            breakpoints: self.terminal.common.breakpoints.clone_for_hashmap(),
+            split:              main_split_data.get_untracked().split_info(self),
+            panel:              self.panel.panel_info(),
+            breakpoints:        self.terminal.common.breakpoints.clone_for_hashmap(),
+            global_breakpoints: debug.global_breakpoints.get_untracked(),
+            exception_filters:  debug
+                .exception_filters
+                .get_untracked()
+                .into_iter()
+                .collect(),
         }
     }
 
@@ -3062,6 +3679,10 @@ impl WindowWorkspaceData {
             | PanelKind::DocumentSymbol
             | PanelKind::References
             | PanelKind::Implementation
+            | PanelKind::WorkspaceEdit
+            | PanelKind::Documentation
+            | PanelKind::FileHistory
+            | PanelKind::GitLog
             | PanelKind::Build => {
                 // Some panels don't accept focus (yet). Fall back to visibility
                 // check in those cases.
@@ -3136,6 +3757,93 @@ impl WindowWorkspaceData {
         self.common.focus.set(Focus::Panel(kind));
     }
 
+    /// Resolve the lines touched by `edit` and show it in the workspace edit
+    /// panel, where it waits to be applied or discarded.
+    pub fn preview_workspace_edit(&self, title: String, edit: WorkspaceEdit) {
+        let Some(edits) = workspace_edits(&edit) else {
+            return;
+        };
+        // `workspace_edits` reads `document_changes` when there are no `changes`
+        let operations = match edit.document_changes {
+            Some(DocumentChanges::Operations(operations))
+                if edit.changes.is_none() =>
+            {
+                Some(operations)
+            },
+            _ => None,
+        };
+        // every line touched, to preview edits spanning several lines
+        let locations = edits
+            .iter()
+            .flat_map(|(uri, edits)| {
+                edits.iter().flat_map(|x| {
+                    (x.range.start.line..=x.range.end.line).map(|line| Location {
+                        uri:   uri.clone(),
+                        range: Range::new(
+                            Position::new(line, 0),
+                            Position::new(line, 0),
+                        ),
+                    })
+                })
+            })
+            .collect();
+        let window_tab_data = self.clone();
+        let scope = self.scope;
+        let send = create_ext_action(self.scope, move |lines: Vec<FileLine>| {
+            let preview = WorkspaceEditPreviewData::new(
+                scope, title, edits, operations, lines,
+            );
+            window_tab_data.flag_ignored_edits(&preview);
+            window_tab_data.main_split.edit_preview.set(Some(preview));
+            window_tab_data.show_panel(PanelKind::WorkspaceEdit);
+        });
+        self.common.proxy.proxy_rpc.references_resolve(
+            locations,
+            move |(_, result)| match result {
+                Ok(ProxyResponse::ReferencesResolveResponse { items }) => {
+                    send(items)
+                },
+                Ok(_) => send(Vec::new()),
+                Err(err) => {
+                    error!("{err:?}");
+                    send(Vec::new())
+                },
+            },
+        );
+    }
+
+    /// Mark the files of `preview` the `.gitignore` files ignore, edits to
+    /// them are most likely unwanted.
+    fn flag_ignored_edits(&self, preview: &WorkspaceEditPreviewData) {
+        let paths = preview.files.iter().map(|x| x.path.clone()).collect();
+        let preview = preview.clone();
+        let send = create_ext_action(self.scope, move |paths: Vec<PathBuf>| {
+            preview.set_ignored(&paths);
+        });
+        self.common
+            .proxy
+            .proxy_rpc
+            .git_ignored_paths(paths, move |(_, result)| match result {
+                Ok(ProxyResponse::GitIgnoredPathsResponse { paths }) => send(paths),
+                Ok(_) => {},
+                // not in a repository
+                Err(err) => debug!("{err:?}"),
+            });
+    }
+
+    pub fn apply_workspace_edit_preview(&self) {
+        if let Some(preview) = self.main_split.edit_preview.get_untracked() {
+            self.main_split
+                .apply_workspace_edit(&preview.checked_edit());
+        }
+        self.discard_workspace_edit_preview();
+    }
+
+    pub fn discard_workspace_edit_preview(&self) {
+        self.main_split.edit_preview.set(None);
+        self.hide_panel(PanelKind::WorkspaceEdit);
+    }
+
     fn run_and_debug(&self, cx: Scope, mode: RunDebugMode, config: RunDebugConfig) {
         debug!("{:?}", config);
         match mode {
@@ -3148,7 +3856,9 @@ impl WindowWorkspaceData {
                 self.terminal.debug.daps.update(|x| {
                     x.insert(dap_id, dap_data);
                 });
-                if config.prelaunch.is_some() {
+                if config.request == DebugRequest::Attach {
+                    self.attach_debugger(cx, config);
+                } else if config.prelaunch.is_some() {
                     self.run_program_in_terminal(cx, &mode, &config, false);
                 } else {
                     self.terminal.dap_start(config);
@@ -3160,6 +3870,65 @@ impl WindowWorkspaceData {
         }
     }
 
+    /// Attach the debugger to the process of an attach config, looking up the
+    /// newest process with the `process-name` when there is no `pid`. The
+    /// session gets a terminal which waits for the process to exit.
+    fn attach_debugger(&self, cx: Scope, config: RunDebugConfig) {
+        let Some(pid) = config.pid else {
+            let Some(process_name) = config.process_name.clone() else {
+                self.terminal.debug.daps.update(|x| {
+                    x.remove(&config.dap_id);
+                });
+                self.common.show_popup_message(
+                    "Attach".to_string(),
+                    MessageType::ERROR,
+                    format!(
+                        "{} has no pid or process-name to attach to",
+                        config.name
+                    ),
+                );
+                return;
+            };
+            let workspace = self.clone();
+            let send = create_ext_action(cx, move |processes: Vec<ProcessInfo>| {
+                if let Some(process) = find_process(&processes, &process_name) {
+                    workspace.attach_debugger_to(cx, config, process.pid);
+                } else {
+                    workspace.terminal.debug.daps.update(|x| {
+                        x.remove(&config.dap_id);
+                    });
+                    workspace.common.show_popup_message(
+                        "Attach".to_string(),
+                        MessageType::ERROR,
+                        format!("No process named {process_name} is running"),
+                    );
+                }
+            });
+            self.common
+                .proxy
+                .proxy_rpc
+                .list_processes(move |(_, result)| match result {
+                    Ok(ProxyResponse::ListProcessesResponse { processes }) => {
+                        send(processes);
+                    },
+                    _ => send(Vec::new()),
+                });
+            return;
+        };
+        self.attach_debugger_to(cx, config, pid);
+    }
+
+    /// Only the session gets `pid`, the terminal keeps `config` as it is so
+    /// that a restart looks the `process-name` up again.
+    fn attach_debugger_to(&self, cx: Scope, mut config: RunDebugConfig, pid: u32) {
+        self.terminal.dap_start(RunDebugConfig {
+            pid: Some(pid),
+            ..config.clone()
+        });
+        config.debug_command = Some(attach_wait_command(pid));
+        self.run_program_in_terminal(cx, &RunDebugMode::Debug, &config, true);
+    }
+
     fn run_program_in_terminal(
         &self,
         _cx: Scope,
@@ -3271,7 +4040,9 @@ impl WindowWorkspaceData {
                 self.terminal.debug.daps.update(|x| {
                     x.insert(dap_id, dap_data);
                 });
-                if config.prelaunch.is_some() {
+                if config.request == DebugRequest::Attach {
+                    self.attach_debugger(self.scope, config);
+                } else if config.prelaunch.is_some() {
                     if !run_debug.is_prelaunch {
                         config
                             .config_source
@@ -3283,7 +4054,7 @@ impl WindowWorkspaceData {
                     run_debug.is_prelaunch = true;
                     terminal.new_process(Some(run_debug));
                 } else {
-                    self.terminal.dap_start(config);
+                    self.terminal.dap_restart(config);
                 };
                 if !self.panel.is_panel_visible(&PanelKind::Debug) {
                     self.panel.show_panel(&PanelKind::Debug);
@@ -38,13 +38,108 @@ pub struct RunDebugConfigs {
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct LapceBreakpoint {
-    pub id:       Option<usize>,
-    pub verified: bool,
-    pub message:  Option<String>,
-    pub line:     usize,
-    pub offset:   usize,
-    pub dap_line: Option<usize>,
-    pub active:   bool,
+    pub id:            Option<usize>,
+    pub verified:      bool,
+    pub message:       Option<String>,
+    pub line:          usize,
+    pub offset:        usize,
+    pub dap_line:      Option<usize>,
+    pub active:        bool,
+    /// Only stop when this expression is true
+    #[serde(default)]
+    pub condition:     Option<String>,
+    /// Only stop when the hit count satisfies this, e.g. `10` or `>= 10`
+    #[serde(default)]
+    pub hit_condition: Option<String>,
+    /// Log this message instead of stopping, `{expr}` is interpolated
+    #[serde(default)]
+    pub log_message:   Option<String>,
+}
+
+impl LapceBreakpoint {
+    pub fn new(line: usize, offset: usize) -> Self {
+        Self {
+            id: None,
+            verified: false,
+            message: None,
+            line,
+            offset,
+            dap_line: None,
+            active: true,
+            condition: None,
+            hit_condition: None,
+            log_message: None,
+        }
+    }
+
+    pub fn is_logpoint(&self) -> bool {
+        self.log_message.is_some()
+    }
+
+    pub fn field(&self, field: BreakpointField) -> Option<&str> {
+        match field {
+            BreakpointField::Condition => self.condition.as_deref(),
+            BreakpointField::HitCondition => self.hit_condition.as_deref(),
+            BreakpointField::LogMessage => self.log_message.as_deref(),
+        }
+    }
+
+    /// Set `field`, an empty value clears it.
+    pub fn set_field(&mut self, field: BreakpointField, value: &str) {
+        let value = value.trim();
+        let value = (!value.is_empty()).then(|| value.to_string());
+        match field {
+            BreakpointField::Condition => self.condition = value,
+            BreakpointField::HitCondition => self.hit_condition = value,
+            BreakpointField::LogMessage => self.log_message = value,
+        }
+    }
+
+    /// The conditions and log message joined for display, e.g. in the
+    /// breakpoints list.
+    pub fn description(&self) -> Option<String> {
+        let parts: Vec<String> = BreakpointField::ALL
+            .iter()
+            .filter_map(|field| {
+                self.field(*field)
+                    .map(|value| format!("{}: {value}", field.name()))
+            })
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
+    pub fn source_breakpoint(&self) -> dap_types::SourceBreakpoint {
+        dap_types::SourceBreakpoint {
+            line:          self.line + 1,
+            column:        None,
+            condition:     self.condition.clone(),
+            hit_condition: self.hit_condition.clone(),
+            log_message:   self.log_message.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BreakpointField {
+    Condition,
+    HitCondition,
+    LogMessage,
+}
+
+impl BreakpointField {
+    pub const ALL: [BreakpointField; 3] = [
+        BreakpointField::Condition,
+        BreakpointField::HitCondition,
+        BreakpointField::LogMessage,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BreakpointField::Condition => "Condition",
+            BreakpointField::HitCondition => "Hit Count",
+            BreakpointField::LogMessage => "Log Message",
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Eq)]
@@ -95,6 +190,8 @@ pub struct DapVariableViewdata {
     pub parent:   Vec<usize>,
     pub expanded: bool,
     pub level:    usize,
+    /// The row of the item in the tree
+    pub index:    usize,
 }
 
 pub enum BreakpointAction<'a> {
@@ -116,4 +213,41 @@ pub enum BreakpointAction<'a> {
         line:   usize,
         offset: usize,
     },
+    /// Set a condition, hit count or log message, adding the breakpoint if
+    /// there is none on the line
+    Edit {
+        path:   &'a Path,
+        line:   usize,
+        offset: usize,
+        field:  BreakpointField,
+        value:  &'a str,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BreakpointField, LapceBreakpoint};
+
+    #[test]
+    fn test_breakpoint_fields() {
+        let mut breakpoint = LapceBreakpoint::new(9, 120);
+        assert_eq!(breakpoint.description(), None);
+
+        breakpoint.set_field(BreakpointField::Condition, " i > 3 ");
+        breakpoint.set_field(BreakpointField::LogMessage, "i = {i}");
+        assert!(breakpoint.is_logpoint());
+        assert_eq!(
+            breakpoint.description().as_deref(),
+            Some("Condition: i > 3, Log Message: i = {i}")
+        );
+
+        let source = breakpoint.source_breakpoint();
+        assert_eq!(source.line, 10);
+        assert_eq!(source.condition.as_deref(), Some("i > 3"));
+        assert_eq!(source.hit_condition, None);
+        assert_eq!(source.log_message.as_deref(), Some("i = {i}"));
+
+        breakpoint.set_field(BreakpointField::LogMessage, "");
+        assert!(!breakpoint.is_logpoint());
+    }
 }
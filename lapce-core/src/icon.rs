@@ -36,11 +36,13 @@ impl LapceIcons {
     pub const DEBUG: &'static str = "debug";
     pub const DEBUG_ALT: &'static str = "debug_alt";
     pub const DEBUG_BREAKPOINT: &'static str = "debug_breakpoint";
+    pub const DEBUG_BREAKPOINT_EDIT: &'static str = "debug_breakpoint_edit";
     pub const DEBUG_BREAKPOINT_INACTIVE: &'static str = "debug_breakpoint_inactive";
     pub const DEBUG_BREAKPOINT_VERIFIED: &'static str = "debug_breakpoint_verified";
     pub const DEBUG_CONSOLE: &'static str = "debug_console";
     pub const DEBUG_CONTINUE: &'static str = "debug_continue";
     pub const DEBUG_DISCONNECT: &'static str = "debug_disconnect";
+    pub const DEBUG_LOGPOINT: &'static str = "debug_logpoint";
     pub const DEBUG_PAUSE: &'static str = "debug_pause";
     pub const DEBUG_RESTART: &'static str = "debug_restart";
     pub const DEBUG_SMALL: &'static str = "debug_small";
@@ -50,6 +52,7 @@ impl LapceIcons {
     pub const DEBUG_STOP: &'static str = "debug_stop";
     pub const DIRECTORY_CLOSED: &'static str = "directory.closed";
     pub const DIRECTORY_OPENED: &'static str = "directory.opened";
+    pub const DOCUMENTATION: &'static str = "documentation";
     pub const DOCUMENT_SYMBOL: &'static str = "document_symbol";
     pub const DROPDOWN_ARROW: &'static str = "dropdown.arrow";
     pub const EDITOR_FOLDING_END: &'static str = "folding.end";
@@ -60,6 +63,7 @@ impl LapceIcons {
     pub const EXTENSIONS: &'static str = "extensions";
     pub const FILE: &'static str = "file";
     pub const FILE_EXPLORER: &'static str = "file_explorer";
+    pub const FILE_HISTORY: &'static str = "file_history";
     pub const FILE_PICKER_UP: &'static str = "file_picker_up";
     pub const FILE_TYPE_ARCHIVE: &'static str = "file-zip";
     pub const FILE_TYPE_BINARY: &'static str = "file-binary";
@@ -71,6 +75,7 @@ impl LapceIcons {
     pub const FOLD: &'static str = "fold";
     pub const FOLD_DOWN: &'static str = "fold.down";
     pub const FOLD_UP: &'static str = "fold.up";
+    pub const GIT_LOG: &'static str = "git_log";
     pub const IMAGE_ERROR: &'static str = "image_error";
     pub const IMAGE_LOADING: &'static str = "image_loading";
     pub const IMPLEMENTATION: &'static str = "document_symbol";
@@ -151,4 +156,5 @@ impl LapceIcons {
     pub const WINDOW_MAXIMIZE: &'static str = "window.maximize";
     pub const WINDOW_MINIMIZE: &'static str = "window.minimize";
     pub const WINDOW_RESTORE: &'static str = "window.restore";
+    pub const WORKSPACE_EDIT: &'static str = "search.replace_all";
 }
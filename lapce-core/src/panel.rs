@@ -25,12 +25,18 @@ pub fn default_panel_order() -> PanelOrder {
             PanelKind::Problem,
             PanelKind::CallHierarchy,
             PanelKind::References,
-            PanelKind::Implementation
+            PanelKind::Implementation,
+            PanelKind::WorkspaceEdit,
+            PanelKind::GitLog
         ],
     );
     order.insert(
         PanelContainerPosition::Right,
-        vector![PanelKind::DocumentSymbol,],
+        vector![
+            PanelKind::DocumentSymbol,
+            PanelKind::Documentation,
+            PanelKind::FileHistory
+        ],
     );
 
     order
@@ -42,12 +48,21 @@ pub enum PanelSection {
     Error,
     Warn,
     Changes,
+    StagedChanges,
+    Stashes,
+    MergeChanges,
+    Compare,
+    Patch,
     Installed,
     Available,
     Process,
     Variable,
+    Watch,
+    Console,
     StackFrame,
     Breakpoint,
+    ExceptionBreakpoint,
+    FunctionBreakpoint,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -133,6 +148,10 @@ pub enum PanelKind {
     References,
     Implementation,
     Build,
+    WorkspaceEdit,
+    Documentation,
+    FileHistory,
+    GitLog,
 }
 
 impl PanelKind {
@@ -150,6 +169,10 @@ impl PanelKind {
             PanelKind::References => LapceIcons::REFERENCES,
             PanelKind::Implementation => LapceIcons::IMPLEMENTATION,
             PanelKind::Build => LapceIcons::DEBUG,
+            PanelKind::WorkspaceEdit => LapceIcons::WORKSPACE_EDIT,
+            PanelKind::Documentation => LapceIcons::DOCUMENTATION,
+            PanelKind::FileHistory => LapceIcons::FILE_HISTORY,
+            PanelKind::GitLog => LapceIcons::GIT_LOG,
         }
     }
 
@@ -180,6 +203,10 @@ impl PanelKind {
             PanelKind::References => PanelContainerPosition::Bottom,
             PanelKind::Implementation => PanelContainerPosition::Bottom,
             PanelKind::Build => PanelContainerPosition::Bottom,
+            PanelKind::WorkspaceEdit => PanelContainerPosition::Bottom,
+            PanelKind::Documentation => PanelContainerPosition::Right,
+            PanelKind::FileHistory => PanelContainerPosition::Right,
+            PanelKind::GitLog => PanelContainerPosition::Bottom,
         }
     }
 
@@ -197,6 +224,10 @@ impl PanelKind {
             PanelKind::References => "References",
             PanelKind::Implementation => "Implementation",
             PanelKind::Build => "Build",
+            PanelKind::WorkspaceEdit => "Workspace Edit Preview",
+            PanelKind::Documentation => "Documentation",
+            PanelKind::FileHistory => "File History",
+            PanelKind::GitLog => "Git Log",
         }
     }
 }
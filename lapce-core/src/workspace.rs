@@ -1,6 +1,7 @@
 use std::{collections::HashMap, fmt::Display, path::PathBuf};
 
 use anyhow::Result;
+use lapce_rpc::dap_types::{ExceptionBreakpointsFilter, GlobalBreakpoints};
 use notify::{RecursiveMode, Watcher};
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
@@ -243,7 +244,12 @@ impl std::fmt::Display for LapceWorkspace {
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct WorkspaceInfo {
-    pub split:       SplitInfo,
-    pub panel:       PanelInfo,
-    pub breakpoints: HashMap<PathBuf, Vec<LapceBreakpoint>>,
+    pub split:              SplitInfo,
+    pub panel:              PanelInfo,
+    pub breakpoints:        HashMap<PathBuf, Vec<LapceBreakpoint>>,
+    #[serde(default)]
+    pub global_breakpoints: GlobalBreakpoints,
+    /// The exception filters of the debuggers started, by debugger type
+    #[serde(default)]
+    pub exception_filters:  HashMap<String, Vec<ExceptionBreakpointsFilter>>,
 }
@@ -1,4 +1,5 @@
 use std::{
+    cell::RefCell,
     collections::{HashMap, HashSet},
     fs, io,
     path::{Path, PathBuf},
@@ -14,7 +15,8 @@ use alacritty_terminal::{event::WindowSize, event_loop::Msg};
 use anyhow::{Context, Result, anyhow};
 use crossbeam_channel::Sender;
 use git2::{
-    DiffOptions, ErrorCode::NotFound, Oid, Repository, build::CheckoutBuilder,
+    Cred, CredentialType, DiffOptions, ErrorCode::NotFound, FetchOptions, Oid,
+    PushOptions, RemoteCallbacks, Repository, build::CheckoutBuilder,
 };
 use grep_matcher::Matcher;
 use grep_regex::RegexMatcherBuilder;
@@ -24,7 +26,7 @@ use lapce_core::directory::Directory;
 use lapce_rpc::{
     RequestId, RpcError, RpcResult,
     buffer::BufferId,
-    core::{CoreNotification, CoreRpcHandler, FileChanged},
+    core::{CoreNotification, CoreRpcHandler, FileChanged, REQUEST_DISMISSED},
     file::FileNodeItem,
     file_line::FileLine,
     proxy::{
@@ -32,14 +34,20 @@ use lapce_rpc::{
         ProxyResponse, ProxyRpcHandler, SearchMatch, WorkspaceContext,
     },
     rust_module_resolve::create_cargo_context,
-    source_control::{DiffInfo, FileDiff},
+    source_control::{
+        BlameHunk, CheckoutOutcome, CommitInfo, CommitOptions, DiffInfo,
+        FILE_NOT_IN_REVISION, FileDiff, FileHistoryEntry, LogEntry, MergeOutcome,
+        PatchFile, PatchHunk, PatchSource, PullOutcome, StageSelection,
+    },
     style::{LineStyle, SemanticStyles},
 };
 use lapce_xi_rope::Rope;
 use log::{debug, error};
 use lsp_types::{
-    CancelParams, MessageType, NumberOrString, Position, Range, ShowMessageParams,
-    TextDocumentItem, Url,
+    CancelParams, MessageType, NumberOrString, Position, ProgressParams,
+    ProgressParamsValue, Range, ShowMessageParams, TextDocumentItem, Url,
+    WorkDoneProgress, WorkDoneProgressBegin, WorkDoneProgressEnd,
+    WorkDoneProgressReport,
     notification::{Cancel, Notification},
 };
 use parking_lot::Mutex;
@@ -47,6 +55,7 @@ use parking_lot::Mutex;
 use crate::{
     buffer::{Buffer, get_mod_time, load_file},
     plugin::{PluginCatalogRpcHandler, catalog::PluginCatalog},
+    process::list_processes,
     terminal::{Terminal, TerminalSender, Terminals},
     watcher::{FileWatcher, Notify, WatchToken},
 };
@@ -208,8 +217,15 @@ impl ProxyHandler for Dispatcher {
             DapStart {
                 config,
                 breakpoints,
+                global_breakpoints,
+                debugger_paths,
             } => {
-                if let Err(err) = self.catalog_rpc.dap_start(config, breakpoints) {
+                if let Err(err) = self.catalog_rpc.dap_start(
+                    config,
+                    breakpoints,
+                    global_breakpoints,
+                    debugger_paths,
+                ) {
                     log::error!("{:?}", err);
                 }
             },
@@ -262,8 +278,15 @@ impl ProxyHandler for Dispatcher {
             DapRestart {
                 config,
                 breakpoints,
+                global_breakpoints,
+                debugger_paths,
             } => {
-                if let Err(err) = self.catalog_rpc.dap_restart(config, breakpoints) {
+                if let Err(err) = self.catalog_rpc.dap_restart(
+                    config,
+                    breakpoints,
+                    global_breakpoints,
+                    debugger_paths,
+                ) {
                     log::error!("{:?}", err);
                 }
             },
@@ -279,28 +302,15 @@ impl ProxyHandler for Dispatcher {
                     log::error!("{:?}", err);
                 }
             },
-            GitCommit { message, diffs } => {
-                if let Some(workspace) = self.workspace.as_ref() {
-                    match git_commit(workspace, &message, diffs) {
-                        Ok(()) => (),
-                        Err(e) => {
-                            self.core_rpc.show_message(
-                                "Git Commit failure".to_owned(),
-                                ShowMessageParams {
-                                    typ:     MessageType::ERROR,
-                                    message: e.to_string(),
-                                },
-                            );
-                        },
-                    }
-                }
-            },
-            GitCheckout { reference } => {
-                if let Some(workspace) = self.workspace.as_ref() {
-                    match git_checkout(workspace, &reference) {
-                        Ok(()) => (),
-                        Err(e) => eprintln!("{e:?}"),
-                    }
+            DapSetGlobalBreakpoints {
+                dap_id,
+                breakpoints,
+            } => {
+                if let Err(err) = self
+                    .catalog_rpc
+                    .dap_set_global_breakpoints(dap_id, breakpoints)
+                {
+                    log::error!("{:?}", err);
                 }
             },
             GitDiscardFilesChanges { files } => {
@@ -432,16 +442,393 @@ impl ProxyHandler for Dispatcher {
                 });
             },
             GetSignature { .. } => {},
-            GitGetRemoteFileUrl { file } => {
-                if let Some(workspace) = self.workspace.as_ref() {
-                    match git_get_remote_file_url(workspace, &file) {
-                        Ok(s) => self.proxy_rpc.handle_response(
-                            id,
-                            Ok(ProxyResponse::GitGetRemoteFileUrl { file_url: s }),
-                        ),
-                        Err(e) => eprintln!("{e:?}"),
+            GitGetRemoteFileUrl {
+                file,
+                lines,
+                templates,
+            } => {
+                // map the lines of the open buffer, unsaved edits included
+                let contents = self
+                    .buffers
+                    .get(&file)
+                    .map(|buffer| buffer.rope.to_string());
+                let result = if let Some(workspace) = self.workspace.as_ref() {
+                    git_get_remote_file_url(
+                        workspace, &file, lines, &templates, contents,
+                    )
+                    .map(|file_url| ProxyResponse::GitGetRemoteFileUrl { file_url })
+                    .map_err(|e| RpcError {
+                        code:    0,
+                        message: e.to_string(),
+                    })
+                } else {
+                    Err(RpcError {
+                        code:    0,
+                        message: "no workspace set".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
+            },
+            GitCommit {
+                message,
+                diffs,
+                options,
+            } => {
+                self.respond_git_background(
+                    id,
+                    "Git Commit",
+                    move |workspace, _| {
+                        git_commit(workspace, &message, diffs, options)?;
+                        Ok(ProxyResponse::GitCommitResponse {})
+                    },
+                );
+            },
+            GitCommitMessage { amend } => {
+                let result = if let Some(workspace) = self.workspace.as_ref() {
+                    git_commit_message(workspace, amend)
+                        .map(|message| ProxyResponse::GitCommitMessageResponse {
+                            message,
+                        })
+                        .map_err(|e| RpcError {
+                            code:    0,
+                            message: e.to_string(),
+                        })
+                } else {
+                    Err(RpcError {
+                        code:    0,
+                        message: "no workspace set".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
+            },
+            GitStage { selection } => {
+                self.respond_git_stage(id, selection, false);
+            },
+            GitUnstage { selection } => {
+                self.respond_git_stage(id, selection, true);
+            },
+            GitBlame { path } => {
+                // Blame the open buffer so that unsaved edits show up as not
+                // committed yet, the rope is cheap to clone
+                let contents =
+                    self.buffers.get(&path).map(|buffer| buffer.rope.clone());
+                let workspace = self.workspace.clone();
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = if let Some(workspace) = workspace {
+                        git_blame(
+                            &workspace,
+                            &path,
+                            contents.map(|rope| rope.to_string()),
+                        )
+                        .map(|blame| ProxyResponse::GitBlameResponse { blame })
+                        .map_err(|e| RpcError {
+                            code:    0,
+                            message: e.to_string(),
+                        })
+                    } else {
+                        Err(RpcError {
+                            code:    0,
+                            message: "no workspace set".to_string(),
+                        })
+                    };
+                    proxy_rpc.handle_response(id, result);
+                });
+            },
+            GitIgnoredPaths { paths } => {
+                // opening the repository and its ignore files takes a while
+                let workspace = self.workspace.clone();
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = if let Some(workspace) = workspace {
+                        git_ignored_paths(&workspace, paths)
+                            .map(|paths| ProxyResponse::GitIgnoredPathsResponse {
+                                paths,
+                            })
+                            .map_err(|e| RpcError {
+                                code:    0,
+                                message: e.to_string(),
+                            })
+                    } else {
+                        Err(RpcError {
+                            code:    0,
+                            message: "no workspace set".to_string(),
+                        })
+                    };
+                    proxy_rpc.handle_response(id, result);
+                });
+            },
+            GitFileHistory { path, from, limit } => {
+                // walking the history takes a while in large repositories
+                let workspace = self.workspace.clone();
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = if let Some(workspace) = workspace {
+                        git_file_history(&workspace, &path, from.as_deref(), limit)
+                            .map(|(history, more)| {
+                                ProxyResponse::GitFileHistoryResponse {
+                                    history,
+                                    more,
+                                }
+                            })
+                            .map_err(|e| RpcError {
+                                code:    0,
+                                message: e.to_string(),
+                            })
+                    } else {
+                        Err(RpcError {
+                            code:    0,
+                            message: "no workspace set".to_string(),
+                        })
+                    };
+                    proxy_rpc.handle_response(id, result);
+                });
+            },
+            GitLog {
+                from,
+                limit,
+                path_filter,
+            } => {
+                let result = if let Some(workspace) = self.workspace.as_ref() {
+                    git_log(
+                        workspace,
+                        from.as_deref(),
+                        limit,
+                        path_filter.as_deref(),
+                    )
+                    .map(|(entries, more)| ProxyResponse::GitLogResponse {
+                        entries,
+                        more,
+                    })
+                    .map_err(|e| RpcError {
+                        code:    0,
+                        message: e.to_string(),
+                    })
+                } else {
+                    Err(RpcError {
+                        code:    0,
+                        message: "no workspace set".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
+            },
+            GitCommitFiles { commit } => {
+                let result = if let Some(workspace) = self.workspace.as_ref() {
+                    git_commit_files(workspace, &commit)
+                        .map(|files| ProxyResponse::GitCommitFilesResponse { files })
+                        .map_err(|e| RpcError {
+                            code:    0,
+                            message: e.to_string(),
+                        })
+                } else {
+                    Err(RpcError {
+                        code:    0,
+                        message: "no workspace set".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
+            },
+            GitCompare { base, target } => {
+                let result = if let Some(workspace) = self.workspace.as_ref() {
+                    git_compare(workspace, &base, target.as_deref())
+                        .map(|files| ProxyResponse::GitCompareResponse { files })
+                        .map_err(|e| RpcError {
+                            code:    0,
+                            message: e.to_string(),
+                        })
+                } else {
+                    Err(RpcError {
+                        code:    0,
+                        message: "no workspace set".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
+            },
+            GitPreviewPatch { patch } => {
+                let result = if let Some(workspace) = self.workspace.as_ref() {
+                    git_preview_patch(workspace, &patch)
+                        .map(|files| ProxyResponse::GitPreviewPatchResponse {
+                            files,
+                        })
+                        .map_err(|e| RpcError {
+                            code:    0,
+                            message: e.to_string(),
+                        })
+                } else {
+                    Err(RpcError {
+                        code:    0,
+                        message: "no workspace set".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
+            },
+            GitApplyPatch { patch, hunks } => {
+                self.respond_git_change(id, |workspace| {
+                    git_apply_patch(workspace, &patch, hunks.as_deref())?;
+                    Ok(ProxyResponse::GitApplyPatchResponse {})
+                });
+            },
+            GitCreatePatch { source } => {
+                let result = if let Some(workspace) = self.workspace.as_ref() {
+                    git_create_patch(workspace, &source)
+                        .map(|patch| ProxyResponse::GitCreatePatchResponse { patch })
+                        .map_err(|e| RpcError {
+                            code:    0,
+                            message: e.to_string(),
+                        })
+                } else {
+                    Err(RpcError {
+                        code:    0,
+                        message: "no workspace set".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
+            },
+            GitCherryPick { commit } => {
+                self.respond_git_change(id, |workspace| {
+                    let conflicts = git_apply_commit(workspace, &commit, false)?;
+                    Ok(ProxyResponse::GitApplyCommitResponse { conflicts })
+                });
+            },
+            GitRevert { commit } => {
+                self.respond_git_change(id, |workspace| {
+                    let conflicts = git_apply_commit(workspace, &commit, true)?;
+                    Ok(ProxyResponse::GitApplyCommitResponse { conflicts })
+                });
+            },
+            GitCheckout {
+                reference,
+                auto_stash,
+            } => {
+                self.respond_git_change(id, |workspace| {
+                    let outcome = git_checkout(workspace, &reference, auto_stash)?;
+                    Ok(ProxyResponse::GitCheckoutResponse { outcome })
+                });
+            },
+            GitCreateBranch { name, start_point } => {
+                self.respond_git_change(id, |workspace| {
+                    let outcome =
+                        git_create_branch(workspace, &name, start_point.as_deref())?;
+                    Ok(ProxyResponse::GitCheckoutResponse { outcome })
+                });
+            },
+            GitRenameBranch { old_name, new_name } => {
+                self.respond_git_change(id, |workspace| {
+                    git_rename_branch(workspace, &old_name, &new_name)?;
+                    Ok(ProxyResponse::GitRenameBranchResponse {})
+                });
+            },
+            GitDeleteBranch { name, force } => {
+                self.respond_git_change(id, |workspace| {
+                    let deleted = git_delete_branch(workspace, &name, force)?;
+                    Ok(ProxyResponse::GitDeleteBranchResponse { deleted })
+                });
+            },
+            GitMerge { reference, no_ff } => {
+                self.respond_git_change(id, |workspace| {
+                    let outcome = git_merge(workspace, &reference, no_ff)?;
+                    Ok(ProxyResponse::GitMergeResponse { outcome })
+                });
+            },
+            GitStashSave { include_untracked } => {
+                self.respond_git_change(id, |workspace| {
+                    git_stash_save(workspace, include_untracked)?;
+                    Ok(ProxyResponse::GitStashResponse {})
+                });
+            },
+            GitStashApply { id: stash } => {
+                self.respond_git_change(id, |workspace| {
+                    git_stash_apply(workspace, &stash, false)?;
+                    Ok(ProxyResponse::GitStashResponse {})
+                });
+            },
+            GitStashPop { id: stash } => {
+                self.respond_git_change(id, |workspace| {
+                    git_stash_apply(workspace, &stash, true)?;
+                    Ok(ProxyResponse::GitStashResponse {})
+                });
+            },
+            GitStashDrop { id: stash } => {
+                self.respond_git_change(id, |workspace| {
+                    git_stash_drop(workspace, &stash)?;
+                    Ok(ProxyResponse::GitStashResponse {})
+                });
+            },
+            GitStashFiles { id: stash } => {
+                let result = if let Some(workspace) = self.workspace.as_ref() {
+                    git_stash_files(workspace, &stash)
+                        .map(|(files, untracked)| {
+                            ProxyResponse::GitStashFilesResponse { files, untracked }
+                        })
+                        .map_err(|e| RpcError {
+                            code:    0,
+                            message: e.to_string(),
+                        })
+                } else {
+                    Err(RpcError {
+                        code:    0,
+                        message: "no workspace set".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
+            },
+            GitFetch { remote } => {
+                self.respond_git_background(
+                    id,
+                    "Git Fetch",
+                    move |workspace, progress| {
+                        git_fetch(workspace, remote.as_deref(), progress)?;
+                        Ok(ProxyResponse::GitRemoteResponse {})
+                    },
+                );
+            },
+            GitPull { rebase } => {
+                self.respond_git_background(
+                    id,
+                    "Git Pull",
+                    move |workspace, progress| {
+                        let outcome = git_pull(workspace, rebase, progress)?;
+                        Ok(ProxyResponse::GitPullResponse { outcome })
+                    },
+                );
+            },
+            GitPush {} => {
+                self.respond_git_background(
+                    id,
+                    "Git Push",
+                    move |workspace, progress| {
+                        git_push(workspace, progress)?;
+                        Ok(ProxyResponse::GitRemoteResponse {})
+                    },
+                );
+            },
+            GitGetFileAtRevision { path, revision } => {
+                let result = if let Some(workspace) = self.workspace.as_ref() {
+                    match git_get_file_at_revision(workspace, &path, &revision) {
+                        Ok(Some(content)) => {
+                            Ok(ProxyResponse::GitGetFileAtRevisionResponse {
+                                content,
+                            })
+                        },
+                        Ok(None) => Err(RpcError {
+                            code:    FILE_NOT_IN_REVISION,
+                            message: format!(
+                                "{} is not in {revision}",
+                                path.display()
+                            ),
+                        }),
+                        Err(e) => Err(RpcError {
+                            code:    0,
+                            message: e.to_string(),
+                        }),
                     }
-                }
+                } else {
+                    Err(RpcError {
+                        code:    0,
+                        message: "no workspace set".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
             },
             GetWorkspaceSymbols { query } => {
                 let proxy_rpc = self.proxy_rpc.clone();
@@ -544,19 +931,31 @@ impl ProxyHandler for Dispatcher {
             ReadDir { path } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 thread::spawn(move || {
+                    let repo = Repository::discover(&path).ok();
                     let result = fs::read_dir(path)
                         .map(|entries| {
                             let mut items = entries
                                 .into_iter()
                                 .filter_map(|entry| {
                                     entry
-                                        .map(|e| FileNodeItem {
-                                            path:                e.path(),
-                                            is_dir:              e.path().is_dir(),
-                                            open:                false,
-                                            read:                false,
-                                            children:            HashMap::new(),
-                                            children_open_count: 0,
+                                        .map(|e| {
+                                            let path = e.path();
+                                            let is_dir = path.is_dir();
+                                            let ignored =
+                                                repo.as_ref().is_some_and(|repo| {
+                                                    git_is_ignored(
+                                                        repo, &path, is_dir,
+                                                    )
+                                                });
+                                            FileNodeItem {
+                                                path,
+                                                is_dir,
+                                                open: false,
+                                                read: false,
+                                                children: HashMap::new(),
+                                                children_open_count: 0,
+                                                ignored,
+                                            }
                                         })
                                         .ok()
                                 })
@@ -825,27 +1224,103 @@ impl ProxyHandler for Dispatcher {
                     },
                 );
             },
-            ReferencesResolve { items } => {
-                let items: Vec<FileLine> = items
-                    .into_iter()
-                    .filter_map(|location| {
-                        let Ok(path) = location.uri.to_file_path() else {
-                            log::error!("get file path fail: {:?}", location.uri);
-                            return None;
-                        };
-                        let buffer = self.get_buffer_or_insert(path.clone());
-                        let line_num = location.range.start.line as usize;
-                        let Ok(content) = buffer.line_to_cow(line_num) else {
-                            log::error!("line_to_cow: {line_num}");
-                            return None;
-                        };
-                        let content = content.to_string();
-                        Some(FileLine {
-                            path,
-                            position: location.range.start,
-                            content,
-                        })
-                    })
+            DapEvaluate {
+                dap_id,
+                expression,
+                frame_id,
+                context,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.dap_evaluate(
+                    dap_id,
+                    expression,
+                    frame_id,
+                    context,
+                    move |_, result| {
+                        proxy_rpc.handle_response(
+                            id,
+                            result.map(|result| {
+                                ProxyResponse::DapEvaluateResponse { result }
+                            }),
+                        );
+                    },
+                );
+            },
+            DapSetVariable {
+                dap_id,
+                reference,
+                name,
+                value,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.dap_set_variable(
+                    dap_id,
+                    reference,
+                    name,
+                    value,
+                    move |_, result| {
+                        proxy_rpc.handle_response(
+                            id,
+                            result.map(|result| {
+                                ProxyResponse::DapSetVariableResponse { result }
+                            }),
+                        );
+                    },
+                );
+            },
+            DapSetExpression {
+                dap_id,
+                expression,
+                value,
+                frame_id,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.dap_set_expression(
+                    dap_id,
+                    expression,
+                    value,
+                    frame_id,
+                    move |_, result| {
+                        proxy_rpc.handle_response(
+                            id,
+                            result.map(|result| {
+                                ProxyResponse::DapSetExpressionResponse { result }
+                            }),
+                        );
+                    },
+                );
+            },
+            ListProcesses {} => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let processes = list_processes();
+                    proxy_rpc.handle_response(
+                        id,
+                        Ok(ProxyResponse::ListProcessesResponse { processes }),
+                    );
+                });
+            },
+            ReferencesResolve { items } => {
+                let items: Vec<FileLine> = items
+                    .into_iter()
+                    .filter_map(|location| {
+                        let Ok(path) = location.uri.to_file_path() else {
+                            log::error!("get file path fail: {:?}", location.uri);
+                            return None;
+                        };
+                        let buffer = self.get_buffer_or_insert(path.clone());
+                        let line_num = location.range.start.line as usize;
+                        let Ok(content) = buffer.line_to_cow(line_num) else {
+                            log::error!("line_to_cow: {line_num}");
+                            return None;
+                        };
+                        let content = content.to_string();
+                        Some(FileLine {
+                            path,
+                            position: location.range.start,
+                            content,
+                        })
+                    })
                     .collect();
                 let resp = ProxyResponse::ReferencesResolveResponse { items };
                 self.proxy_rpc.handle_response(id, Ok(resp));
@@ -1008,6 +1483,107 @@ impl Dispatcher {
         self.proxy_rpc.handle_response(id, result);
     }
 
+    /// Move `selection` in or out of the index, then push the new diff info
+    /// without waiting for the file watcher.
+    fn respond_git_stage(
+        &self,
+        id: RequestId,
+        selection: StageSelection,
+        unstage: bool,
+    ) {
+        let Some(workspace) = self.workspace.as_ref() else {
+            self.respond_rpc(
+                id,
+                Err(RpcError {
+                    code:    0,
+                    message: "no workspace set".to_string(),
+                }),
+            );
+            return;
+        };
+        let result = if unstage {
+            git_unstage(workspace, selection)
+        } else {
+            git_stage(workspace, selection)
+        };
+        let result =
+            result
+                .map(|_| ProxyResponse::GitStageResponse {})
+                .map_err(|e| RpcError {
+                    code:    0,
+                    message: e.to_string(),
+                });
+        if result.is_ok()
+            && let Some(diff) = git_diff_new(workspace)
+        {
+            self.core_rpc.diff_info(diff);
+        }
+        self.respond_rpc(id, result);
+    }
+
+    /// Respond with the result of a git operation changing the repository,
+    /// then send the new state of the repository to the editor.
+    fn respond_git_change(
+        &self,
+        id: RequestId,
+        f: impl FnOnce(&Path) -> Result<ProxyResponse>,
+    ) {
+        let Some(workspace) = self.workspace.as_ref() else {
+            self.respond_rpc(
+                id,
+                Err(RpcError {
+                    code:    0,
+                    message: "no workspace set".to_string(),
+                }),
+            );
+            return;
+        };
+        let result = f(workspace).map_err(|e| RpcError {
+            code:    0,
+            message: e.to_string(),
+        });
+        if let Some(diff) = git_diff_new(workspace) {
+            self.core_rpc.diff_info(diff);
+        }
+        self.respond_rpc(id, result);
+    }
+
+    /// Like [`Self::respond_git_change`], but on another thread since talking
+    /// to a remote or running hooks can take a while. Its progress is
+    /// reported under `title`.
+    fn respond_git_background(
+        &self,
+        id: RequestId,
+        title: &str,
+        f: impl FnOnce(&Path, &GitProgress) -> Result<ProxyResponse> + Send + 'static,
+    ) {
+        let Some(workspace) = self.workspace.clone() else {
+            self.respond_rpc(
+                id,
+                Err(RpcError {
+                    code:    0,
+                    message: "no workspace set".to_string(),
+                }),
+            );
+            return;
+        };
+        let title = title.to_string();
+        let core_rpc = self.core_rpc.clone();
+        let proxy_rpc = self.proxy_rpc.clone();
+        thread::spawn(move || {
+            let progress = GitProgress::begin(core_rpc.clone(), title);
+            let result = f(&workspace, &progress).map_err(|e| RpcError {
+                code:    0,
+                message: e.to_string(),
+            });
+            drop(progress);
+            if let Some(diff) = git_diff_new(&workspace) {
+                core_rpc.diff_info(diff);
+            }
+            proxy_rpc.handle_response(id, result);
+        });
+    }
+
     fn get_buffer_or_insert(&mut self, path: PathBuf) -> &mut Buffer {
         self.buffers
             .entry(path.clone())
@@ -1400,8 +1976,28 @@ impl Dispatcher {
                 let proxy_rpc = self.proxy_rpc.clone();
                 self.catalog_rpc.get_code_actions(
                     &path,
-                    position,
+                    Range {
+                        start: position,
+                        end:   position,
+                    },
                     diagnostics,
+                    None,
+                    move |plugin_id, result| {
+                        let result = result.map(|resp| {
+                            ProxyResponse::GetCodeActionsResponse { plugin_id, resp }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                    id,
+                );
+            },
+            GetCodeActionsOfKind { path, range, only } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_code_actions(
+                    &path,
+                    range,
+                    Vec::new(),
+                    Some(only),
                     move |plugin_id, result| {
                         let result = result.map(|resp| {
                             ProxyResponse::GetCodeActionsResponse { plugin_id, resp }
@@ -1689,6 +2285,7 @@ fn git_commit(
     workspace_path: &Path,
     message: &str,
     diffs: Vec<FileDiff>,
+    options: CommitOptions,
 ) -> Result<()> {
     let repo = Repository::discover(workspace_path)?;
     let mut index = repo.index()?;
@@ -1707,164 +2304,1044 @@ fn git_commit(
         }
     }
     index.write()?;
-    let tree = index.write_tree()?;
-    let tree = repo.find_tree(tree)?;
-
-    match repo.signature() {
-        Ok(signature) => {
-            let parents = repo
-                .head()
-                .and_then(|head| Ok(vec![head.peel_to_commit()?]))
-                .unwrap_or(vec![]);
-            let parents_refs = parents.iter().collect::<Vec<_>>();
-
-            repo.commit(
-                Some("HEAD"),
-                &signature,
-                &signature,
-                message,
-                &tree,
-                &parents_refs,
-            )?;
-            Ok(())
-        },
-        Err(e) => match e.code() {
-            NotFound => Err(anyhow!(
-                "No user.name and/or user.email configured for this git repository."
-            )),
-            _ => Err(anyhow!(
-                "Error while creating commit's signature: {}",
-                e.message()
-            )),
+
+    let signature = match repo.signature() {
+        Ok(signature) => signature,
+        Err(e) => {
+            return match e.code() {
+                NotFound => Err(anyhow!(
+                    "No user.name and/or user.email configured for this git \
+                     repository."
+                )),
+                _ => Err(anyhow!(
+                    "Error while creating commit's signature: {}",
+                    e.message()
+                )),
+            };
         },
+    };
+
+    // the lines starting with `#` are comments of the template, which git
+    // strips the same way
+    let mut message = if git_commit_template(&repo).is_some() {
+        git2::message_prettify(message, Some(b'#'))?
+    } else {
+        message.to_string()
+    };
+    if options.sign_off {
+        message = git_sign_off(&message, &signature);
+    }
+
+    if options.run_hooks {
+        git_run_hook(&repo, "pre-commit", &[])?;
+        // the hook may have staged more changes
+        index.read(true)?;
+
+        let message_file = repo.path().join("COMMIT_EDITMSG");
+        fs::write(&message_file, &message)?;
+        if git_run_hook(&repo, "commit-msg", &[message_file.as_os_str()])? {
+            message = fs::read_to_string(&message_file)?;
+        }
+    }
+    let message = message.trim();
+    if message.is_empty() {
+        return Err(anyhow!("Aborting commit due to empty commit message."));
+    }
+
+    let tree = repo.find_tree(index.write_tree()?)?;
+    if options.amend {
+        repo.head()?.peel_to_commit()?.amend(
+            Some("HEAD"),
+            None,
+            Some(&signature),
+            None,
+            Some(message),
+            Some(&tree),
+        )?;
+    } else {
+        let parents = repo
+            .head()
+            .and_then(|head| Ok(vec![head.peel_to_commit()?]))
+            .unwrap_or(vec![]);
+        let parents_refs = parents.iter().collect::<Vec<_>>();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents_refs,
+        )?;
     }
+    Ok(())
+}
+
+/// The content of the file set as `commit.template`, if any.
+fn git_commit_template(repo: &Repository) -> Option<String> {
+    let path = repo.config().ok()?.get_path("commit.template").ok()?;
+    let path = match repo.workdir() {
+        Some(workdir) if path.is_relative() => workdir.join(path),
+        _ => path,
+    };
+    fs::read_to_string(path).ok()
 }
 
-fn git_checkout(workspace_path: &Path, reference: &str) -> Result<()> {
+/// The message to start the commit box with.
+fn git_commit_message(workspace_path: &Path, amend: bool) -> Result<Option<String>> {
     let repo = Repository::discover(workspace_path)?;
-    let (object, reference) = repo.revparse_ext(reference)?;
-    repo.checkout_tree(&object, None)?;
-    repo.set_head(reference.unwrap().name().unwrap())?;
-    Ok(())
+    if amend {
+        let commit = repo.head()?.peel_to_commit()?;
+        Ok(commit
+            .message()
+            .map(|message| message.trim_end().to_string()))
+    } else {
+        Ok(git_commit_template(&repo))
+    }
 }
 
-fn git_discard_files_changes<'a>(
+/// End `message` with a `Signed-off-by` trailer for `signature`, unless it is
+/// already there. It joins the trailers of the last paragraph when there are
+/// some.
+fn git_sign_off(message: &str, signature: &git2::Signature) -> String {
+    let trailer = format!(
+        "Signed-off-by: {} <{}>",
+        signature.name().unwrap_or_default(),
+        signature.email().unwrap_or_default()
+    );
+    let message = message.trim_end();
+    if message.lines().any(|line| line == trailer) {
+        return message.to_string();
+    }
+    let last_paragraph = message.rsplit("\n\n").next().unwrap_or_default();
+    let has_trailers = message.contains("\n\n")
+        && last_paragraph.lines().all(|line| {
+            line.split_once(": ").is_some_and(|(key, _)| {
+                !key.is_empty()
+                    && key.chars().all(|c| c.is_alphanumeric() || c == '-')
+            })
+        });
+    if has_trailers {
+        format!("{message}\n{trailer}\n")
+    } else {
+        format!("{message}\n\n{trailer}\n")
+    }
+}
+
+/// Run the hook `name` of the repository with `args` if it exists and is
+/// executable, returning whether it ran. A hook exiting with an error fails
+/// with its output.
+fn git_run_hook(
+    repo: &Repository,
+    name: &str,
+    args: &[&std::ffi::OsStr],
+) -> Result<bool> {
+    let hooks = repo
+        .config()
+        .ok()
+        .and_then(|config| config.get_path("core.hooksPath").ok())
+        .map(|path| match repo.workdir() {
+            Some(workdir) if path.is_relative() => workdir.join(path),
+            _ => path,
+        })
+        .unwrap_or_else(|| repo.path().join("hooks"));
+    let hook = hooks.join(name);
+    if !is_executable(&hook) {
+        return Ok(false);
+    }
+
+    let workdir = repo.workdir().ok_or(anyhow!("bare repository"))?;
+    // hooks are usually shell scripts, which windows can't run by itself
+    let mut command = if cfg!(windows) {
+        let mut command = std::process::Command::new("sh");
+        command.arg(&hook);
+        command
+    } else {
+        std::process::Command::new(&hook)
+    };
+    let output = command
+        .args(args)
+        .current_dir(workdir)
+        .env("GIT_DIR", repo.path())
+        .env("GIT_INDEX_FILE", repo.path().join("index"))
+        .output()
+        .with_context(|| format!("failed to run the {name} hook"))?;
+    if !output.status.success() {
+        let mut message = format!("The {name} hook failed");
+        let mut out = String::from_utf8_lossy(&output.stdout).into_owned();
+        out.push_str(&String::from_utf8_lossy(&output.stderr));
+        let out = out.trim();
+        if !out.is_empty() {
+            message.push_str(":\n");
+            message.push_str(out);
+        }
+        return Err(anyhow!(message));
+    }
+    Ok(true)
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|metadata| {
+        metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+    })
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Switch to `reference`. Local changes that would be overwritten stop the
+/// checkout, unless `auto_stash` is set: they are then stashed and applied
+/// again on top of the new ref.
+fn git_checkout(
     workspace_path: &Path,
-    files: impl Iterator<Item = &'a Path>,
-) -> Result<()> {
-    let repo = Repository::discover(workspace_path)?;
+    reference: &str,
+    auto_stash: bool,
+) -> Result<CheckoutOutcome> {
+    let mut repo = Repository::discover(workspace_path)?;
+    let (id, ref_name) = {
+        let (object, reference) = repo.revparse_ext(reference)?;
+        let commit = object.peel_to_commit()?;
+        let ref_name = reference
+            .filter(|reference| reference.is_branch())
+            .and_then(|reference| reference.name().map(str::to_string));
+        (commit.id(), ref_name)
+    };
 
-    let mut checkout_b = CheckoutBuilder::new();
-    checkout_b.update_only(false).force();
+    let stashed = if auto_stash && git_has_local_changes(&repo)? {
+        let signature = repo.signature()?;
+        repo.stash_save(
+            &signature,
+            &format!("lapce: auto-stash before checkout of {reference}"),
+            Some(git2::StashFlags::INCLUDE_UNTRACKED),
+        )?;
+        true
+    } else {
+        false
+    };
 
-    let mut had_path = false;
-    for path in files {
-        // Remove the workspace path so it is relative to the folder
-        if let Ok(path) = path.strip_prefix(workspace_path) {
-            had_path = true;
-            checkout_b.path(path);
+    let checkout = repo.find_object(id, None).and_then(|object| {
+        repo.checkout_tree(&object, Some(CheckoutBuilder::new().safe()))
+    });
+    if let Err(err) = checkout {
+        if stashed {
+            repo.stash_pop(0, None)?;
+        } else if err.code() == git2::ErrorCode::Conflict {
+            return Ok(CheckoutOutcome::LocalChanges);
         }
+        return Err(err.into());
+    }
+    match ref_name {
+        Some(ref_name) => repo.set_head(&ref_name)?,
+        None => repo.set_head_detached(id)?,
     }
 
-    if !had_path {
-        // If there we no paths then we do nothing
-        // because the default behavior of checkout builder is to select all files
-        // if it is not given a path
-        return Ok(());
+    if stashed && repo.stash_pop(0, None).is_err() {
+        return Ok(CheckoutOutcome::StashConflicts);
     }
+    Ok(CheckoutOutcome::CheckedOut)
+}
 
-    repo.checkout_index(None, Some(&mut checkout_b))?;
+fn git_has_local_changes(repo: &Repository) -> Result<bool> {
+    let mut options = git2::StatusOptions::new();
+    options.include_untracked(true).include_ignored(false);
+    Ok(!repo.statuses(Some(&mut options))?.is_empty())
+}
 
-    Ok(())
+/// Create the branch `name` at `start_point` and check it out.
+fn git_create_branch(
+    workspace_path: &Path,
+    name: &str,
+    start_point: Option<&str>,
+) -> Result<CheckoutOutcome> {
+    {
+        let repo = Repository::discover(workspace_path)?;
+        let commit = match start_point {
+            Some(start_point) => {
+                repo.revparse_single(start_point)?.peel_to_commit()?
+            },
+            None => repo.head()?.peel_to_commit()?,
+        };
+        repo.branch(name, &commit, false)?;
+    }
+    git_checkout(workspace_path, name, false)
 }
 
-fn git_discard_workspace_changes(workspace_path: &Path) -> Result<()> {
+fn git_rename_branch(
+    workspace_path: &Path,
+    old_name: &str,
+    new_name: &str,
+) -> Result<()> {
     let repo = Repository::discover(workspace_path)?;
-    let mut checkout_b = CheckoutBuilder::new();
-    checkout_b.force();
-
-    repo.checkout_index(None, Some(&mut checkout_b))?;
-
+    let mut branch = repo.find_branch(old_name, git2::BranchType::Local)?;
+    branch.rename(new_name, false)?;
     Ok(())
 }
 
-fn git_delta_format(
+/// Delete the local branch `name`. Unless `force` is set, a branch that isn't
+/// merged into `HEAD` or its upstream is kept and `false` returned.
+fn git_delete_branch(
     workspace_path: &Path,
-    delta: &git2::DiffDelta,
-) -> Option<(git2::Delta, git2::Oid, PathBuf)> {
-    match delta.status() {
-        git2::Delta::Added | git2::Delta::Untracked => Some((
-            git2::Delta::Added,
-            delta.new_file().id(),
-            delta.new_file().path().map(|p| workspace_path.join(p))?,
-        )),
-        git2::Delta::Deleted => Some((
-            git2::Delta::Deleted,
-            delta.old_file().id(),
-            delta.old_file().path().map(|p| workspace_path.join(p))?,
-        )),
-        git2::Delta::Modified => Some((
-            git2::Delta::Modified,
-            delta.new_file().id(),
-            delta.new_file().path().map(|p| workspace_path.join(p))?,
-        )),
-        _ => None,
+    name: &str,
+    force: bool,
+) -> Result<bool> {
+    let repo = Repository::discover(workspace_path)?;
+    let mut branch = repo.find_branch(name, git2::BranchType::Local)?;
+    if branch.is_head() {
+        return Err(anyhow!("Cannot delete the checked out branch {name}"));
+    }
+    if !force {
+        let tip = branch.get().peel_to_commit()?.id();
+        let head = repo.head().ok().and_then(|head| head.target());
+        let upstream = branch
+            .upstream()
+            .ok()
+            .and_then(|upstream| upstream.get().target());
+        let merged = [head, upstream].into_iter().flatten().any(|id| {
+            id == tip || repo.graph_descendant_of(id, tip).unwrap_or(false)
+        });
+        if !merged {
+            return Ok(false);
+        }
     }
+    branch.delete()?;
+    Ok(true)
 }
 
-fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
-    let repo = Repository::discover(workspace_path).ok()?;
-    let name = match repo.head() {
-        Ok(head) => head.shorthand()?.to_string(),
-        _ => "(No branch)".to_owned(),
+/// Merge `reference` into the current branch, fast-forwarding when possible
+/// unless `no_ff` is set.
+fn git_merge(
+    workspace_path: &Path,
+    reference: &str,
+    no_ff: bool,
+) -> Result<MergeOutcome> {
+    let repo = Repository::discover(workspace_path)?;
+    let (object, git_ref) = repo.revparse_ext(reference)?;
+    let their_commit = object.peel_to_commit()?;
+    let annotated = match &git_ref {
+        Some(git_ref) => repo.reference_to_annotated_commit(git_ref)?,
+        None => repo.find_annotated_commit(their_commit.id())?,
     };
 
-    let mut branches = Vec::new();
-    for branch in repo.branches(None).ok()? {
-        branches.push(branch.ok()?.0.name().ok()??.to_string());
+    let (analysis, _) = repo.merge_analysis(&[&annotated])?;
+    if analysis.is_up_to_date() {
+        return Ok(MergeOutcome::UpToDate);
     }
-
-    let mut tags = Vec::new();
-    if let Ok(git_tags) = repo.tag_names(None) {
-        for tag in git_tags.into_iter().flatten() {
-            tags.push(tag.to_owned());
-        }
+    if analysis.is_fast_forward() && !no_ff {
+        repo.checkout_tree(
+            their_commit.as_object(),
+            Some(CheckoutBuilder::new().safe()),
+        )?;
+        repo.head()?.set_target(
+            their_commit.id(),
+            &format!("merge {reference}: Fast-forward"),
+        )?;
+        return Ok(MergeOutcome::FastForward);
     }
 
-    let mut deltas = Vec::new();
-    let mut diff_options = DiffOptions::new();
-    let diff = repo
-        .diff_index_to_workdir(
-            None,
-            Some(
-                diff_options
-                    .include_untracked(true)
-                    .recurse_untracked_dirs(true),
-            ),
-        )
-        .ok()?;
-    for delta in diff.deltas() {
-        if let Some(delta) = git_delta_format(workspace_path, &delta) {
-            deltas.push(delta);
-        }
+    repo.merge(&[&annotated], None, None)?;
+    let mut index = repo.index()?;
+    if index.has_conflicts() {
+        return Ok(MergeOutcome::Conflicts);
     }
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let signature = repo.signature()?;
+    let head_commit = repo.head()?.peel_to_commit()?;
+    let message = match git_ref.as_ref().filter(|git_ref| git_ref.is_branch()) {
+        Some(_) => format!("Merge branch '{reference}'"),
+        None => format!("Merge commit '{}'", their_commit.id()),
+    };
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &message,
+        &tree,
+        &[&head_commit, &their_commit],
+    )?;
+    repo.cleanup_state()?;
+    Ok(MergeOutcome::Merged)
+}
+
+/// Reports the progress of talking to a remote as work done progress, which
+/// ends when this is dropped.
+struct GitProgress {
+    core_rpc: CoreRpcHandler,
+    token:    NumberOrString,
+    /// The last report, to not send one for every object transferred
+    last:     RefCell<Option<(&'static str, u32)>>,
+}
+
+impl GitProgress {
+    fn begin(core_rpc: CoreRpcHandler, title: String) -> Self {
+        static TOKEN: AtomicU64 = AtomicU64::new(0);
+        let token = NumberOrString::String(format!(
+            "lapce-git-{}",
+            TOKEN.fetch_add(1, Ordering::Relaxed)
+        ));
+        core_rpc.work_done_progress(ProgressParams {
+            token: token.clone(),
+            value: ProgressParamsValue::WorkDone(WorkDoneProgress::Begin(
+                WorkDoneProgressBegin {
+                    title,
+                    cancellable: Some(false),
+                    message: None,
+                    percentage: None,
+                },
+            )),
+        });
+        Self {
+            core_rpc,
+            token,
+            last: RefCell::new(None),
+        }
+    }
+
+    fn report(&self, stage: &'static str, current: usize, total: usize) {
+        let percentage = (current * 100).checked_div(total).unwrap_or(0) as u32;
+        if self.last.replace(Some((stage, percentage))) == Some((stage, percentage))
+        {
+            return;
+        }
+        self.core_rpc.work_done_progress(ProgressParams {
+            token: self.token.clone(),
+            value: ProgressParamsValue::WorkDone(WorkDoneProgress::Report(
+                WorkDoneProgressReport {
+                    cancellable: Some(false),
+                    message:     Some(format!("{stage} {current}/{total}")),
+                    percentage:  Some(percentage),
+                },
+            )),
+        });
+    }
+}
+
+impl Drop for GitProgress {
+    fn drop(&mut self) {
+        self.core_rpc.work_done_progress(ProgressParams {
+            token: self.token.clone(),
+            value: ProgressParamsValue::WorkDone(WorkDoneProgress::End(
+                WorkDoneProgressEnd { message: None },
+            )),
+        });
+    }
+}
+
+/// Callbacks for talking to a remote that report the transfer progress and
+/// look for credentials the way git does.
+fn git_remote_callbacks<'a>(
+    repo: &Repository,
+    progress: &'a GitProgress,
+) -> RemoteCallbacks<'a> {
+    // the credential helper can only be read from a snapshot of the config
+    let config = repo.config().and_then(|mut config| config.snapshot()).ok();
+    let mut tried = CredentialsTried::default();
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |url, username, allowed| {
+        git_credentials(
+            url,
+            username,
+            allowed,
+            config.as_ref(),
+            &progress.core_rpc,
+            &mut tried,
+        )
+    });
+    callbacks.transfer_progress(move |stats| {
+        if stats.received_objects() < stats.total_objects() {
+            progress.report(
+                "Receiving objects",
+                stats.received_objects(),
+                stats.total_objects(),
+            );
+        } else if stats.total_deltas() > 0 {
+            progress.report(
+                "Resolving deltas",
+                stats.indexed_deltas(),
+                stats.total_deltas(),
+            );
+        }
+        true
+    });
+    callbacks.push_transfer_progress(move |current, total, _| {
+        progress.report("Writing objects", current, total);
+    });
+    callbacks
+}
+
+/// What was offered to a remote so far. libgit2 asks again whenever the
+/// credentials were refused, so each is only offered once.
+#[derive(Default)]
+struct CredentialsTried {
+    ssh_agent:          bool,
+    /// The default private key, without a passphrase
+    ssh_key:            bool,
+    /// The default private key, with a passphrase asked for
+    ssh_key_passphrase: bool,
+    user_pass:          bool,
+    default:            bool,
+}
+
+/// Tries ssh-agent and then the default private key for ssh remotes, and the
+/// configured credential helper for the others. What is still missing is
+/// asked for in the app, or through `GIT_ASKPASS`, `core.askPass` or
+/// `SSH_ASKPASS` when the app can't be asked.
+fn git_credentials(
+    url: &str,
+    username: Option<&str>,
+    allowed: CredentialType,
+    config: Option<&git2::Config>,
+    core_rpc: &CoreRpcHandler,
+    tried: &mut CredentialsTried,
+) -> Result<Cred, git2::Error> {
+    let ask = |prompt: String, secret: bool| {
+        git_ask_credential(core_rpc, config, prompt, secret)
+    };
+    if allowed.contains(CredentialType::USERNAME) {
+        return Cred::username(username.unwrap_or("git"));
+    }
+    if allowed.contains(CredentialType::SSH_KEY) {
+        let username = username.unwrap_or("git");
+        if !tried.ssh_agent {
+            tried.ssh_agent = true;
+            return Cred::ssh_key_from_agent(username);
+        }
+        if let Some(key) = git_default_ssh_key() {
+            if !tried.ssh_key {
+                tried.ssh_key = true;
+                return Cred::ssh_key(username, None, &key, None);
+            }
+            if !tried.ssh_key_passphrase {
+                tried.ssh_key_passphrase = true;
+                let passphrase =
+                    ask(format!("Passphrase for '{}': ", key.display()), true)?;
+                return Cred::ssh_key(username, None, &key, Some(&passphrase));
+            }
+        }
+    }
+    if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) && !tried.user_pass {
+        tried.user_pass = true;
+        if let Some(config) = config
+            && let Ok(cred) = Cred::credential_helper(config, url, username)
+        {
+            return Ok(cred);
+        }
+        let username = match username {
+            Some(username) => username.to_string(),
+            None => ask(format!("Username for '{url}': "), false)?,
+        };
+        let password = ask(format!("Password for '{url}': "), true)?;
+        return Cred::userpass_plaintext(&username, &password);
+    }
+    if allowed.contains(CredentialType::DEFAULT) && !tried.default {
+        tried.default = true;
+        return Cred::default();
+    }
+    Err(git2::Error::from_str(&format!(
+        "no accepted credentials found for {url}"
+    )))
+}
+
+/// Ask the user in the app, falling back to the askpass program when the app
+/// can't be asked. Dismissing the prompt gives up on the remote.
+fn git_ask_credential(
+    core_rpc: &CoreRpcHandler,
+    config: Option<&git2::Config>,
+    prompt: String,
+    secret: bool,
+) -> Result<String, git2::Error> {
+    match core_rpc.git_credential(prompt.clone(), secret) {
+        Ok(answer) => Ok(answer),
+        Err(err) if err.code == REQUEST_DISMISSED => {
+            Err(git2::Error::from_str(&err.message))
+        },
+        Err(err) => {
+            let Some(program) = git_askpass_program(config) else {
+                return Err(git2::Error::from_str(&err.message));
+            };
+            git_askpass(&program, &prompt)
+        },
+    }
+}
+
+/// The first of the private keys ssh looks for by default that exists.
+fn git_default_ssh_key() -> Option<PathBuf> {
+    let ssh_dir = directories::BaseDirs::new()?.home_dir().join(".ssh");
+    ["id_ed25519", "id_ecdsa", "id_rsa"]
+        .into_iter()
+        .map(|name| ssh_dir.join(name))
+        .find(|path| path.exists())
+}
+
+fn git_askpass_program(config: Option<&git2::Config>) -> Option<String> {
+    std::env::var("GIT_ASKPASS")
+        .ok()
+        .or_else(|| config?.get_string("core.askPass").ok())
+        .or_else(|| std::env::var("SSH_ASKPASS").ok())
+        .filter(|program| !program.is_empty())
+}
+
+fn git_askpass(program: &str, prompt: &str) -> Result<String, git2::Error> {
+    let output = std::process::Command::new(program)
+        .arg(prompt)
+        .output()
+        .map_err(|e| git2::Error::from_str(&format!("{program}: {e}")))?;
+    if !output.status.success() {
+        return Err(git2::Error::from_str(&format!(
+            "{program} exited with {}",
+            output.status
+        )));
+    }
+    let answer = String::from_utf8_lossy(&output.stdout);
+    Ok(answer.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Whether `path` is ignored by the `.gitignore` files of the repository.
+fn git_is_ignored(repo: &Repository, path: &Path, is_dir: bool) -> bool {
+    let Some(rel) = repo
+        .workdir()
+        .and_then(|workdir| path.strip_prefix(workdir).ok())
+    else {
+        return false;
+    };
+    // A trailing separator makes patterns that only match directories apply
+    let rel = if is_dir {
+        rel.join("")
+    } else {
+        rel.to_path_buf()
+    };
+    repo.is_path_ignored(rel).unwrap_or(false)
+}
+
+/// The paths among `paths` ignored by the `.gitignore` files of the
+/// repository, directly or through one of their parent directories.
+fn git_ignored_paths(
+    workspace_path: &Path,
+    paths: Vec<PathBuf>,
+) -> Result<Vec<PathBuf>> {
+    let repo = Repository::discover(workspace_path)?;
+    Ok(paths
+        .into_iter()
+        .filter(|path| git_is_ignored(&repo, path, false))
+        .collect())
+}
+
+/// The remote of the current branch's upstream, falling back to `origin` and
+/// then to the first remote.
+fn git_default_remote(repo: &Repository) -> Result<String> {
+    if let Ok(head) = repo.head()
+        && let Some(name) = head.name()
+        && let Ok(remote) = repo.branch_upstream_remote(name)
+        && let Some(remote) = remote.as_str()
+    {
+        return Ok(remote.to_string());
+    }
+    let remotes = repo.remotes()?;
+    remotes
+        .iter()
+        .flatten()
+        .find(|remote| *remote == "origin")
+        .or_else(|| remotes.iter().flatten().next())
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("the repository has no remote"))
+}
+
+fn git_fetch(
+    workspace_path: &Path,
+    remote: Option<&str>,
+    progress: &GitProgress,
+) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let remote = match remote {
+        Some(remote) => remote.to_string(),
+        None => git_default_remote(&repo)?,
+    };
+    let mut remote = repo.find_remote(&remote)?;
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(git_remote_callbacks(&repo, progress));
+    remote.fetch::<&str>(&[], Some(&mut options), None)?;
+    Ok(())
+}
+
+/// The name of the branch HEAD is on, e.g. `refs/heads/main`.
+fn git_head_branch(repo: &Repository) -> Result<String> {
+    let head = repo.head()?;
+    match head.name() {
+        Some(name) if head.is_branch() => Ok(name.to_string()),
+        _ => Err(anyhow!("HEAD is not on a branch")),
+    }
+}
+
+fn git_pull(
+    workspace_path: &Path,
+    rebase: bool,
+    progress: &GitProgress,
+) -> Result<PullOutcome> {
+    let repo = Repository::discover(workspace_path)?;
+    let head_name = git_head_branch(&repo)?;
+    let upstream_name = repo
+        .branch_upstream_name(&head_name)
+        .context("the current branch has no upstream")?;
+    let upstream_name = upstream_name
+        .as_str()
+        .ok_or_else(|| anyhow!("the upstream name is not valid utf-8"))?;
+    let remote = repo.branch_upstream_remote(&head_name)?;
+    let remote = remote
+        .as_str()
+        .ok_or_else(|| anyhow!("the remote name is not valid utf-8"))?;
+    git_fetch(workspace_path, Some(remote), progress)?;
+
+    let upstream = repo.find_reference(upstream_name)?;
+    let annotated = repo.reference_to_annotated_commit(&upstream)?;
+    let (analysis, _) = repo.merge_analysis(&[&annotated])?;
+    if analysis.is_up_to_date() {
+        return Ok(PullOutcome::UpToDate);
+    }
+    if analysis.is_fast_forward() {
+        let target = upstream.peel_to_commit()?;
+        repo.checkout_tree(target.as_object(), Some(CheckoutBuilder::new().safe()))?;
+        repo.head()?.set_target(
+            target.id(),
+            &format!("pull: Fast-forward to {upstream_name}"),
+        )?;
+        return Ok(PullOutcome::FastForward);
+    }
+    if !rebase {
+        return Err(anyhow!(
+            "the branch has diverged from its upstream, pull with rebase instead"
+        ));
+    }
+
+    let signature = repo.signature()?;
+    let mut rebase = repo.rebase(None, Some(&annotated), None, None)?;
+    if let Err(err) = git_rebase_commits(&repo, &mut rebase, &signature) {
+        rebase.abort()?;
+        return Err(err);
+    }
+    rebase.finish(Some(&signature))?;
+    Ok(PullOutcome::Rebased)
+}
+
+fn git_rebase_commits(
+    repo: &Repository,
+    rebase: &mut git2::Rebase,
+    signature: &git2::Signature,
+) -> Result<()> {
+    while let Some(operation) = rebase.next() {
+        operation?;
+        if repo.index()?.has_conflicts() {
+            return Err(anyhow!(
+                "rebasing onto the upstream ran into conflicts, the pull was undone"
+            ));
+        }
+        match rebase.commit(None, signature, None) {
+            // the changes of the commit are already upstream
+            Err(err) if err.code() == git2::ErrorCode::Applied => {},
+            result => {
+                result?;
+            },
+        }
+    }
+    Ok(())
+}
+
+fn git_push(workspace_path: &Path, progress: &GitProgress) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let head_name = git_head_branch(&repo)?;
+    let (remote_name, remote_ref, has_upstream) =
+        match repo.branch_upstream_merge(&head_name) {
+            Ok(merge) => {
+                let remote = repo.branch_upstream_remote(&head_name)?;
+                (
+                    remote.as_str().unwrap_or_default().to_string(),
+                    merge.as_str().unwrap_or_default().to_string(),
+                    true,
+                )
+            },
+            Err(_) => (git_default_remote(&repo)?, head_name.clone(), false),
+        };
+
+    let mut remote = repo.find_remote(&remote_name)?;
+    let rejected = RefCell::new(None);
+    let mut callbacks = git_remote_callbacks(&repo, progress);
+    callbacks.push_update_reference(|refname, status| {
+        if let Some(status) = status {
+            *rejected.borrow_mut() =
+                Some(format!("{refname} was rejected: {status}"));
+        }
+        Ok(())
+    });
+    let mut options = PushOptions::new();
+    options.remote_callbacks(callbacks);
+    remote.push(&[format!("{head_name}:{remote_ref}")], Some(&mut options))?;
+    if let Some(rejected) = rejected.borrow_mut().take() {
+        return Err(anyhow!(rejected));
+    }
+
+    if !has_upstream {
+        let branch_name = head_name.trim_start_matches("refs/heads/");
+        repo.find_branch(branch_name, git2::BranchType::Local)?
+            .set_upstream(Some(&format!("{remote_name}/{branch_name}")))?;
+    }
+    Ok(())
+}
+
+/// How many commits the current branch is ahead of and behind its upstream.
+fn git_ahead_behind(repo: &Repository) -> Option<(usize, usize)> {
+    let head = repo.head().ok()?;
+    let upstream = repo.branch_upstream_name(head.name()?).ok()?;
+    let upstream = repo.refname_to_id(upstream.as_str()?).ok()?;
+    repo.graph_ahead_behind(head.target()?, upstream).ok()
+}
+
+fn git_discard_files_changes<'a>(
+    workspace_path: &Path,
+    files: impl Iterator<Item = &'a Path>,
+) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+
+    let mut checkout_b = CheckoutBuilder::new();
+    checkout_b.update_only(false).force();
+
+    let mut had_path = false;
+    for path in files {
+        // Remove the workspace path so it is relative to the folder
+        if let Ok(path) = path.strip_prefix(workspace_path) {
+            had_path = true;
+            checkout_b.path(path);
+        }
+    }
+
+    if !had_path {
+        // If there we no paths then we do nothing
+        // because the default behavior of checkout builder is to select all files
+        // if it is not given a path
+        return Ok(());
+    }
+
+    repo.checkout_index(None, Some(&mut checkout_b))?;
+
+    Ok(())
+}
+
+fn git_discard_workspace_changes(workspace_path: &Path) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let mut checkout_b = CheckoutBuilder::new();
+    checkout_b.force();
+
+    repo.checkout_index(None, Some(&mut checkout_b))?;
+
+    Ok(())
+}
+
+fn git_delta_format(
+    workspace_path: &Path,
+    delta: &git2::DiffDelta,
+) -> Option<(git2::Delta, git2::Oid, PathBuf)> {
+    match delta.status() {
+        git2::Delta::Added | git2::Delta::Untracked => Some((
+            git2::Delta::Added,
+            delta.new_file().id(),
+            delta.new_file().path().map(|p| workspace_path.join(p))?,
+        )),
+        git2::Delta::Deleted => Some((
+            git2::Delta::Deleted,
+            delta.old_file().id(),
+            delta.old_file().path().map(|p| workspace_path.join(p))?,
+        )),
+        git2::Delta::Modified => Some((
+            git2::Delta::Modified,
+            delta.new_file().id(),
+            delta.new_file().path().map(|p| workspace_path.join(p))?,
+        )),
+        _ => None,
+    }
+}
+
+fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
+    let mut repo = Repository::discover(workspace_path).ok()?;
+    let stashes = git_stash_list(&mut repo);
+    let ahead_behind = git_ahead_behind(&repo);
+    let conflicts = git_conflicts(&repo, workspace_path);
+    let name = match repo.head() {
+        Ok(head) => head.shorthand()?.to_string(),
+        _ => "(No branch)".to_owned(),
+    };
+    let head_commit = repo
+        .head()
+        .ok()
+        .and_then(|head| head.target())
+        .map(|id| id.to_string());
+
+    let mut branches = Vec::new();
+    for branch in repo.branches(None).ok()? {
+        branches.push(branch.ok()?.0.name().ok()??.to_string());
+    }
+
+    let mut tags = Vec::new();
+    if let Ok(git_tags) = repo.tag_names(None) {
+        for tag in git_tags.into_iter().flatten() {
+            tags.push(tag.to_owned());
+        }
+    }
+
+    let mut diff_options = DiffOptions::new();
+    let diff = repo
+        .diff_index_to_workdir(
+            None,
+            Some(
+                diff_options
+                    .include_untracked(true)
+                    .recurse_untracked_dirs(true),
+            ),
+        )
+        .ok()?;
+    let diffs = git_file_diffs(workspace_path, diff.deltas());
 
     let oid = match repo.revparse_single("HEAD^{tree}") {
         Ok(obj) => obj.id(),
         _ => Oid::zero(),
     };
 
-    let cached_diff = repo
+    let staged = repo
         .diff_tree_to_index(repo.find_tree(oid).ok().as_ref(), None, None)
-        .ok();
+        .map(|cached_diff| git_file_diffs(workspace_path, cached_diff.deltas()))
+        .unwrap_or_default();
 
-    if let Some(cached_diff) = cached_diff {
-        for delta in cached_diff.deltas() {
-            if let Some(delta) = git_delta_format(workspace_path, &delta) {
-                deltas.push(delta);
-            }
+    Some(DiffInfo {
+        head: name,
+        head_commit,
+        branches,
+        tags,
+        diffs,
+        staged,
+        stashes,
+        ahead_behind,
+        conflicts,
+    })
+}
+
+/// The files with conflicts in the index.
+fn git_conflicts(repo: &Repository, workspace_path: &Path) -> Vec<PathBuf> {
+    let Ok(index) = repo.index() else {
+        return Vec::new();
+    };
+    if !index.has_conflicts() {
+        return Vec::new();
+    }
+    let Ok(conflicts) = index.conflicts() else {
+        return Vec::new();
+    };
+    conflicts
+        .flatten()
+        .filter_map(|conflict| {
+            let entry = conflict.our.or(conflict.their).or(conflict.ancestor)?;
+            let path = std::str::from_utf8(&entry.path).ok()?;
+            Some(workspace_path.join(path))
+        })
+        .collect()
+}
+
+/// The stashes of the repository with the files each one changed, most
+/// recent first.
+fn git_stash_list(repo: &mut Repository) -> Vec<StashEntry> {
+    let mut stashes = Vec::new();
+    if let Err(err) = repo.stash_foreach(|index, message, id| {
+        stashes.push(StashEntry {
+            index,
+            id: id.to_string(),
+            message: message.to_string(),
+        });
+        true
+    }) {
+        error!("{err:?}");
+    }
+    stashes
+}
+
+/// The index of the stash whose commit is `id`, looked up again as the
+/// indexes change when stashes are saved and dropped.
+fn git_stash_index(repo: &mut Repository, id: &str) -> Result<usize> {
+    let oid = Oid::from_str(id)?;
+    let mut found = None;
+    repo.stash_foreach(|index, _, stash_id| {
+        if *stash_id == oid {
+            found = Some(index);
         }
+        found.is_none()
+    })?;
+    found.ok_or_else(|| anyhow!("the stash {id} no longer exists"))
+}
+
+/// Apply the stash `id` to the working copy, dropping it when `pop` is set.
+fn git_stash_apply(workspace_path: &Path, id: &str, pop: bool) -> Result<()> {
+    let mut repo = Repository::discover(workspace_path)?;
+    let index = git_stash_index(&mut repo, id)?;
+    if pop {
+        repo.stash_pop(index, None)?;
+    } else {
+        repo.stash_apply(index, None)?;
     }
+    Ok(())
+}
+
+fn git_stash_drop(workspace_path: &Path, id: &str) -> Result<()> {
+    let mut repo = Repository::discover(workspace_path)?;
+    let index = git_stash_index(&mut repo, id)?;
+    repo.stash_drop(index)?;
+    Ok(())
+}
+
+/// The changes the stash `id` recorded, and the untracked files it recorded
+/// in its third parent.
+fn git_stash_files(
+    workspace_path: &Path,
+    id: &str,
+) -> Result<(Vec<FileDiff>, Vec<PathBuf>)> {
+    let repo = Repository::discover(workspace_path)?;
+    let commit = repo.find_commit(git_commit_id(&repo, id)?)?;
+    // the first parent is the commit the stash was made on
+    let parent_tree = commit.parent(0)?.tree()?;
+    let diff =
+        repo.diff_tree_to_tree(Some(&parent_tree), Some(&commit.tree()?), None)?;
+    let files = git_file_diffs(workspace_path, diff.deltas());
+    let untracked = match commit.parent(2) {
+        Ok(untracked) => {
+            let diff =
+                repo.diff_tree_to_tree(None, Some(&untracked.tree()?), None)?;
+            diff.deltas()
+                .filter_map(|delta| delta.new_file().path())
+                .map(|path| workspace_path.join(path))
+                .collect()
+        },
+        Err(_) => Vec::new(),
+    };
+    Ok((files, untracked))
+}
+
+fn git_stash_save(workspace_path: &Path, include_untracked: bool) -> Result<()> {
+    let mut repo = Repository::discover(workspace_path)?;
+    let signature = repo.signature()?;
+    let flags = if include_untracked {
+        git2::StashFlags::INCLUDE_UNTRACKED
+    } else {
+        git2::StashFlags::DEFAULT
+    };
+    repo.stash_save2(&signature, None, Some(flags))?;
+    Ok(())
+}
+
+/// Turn the deltas of a diff into `FileDiff`s, pairing an added and a deleted
+/// file with the same content into a rename.
+fn git_file_diffs<'a>(
+    workspace_path: &Path,
+    deltas: impl Iterator<Item = git2::DiffDelta<'a>>,
+) -> Vec<FileDiff> {
+    let deltas: Vec<_> = deltas
+        .filter_map(|delta| git_delta_format(workspace_path, &delta))
+        .collect();
     let mut renames = Vec::new();
     let mut renamed_deltas = HashSet::new();
 
@@ -1881,96 +3358,1107 @@ fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
         }
     }
 
-    let mut file_diffs = Vec::new();
-    for (added_index, deleted_index) in renames.iter() {
-        file_diffs.push(FileDiff::Renamed(
-            deltas[*added_index].2.clone(),
-            deltas[*deleted_index].2.clone(),
-        ));
+    let mut file_diffs = Vec::new();
+    for (added_index, deleted_index) in renames.iter() {
+        file_diffs.push(FileDiff::Renamed(
+            deltas[*added_index].2.clone(),
+            deltas[*deleted_index].2.clone(),
+        ));
+    }
+    for (i, delta) in deltas.iter().enumerate() {
+        if renamed_deltas.contains(&i) {
+            continue;
+        }
+        let diff = match delta.0 {
+            git2::Delta::Added => FileDiff::Added(delta.2.clone()),
+            git2::Delta::Deleted => FileDiff::Deleted(delta.2.clone()),
+            git2::Delta::Modified => FileDiff::Modified(delta.2.clone()),
+            _ => continue,
+        };
+        file_diffs.push(diff);
+    }
+    file_diffs.sort_by_key(|d| match d {
+        FileDiff::Modified(p)
+        | FileDiff::Added(p)
+        | FileDiff::Renamed(p, _)
+        | FileDiff::Deleted(p) => p.clone(),
+    });
+    file_diffs
+}
+
+fn git_stage(workspace_path: &Path, selection: StageSelection) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let (path, selection) = match selection {
+        StageSelection::Files(files) => {
+            let mut index = repo.index()?;
+            for path in files {
+                let relative = path.strip_prefix(workspace_path)?;
+                if path.exists() {
+                    index.add_path(relative)?;
+                } else {
+                    index.remove_path(relative)?;
+                }
+            }
+            index.write()?;
+            return Ok(());
+        },
+        StageSelection::Hunk { path, line } => (path, HunkSelection::Block(line)),
+        StageSelection::Lines { path, start, end } => {
+            (path, HunkSelection::Lines(start, end))
+        },
+    };
+
+    let relative = path.strip_prefix(workspace_path)?;
+    let mut diff_options = DiffOptions::new();
+    diff_options
+        .pathspec(relative)
+        .disable_pathspec_match(true)
+        .include_untracked(true)
+        .show_untracked_content(true);
+    let diff = repo.diff_index_to_workdir(None, Some(&mut diff_options))?;
+    let patch = git2::Patch::from_diff(&diff, 0)?
+        .ok_or_else(|| anyhow!("{} has no changes to stage", relative.display()))?;
+    let new_file_mode = if patch.delta().status() == git2::Delta::Untracked {
+        Some(git_workdir_file_mode(&path)?)
+    } else {
+        None
+    };
+    let hunks = diff_hunks(&patch)?;
+    let body = filter_hunks(&hunks, selection, false);
+    git_apply_to_index(&repo, relative, new_file_mode, &body)
+}
+
+fn git_unstage(workspace_path: &Path, selection: StageSelection) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let (path, selection) = match selection {
+        StageSelection::Files(files) => {
+            let paths = files
+                .iter()
+                .map(|path| path.strip_prefix(workspace_path))
+                .collect::<Result<Vec<_>, _>>()?;
+            // Without a HEAD the entries are simply removed from the index
+            let head = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+            repo.reset_default(head.as_ref().map(|x| x.as_object()), paths)?;
+            return Ok(());
+        },
+        StageSelection::Hunk { path, line } => (path, HunkSelection::Block(line)),
+        StageSelection::Lines { path, start, end } => {
+            (path, HunkSelection::Lines(start, end))
+        },
+    };
+
+    let relative = path.strip_prefix(workspace_path)?;
+    // The selection is made in the working copy, which can have unstaged
+    // changes on top of the index
+    let mut diff_options = DiffOptions::new();
+    diff_options.pathspec(relative).disable_pathspec_match(true);
+    let unstaged = repo.diff_index_to_workdir(None, Some(&mut diff_options))?;
+    let unstaged = match git2::Patch::from_diff(&unstaged, 0)? {
+        Some(patch) => diff_hunks(&patch)?,
+        None => Vec::new(),
+    };
+    let selection = match selection {
+        HunkSelection::Block(line) => {
+            HunkSelection::Block(new_line_to_old(&unstaged, line))
+        },
+        HunkSelection::Lines(start, end) => HunkSelection::Lines(
+            new_line_to_old(&unstaged, start),
+            new_line_to_old(&unstaged, end),
+        ),
+    };
+
+    let head = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+    let mut diff_options = DiffOptions::new();
+    diff_options.pathspec(relative).disable_pathspec_match(true);
+    let diff =
+        repo.diff_tree_to_index(head.as_ref(), None, Some(&mut diff_options))?;
+    let patch = git2::Patch::from_diff(&diff, 0)?
+        .ok_or_else(|| anyhow!("{} has no staged changes", relative.display()))?;
+    if patch.delta().status() == git2::Delta::Deleted {
+        return Err(anyhow!(
+            "{} is staged for deletion, unstage the whole file instead",
+            relative.display()
+        ));
+    }
+    let hunks = diff_hunks(&patch)?;
+    let body = filter_hunks(&hunks, selection, true);
+    git_apply_to_index(&repo, relative, None, &body)
+}
+
+/// The mode git records for the file at `path` of the working tree.
+fn git_workdir_file_mode(path: &Path) -> Result<&'static str> {
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.is_symlink() {
+        return Ok("120000");
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 != 0 {
+            return Ok("100755");
+        }
+    }
+    Ok("100644")
+}
+
+/// Apply the hunks of `body` to the index, creating the file with
+/// `new_file_mode` when it isn't tracked yet.
+fn git_apply_to_index(
+    repo: &Repository,
+    relative: &Path,
+    new_file_mode: Option<&str>,
+    body: &str,
+) -> Result<()> {
+    if body.is_empty() {
+        return Err(anyhow!("No changes in the selected lines"));
+    }
+    let name = relative.to_string_lossy().replace('\\', "/");
+    let header = if let Some(mode) = new_file_mode {
+        format!(
+            "diff --git a/{name} b/{name}\nnew file mode {mode}\n--- \
+             /dev/null\n+++ b/{name}\n"
+        )
+    } else {
+        format!("diff --git a/{name} b/{name}\n--- a/{name}\n+++ b/{name}\n")
+    };
+    let diff = git2::Diff::from_buffer(format!("{header}{body}").as_bytes())?;
+    repo.apply(&diff, git2::ApplyLocation::Index, None)?;
+    Ok(())
+}
+
+/// Which changed lines of a diff to keep, in 0-based lines of the new side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum HunkSelection {
+    /// The runs of consecutive changed lines touching the line
+    Block(u32),
+    /// The changed lines in `start..=end`
+    Lines(u32, u32),
+}
+
+#[derive(Clone, Debug)]
+struct Hunk {
+    old_start: u32,
+    old_lines: u32,
+    new_start: u32,
+    new_lines: u32,
+    lines:     Vec<HunkLine>,
+}
+
+#[derive(Clone, Debug)]
+struct HunkLine {
+    /// ' ', '+' or '-'
+    origin:   char,
+    content:  String,
+    /// 0-based line in the new side, or where the line was removed
+    new_line: u32,
+    /// 0-based line in the old side, or where the line was added
+    old_line: u32,
+}
+
+/// The 0-based line a hunk starts at. Empty ranges in a unified diff point at
+/// the line before them.
+fn hunk_range_start(start: u32, lines: u32) -> u32 {
+    if lines == 0 {
+        start
+    } else {
+        start.saturating_sub(1)
+    }
+}
+
+fn diff_hunks(patch: &git2::Patch) -> Result<Vec<Hunk>> {
+    let mut hunks = Vec::new();
+    for hunk_idx in 0..patch.num_hunks() {
+        let (hunk, line_count) = patch.hunk(hunk_idx)?;
+        let mut next_new = hunk_range_start(hunk.new_start(), hunk.new_lines());
+        let mut next_old = hunk_range_start(hunk.old_start(), hunk.old_lines());
+        let mut lines = Vec::with_capacity(line_count);
+        for line_idx in 0..line_count {
+            let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+            let origin = line.origin();
+            let (new_line, old_line) = (next_new, next_old);
+            match origin {
+                ' ' => {
+                    next_new += 1;
+                    next_old += 1;
+                },
+                '+' => next_new += 1,
+                '-' => next_old += 1,
+                // "No newline at end of file" markers, recovered from the
+                // content of the last line when the patch is written
+                _ => continue,
+            }
+            lines.push(HunkLine {
+                origin,
+                content: String::from_utf8_lossy(line.content()).into_owned(),
+                new_line,
+                old_line,
+            });
+        }
+        hunks.push(Hunk {
+            old_start: hunk.old_start(),
+            old_lines: hunk.old_lines(),
+            new_start: hunk.new_start(),
+            new_lines: hunk.new_lines(),
+            lines,
+        });
+    }
+    Ok(hunks)
+}
+
+/// Map a line of the new side of `hunks` to the old side. Lines added by the
+/// diff map to where they were inserted.
+fn new_line_to_old(hunks: &[Hunk], line: u32) -> u32 {
+    let mut delta: i64 = 0;
+    for hunk in hunks {
+        if line < hunk_range_start(hunk.new_start, hunk.new_lines) {
+            break;
+        }
+        if let Some(l) = hunk
+            .lines
+            .iter()
+            .find(|l| l.origin != '-' && l.new_line == line)
+        {
+            return l.old_line;
+        }
+        let old_end =
+            hunk_range_start(hunk.old_start, hunk.old_lines) + hunk.old_lines;
+        let new_end =
+            hunk_range_start(hunk.new_start, hunk.new_lines) + hunk.new_lines;
+        delta = old_end as i64 - new_end as i64;
+    }
+    (line as i64 + delta).max(0) as u32
+}
+
+/// Write the hunks of a patch that only applies the selected changed lines.
+/// With `reverse`, the patch takes the selected lines back out of the new
+/// side instead.
+fn filter_hunks(hunks: &[Hunk], selection: HunkSelection, reverse: bool) -> String {
+    let mut out = String::new();
+    // How many more lines the result has than the side the patch applies to
+    let mut offset: i64 = 0;
+    for hunk in hunks {
+        let selected = selected_lines(hunk, selection);
+        if !selected.iter().any(|x| *x) {
+            continue;
+        }
+
+        let mut body = String::new();
+        let (mut old_count, mut new_count) = (0u32, 0u32);
+        for (line, selected) in hunk.lines.iter().zip(selected) {
+            let origin = match (line.origin, reverse, selected) {
+                (' ', _, _) => ' ',
+                ('+', false, true) | ('-', true, true) => '+',
+                ('-', false, true) | ('+', true, true) => '-',
+                // Unselected lines stay as they are on the side the patch
+                // applies to
+                ('-', false, false) | ('+', true, false) => ' ',
+                _ => continue,
+            };
+            match origin {
+                ' ' => {
+                    old_count += 1;
+                    new_count += 1;
+                },
+                '+' => new_count += 1,
+                _ => old_count += 1,
+            }
+            body.push(origin);
+            body.push_str(&line.content);
+            if !line.content.ends_with('\n') {
+                body.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+
+        // Every line of the side the patch applies to is kept, as context or
+        // as a removal, so that side of the header doesn't change
+        let (old_start, old_lines) = if reverse {
+            (hunk.new_start, hunk.new_lines)
+        } else {
+            (hunk.old_start, hunk.old_lines)
+        };
+        let new_start = hunk_range_start(old_start, old_lines) as i64
+            + offset
+            + if new_count == 0 { 0 } else { 1 };
+        out.push_str(&format!(
+            "@@ -{old_start},{old_count} +{new_start},{new_count} @@\n"
+        ));
+        out.push_str(&body);
+        offset += new_count as i64 - old_count as i64;
+    }
+    out
+}
+
+/// Whether each line of `hunk` is a selected change. A removed line replaced
+/// by added ones goes with the added line at the same position in their run,
+/// a pure deletion with the lines on both sides of it.
+fn selected_lines(hunk: &Hunk, selection: HunkSelection) -> Vec<bool> {
+    let (start, end) = match selection {
+        HunkSelection::Block(line) => (line, line),
+        HunkSelection::Lines(start, end) => (start, end),
+    };
+    let in_range = |line: u32| line >= start && line <= end;
+
+    let mut selected = vec![false; hunk.lines.len()];
+    let mut block_start = None;
+    for i in 0..=hunk.lines.len() {
+        let changed = hunk.lines.get(i).is_some_and(|line| line.origin != ' ');
+        match (changed, block_start) {
+            (true, None) => block_start = Some(i),
+            (false, Some(first)) => {
+                block_start = None;
+                let block = &hunk.lines[first..i];
+                let added: Vec<u32> = block
+                    .iter()
+                    .filter(|line| line.origin == '+')
+                    .map(|line| line.new_line)
+                    .collect();
+                let mut removed = 0;
+                for (j, line) in block.iter().enumerate() {
+                    selected[first + j] = if line.origin == '+' {
+                        in_range(line.new_line)
+                    } else if let Some(last) = added.last() {
+                        let paired = added.get(removed).unwrap_or(last);
+                        removed += 1;
+                        in_range(*paired)
+                    } else {
+                        line.new_line >= start
+                            && line.new_line <= end.saturating_add(1)
+                    };
+                }
+                if let HunkSelection::Block(_) = selection
+                    && selected[first..i].iter().any(|x| *x)
+                {
+                    selected[first..i].fill(true);
+                }
+            },
+            _ => {},
+        }
+    }
+    selected
+}
+
+fn file_get_head(workspace_path: &Path, path: &Path) -> Result<(String, String)> {
+    let repo = Repository::discover(workspace_path)?;
+    let head = repo.head()?;
+    let tree = head.peel_to_tree()?;
+    let tree_entry = tree.get_path(path.strip_prefix(workspace_path)?)?;
+    let blob = repo.find_blob(tree_entry.id())?;
+    let id = blob.id().to_string();
+    let content = std::str::from_utf8(blob.content())
+        .with_context(|| "content bytes to string")?
+        .to_string();
+    Ok((id, content))
+}
+
+/// The path of `path` relative to the working directory of `repo`.
+fn git_relative_path<'a>(repo: &Repository, path: &'a Path) -> Result<&'a Path> {
+    let workdir = repo.workdir().ok_or(anyhow!("bare repository"))?;
+    Ok(path.strip_prefix(workdir)?)
+}
+
+fn git_blame(
+    workspace_path: &Path,
+    path: &Path,
+    contents: Option<String>,
+) -> Result<Vec<BlameHunk>> {
+    let repo = Repository::discover(workspace_path)?;
+    let blame = repo.blame_file(git_relative_path(&repo, path)?, None)?;
+    let contents = match contents {
+        Some(contents) => contents,
+        None => fs::read_to_string(path)?,
+    };
+    let blame = blame.blame_buffer(contents.as_bytes())?;
+
+    let mut commits: HashMap<Oid, CommitInfo> = HashMap::new();
+    let mut hunks = Vec::new();
+    for hunk in blame.iter() {
+        let id = hunk.final_commit_id();
+        let commit = if id.is_zero() {
+            None
+        } else if let Some(commit) = commits.get(&id) {
+            Some(commit.clone())
+        } else {
+            let commit = git_commit_info(&repo.find_commit(id)?);
+            commits.insert(id, commit.clone());
+            Some(commit)
+        };
+        hunks.push(BlameHunk {
+            start_line: hunk.final_start_line().saturating_sub(1) as u32,
+            lines: hunk.lines_in_hunk() as u32,
+            commit,
+        });
+    }
+    Ok(hunks)
+}
+
+fn git_commit_info(commit: &git2::Commit) -> CommitInfo {
+    let author = commit.author();
+    CommitInfo {
+        id:      commit.id().to_string(),
+        author:  author.name().unwrap_or_default().to_string(),
+        time:    author.when().seconds(),
+        summary: commit.summary().unwrap_or_default().to_string(),
+    }
+}
+
+/// A page of at most `limit` commits reachable from HEAD that changed `path`,
+/// newest first, following the file across renames. The page starts after the
+/// commit `from`, where the file is at `path`, and whether more commits follow
+/// it is returned along.
+fn git_file_history(
+    workspace_path: &Path,
+    path: &Path,
+    from: Option<&str>,
+    limit: usize,
+) -> Result<(Vec<FileHistoryEntry>, bool)> {
+    let repo = Repository::discover(workspace_path)?;
+    let workdir = repo
+        .workdir()
+        .ok_or(anyhow!("bare repository"))?
+        .to_path_buf();
+    let mut relative = git_relative_path(&repo, path)?.to_path_buf();
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+    revwalk.push_head()?;
+    let from = from.map(|from| git_commit_id(&repo, from)).transpose()?;
+
+    let mut history = Vec::new();
+    // the walk is done again to resume after `from`, as the commits after it
+    // aren't all its ancestors when branches were merged
+    for id in git_walk_after(revwalk, from)? {
+        let commit = repo.find_commit(id)?;
+        let Ok(entry) = commit.tree()?.get_path(&relative) else {
+            continue;
+        };
+        let parent_entries: Vec<Option<Oid>> = commit
+            .parents()
+            .map(|parent| {
+                let tree = parent.tree().ok()?;
+                tree.get_path(&relative).ok().map(|entry| entry.id())
+            })
+            .collect();
+        // unchanged compared to one of the parents
+        if parent_entries.contains(&Some(entry.id())) {
+            continue;
+        }
+        let previous_path = if let Some(None) = parent_entries.first() {
+            git_renamed_from(&repo, &commit, &relative)?
+        } else {
+            None
+        };
+        if history.len() == limit {
+            return Ok((history, true));
+        }
+        history.push(FileHistoryEntry {
+            commit:        git_commit_info(&commit),
+            path:          workdir.join(&relative),
+            previous_path: previous_path.as_ref().map(|path| workdir.join(path)),
+        });
+        if let Some(previous_path) = previous_path {
+            relative = previous_path;
+        }
+    }
+    Ok((history, false))
+}
+
+/// The commits of `revwalk` after `from`, or all of them without it.
+fn git_walk_after(
+    revwalk: git2::Revwalk<'_>,
+    from: Option<Oid>,
+) -> Result<std::vec::IntoIter<Oid>> {
+    let mut ids = revwalk.collect::<Result<Vec<_>, _>>()?.into_iter();
+    if let Some(from) = from
+        && !ids.any(|id| id == from)
+    {
+        return Err(anyhow!("{from} is not in the history"));
+    }
+    Ok(ids)
+}
+
+/// The id of the commit `id`, which must exist in the repository.
+fn git_commit_id(repo: &Repository, id: &str) -> Result<Oid> {
+    let oid = Oid::from_str(id)?;
+    repo.find_commit(oid)
+        .map(|commit| commit.id())
+        .map_err(|_| anyhow!("unknown commit {id}"))
+}
+
+/// A page of at most `limit` commits reachable from `HEAD` and the local and
+/// remote branches, starting after the commit `from`, and whether more
+/// follow it.
+fn git_log(
+    workspace_path: &Path,
+    from: Option<&str>,
+    limit: usize,
+    path_filter: Option<&Path>,
+) -> Result<(Vec<LogEntry>, bool)> {
+    let repo = Repository::discover(workspace_path)?;
+    let relative = path_filter
+        .map(|path| git_relative_path(&repo, path))
+        .transpose()?;
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+    let from = from.map(|from| git_commit_id(&repo, from)).transpose()?;
+    // an unborn HEAD has nothing to list
+    if revwalk.push_head().is_err() {
+        return Ok((Vec::new(), false));
+    }
+    revwalk.push_glob("refs/heads")?;
+    revwalk.push_glob("refs/remotes")?;
+
+    let mut refs: HashMap<Oid, Vec<String>> = HashMap::new();
+    if let Ok(head) = repo.head()
+        && let Some(id) = head.target()
+    {
+        refs.entry(id).or_default().push("HEAD".to_string());
+    }
+    for reference in repo.references()?.flatten() {
+        if !(reference.is_branch() || reference.is_remote() || reference.is_tag()) {
+            continue;
+        }
+        let (Some(name), Ok(commit)) =
+            (reference.shorthand(), reference.peel_to_commit())
+        else {
+            continue;
+        };
+        // `origin/HEAD` only repeats the default branch of the remote
+        if reference.is_remote() && name.ends_with("/HEAD") {
+            continue;
+        }
+        refs.entry(commit.id()).or_default().push(name.to_string());
+    }
+
+    let mut entries = Vec::new();
+    // the walk is done again to resume after `from`, as the commits after it
+    // aren't all its ancestors when branches were merged
+    for id in git_walk_after(revwalk, from)? {
+        let commit = repo.find_commit(id)?;
+        if let Some(relative) = relative {
+            let entry = commit
+                .tree()?
+                .get_path(relative)
+                .ok()
+                .map(|entry| entry.id());
+            let unchanged = if commit.parent_count() == 0 {
+                entry.is_none()
+            } else {
+                commit.parents().any(|parent| {
+                    let parent_entry = parent
+                        .tree()
+                        .ok()
+                        .and_then(|tree| tree.get_path(relative).ok())
+                        .map(|entry| entry.id());
+                    parent_entry == entry
+                })
+            };
+            if unchanged {
+                continue;
+            }
+        }
+        if entries.len() == limit {
+            return Ok((entries, true));
+        }
+        entries.push(LogEntry {
+            commit:  git_commit_info(&commit),
+            parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+            refs:    refs.remove(&id).unwrap_or_default(),
+        });
+    }
+    Ok((entries, false))
+}
+
+/// The files `commit` changed compared to its first parent, or every file of
+/// a root commit.
+fn git_commit_files(workspace_path: &Path, commit: &str) -> Result<Vec<FileDiff>> {
+    let repo = Repository::discover(workspace_path)?;
+    let commit = repo.revparse_single(commit)?.peel_to_commit()?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let diff =
+        repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    Ok(git_file_diffs(workspace_path, diff.deltas()))
+}
+
+/// The files changed from `base` to `target`, or to the working copy with the
+/// untracked files when there is no `target`.
+fn git_compare(
+    workspace_path: &Path,
+    base: &str,
+    target: Option<&str>,
+) -> Result<Vec<FileDiff>> {
+    let repo = Repository::discover(workspace_path)?;
+    let base = repo.revparse_single(base)?.peel_to_tree()?;
+    let diff = match target {
+        Some(target) => {
+            let target = repo.revparse_single(target)?.peel_to_tree()?;
+            repo.diff_tree_to_tree(Some(&base), Some(&target), None)?
+        },
+        None => {
+            let mut diff_options = DiffOptions::new();
+            diff_options.include_untracked(true);
+            diff_options.recurse_untracked_dirs(true);
+            repo.diff_tree_to_workdir_with_index(
+                Some(&base),
+                Some(&mut diff_options),
+            )?
+        },
+    };
+    Ok(git_file_diffs(workspace_path, diff.deltas()))
+}
+
+/// The number of hunks of each file of `diff`.
+fn git_patch_hunk_counts(diff: &git2::Diff) -> Result<Vec<usize>> {
+    (0..diff.deltas().len())
+        .map(|i| {
+            Ok(git2::Patch::from_diff(diff, i)?
+                .map(|patch| patch.num_hunks())
+                .unwrap_or(0))
+        })
+        .collect()
+}
+
+/// Apply `diff` to the working copy, only the hunks at the given indexes
+/// when `hunks` is set. The files none of whose hunks are picked are left
+/// alone, so that picking no hunk of a new file doesn't create it empty.
+/// Nothing is changed when `check` is set.
+fn git_apply_diff(
+    repo: &Repository,
+    diff: &git2::Diff,
+    hunks: Option<&[usize]>,
+    check: bool,
+) -> Result<()> {
+    let counts = git_patch_hunk_counts(diff)?;
+    let delta = std::cell::Cell::new(0);
+    let hunk = std::cell::Cell::new(0);
+    let mut options = git2::ApplyOptions::new();
+    options.check(check);
+    if let Some(hunks) = hunks {
+        options.delta_callback(|_| {
+            let i = delta.get();
+            delta.set(i + 1);
+            let start = counts[..i].iter().sum::<usize>();
+            hunk.set(start);
+            (start..start + counts[i]).any(|h| hunks.contains(&h))
+        });
+        options.hunk_callback(|_| {
+            let i = hunk.get();
+            hunk.set(i + 1);
+            hunks.contains(&i)
+        });
+    }
+    repo.apply(diff, git2::ApplyLocation::WorkDir, Some(&mut options))?;
+    Ok(())
+}
+
+/// The files and hunks of `patch`, with whether each hunk applies on its own
+/// to the working copy.
+fn git_preview_patch(workspace_path: &Path, patch: &str) -> Result<Vec<PatchFile>> {
+    let repo = Repository::discover(workspace_path)?;
+    let diff = git2::Diff::from_buffer(patch.as_bytes())?;
+    let counts = git_patch_hunk_counts(&diff)?;
+    let mut files = Vec::new();
+    let mut index = 0;
+    for (i, delta) in diff.deltas().enumerate() {
+        let Some(path) = delta.new_file().path().or(delta.old_file().path()) else {
+            continue;
+        };
+        let mut hunks = Vec::new();
+        if let Some(patch) = git2::Patch::from_diff(&diff, i)? {
+            for h in 0..counts[i] {
+                let (hunk, _) = patch.hunk(h)?;
+                let applies =
+                    git_apply_diff(&repo, &diff, Some(&[index]), true).is_ok();
+                hunks.push(PatchHunk {
+                    header: String::from_utf8_lossy(hunk.header())
+                        .trim_end()
+                        .to_string(),
+                    applies,
+                });
+                index += 1;
+            }
+        }
+        files.push(PatchFile {
+            path: workspace_path.join(path),
+            hunks,
+        });
+    }
+    Ok(files)
+}
+
+fn git_apply_patch(
+    workspace_path: &Path,
+    patch: &str,
+    hunks: Option<&[usize]>,
+) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let diff = git2::Diff::from_buffer(patch.as_bytes())?;
+    git_apply_diff(&repo, &diff, hunks, false)
+}
+
+/// The changes of `source` as a patch. The patch of a commit is formatted
+/// as an email, like `git format-patch` does, so that `git am` can commit
+/// it again.
+fn git_create_patch(workspace_path: &Path, source: &PatchSource) -> Result<String> {
+    let repo = Repository::discover(workspace_path)?;
+    let mut diff_options = DiffOptions::new();
+    let diff = match source {
+        PatchSource::Unstaged => {
+            diff_options
+                .include_untracked(true)
+                .recurse_untracked_dirs(true)
+                .show_untracked_content(true);
+            repo.diff_index_to_workdir(None, Some(&mut diff_options))?
+        },
+        PatchSource::Staged => {
+            let head = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+            repo.diff_tree_to_index(head.as_ref(), None, Some(&mut diff_options))?
+        },
+        PatchSource::Commit(commit) => {
+            let commit = repo.revparse_single(commit)?.peel_to_commit()?;
+            let email = git2::Email::from_commit(
+                &commit,
+                &mut git2::EmailCreateOptions::new(),
+            )?;
+            return Ok(String::from_utf8_lossy(email.as_slice()).to_string());
+        },
+    };
+    if diff.deltas().next().is_none() {
+        return Err(anyhow!("there are no changes"));
     }
-    for (i, delta) in deltas.iter().enumerate() {
-        if renamed_deltas.contains(&i) {
-            continue;
+    let mut patch = Vec::new();
+    diff.print(git2::DiffFormat::Patch, |_, _, line| {
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            patch.push(line.origin() as u8);
         }
-        let diff = match delta.0 {
-            git2::Delta::Added => FileDiff::Added(delta.2.clone()),
-            git2::Delta::Deleted => FileDiff::Deleted(delta.2.clone()),
-            git2::Delta::Modified => FileDiff::Modified(delta.2.clone()),
-            _ => continue,
-        };
-        file_diffs.push(diff);
+        patch.extend_from_slice(line.content());
+        true
+    })?;
+    Ok(String::from_utf8_lossy(&patch).to_string())
+}
+
+/// Cherry-pick `commit`, or revert it, and commit the result unless it
+/// conflicts. With conflicts the repository is left in the cherry-pick or
+/// revert state with the message prepared, like git does, and `true` is
+/// returned.
+fn git_apply_commit(
+    workspace_path: &Path,
+    commit: &str,
+    revert: bool,
+) -> Result<bool> {
+    let repo = Repository::discover(workspace_path)?;
+    let commit = repo.revparse_single(commit)?.peel_to_commit()?;
+    // the whole index is committed, so it mustn't have anything else in it
+    let mut options = git2::StatusOptions::new();
+    options.include_untracked(false).include_ignored(false);
+    if !repo.statuses(Some(&mut options))?.is_empty() {
+        return Err(anyhow!(
+            "commit or stash the local changes before applying a commit"
+        ));
     }
-    file_diffs.sort_by_key(|d| match d {
-        FileDiff::Modified(p)
-        | FileDiff::Added(p)
-        | FileDiff::Renamed(p, _)
-        | FileDiff::Deleted(p) => p.clone(),
-    });
-    Some(DiffInfo {
-        head: name,
-        branches,
-        tags,
-        diffs: file_diffs,
-    })
+    // the changes of a merge commit are taken against its first parent
+    let mainline = if commit.parent_count() > 1 { 1 } else { 0 };
+    if revert {
+        let mut options = git2::RevertOptions::new();
+        options.mainline(mainline);
+        repo.revert(&commit, Some(&mut options))?;
+    } else {
+        let mut options = git2::CherrypickOptions::new();
+        options.mainline(mainline);
+        repo.cherrypick(&commit, Some(&mut options))?;
+    }
+
+    let mut index = repo.index()?;
+    if index.has_conflicts() {
+        return Ok(true);
+    }
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let signature = repo.signature()?;
+    let head_commit = repo.head()?.peel_to_commit()?;
+    let (author, message) = if revert {
+        let message = format!(
+            "Revert \"{}\"\n\nThis reverts commit {}.\n",
+            commit.summary().unwrap_or_default(),
+            commit.id()
+        );
+        (signature.clone(), message)
+    } else {
+        (
+            commit.author().to_owned(),
+            commit.message().unwrap_or_default().to_string(),
+        )
+    };
+    repo.commit(
+        Some("HEAD"),
+        &author,
+        &signature,
+        &message,
+        &tree,
+        &[&head_commit],
+    )?;
+    repo.cleanup_state()?;
+    Ok(false)
 }
 
-fn file_get_head(workspace_path: &Path, path: &Path) -> Result<(String, String)> {
+/// The path `relative` had in the first parent of `commit`, if the commit
+/// renamed it.
+fn git_renamed_from(
+    repo: &Repository,
+    commit: &git2::Commit,
+    relative: &Path,
+) -> Result<Option<PathBuf>> {
+    let parent_tree = commit.parent(0)?.tree()?;
+    let mut diff =
+        repo.diff_tree_to_tree(Some(&parent_tree), Some(&commit.tree()?), None)?;
+    diff.find_similar(Some(git2::DiffFindOptions::new().renames(true)))?;
+    Ok(diff
+        .deltas()
+        .find(|delta| {
+            delta.status() == git2::Delta::Renamed
+                && delta.new_file().path() == Some(relative)
+        })
+        .and_then(|delta| delta.old_file().path().map(Path::to_path_buf)))
+}
+
+/// The content of `path` as of `revision`, which is anything git can resolve
+/// to a commit (`HEAD~2`, a branch name, `<sha>^`...).
+/// The content of `path` at `revision`, `None` when the revision doesn't have
+/// the file.
+fn git_get_file_at_revision(
+    workspace_path: &Path,
+    path: &Path,
+    revision: &str,
+) -> Result<Option<String>> {
     let repo = Repository::discover(workspace_path)?;
-    let head = repo.head()?;
-    let tree = head.peel_to_tree()?;
-    let tree_entry = tree.get_path(path.strip_prefix(workspace_path)?)?;
-    let blob = repo.find_blob(tree_entry.id())?;
-    let id = blob.id().to_string();
+    let relative_path = git_relative_path(&repo, path)?;
+    // `:1`, `:2` and `:3` are the base, ours and theirs versions of a file with
+    // conflicts, as in `git show :2:path`
+    let id = match revision.strip_prefix(':').map(str::parse::<i32>) {
+        Some(Ok(stage)) => match repo.index()?.get_path(relative_path, stage) {
+            Some(entry) => entry.id,
+            None => return Ok(None),
+        },
+        _ => {
+            let tree = repo.revparse_single(revision)?.peel_to_tree()?;
+            match tree.get_path(relative_path) {
+                Ok(entry) => entry.id(),
+                Err(err) if err.code() == NotFound => return Ok(None),
+                Err(err) => return Err(err.into()),
+            }
+        },
+    };
+    let blob = repo.find_blob(id)?;
     let content = std::str::from_utf8(blob.content())
         .with_context(|| "content bytes to string")?
         .to_string();
-    Ok((id, content))
+    Ok(Some(content))
 }
 
-fn git_get_remote_file_url(workspace_path: &Path, file: &Path) -> Result<String> {
+/// A link to `lines` of `file`, 0-based and inclusive, in the web view of
+/// the forge hosting the default remote. It points at the newest commit the
+/// remote has, with the lines moved to where they are in it when the file
+/// changed since. The lines are those of `contents` when given, the file on
+/// disk otherwise.
+fn git_get_remote_file_url(
+    workspace_path: &Path,
+    file: &Path,
+    lines: Option<(u32, u32)>,
+    templates: &HashMap<String, String>,
+    contents: Option<String>,
+) -> Result<String> {
     let repo = Repository::discover(workspace_path)?;
-    let head = repo.head()?;
-    let target_remote = repo.find_remote(
-        repo.branch_upstream_remote(head.name().unwrap())?
-            .as_str()
-            .unwrap(),
-    )?;
+    let remote_name = git_default_remote(&repo)?;
+    let remote = repo.find_remote(&remote_name)?;
 
     // Grab URL part of remote
-    let remote = target_remote
+    let remote = remote
         .url()
         .ok_or(anyhow!("Failed to convert remote to str"))?;
+    let (host, namespace) = git_remote_web_location(remote)?;
+
+    let commit = git_pushed_commit(&repo, &remote_name)?;
+    let workdir = repo.workdir().ok_or(anyhow!("the repository is bare"))?;
+    let relative = file.strip_prefix(workdir)?;
+    let tree = repo.find_commit(commit)?.tree()?;
+    let Ok(entry) = tree.get_path(relative) else {
+        return Err(anyhow!(
+            "{} is not in any commit of {remote_name}",
+            relative.display()
+        ));
+    };
+
+    let lines = if let Some((start, end)) = lines {
+        let contents = match contents {
+            Some(contents) => contents,
+            None => fs::read_to_string(file)?,
+        };
+        let blob = entry.to_object(&repo)?.peel_to_blob()?;
+        let patch = git2::Patch::from_blob_and_buffer(
+            Some(&blob),
+            Some(relative),
+            contents.as_bytes(),
+            Some(relative),
+            None,
+        )?;
+        let hunks = diff_hunks(&patch)?;
+        let start = new_line_to_old(&hunks, start);
+        let end = new_line_to_old(&hunks, end).max(start);
+        Some((start, end))
+    } else {
+        None
+    };
 
+    let file_path = relative
+        .to_str()
+        .ok_or(anyhow!("Couldn't convert file path to str"))?
+        .replace('\\', "/");
+    Ok(git_permalink(
+        templates,
+        &host,
+        &namespace,
+        &commit.to_string(),
+        &file_path,
+        lines,
+    ))
+}
+
+/// The host of the web view of the forge at `remote`, with its port, and the
+/// namespace of the repository on it (e.g. organisation/project on GitHub,
+/// org/team/team/../project on GitLab). The port of an ssh remote is the one
+/// of ssh, so it is left out.
+fn git_remote_web_location(remote: &str) -> Result<(String, String)> {
     let remote_url = match Url::parse(remote) {
-        Ok(url) => url,
-        Err(_) => {
+        Ok(url) if url.host_str().is_some() => url,
+        _ => {
             // Parse URL as ssh
             Url::parse(&format!("ssh://{}", remote.replacen(':', "/", 1)))?
         },
     };
 
-    // Get host part
     let host = remote_url
         .host_str()
         .ok_or(anyhow!("Couldn't find remote host"))?;
-    // Get namespace (e.g. organisation/project in case of GitHub,
-    // org/team/team/team/../project on GitLab)
-    let namespace = if let Some(stripped) = remote_url.path().strip_suffix(".git") {
-        stripped
-    } else {
-        remote_url.path()
+    let host = match remote_url.port() {
+        Some(port) if matches!(remote_url.scheme(), "http" | "https") => {
+            format!("{host}:{port}")
+        },
+        _ => host.to_string(),
     };
+    let namespace = remote_url.path().trim_start_matches('/');
+    let namespace = namespace.strip_suffix(".git").unwrap_or(namespace);
+    Ok((host, namespace.to_string()))
+}
 
-    let commit = head.peel_to_commit()?.id();
+/// The newest commit of `HEAD` that the remote has, going by the last
+/// fetch, or `HEAD` itself when nothing was fetched from it.
+fn git_pushed_commit(repo: &Repository, remote: &str) -> Result<Oid> {
+    let head = repo.head()?.peel_to_commit()?.id();
+    let mut pushed: Option<Oid> = None;
+    for reference in repo.references_glob(&format!("refs/remotes/{remote}/*"))? {
+        let Some(target) = reference?.resolve().ok().and_then(|r| r.target()) else {
+            continue;
+        };
+        let Ok(base) = repo.merge_base(head, target) else {
+            continue;
+        };
+        if base == head {
+            return Ok(head);
+        }
+        if pushed.is_none_or(|pushed| {
+            repo.graph_descendant_of(base, pushed).unwrap_or(false)
+        }) {
+            pushed = Some(base);
+        }
+    }
+    Ok(pushed.unwrap_or(head))
+}
 
-    let file_path = file
-        .strip_prefix(workspace_path)?
-        .to_str()
-        .ok_or(anyhow!("Couldn't convert file path to str"))?;
+/// The URL templates of the forges known by name.
+fn git_forge_template(name: &str) -> Option<&'static str> {
+    match name {
+        "github" => {
+            Some("https://{host}/{repo}/blob/{commit}/{file}#L{start}-L{end}")
+        },
+        "gitlab" => {
+            Some("https://{host}/{repo}/-/blob/{commit}/{file}#L{start}-{end}")
+        },
+        "gitea" | "forgejo" => {
+            Some("https://{host}/{repo}/src/commit/{commit}/{file}#L{start}-L{end}")
+        },
+        "bitbucket" => {
+            Some("https://{host}/{repo}/src/{commit}/{file}#lines-{start}:{end}")
+        },
+        _ => None,
+    }
+}
 
-    let url = format!("https://{host}{namespace}/blob/{commit}/{file_path}",);
+/// Fill in the URL template for `host`, with its port if any, which is either a
+/// forge name or a template with `{host}`, `{repo}`, `{commit}`, `{file}`,
+/// `{start}` and `{end}` in it. Hosts without a template get the one of the
+/// forge their name hints at, GitHub's otherwise. Lines are 0-based and
+/// inclusive. For a single line what is between `{start}` and `{end}` is left
+/// out, and without lines the `#` part is.
+fn git_permalink(
+    templates: &HashMap<String, String>,
+    host: &str,
+    repo: &str,
+    commit: &str,
+    file: &str,
+    lines: Option<(u32, u32)>,
+) -> String {
+    // a template may be set for the host with or without its port
+    let template = templates
+        .get(host)
+        .or_else(|| templates.get(host.split(':').next()?))
+        .map(String::as_str);
+    let template = match template {
+        Some(template) => git_forge_template(template).unwrap_or(template),
+        None => {
+            let forge = if host.contains("gitlab") {
+                "gitlab"
+            } else if host.contains("bitbucket") {
+                "bitbucket"
+            } else if ["gitea", "forgejo", "codeberg"]
+                .iter()
+                .any(|name| host.contains(name))
+            {
+                "gitea"
+            } else {
+                "github"
+            };
+            git_forge_template(forge).unwrap_or_default()
+        },
+    };
 
-    Ok(url)
+    let mut template = template.to_string();
+    match lines {
+        None => {
+            if let Some(i) = template.find('#') {
+                template.truncate(i);
+            }
+        },
+        Some((start, end)) if start == end => {
+            if let (Some(start), Some(end)) =
+                (template.find("{start}"), template.find("{end}"))
+                && start < end
+            {
+                template
+                    .replace_range(start + "{start}".len()..end + "{end}".len(), "");
+            }
+        },
+        Some(_) => {},
+    }
+    let (start, end) = lines.unwrap_or_default();
+    template
+        .replace("{host}", host)
+        .replace("{repo}", repo)
+        .replace("{commit}", commit)
+        .replace("{file}", file)
+        .replace("{start}", &(start + 1).to_string())
+        .replace("{end}", &(end + 1).to_string())
 }
 
 fn search_in_path(
@@ -2058,3 +4546,620 @@ fn search_in_path(
 
     Ok(ProxyResponse::GlobalSearchResponse { matches })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{HashMap, HashSet},
+        path::Path,
+    };
+
+    use git2::{Oid, Repository, Signature};
+    use lapce_rpc::{
+        core::CoreRpcHandler,
+        source_control::{CommitOptions, FileDiff, PatchSource, PullOutcome},
+    };
+
+    use super::{
+        GitProgress, Hunk, HunkLine, HunkSelection, filter_hunks, git_ahead_behind,
+        git_apply_commit, git_apply_patch, git_commit, git_compare,
+        git_create_patch, git_fetch, git_file_history, git_ignored_paths, git_log,
+        git_permalink, git_preview_patch, git_pull, git_push,
+        git_remote_web_location, git_sign_off, git_stash_apply, git_stash_drop,
+        git_stash_files, git_stash_list, git_stash_save, new_line_to_old,
+    };
+
+    /// `a b c d e` changed into `a B c d e f`
+    fn hunk() -> Hunk {
+        let lines = [
+            (' ', "a", 0, 0),
+            ('-', "b", 1, 1),
+            ('+', "B", 1, 2),
+            (' ', "c", 2, 2),
+            (' ', "d", 3, 3),
+            (' ', "e", 4, 4),
+            ('+', "f", 5, 5),
+        ];
+        Hunk {
+            old_start: 1,
+            old_lines: 5,
+            new_start: 1,
+            new_lines: 6,
+            lines:     lines
+                .into_iter()
+                .map(|(origin, content, new_line, old_line)| HunkLine {
+                    origin,
+                    content: format!("{content}\n"),
+                    new_line,
+                    old_line,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_filter_hunks_lines() {
+        assert_eq!(
+            filter_hunks(&[hunk()], HunkSelection::Lines(5, 5), false),
+            "@@ -1,5 +1,6 @@\n a\n b\n c\n d\n e\n+f\n"
+        );
+        assert_eq!(
+            filter_hunks(&[hunk()], HunkSelection::Lines(1, 1), false),
+            "@@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n"
+        );
+        assert_eq!(
+            filter_hunks(&[hunk()], HunkSelection::Lines(2, 4), false),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_filter_hunks_block() {
+        assert_eq!(
+            filter_hunks(&[hunk()], HunkSelection::Block(1), false),
+            "@@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n"
+        );
+    }
+
+    #[test]
+    fn test_filter_hunks_reverse() {
+        assert_eq!(
+            filter_hunks(&[hunk()], HunkSelection::Lines(1, 1), true),
+            "@@ -1,6 +1,6 @@\n a\n+b\n-B\n c\n d\n e\n f\n"
+        );
+    }
+
+    #[test]
+    fn test_new_line_to_old() {
+        let hunks = [hunk()];
+        assert_eq!(new_line_to_old(&hunks, 2), 2);
+        assert_eq!(new_line_to_old(&hunks, 5), 5);
+        assert_eq!(new_line_to_old(&hunks, 10), 9);
+    }
+
+    fn clone(remote: &Path, path: &Path) -> Repository {
+        let repo = Repository::clone(&format!("file://{}", remote.display()), path)
+            .unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        repo
+    }
+
+    fn commit_file(repo: &Repository, file: &str) {
+        commit_content(repo, file, file, &[]);
+    }
+
+    /// Commit `content` written to `file`, with `content` as the message and
+    /// the commits of `merged` as more parents.
+    fn commit_content(repo: &Repository, file: &str, content: &str, merged: &[Oid]) {
+        std::fs::write(repo.workdir().unwrap().join(file), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(file)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let merged = merged.iter().map(|id| repo.find_commit(*id).unwrap());
+        let parents = parent.into_iter().chain(merged).collect::<Vec<_>>();
+        let parents = parents.iter().collect::<Vec<_>>();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            content,
+            &tree,
+            &parents,
+        )
+        .unwrap();
+    }
+
+    /// Move HEAD to a new branch `name` at `start`, without touching the
+    /// working tree.
+    fn switch_branch(repo: &Repository, name: &str, start: Oid) {
+        let start = repo.find_commit(start).unwrap();
+        repo.branch(name, &start, true).unwrap();
+        repo.set_head(&format!("refs/heads/{name}")).unwrap();
+    }
+
+    fn head_id(repo: &Repository) -> Oid {
+        repo.head().unwrap().peel_to_commit().unwrap().id()
+    }
+
+    /// Every page of at most `limit` entries, until there are no more.
+    fn all_pages(
+        limit: usize,
+        mut page: impl FnMut(Option<&str>, usize) -> (Vec<String>, bool),
+    ) -> Vec<String> {
+        let mut ids: Vec<String> = Vec::new();
+        loop {
+            let (entries, more) = page(ids.last().map(String::as_str), limit);
+            ids.extend(entries);
+            if !more {
+                return ids;
+            }
+        }
+    }
+
+    #[test]
+    fn test_push_fetch_pull() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote = dir.path().join("remote.git");
+        Repository::init_bare(&remote).unwrap();
+        let progress = GitProgress::begin(CoreRpcHandler::new(), String::new());
+
+        let first = clone(&remote, &dir.path().join("first"));
+        let first_path = first.workdir().unwrap().to_path_buf();
+        commit_file(&first, "a");
+        git_push(&first_path, &progress).unwrap();
+        assert_eq!(git_ahead_behind(&first), Some((0, 0)));
+
+        let second = clone(&remote, &dir.path().join("second"));
+        commit_file(&second, "b");
+        git_push(second.workdir().unwrap(), &progress).unwrap();
+
+        commit_file(&first, "c");
+        git_fetch(&first_path, None, &progress).unwrap();
+        assert_eq!(git_ahead_behind(&first), Some((1, 1)));
+
+        assert!(git_pull(&first_path, false, &progress).is_err());
+        assert_eq!(
+            git_pull(&first_path, true, &progress).unwrap(),
+            PullOutcome::Rebased
+        );
+        assert_eq!(git_ahead_behind(&first), Some((1, 0)));
+        assert!(first_path.join("b").exists());
+    }
+
+    #[test]
+    fn test_file_history_pages() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let path = dir.path().join("a");
+        for (file, content) in [("a", "1"), ("b", "b"), ("a", "2"), ("a", "3")] {
+            commit_content(&repo, file, content, &[]);
+        }
+
+        let (first, more) = git_file_history(dir.path(), &path, None, 2).unwrap();
+        assert!(more);
+        let summaries: Vec<_> =
+            first.iter().map(|e| e.commit.summary.as_str()).collect();
+        assert_eq!(summaries, ["3", "2"]);
+        let (second, more) =
+            git_file_history(dir.path(), &path, Some(&first[1].commit.id), 2)
+                .unwrap();
+        assert!(!more);
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].commit.summary, "1");
+
+        let unknown = "0123456789012345678901234567890123456789";
+        assert!(git_file_history(dir.path(), &path, Some(unknown), 2).is_err());
+    }
+
+    #[test]
+    fn test_file_history_pages_across_merge() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let path = dir.path().join("a");
+        commit_content(&repo, "a", "base", &[]);
+        let base = head_id(&repo);
+        switch_branch(&repo, "side", base);
+        commit_content(&repo, "a", "side 1", &[]);
+        commit_content(&repo, "a", "side 2", &[]);
+        let side = head_id(&repo);
+        switch_branch(&repo, "main", base);
+        commit_content(&repo, "a", "main", &[]);
+        commit_content(&repo, "a", "merged", &[side]);
+
+        for limit in 1..=3 {
+            let ids = all_pages(limit, |from, limit| {
+                let (entries, more) =
+                    git_file_history(dir.path(), &path, from, limit).unwrap();
+                let ids = entries.into_iter().map(|e| e.commit.id).collect();
+                (ids, more)
+            });
+            assert_eq!(ids.len(), 5);
+            assert_eq!(ids.iter().collect::<HashSet<_>>().len(), 5);
+        }
+    }
+
+    #[test]
+    fn test_log_pages_and_revert() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        for file in ["a", "b", "c"] {
+            commit_file(&repo, file);
+        }
+
+        let (first, more) = git_log(dir.path(), None, 2, None).unwrap();
+        assert!(more);
+        assert_eq!(first[0].commit.summary, "c");
+        assert!(first[0].refs.contains(&"HEAD".to_string()));
+        assert_eq!(first[0].parents, vec![first[1].commit.id.clone()]);
+        let (second, more) =
+            git_log(dir.path(), Some(&first[1].commit.id), 2, None).unwrap();
+        assert!(!more);
+        assert_eq!(second.len(), 1);
+        assert!(second[0].parents.is_empty());
+        let unknown = "0123456789012345678901234567890123456789";
+        assert!(git_log(dir.path(), Some(unknown), 2, None).is_err());
+
+        let (filtered, _) =
+            git_log(dir.path(), None, 10, Some(&dir.path().join("b"))).unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].commit.summary, "b");
+
+        // staged changes would end up in the commit
+        std::fs::write(dir.path().join("a"), "changed").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("a")).unwrap();
+        index.write().unwrap();
+        assert!(git_apply_commit(dir.path(), &first[0].commit.id, true).is_err());
+        std::fs::write(dir.path().join("a"), "a").unwrap();
+        index.add_path(Path::new("a")).unwrap();
+        index.write().unwrap();
+
+        assert!(!git_apply_commit(dir.path(), &first[0].commit.id, true).unwrap());
+        assert!(!dir.path().join("c").exists());
+        let (log, _) = git_log(dir.path(), None, 1, None).unwrap();
+        assert_eq!(log[0].commit.summary, "Revert \"c\"");
+    }
+
+    #[test]
+    fn test_log_pages_across_merge() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit_file(&repo, "a");
+        let base = head_id(&repo);
+        switch_branch(&repo, "side", base);
+        commit_file(&repo, "s1");
+        commit_file(&repo, "s2");
+        let side = head_id(&repo);
+        switch_branch(&repo, "main", base);
+        commit_file(&repo, "m1");
+        commit_content(&repo, "m2", "merge", &[side]);
+        // an unmerged branch newer than the commits of main
+        switch_branch(&repo, "topic", base);
+        commit_file(&repo, "t1");
+        repo.set_head("refs/heads/main").unwrap();
+
+        for limit in 1..=3 {
+            let ids = all_pages(limit, |from, limit| {
+                let (entries, more) =
+                    git_log(dir.path(), from, limit, None).unwrap();
+                let ids = entries.into_iter().map(|e| e.commit.id).collect();
+                (ids, more)
+            });
+            assert_eq!(ids.len(), 6);
+            assert_eq!(ids.iter().collect::<HashSet<_>>().len(), 6);
+        }
+    }
+
+    #[test]
+    fn test_stash() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut repo = Repository::init(dir.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        commit_file(&repo, "a");
+        let [a, b] = ["a", "b"].map(|file| dir.path().join(file));
+        std::fs::write(&a, "changed").unwrap();
+        std::fs::write(&b, "b").unwrap();
+
+        git_stash_save(dir.path(), true).unwrap();
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "a");
+        assert!(!b.exists());
+        let stashes = git_stash_list(&mut repo);
+        assert_eq!(stashes.len(), 1);
+        let id = stashes[0].id.clone();
+        assert_eq!(
+            git_stash_files(dir.path(), &id).unwrap(),
+            (vec![FileDiff::Modified(a.clone())], vec![b.clone()])
+        );
+
+        git_stash_apply(dir.path(), &id, false).unwrap();
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "changed");
+        assert!(b.exists());
+        assert_eq!(git_stash_list(&mut repo).len(), 1);
+
+        // a newer stash moves it to `stash@{1}`
+        git_stash_save(dir.path(), true).unwrap();
+        let stashes = git_stash_list(&mut repo);
+        assert_eq!(stashes[1].id, id);
+        git_stash_drop(dir.path(), &id).unwrap();
+        let stashes = git_stash_list(&mut repo);
+        assert_eq!(stashes.len(), 1);
+        assert_ne!(stashes[0].id, id);
+        assert!(git_stash_drop(dir.path(), &id).is_err());
+
+        git_stash_apply(dir.path(), &stashes[0].id, true).unwrap();
+        assert!(git_stash_list(&mut repo).is_empty());
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "changed");
+        assert!(b.exists());
+    }
+
+    #[test]
+    fn test_compare() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit_file(&repo, "a");
+        commit_file(&repo, "b");
+        std::fs::write(dir.path().join("c"), "c").unwrap();
+
+        assert_eq!(
+            git_compare(dir.path(), "HEAD~1", Some("HEAD")).unwrap(),
+            vec![FileDiff::Added(dir.path().join("b"))]
+        );
+        let mut files = git_compare(dir.path(), "HEAD~1", None).unwrap();
+        files.sort_by(|a, b| a.path().cmp(b.path()));
+        assert_eq!(
+            files,
+            vec![
+                FileDiff::Added(dir.path().join("b")),
+                FileDiff::Added(dir.path().join("c")),
+            ]
+        );
+        assert!(git_compare(dir.path(), "missing", None).is_err());
+    }
+
+    #[test]
+    fn test_ignored_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        Repository::init(dir.path()).unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "target/\n*.log\n").unwrap();
+
+        let paths = ["src/main.rs", "target/debug/build/out.rs", "run.log"]
+            .map(|path| dir.path().join(path));
+        assert_eq!(
+            git_ignored_paths(dir.path(), paths.to_vec()).unwrap(),
+            paths[1..].to_vec()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stage_hunk_of_untracked_script() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit_file(&repo, "a");
+        let script = dir.path().join("run.sh");
+        std::fs::write(&script, "#!/bin/sh\necho run\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+
+        git_stage(
+            dir.path(),
+            StageSelection::Hunk {
+                path: script,
+                line: 0,
+            },
+        )
+        .unwrap();
+        let index = repo.index().unwrap();
+        let entry = index.get_path(Path::new("run.sh"), 0).unwrap();
+        assert_eq!(entry.mode, 0o100755);
+    }
+
+    #[test]
+    fn test_patch() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit_file(&repo, "a");
+        let file = dir.path().join("a");
+        let text = |first: &str, last: &str| {
+            format!("{first}\n2\n3\n4\n5\n6\n7\n8\n9\n{last}\n")
+        };
+        std::fs::write(&file, text("1", "10")).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("a")).unwrap();
+        index.write().unwrap();
+        std::fs::write(&file, text("one", "ten")).unwrap();
+
+        let patch = git_create_patch(dir.path(), &PatchSource::Unstaged).unwrap();
+        std::fs::write(&file, text("1", "10")).unwrap();
+
+        let files = git_preview_patch(dir.path(), &patch).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, file);
+        assert_eq!(files[0].hunks.len(), 2);
+        assert!(files[0].hunks.iter().all(|hunk| hunk.applies));
+
+        git_apply_patch(dir.path(), &patch, Some(&[1])).unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), text("1", "ten"));
+        let files = git_preview_patch(dir.path(), &patch).unwrap();
+        assert_eq!(
+            files[0]
+                .hunks
+                .iter()
+                .map(|hunk| hunk.applies)
+                .collect::<Vec<_>>(),
+            vec![true, false]
+        );
+
+        assert!(git_create_patch(dir.path(), &PatchSource::Staged).is_ok());
+        let email =
+            git_create_patch(dir.path(), &PatchSource::Commit("HEAD".to_string()))
+                .unwrap();
+        assert!(email.starts_with("From "));
+    }
+
+    #[test]
+    fn test_remote_file_url_of_unsaved_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        repo.remote("origin", "https://github.com/org/repo.git")
+            .unwrap();
+        commit_content(&repo, "a", "1\n2\n3\n", &[]);
+        let head = head_id(&repo);
+        let file = dir.path().join("a");
+        let url = |line, contents: Option<&str>| {
+            git_get_remote_file_url(
+                dir.path(),
+                &file,
+                Some((line, line)),
+                &HashMap::new(),
+                contents.map(str::to_string),
+            )
+            .unwrap()
+        };
+
+        let expected = format!("https://github.com/org/repo/blob/{head}/a#L3");
+        assert_eq!(url(2, None), expected);
+        // two lines added above in the editor only
+        assert_eq!(url(4, Some("0\n0\n1\n2\n3\n")), expected);
+    }
+
+    #[test]
+    fn test_permalink() {
+        let mut templates = HashMap::new();
+        templates.insert("git.corp".to_string(), "bitbucket".to_string());
+        templates.insert(
+            "code.corp".to_string(),
+            "https://{host}/r/{repo}/{commit}/{file}#{start}..{end}".to_string(),
+        );
+        let link = |host: &str, lines: Option<(u32, u32)>| {
+            git_permalink(&templates, host, "org/repo", "abc", "src/lib.rs", lines)
+        };
+        assert_eq!(
+            link("github.com", Some((9, 19))),
+            "https://github.com/org/repo/blob/abc/src/lib.rs#L10-L20"
+        );
+        assert_eq!(
+            link("gitlab.example.com", Some((9, 9))),
+            "https://gitlab.example.com/org/repo/-/blob/abc/src/lib.rs#L10"
+        );
+        assert_eq!(
+            link("git.corp", Some((9, 19))),
+            "https://git.corp/org/repo/src/abc/src/lib.rs#lines-10:20"
+        );
+        assert_eq!(
+            link("code.corp", Some((0, 1))),
+            "https://code.corp/r/org/repo/abc/src/lib.rs#1..2"
+        );
+        assert_eq!(
+            link("code.corp", None),
+            "https://code.corp/r/org/repo/abc/src/lib.rs"
+        );
+        assert_eq!(
+            link("git.corp:8443", Some((9, 19))),
+            "https://git.corp:8443/org/repo/src/abc/src/lib.rs#lines-10:20"
+        );
+    }
+
+    #[test]
+    fn test_remote_web_location() {
+        let location = |remote: &str| git_remote_web_location(remote).unwrap();
+        let expected = |host: &str| (host.to_string(), "org/repo".to_string());
+        assert_eq!(
+            location("git@github.com:org/repo.git"),
+            expected("github.com")
+        );
+        assert_eq!(
+            location("https://git.corp:8443/org/repo.git"),
+            expected("git.corp:8443")
+        );
+        assert_eq!(
+            location("https://github.com/org/repo"),
+            expected("github.com")
+        );
+        assert_eq!(
+            location("ssh://git@git.corp:2222/org/repo.git"),
+            expected("git.corp")
+        );
+    }
+
+    #[test]
+    fn test_sign_off() {
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        let trailer = "Signed-off-by: Test <test@example.com>";
+        assert_eq!(
+            git_sign_off("Fix it\n", &signature),
+            format!("Fix it\n\n{trailer}\n")
+        );
+        assert_eq!(
+            git_sign_off("Fix it\n\nCo-authored-by: A <a@b.c>", &signature),
+            format!("Fix it\n\nCo-authored-by: A <a@b.c>\n{trailer}\n")
+        );
+        assert_eq!(
+            git_sign_off(&format!("Fix it\n\n{trailer}\n"), &signature),
+            format!("Fix it\n\n{trailer}")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_commit_hook_and_amend() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        commit_file(&repo, "a");
+
+        let hook = repo.path().join("hooks").join("commit-msg");
+        std::fs::create_dir_all(hook.parent().unwrap()).unwrap();
+        std::fs::write(
+            &hook,
+            "#!/bin/sh\ngrep -q '^feat' \"$1\" || { echo 'not a feat'; exit 1; }\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+
+        std::fs::write(dir.path().join("b"), "b").unwrap();
+        let diffs = vec![FileDiff::Added(dir.path().join("b"))];
+        let options = CommitOptions {
+            run_hooks: true,
+            ..Default::default()
+        };
+        let err = git_commit(dir.path(), "fix b", diffs.clone(), options)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("commit-msg") && err.contains("not a feat"));
+
+        git_commit(dir.path(), "feat b", diffs, options).unwrap();
+        // rewording leaves what isn't staged out of the commit
+        std::fs::write(dir.path().join("a"), "changed").unwrap();
+        let options = CommitOptions {
+            amend: true,
+            ..options
+        };
+        git_commit(dir.path(), "feat: b", Vec::new(), options).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.message(), Some("feat: b"));
+        assert_eq!(head.parent_count(), 1);
+        assert!(head.tree().unwrap().get_name("b").is_some());
+        assert_eq!(
+            repo.status_file(Path::new("a")).unwrap(),
+            git2::Status::WT_MODIFIED
+        );
+    }
+}
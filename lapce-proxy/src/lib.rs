@@ -4,6 +4,7 @@ pub mod buffer;
 pub mod cli;
 pub mod dispatch;
 pub mod plugin;
+pub mod process;
 pub mod rust_build;
 pub mod terminal;
 pub mod watcher;
@@ -15,8 +15,8 @@ use lapce_core::directory::Directory;
 use lapce_rpc::{
     RpcError,
     dap_types::{
-        self, DapId, DapServer, RunDebugConfig, SetBreakpointsResponse,
-        SourceBreakpoint,
+        self, DapAdapter, DapId, DapServer, DebuggerPaths, GlobalBreakpoints,
+        RunDebugConfig, SetBreakpointsResponse, SourceBreakpoint,
     },
     plugin::{PluginId, VoltID, VoltInfo, VoltMetadata},
     proxy::ProxyResponse,
@@ -36,6 +36,7 @@ use serde_json::Value;
 use super::{
     DapNotificationOfUser, PluginCatalogNotification, PluginCatalogRpcHandler,
     dap::{DapClient, DapRpcHandler, DebuggerData},
+    debugger::{builtin_debugger, rust_sysroot},
     install_volt,
     psp::{ClonableCallback, PluginServerRpc, PluginServerRpcHandler, RpcCallback},
     wasi::start_volt,
@@ -472,6 +473,90 @@ impl PluginCatalog {
         }
     }
 
+    pub fn dap_evaluate(
+        &self,
+        dap_id: DapId,
+        expression: String,
+        frame_id: Option<usize>,
+        context: String,
+        f: Box<dyn RpcCallback<dap_types::EvaluateResponse, RpcError>>,
+    ) {
+        if let Some(dap) = self.daps.get(&dap_id) {
+            dap.evaluate_async(
+                expression,
+                frame_id,
+                context,
+                |id, result: Result<dap_types::EvaluateResponse, RpcError>| {
+                    f.call(id, result)
+                },
+            );
+        } else {
+            f.call(
+                Id::Num(0),
+                Err(RpcError {
+                    code:    0,
+                    message: "plugin doesn't exist".to_string(),
+                }),
+            );
+        }
+    }
+
+    pub fn dap_set_variable(
+        &self,
+        dap_id: DapId,
+        reference: usize,
+        name: String,
+        value: String,
+        f: Box<dyn RpcCallback<dap_types::SetVariableResponse, RpcError>>,
+    ) {
+        if let Some(dap) = self.daps.get(&dap_id) {
+            dap.set_variable_async(
+                reference,
+                name,
+                value,
+                |id, result: Result<dap_types::SetVariableResponse, RpcError>| {
+                    f.call(id, result)
+                },
+            );
+        } else {
+            f.call(
+                Id::Num(0),
+                Err(RpcError {
+                    code:    0,
+                    message: "plugin doesn't exist".to_string(),
+                }),
+            );
+        }
+    }
+
+    pub fn dap_set_expression(
+        &self,
+        dap_id: DapId,
+        expression: String,
+        value: String,
+        frame_id: Option<usize>,
+        f: Box<dyn RpcCallback<dap_types::SetExpressionResponse, RpcError>>,
+    ) {
+        if let Some(dap) = self.daps.get(&dap_id) {
+            dap.set_expression_async(
+                expression,
+                value,
+                frame_id,
+                |id, result: Result<dap_types::SetExpressionResponse, RpcError>| {
+                    f.call(id, result)
+                },
+            );
+        } else {
+            f.call(
+                Id::Num(0),
+                Err(RpcError {
+                    code:    0,
+                    message: "plugin doesn't exist".to_string(),
+                }),
+            );
+        }
+    }
+
     pub fn dap_get_scopes(
         &self,
         dap_id: DapId,
@@ -689,6 +774,7 @@ impl PluginCatalog {
                         debugger_type,
                         program,
                         args,
+                        adapter: DapAdapter::Plugin,
                     },
                 );
             },
@@ -720,8 +806,15 @@ impl PluginCatalog {
             DapStart {
                 config,
                 breakpoints,
+                global_breakpoints,
+                debugger_paths,
             } => {
-                self.dap_start(config, breakpoints);
+                self.dap_start(
+                    config,
+                    breakpoints,
+                    global_breakpoints,
+                    debugger_paths,
+                );
             },
             DapProcessId {
                 dap_id,
@@ -776,11 +869,26 @@ impl PluginCatalog {
             DapRestart {
                 config,
                 breakpoints,
+                global_breakpoints,
+                debugger_paths,
             } => {
                 if let Some(dap) = self.daps.remove(&config.dap_id) {
                     dap.stop();
                 }
-                self.dap_start(config, breakpoints);
+                self.dap_start(
+                    config,
+                    breakpoints,
+                    global_breakpoints,
+                    debugger_paths,
+                );
+            },
+            DapSetGlobalBreakpoints {
+                dap_id,
+                breakpoints,
+            } => {
+                if let Some(dap) = self.daps.get(&dap_id) {
+                    dap.set_global_breakpoints(breakpoints);
+                }
             },
             DapSetBreakpoints {
                 dap_id,
@@ -816,23 +924,35 @@ impl PluginCatalog {
         &mut self,
         config: RunDebugConfig,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
+        debugger_paths: DebuggerPaths,
     ) {
         let workspace = self.workspace.clone();
         let plugin_rpc = self.plugin_rpc.clone();
-        if let Some(debugger) = config
-            .ty
-            .as_ref()
-            .and_then(|ty| self.debuggers.get(ty).cloned())
-        {
+        if let Some(debugger) = config.ty.as_ref().and_then(|ty| {
+            self.debuggers
+                .get(ty)
+                .cloned()
+                .or_else(|| builtin_debugger(ty, &debugger_paths))
+        }) {
             thread::spawn(move || {
+                let adapter = debugger.adapter;
+                let sysroot = if adapter == DapAdapter::Plugin {
+                    None
+                } else {
+                    rust_sysroot(workspace.as_deref())
+                };
                 match DapClient::start(
                     DapServer {
                         program: debugger.program,
-                        args:    debugger.args.unwrap_or_default(),
-                        cwd:     workspace,
+                        args: debugger.args.unwrap_or_default(),
+                        cwd: workspace,
+                        adapter,
+                        sysroot: sysroot.clone(),
                     },
                     config.clone(),
                     breakpoints,
+                    global_breakpoints,
                     plugin_rpc.clone(),
                 ) {
                     Ok(dap_rpc) => {
@@ -840,7 +960,9 @@ impl PluginCatalog {
                             log::error!("plugin_rpc.dap_loaded {:?}", err);
                         }
 
-                        if let Err(err) = dap_rpc.launch(&config) {
+                        if let Err(err) =
+                            dap_rpc.launch(&config, adapter, sysroot.as_deref())
+                        {
                             log::error!("dap_rpc.launch {:?}", err);
                         }
                     },
@@ -855,8 +977,8 @@ impl PluginCatalog {
                 "debug fail".to_owned(),
                 ShowMessageParams {
                     typ:     MessageType::ERROR,
-                    message: "Debugger not found. Please install the appropriate \
-                              plugin."
+                    message: "Debugger not found. Please install lldb-dap or gdb, \
+                              or the appropriate plugin."
                         .to_owned(),
                 },
             )
@@ -1,7 +1,7 @@
 use std::{
     collections::HashMap,
     io::{BufReader, BufWriter, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::{Child, Command, Stdio},
     sync::{
         Arc,
@@ -16,16 +16,20 @@ use jsonrpc_lite::Id;
 use lapce_rpc::{
     RpcError,
     dap_types::{
-        self, ConfigurationDone, Continue, ContinueArguments, DapEvent, DapId,
-        DapPayload, DapRequest, DapResponse, DapServer, DebuggerCapabilities,
-        Disconnect, Initialize, Launch, Next, NextArguments, Pause, PauseArguments,
-        Request, RunDebugConfig, RunInTerminal, RunInTerminalArguments,
-        RunInTerminalResponse, Scope, Scopes, ScopesArguments, ScopesResponse,
-        SetBreakpoints, SetBreakpointsArguments, SetBreakpointsResponse, Source,
-        SourceBreakpoint, StackTrace, StackTraceArguments, StackTraceResponse,
-        StepIn, StepInArguments, StepOut, StepOutArguments, Terminate, ThreadId,
-        Threads, ThreadsResponse, Variable, Variables, VariablesArguments,
-        VariablesResponse,
+        self, Attach, ConfigurationDone, Continue, ContinueArguments, DapAdapter,
+        DapEvent, DapId, DapPayload, DapRequest, DapResponse, DapServer,
+        DebugRequest, DebuggerCapabilities, Disconnect, Evaluate, EvaluateArguments,
+        EvaluateResponse, GlobalBreakpoints, Initialize, Launch, Next,
+        NextArguments, Pause, PauseArguments, Request, RunDebugConfig,
+        RunInTerminal, RunInTerminalArguments, RunInTerminalResponse, Scope, Scopes,
+        ScopesArguments, ScopesResponse, SetBreakpoints, SetBreakpointsArguments,
+        SetBreakpointsResponse, SetExceptionBreakpoints, SetExpression,
+        SetExpressionArguments, SetExpressionResponse, SetFunctionBreakpoints,
+        SetFunctionBreakpointsArguments, SetVariable, SetVariableArguments,
+        SetVariableResponse, Source, SourceBreakpoint, StackTrace,
+        StackTraceArguments, StackTraceResponse, StepIn, StepInArguments, StepOut,
+        StepOutArguments, Terminate, ThreadId, Threads, ThreadsResponse, Variable,
+        Variables, VariablesArguments, VariablesResponse,
     },
     terminal::TermId,
 };
@@ -35,6 +39,7 @@ use serde_json::Value;
 
 use super::{
     PluginCatalogRpcHandler,
+    debugger::{adapter_args, launch_arguments},
     psp::{ResponseHandler, RpcCallback},
 };
 
@@ -51,6 +56,7 @@ pub struct DapClient {
     dap_server:         DapServer,
     config:             RunDebugConfig,
     breakpoints:        HashMap<PathBuf, Vec<SourceBreakpoint>>,
+    global_breakpoints: GlobalBreakpoints,
     term_id:            Option<TermId>,
     capabilities:       Option<DebuggerCapabilities>,
     terminated:         bool,
@@ -64,6 +70,7 @@ impl DapClient {
         dap_server: DapServer,
         config: RunDebugConfig,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
         plugin_rpc: PluginCatalogRpcHandler,
     ) -> Result<Self> {
         let dap_rpc = DapRpcHandler::new(config.dap_id);
@@ -74,6 +81,7 @@ impl DapClient {
             config,
             dap_rpc,
             breakpoints,
+            global_breakpoints,
             term_id: None,
             capabilities: None,
             terminated: false,
@@ -87,9 +95,16 @@ impl DapClient {
         dap_server: DapServer,
         config: RunDebugConfig,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
         plugin_rpc: PluginCatalogRpcHandler,
     ) -> Result<DapRpcHandler> {
-        let mut dap = Self::new(dap_server, config, breakpoints, plugin_rpc)?;
+        let mut dap = Self::new(
+            dap_server,
+            config,
+            breakpoints,
+            global_breakpoints,
+            plugin_rpc,
+        )?;
         dap.start_process()?;
 
         let dap_rpc = dap.dap_rpc.clone();
@@ -107,11 +122,13 @@ impl DapClient {
 
     fn start_process(&self) -> Result<()> {
         let program = self.dap_server.program.clone();
-        let mut process = Self::process(
-            &program,
-            &self.dap_server.args,
-            self.dap_server.cwd.as_ref(),
-        )?;
+        let mut args = self.dap_server.args.clone();
+        args.extend(adapter_args(
+            self.dap_server.adapter,
+            self.dap_server.sysroot.as_deref(),
+        ));
+        let mut process =
+            Self::process(&program, &args, self.dap_server.cwd.as_ref())?;
         let stdin = process.stdin.take().unwrap();
         let stdout = process.stdout.take().unwrap();
         // let stderr = process.stderr.take().unwrap();
@@ -207,7 +224,7 @@ impl DapClient {
                     serde_json::from_value(value.clone())?;
                 let mut config = self.config.clone();
                 config.debug_command = Some(args.args);
-                self.plugin_rpc.core_rpc.dap_run_in_terminal(config);
+                self.plugin_rpc.core_rpc.dap_run_in_terminal(config, None);
                 let (term_id, shell_process_id) =
                     self.dap_rpc.termain_process_rx.recv()?;
                 self.term_id = Some(term_id);
@@ -249,6 +266,7 @@ impl DapClient {
                             }
                         },)
                 }
+                self.send_global_breakpoints();
                 // send dap configurations here
                 self.dap_rpc
                     .request_async::<ConfigurationDone>((), |_, rs| {
@@ -351,7 +369,19 @@ impl DapClient {
             DapEvent::Breakpoint { .. } => {},
             DapEvent::Module { .. } => {},
             DapEvent::LoadedSource { .. } => {},
-            DapEvent::Process(_) => {},
+            DapEvent::Process(process) => {
+                // an adapter which didn't run the program in a terminal, like
+                // gdb, still needs a terminal for the session, which waits
+                // for the process
+                if self.term_id.is_none()
+                    && self.config.request == DebugRequest::Launch
+                    && let Some(pid) = process.system_process_id
+                {
+                    self.plugin_rpc
+                        .core_rpc
+                        .dap_run_in_terminal(self.config.clone(), Some(pid as u32));
+                }
+            },
             DapEvent::Capabilities(_) => {},
             DapEvent::Memory(_) => {},
         }
@@ -379,19 +409,28 @@ impl DapClient {
             .dap_rpc
             .request::<Initialize>(params)
             .map_err(|e| anyhow!(e.message))?;
+        self.plugin_rpc
+            .core_rpc
+            .dap_capabilities(self.dap_rpc.dap_id, resp.clone());
         self.capabilities = Some(resp);
 
         Ok(())
     }
 
+    /// Whether the session is stopped with a terminate request rather than a
+    /// disconnect. Attached processes are never terminated, only detached from.
+    fn stops_by_terminate(&self) -> bool {
+        self.config.request == DebugRequest::Launch
+            && self
+                .capabilities
+                .as_ref()
+                .and_then(|c| c.supports_terminate_request)
+                .unwrap_or(false)
+    }
+
     fn _stop_by_other(&mut self) {
         let dap_rpc = self.dap_rpc.clone();
-        if self
-            .capabilities
-            .as_ref()
-            .and_then(|c| c.supports_terminate_request)
-            .unwrap_or(false)
-        {
+        if self.stops_by_terminate() {
             dap_rpc.terminate()
         } else {
             dap_rpc.disconnect();
@@ -405,13 +444,7 @@ impl DapClient {
         if !self.restarted {
             return Ok(());
         }
-        if !self
-            .capabilities
-            .as_ref()
-            .and_then(|c| c.supports_terminate_request)
-            .unwrap_or(false)
-            && !self.disconnected
-        {
+        if !self.stops_by_terminate() && !self.disconnected {
             return Ok(());
         }
 
@@ -426,8 +459,10 @@ impl DapClient {
 
         let dap_rpc = self.dap_rpc.clone();
         let config = self.config.clone();
+        let adapter = self.dap_server.adapter;
+        let sysroot = self.dap_server.sysroot.clone();
         thread::spawn(move || {
-            if let Err(err) = dap_rpc.launch(&config) {
+            if let Err(err) = dap_rpc.launch(&config, adapter, sysroot.as_deref()) {
                 error!("{:?}", err);
             }
         });
@@ -435,6 +470,50 @@ impl DapClient {
         Ok(())
     }
 
+    /// Send the exception filters and function breakpoints, as far as the
+    /// debugger supports them.
+    fn send_global_breakpoints(&self) {
+        let Some(capabilities) = self.capabilities.as_ref() else {
+            return;
+        };
+        if let Some(filters) = capabilities
+            .exception_breakpoint_filters
+            .as_ref()
+            .filter(|filters| !filters.is_empty())
+        {
+            let args = self.global_breakpoints.exception_breakpoints_arguments(
+                filters,
+                capabilities
+                    .supports_exception_filter_options
+                    .unwrap_or(false),
+            );
+            self.dap_rpc
+                .request_async::<SetExceptionBreakpoints>(args, |_, rs| {
+                    if let Err(e) = rs {
+                        error!("request SetExceptionBreakpoints: {:?}", e)
+                    }
+                });
+        }
+        if capabilities.supports_function_breakpoints.unwrap_or(false) {
+            let args = SetFunctionBreakpointsArguments {
+                breakpoints: self.global_breakpoints.functions.clone(),
+            };
+            self.dap_rpc
+                .request_async::<SetFunctionBreakpoints>(args, |_, rs| {
+                    if let Err(e) = rs {
+                        error!("request SetFunctionBreakpoints: {:?}", e)
+                    }
+                });
+        }
+    }
+
+    fn set_global_breakpoints(&mut self, breakpoints: GlobalBreakpoints) {
+        self.global_breakpoints = breakpoints;
+        if !self.terminated {
+            self.send_global_breakpoints();
+        }
+    }
+
     fn restart(&mut self, breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>) {
         self.restarted = true;
         self.breakpoints = breakpoints;
@@ -477,6 +556,7 @@ pub enum DapRpc {
     EventFromDap(DapEvent),
     TerminateByUser,
     Restart(HashMap<PathBuf, Vec<SourceBreakpoint>>),
+    SetGlobalBreakpoints(GlobalBreakpoints),
     // Shutdown,
     DisconnectedByError,
 }
@@ -486,6 +566,7 @@ pub struct DebuggerData {
     pub debugger_type: String,
     pub program:       String,
     pub args:          Option<Vec<String>>,
+    pub adapter:       DapAdapter,
 }
 
 #[derive(Clone)]
@@ -556,6 +637,9 @@ impl DapRpcHandler {
                 DapRpc::Restart(breakpoints) => {
                     dap_client.restart(breakpoints);
                 },
+                DapRpc::SetGlobalBreakpoints(breakpoints) => {
+                    dap_client.set_global_breakpoints(breakpoints);
+                },
             }
             if dap_client.terminated {
                 break;
@@ -684,17 +768,35 @@ impl DapRpcHandler {
         terminated
     }
 
-    pub fn launch(&self, config: &RunDebugConfig) -> Result<()> {
+    /// Start debugging the program of the config, or attach to its process
+    /// when the config is an attach request.
+    pub fn launch(
+        &self,
+        config: &RunDebugConfig,
+        adapter: DapAdapter,
+        sysroot: Option<&Path>,
+    ) -> Result<()> {
+        if config.request == DebugRequest::Attach {
+            return self.attach(config);
+        }
+        let params = launch_arguments(adapter, config, sysroot);
+        let _resp = self
+            .request::<Launch>(params)
+            .map_err(|e| anyhow!(e.message))?;
+        Ok(())
+    }
+
+    fn attach(&self, config: &RunDebugConfig) -> Result<()> {
+        // the app resolves a process name to its pid before starting
+        let Some(pid) = config.pid else {
+            return Err(anyhow!("no pid to attach to"));
+        };
         let params = serde_json::json!({
-            "program": config.program,
-            "args": config.args,
             "cwd": config.cwd,
-            "runInTerminal": true,
-            "env": config.env,
-            "terminal":"integrated"
+            "pid": pid,
         });
         let _resp = self
-            .request::<Launch>(params)
+            .request::<Attach>(params)
             .map_err(|e| anyhow!(e.message))?;
         Ok(())
     }
@@ -711,6 +813,13 @@ impl DapRpcHandler {
         }
     }
 
+    pub fn set_global_breakpoints(&self, breakpoints: GlobalBreakpoints) {
+        if let Err(err) = self.rpc_tx.send(DapRpc::SetGlobalBreakpoints(breakpoints))
+        {
+            error!("{:?}", err);
+        }
+    }
+
     fn disconnected(&self) {
         if let Err(err) = self.rpc_tx.send(DapRpc::DisconnectedByError) {
             error!("{:?}", err);
@@ -848,6 +957,57 @@ impl DapRpcHandler {
         self.request_async::<Variables>(args, f);
     }
 
+    pub fn evaluate_async(
+        &self,
+        expression: String,
+        frame_id: Option<usize>,
+        context: String,
+        f: impl RpcCallback<EvaluateResponse, RpcError> + 'static,
+    ) {
+        let args = EvaluateArguments {
+            expression,
+            frame_id,
+            context: Some(context),
+            format: None,
+        };
+
+        self.request_async::<Evaluate>(args, f);
+    }
+
+    pub fn set_variable_async(
+        &self,
+        variables_reference: usize,
+        name: String,
+        value: String,
+        f: impl RpcCallback<SetVariableResponse, RpcError> + 'static,
+    ) {
+        let args = SetVariableArguments {
+            variables_reference,
+            name,
+            value,
+            format: None,
+        };
+
+        self.request_async::<SetVariable>(args, f);
+    }
+
+    pub fn set_expression_async(
+        &self,
+        expression: String,
+        value: String,
+        frame_id: Option<usize>,
+        f: impl RpcCallback<SetExpressionResponse, RpcError> + 'static,
+    ) {
+        let args = SetExpressionArguments {
+            expression,
+            value,
+            frame_id,
+            format: None,
+        };
+
+        self.request_async::<SetExpression>(args, f);
+    }
+
     pub fn next(&self, thread_id: ThreadId) {
         let args = NextArguments {
             thread_id,
@@ -0,0 +1,218 @@
+//! The debuggers which are available without a plugin: `lldb-dap` and gdb's
+//! DAP mode.
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use lapce_rpc::dap_types::{DapAdapter, DebuggerPaths, RunDebugConfig};
+use serde_json::{Value, json};
+
+use super::dap::DebuggerData;
+
+/// Get the built-in debugger for the debugger type of a run config. `lldb` is
+/// served by `lldb-dap` when no plugin has registered it.
+pub fn builtin_debugger(
+    debugger_type: &str,
+    paths: &DebuggerPaths,
+) -> Option<DebuggerData> {
+    let (adapter, program, args) = match debugger_type {
+        "lldb-dap" | "lldb" => (
+            DapAdapter::LldbDap,
+            find_program(&paths.lldb_dap, &["lldb-dap", "lldb-vscode"])?,
+            None,
+        ),
+        "gdb" => (
+            DapAdapter::Gdb,
+            find_program(&paths.gdb, &["gdb"])?,
+            Some(vec!["--interpreter=dap".to_string()]),
+        ),
+        _ => return None,
+    };
+    Some(DebuggerData {
+        debugger_type: debugger_type.to_string(),
+        program,
+        args,
+        adapter,
+    })
+}
+
+/// The configured program, or the first of `names` found on `PATH`.
+fn find_program(configured: &str, names: &[&str]) -> Option<String> {
+    if !configured.is_empty() {
+        return Some(configured.to_string());
+    }
+    let path = env::var_os("PATH")?;
+    names.iter().find_map(|name| {
+        env::split_paths(&path)
+            .map(|dir| dir.join(name))
+            .find(|program| program.is_file())
+            .map(|program| program.to_string_lossy().into_owned())
+    })
+}
+
+/// The sysroot of the Rust toolchain used in `cwd`, which has the pretty
+/// printers of the standard library types.
+pub fn rust_sysroot(cwd: Option<&Path>) -> Option<PathBuf> {
+    let mut command = Command::new("rustc");
+    command.args(["--print", "sysroot"]);
+    if let Some(cwd) = cwd {
+        command.current_dir(cwd);
+    }
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let sysroot = String::from_utf8(output.stdout).ok()?;
+    let etc = Path::new(sysroot.trim()).join("lib/rustlib/etc");
+    etc.is_dir().then(|| PathBuf::from(sysroot.trim()))
+}
+
+/// The extra arguments of the adapter process, which load the Rust pretty
+/// printers into gdb the way `rust-gdb` does.
+pub fn adapter_args(adapter: DapAdapter, sysroot: Option<&Path>) -> Vec<String> {
+    match (adapter, sysroot) {
+        (DapAdapter::Gdb, Some(sysroot)) => {
+            let etc = sysroot.join("lib/rustlib/etc");
+            let etc = etc.to_string_lossy();
+            vec![
+                "-iex".to_string(),
+                format!("python import sys; sys.path.insert(0, '{etc}')"),
+                "-iex".to_string(),
+                format!("add-auto-load-safe-path {etc}"),
+                format!("--directory={etc}"),
+            ]
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// The arguments of the launch request, as each adapter names them.
+pub fn launch_arguments(
+    adapter: DapAdapter,
+    config: &RunDebugConfig,
+    sysroot: Option<&Path>,
+) -> Value {
+    match adapter {
+        DapAdapter::Plugin => json!({
+            "program": config.program,
+            "args": config.args,
+            "cwd": config.cwd,
+            "runInTerminal": true,
+            "env": config.env,
+            "terminal":"integrated"
+        }),
+        DapAdapter::LldbDap => {
+            // lldb-dap only takes the environment as "KEY=VALUE" strings
+            let env: Vec<String> = config
+                .env
+                .iter()
+                .flatten()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect();
+            let init_commands: Vec<String> = sysroot
+                .map(|sysroot| {
+                    let etc = sysroot.join("lib/rustlib/etc");
+                    vec![
+                        format!(
+                            "command script import \"{}\"",
+                            etc.join("lldb_lookup.py").to_string_lossy()
+                        ),
+                        format!(
+                            "command source -s 0 \"{}\"",
+                            etc.join("lldb_commands").to_string_lossy()
+                        ),
+                    ]
+                })
+                .unwrap_or_default();
+            json!({
+                "program": config.program,
+                "args": config.args.clone().unwrap_or_default(),
+                "cwd": config.cwd,
+                "env": env,
+                "stopOnEntry": config.stop_on_entry,
+                "runInTerminal": true,
+                "initCommands": init_commands,
+            })
+        },
+        // gdb can't run the program in a terminal, the pid comes with the
+        // process event instead
+        DapAdapter::Gdb => json!({
+            "program": config.program,
+            "args": config.args.clone().unwrap_or_default(),
+            "cwd": config.cwd,
+            "env": config.env.clone().unwrap_or_default(),
+            "stopAtBeginningOfMainSubprogram": config.stop_on_entry,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, path::Path};
+
+    use lapce_rpc::dap_types::{DapAdapter, RunDebugConfig};
+    use serde_json::json;
+
+    use super::{adapter_args, launch_arguments};
+
+    fn config() -> RunDebugConfig {
+        let mut config: RunDebugConfig = toml::from_str(
+            r#"
+            name = "check"
+            program = "/src/target/debug/check"
+            args = ["--all"]
+            cwd = "/src"
+            stop-on-entry = true
+            "#,
+        )
+        .unwrap();
+        config.env = Some(HashMap::from([(
+            "RUST_LOG".to_string(),
+            "info".to_string(),
+        )]));
+        config
+    }
+
+    #[test]
+    fn test_lldb_dap_launch_arguments() {
+        let args = launch_arguments(
+            DapAdapter::LldbDap,
+            &config(),
+            Some(Path::new("/rust")),
+        );
+        assert_eq!(args["program"], json!("/src/target/debug/check"));
+        assert_eq!(args["args"], json!(["--all"]));
+        assert_eq!(args["cwd"], json!("/src"));
+        assert_eq!(args["env"], json!(["RUST_LOG=info"]));
+        assert_eq!(args["stopOnEntry"], json!(true));
+        assert_eq!(
+            args["initCommands"],
+            json!([
+                "command script import \"/rust/lib/rustlib/etc/lldb_lookup.py\"",
+                "command source -s 0 \"/rust/lib/rustlib/etc/lldb_commands\""
+            ])
+        );
+    }
+
+    #[test]
+    fn test_gdb_launch_arguments() {
+        let args = launch_arguments(DapAdapter::Gdb, &config(), None);
+        assert_eq!(args["program"], json!("/src/target/debug/check"));
+        assert_eq!(args["args"], json!(["--all"]));
+        assert_eq!(args["env"], json!({ "RUST_LOG": "info" }));
+        assert_eq!(args["stopAtBeginningOfMainSubprogram"], json!(true));
+        assert_eq!(args.get("runInTerminal"), None);
+
+        assert!(adapter_args(DapAdapter::Gdb, None).is_empty());
+        assert_eq!(
+            adapter_args(DapAdapter::Gdb, Some(Path::new("/rust"))).last(),
+            Some(&"--directory=/rust/lib/rustlib/etc".to_string())
+        );
+        assert!(
+            adapter_args(DapAdapter::LldbDap, Some(Path::new("/rust"))).is_empty()
+        );
+    }
+}
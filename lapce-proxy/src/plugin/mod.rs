@@ -1,5 +1,6 @@
 pub mod catalog;
 pub mod dap;
+pub mod debugger;
 pub mod lsp;
 pub mod psp;
 pub mod wasi;
@@ -23,7 +24,10 @@ use jsonrpc_lite::Id;
 use lapce_rpc::{
     RequestId, RpcError, SnippetTextEdit,
     core::CoreRpcHandler,
-    dap_types::{self, DapId, RunDebugConfig, SourceBreakpoint, ThreadId},
+    dap_types::{
+        self, DapId, DebuggerPaths, GlobalBreakpoints, RunDebugConfig,
+        SourceBreakpoint, ThreadId,
+    },
     plugin::{PluginId, VoltInfo, VoltMetadata},
     proxy::ProxyRpcHandler,
     style::LineStyle,
@@ -125,6 +129,27 @@ pub enum PluginCatalogRpc {
         reference: usize,
         f:         Box<dyn RpcCallback<Vec<dap_types::Variable>, RpcError>>,
     },
+    DapEvaluate {
+        dap_id:     DapId,
+        expression: String,
+        frame_id:   Option<usize>,
+        context:    String,
+        f:          Box<dyn RpcCallback<dap_types::EvaluateResponse, RpcError>>,
+    },
+    DapSetVariable {
+        dap_id:    DapId,
+        reference: usize,
+        name:      String,
+        value:     String,
+        f:         Box<dyn RpcCallback<dap_types::SetVariableResponse, RpcError>>,
+    },
+    DapSetExpression {
+        dap_id:     DapId,
+        expression: String,
+        value:      String,
+        frame_id:   Option<usize>,
+        f:          Box<dyn RpcCallback<dap_types::SetExpressionResponse, RpcError>>,
+    },
     DapGetScopes {
         dap_id:   DapId,
         frame_id: usize,
@@ -165,8 +190,10 @@ pub enum PluginCatalogRpc {
 pub enum DapNotificationOfUser {
     DapDisconnected(DapId),
     DapStart {
-        config:      RunDebugConfig,
-        breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        config:             RunDebugConfig,
+        breakpoints:        HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
+        debugger_paths:     DebuggerPaths,
     },
     DapProcessId {
         dap_id:     DapId,
@@ -200,14 +227,20 @@ pub enum DapNotificationOfUser {
         dap_id: DapId,
     },
     DapRestart {
-        config:      RunDebugConfig,
-        breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        config:             RunDebugConfig,
+        breakpoints:        HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
+        debugger_paths:     DebuggerPaths,
     },
     DapSetBreakpoints {
         dap_id:      DapId,
         path:        PathBuf,
         breakpoints: Vec<SourceBreakpoint>,
     },
+    DapSetGlobalBreakpoints {
+        dap_id:      DapId,
+        breakpoints: GlobalBreakpoints,
+    },
 }
 
 impl From<DapNotificationOfUser> for PluginCatalogNotification {
@@ -380,6 +413,34 @@ impl PluginCatalogRpcHandler {
                 } => {
                     plugin.dap_variable(dap_id, reference, f);
                 },
+                PluginCatalogRpc::DapEvaluate {
+                    dap_id,
+                    expression,
+                    frame_id,
+                    context,
+                    f,
+                } => {
+                    plugin.dap_evaluate(dap_id, expression, frame_id, context, f);
+                },
+                PluginCatalogRpc::DapSetVariable {
+                    dap_id,
+                    reference,
+                    name,
+                    value,
+                    f,
+                } => {
+                    plugin.dap_set_variable(dap_id, reference, name, value, f);
+                },
+                PluginCatalogRpc::DapSetExpression {
+                    dap_id,
+                    expression,
+                    value,
+                    frame_id,
+                    f,
+                } => {
+                    plugin
+                        .dap_set_expression(dap_id, expression, value, frame_id, f);
+                },
                 PluginCatalogRpc::DapGetScopes {
                     dap_id,
                     frame_id,
@@ -888,8 +949,9 @@ impl PluginCatalogRpcHandler {
     pub fn get_code_actions(
         &self,
         path: &Path,
-        position: Position,
+        range: Range,
         diagnostics: Vec<Diagnostic>,
+        only: Option<Vec<CodeActionKind>>,
         cb: impl FnOnce(PluginId, Result<CodeActionResponse, RpcError>)
         + Clone
         + Send
@@ -899,18 +961,15 @@ impl PluginCatalogRpcHandler {
         let uri = Url::from_file_path(path).unwrap();
         let method = CodeActionRequest::METHOD;
         let params = CodeActionParams {
-            text_document:             TextDocumentIdentifier { uri },
-            range:                     Range {
-                start: position,
-                end:   position,
-            },
-            context:                   CodeActionContext {
+            text_document: TextDocumentIdentifier { uri },
+            range,
+            context: CodeActionContext {
                 diagnostics,
-                only: None,
+                only,
                 trigger_kind: None,
             },
             work_done_progress_params: WorkDoneProgressParams::default(),
-            partial_result_params:     PartialResultParams::default(),
+            partial_result_params: PartialResultParams::default(),
         };
         let language_id =
             Some(language_id_from_path(path).unwrap_or("").to_string());
@@ -1623,11 +1682,15 @@ impl PluginCatalogRpcHandler {
         &self,
         config: RunDebugConfig,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
+        debugger_paths: DebuggerPaths,
     ) -> Result<()> {
         self.catalog_notification(
             DapNotificationOfUser::DapStart {
                 config,
                 breakpoints,
+                global_breakpoints,
+                debugger_paths,
             }
             .into(),
         )
@@ -1695,11 +1758,15 @@ impl PluginCatalogRpcHandler {
         &self,
         config: RunDebugConfig,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
+        debugger_paths: DebuggerPaths,
     ) -> Result<()> {
         self.catalog_notification(
             DapNotificationOfUser::DapRestart {
                 config,
                 breakpoints,
+                global_breakpoints,
+                debugger_paths,
             }
             .into(),
         )
@@ -1725,6 +1792,20 @@ impl PluginCatalogRpcHandler {
         )
     }
 
+    pub fn dap_set_global_breakpoints(
+        &self,
+        dap_id: DapId,
+        breakpoints: GlobalBreakpoints,
+    ) -> Result<()> {
+        self.catalog_notification(
+            DapNotificationOfUser::DapSetGlobalBreakpoints {
+                dap_id,
+                breakpoints,
+            }
+            .into(),
+        )
+    }
+
     pub fn dap_variable(
         &self,
         dap_id: DapId,
@@ -1740,6 +1821,69 @@ impl PluginCatalogRpcHandler {
         }
     }
 
+    pub fn dap_evaluate(
+        &self,
+        dap_id: DapId,
+        expression: String,
+        frame_id: Option<usize>,
+        context: String,
+        f: impl FnOnce(Id, Result<dap_types::EvaluateResponse, RpcError>)
+        + Send
+        + 'static,
+    ) {
+        if let Err(err) = self.plugin_tx.send(PluginCatalogRpc::DapEvaluate {
+            dap_id,
+            expression,
+            frame_id,
+            context,
+            f: Box::new(f),
+        }) {
+            log::error!("{:?}", err);
+        }
+    }
+
+    pub fn dap_set_variable(
+        &self,
+        dap_id: DapId,
+        reference: usize,
+        name: String,
+        value: String,
+        f: impl FnOnce(Id, Result<dap_types::SetVariableResponse, RpcError>)
+        + Send
+        + 'static,
+    ) {
+        if let Err(err) = self.plugin_tx.send(PluginCatalogRpc::DapSetVariable {
+            dap_id,
+            reference,
+            name,
+            value,
+            f: Box::new(f),
+        }) {
+            log::error!("{:?}", err);
+        }
+    }
+
+    pub fn dap_set_expression(
+        &self,
+        dap_id: DapId,
+        expression: String,
+        value: String,
+        frame_id: Option<usize>,
+        f: impl FnOnce(Id, Result<dap_types::SetExpressionResponse, RpcError>)
+        + Send
+        + 'static,
+    ) {
+        if let Err(err) = self.plugin_tx.send(PluginCatalogRpc::DapSetExpression {
+            dap_id,
+            expression,
+            value,
+            frame_id,
+            f: Box::new(f),
+        }) {
+            log::error!("{:?}", err);
+        }
+    }
+
     pub fn dap_get_scopes(
         &self,
         dap_id: DapId,
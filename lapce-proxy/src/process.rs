@@ -0,0 +1,90 @@
+use lapce_rpc::dap_types::ProcessInfo;
+
+/// List the processes running on this machine which a debugger could attach
+/// to, sorted by pid.
+#[cfg(target_os = "linux")]
+pub fn list_processes() -> Vec<ProcessInfo> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let own_pid = std::process::id();
+    let mut processes: Vec<ProcessInfo> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse::<u32>().ok()?;
+            if pid == own_pid {
+                return None;
+            }
+            let path = entry.path();
+            let name = std::fs::read_to_string(path.join("comm")).ok()?;
+            let name = name.trim_end().to_string();
+            let cmdline = std::fs::read(path.join("cmdline")).unwrap_or_default();
+            let command_line = parse_cmdline(&cmdline);
+            // kernel threads have no command line
+            if command_line.is_empty() {
+                return None;
+            }
+            let start_time = std::fs::read_to_string(path.join("stat"))
+                .ok()
+                .and_then(|stat| parse_start_time(&stat))
+                .unwrap_or_default();
+            Some(ProcessInfo {
+                pid,
+                name,
+                command_line,
+                start_time,
+            })
+        })
+        .collect();
+    processes.sort_by_key(|p| p.pid);
+    processes
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn list_processes() -> Vec<ProcessInfo> {
+    Vec::new()
+}
+
+/// Turn the NUL separated arguments of `/proc/<pid>/cmdline` into a single
+/// space separated command line.
+#[cfg(any(target_os = "linux", test))]
+fn parse_cmdline(cmdline: &[u8]) -> String {
+    cmdline
+        .split(|b| *b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(String::from_utf8_lossy)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The start time of `/proc/<pid>/stat`, its 22nd field. The name in the
+/// second field is in parentheses and can hold spaces and parentheses.
+#[cfg(any(target_os = "linux", test))]
+fn parse_start_time(stat: &str) -> Option<u64> {
+    let (_, fields) = stat.rsplit_once(')')?;
+    // the fields after the name start with the 3rd one
+    fields.split_whitespace().nth(22 - 3)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_cmdline, parse_start_time};
+
+    #[test]
+    fn test_parse_cmdline() {
+        assert_eq!(parse_cmdline(b""), "");
+        assert_eq!(
+            parse_cmdline(b"/usr/bin/cargo\0run\0--release\0"),
+            "/usr/bin/cargo run --release"
+        );
+        assert_eq!(parse_cmdline(b"server"), "server");
+    }
+
+    #[test]
+    fn test_parse_start_time() {
+        let stat = "4242 (tokio (w) 1) S 1 4242 4242 0 -1 4194560 2417 0 0 0 3 1 0 \
+                    0 20 0 1 0 987654 12345678 900";
+        assert_eq!(parse_start_time(stat), Some(987654));
+        assert_eq!(parse_start_time("4242 (server) S 1"), None);
+    }
+}
@@ -19,7 +19,8 @@ use serde::{Deserialize, Serialize};
 use crate::{
     RequestId, RpcError, RpcMessage,
     dap_types::{
-        self, DapId, RunDebugConfig, Scope, StackFrame, Stopped, ThreadId, Variable,
+        self, DapId, DebuggerCapabilities, RunDebugConfig, Scope, StackFrame,
+        Stopped, ThreadId, Variable,
     },
     file::PathObject,
     plugin::{PluginId, VoltInfo, VoltMetadata},
@@ -140,6 +141,9 @@ pub enum CoreNotification {
     },
     DapRunInTerminal {
         config: RunDebugConfig,
+        /// The process launched by a debugger which can't run the program
+        /// in a terminal, for the terminal to wait for
+        pid:    Option<u32>,
     },
     Log {
         level:   LogLevel,
@@ -155,6 +159,10 @@ pub enum CoreNotification {
     DapContinued {
         dap_id: DapId,
     },
+    DapCapabilities {
+        dap_id:       DapId,
+        capabilities: DebuggerCapabilities,
+    },
     DapBreakpointsResp {
         dap_id:      DapId,
         path:        PathBuf,
@@ -162,13 +170,29 @@ pub enum CoreNotification {
     },
 }
 
+/// The code of the error answering a request the user dismissed.
+pub const REQUEST_DISMISSED: i64 = 1;
+
+/// Proxy asks core(lapce) and waits for the answer
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum CoreRequest {}
+#[serde(rename_all = "snake_case")]
+#[serde(tag = "method", content = "params")]
+pub enum CoreRequest {
+    /// Ask the user for a username, password or passphrase a git remote
+    /// needs
+    GitCredential {
+        prompt: String,
+        /// Whether the answer must not be shown while it is typed
+        secret: bool,
+    },
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "method", content = "params")]
-pub enum CoreResponse {}
+pub enum CoreResponse {
+    GitCredentialResponse { answer: String },
+}
 
 pub type CoreMessage = RpcMessage<CoreRequest, CoreNotification, CoreResponse>;
 
@@ -250,6 +274,18 @@ impl CoreRpcHandler {
         })
     }
 
+    /// Ask the user for a credential of a git remote, blocking until they
+    /// answered.
+    pub fn git_credential(
+        &self,
+        prompt: String,
+        secret: bool,
+    ) -> Result<String, RpcError> {
+        match self.request(CoreRequest::GitCredential { prompt, secret })? {
+            CoreResponse::GitCredentialResponse { answer } => Ok(answer),
+        }
+    }
+
     pub fn shutdown(&self) {
         if let Err(err) = self.tx.send(CoreRpc::Shutdown) {
             log::error!("{:?}", err);
@@ -322,8 +358,8 @@ impl CoreRpcHandler {
         });
     }
 
-    pub fn dap_run_in_terminal(&self, config: RunDebugConfig) {
-        self.notification(CoreNotification::DapRunInTerminal { config });
+    pub fn dap_run_in_terminal(&self, config: RunDebugConfig, pid: Option<u32>) {
+        self.notification(CoreNotification::DapRunInTerminal { config, pid });
     }
 
     pub fn log(&self, level: LogLevel, message: String, target: Option<String>) {
@@ -431,6 +467,17 @@ impl CoreRpcHandler {
         self.notification(CoreNotification::DapContinued { dap_id });
     }
 
+    pub fn dap_capabilities(
+        &self,
+        dap_id: DapId,
+        capabilities: DebuggerCapabilities,
+    ) {
+        self.notification(CoreNotification::DapCapabilities {
+            dap_id,
+            capabilities,
+        });
+    }
+
     pub fn dap_breakpoints_resp(
         &self,
         dap_id: DapId,
@@ -33,6 +33,31 @@ pub struct DapServer {
     pub program: String,
     pub args:    Vec<String>,
     pub cwd:     Option<PathBuf>,
+    pub adapter: DapAdapter,
+    /// The sysroot of the Rust toolchain, whose pretty printers the built-in
+    /// adapters load
+    pub sysroot: Option<PathBuf>,
+}
+
+/// The kind of debug adapter a [`DapServer`] runs, which decides how a
+/// [`RunDebugConfig`] is turned into launch arguments
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DapAdapter {
+    /// An adapter registered by a plugin, e.g. CodeLLDB
+    #[default]
+    Plugin,
+    /// `lldb-dap`, formerly `lldb-vscode`, which ships with LLVM
+    LldbDap,
+    /// `gdb --interpreter=dap`, available since gdb 14
+    Gdb,
+}
+
+/// The configured paths of the built-in debuggers. An empty path means the
+/// debugger is looked up on `PATH`.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DebuggerPaths {
+    pub lldb_dap: String,
+    pub gdb:      String,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
@@ -60,10 +85,17 @@ pub struct RunDebugConfig {
     #[serde(rename = "type")]
     pub ty:             Option<String>,
     pub name:           String,
+    #[serde(default)]
+    pub request:        DebugRequest,
+    /// The program to launch, unused when attaching
+    #[serde(default)]
     pub program:        String,
     pub args:           Option<Vec<String>>,
     pub cwd:            Option<String>,
     pub env:            Option<HashMap<String, String>>,
+    /// Stop at the entry of the program when launching it
+    #[serde(default)]
+    pub stop_on_entry:  bool,
     pub prelaunch:      Option<RunDebugProgram>,
     #[serde(skip)]
     pub debug_command:  Option<Vec<String>>,
@@ -73,6 +105,10 @@ pub struct RunDebugConfig {
     pub tracing_output: bool,
     #[serde(default)]
     pub config_source:  ConfigSource,
+    /// The process to attach to
+    pub pid:            Option<u32>,
+    /// The name of the process to attach to, when there is no `pid`
+    pub process_name:   Option<String>,
 }
 
 impl RunDebugConfig {
@@ -99,6 +135,35 @@ impl RunDebugConfig {
             })
         });
     }
+
+    /// The process an attach config is for, as shown to the user
+    pub fn attach_target(&self) -> String {
+        match (self.pid, self.process_name.as_ref()) {
+            (Some(pid), _) => format!("pid {pid}"),
+            (None, Some(process_name)) => process_name.clone(),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// Whether a debug session starts its program or attaches to a running
+/// process
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DebugRequest {
+    #[default]
+    Launch,
+    Attach,
+}
+
+/// A local process which can be attached to
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProcessInfo {
+    pub pid:          u32,
+    pub name:         String,
+    pub command_line: String,
+    /// When the process started, in clock ticks since boot
+    pub start_time:   u64,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
@@ -110,6 +175,7 @@ pub enum ConfigSource {
     RustCodeLensRestart {
         program: String,
     },
+    ProcessPicker,
 }
 impl ConfigSource {
     pub fn update_program(&mut self, program: &str) {
@@ -566,6 +632,15 @@ impl Request for Launch {
     const COMMAND: &'static str = "launch";
 }
 
+pub enum Attach {}
+
+impl Request for Attach {
+    type Arguments = Value;
+    type Result = Value;
+
+    const COMMAND: &'static str = "attach";
+}
+
 #[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RunInTerminalResponse {
@@ -627,6 +702,159 @@ impl Request for SetBreakpoints {
     const COMMAND: &'static str = "setBreakpoints";
 }
 
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExceptionFilterOptions {
+    pub filter_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetExceptionBreakpointsArguments {
+    pub filters:        Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter_options: Option<Vec<ExceptionFilterOptions>>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetExceptionBreakpointsResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub breakpoints: Option<Vec<Breakpoint>>,
+}
+
+#[derive(Debug)]
+pub enum SetExceptionBreakpoints {}
+
+impl Request for SetExceptionBreakpoints {
+    type Arguments = SetExceptionBreakpointsArguments;
+    type Result = Option<SetExceptionBreakpointsResponse>;
+
+    const COMMAND: &'static str = "setExceptionBreakpoints";
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionBreakpoint {
+    pub name:          String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition:     Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hit_condition: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetFunctionBreakpointsArguments {
+    pub breakpoints: Vec<FunctionBreakpoint>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetFunctionBreakpointsResponse {
+    #[serde(default)]
+    pub breakpoints: Vec<Breakpoint>,
+}
+
+#[derive(Debug)]
+pub enum SetFunctionBreakpoints {}
+
+impl Request for SetFunctionBreakpoints {
+    type Arguments = SetFunctionBreakpointsArguments;
+    type Result = SetFunctionBreakpointsResponse;
+
+    const COMMAND: &'static str = "setFunctionBreakpoints";
+}
+
+/// An exception filter of a debugger as it was set up in a workspace
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+pub struct ExceptionFilterSetting {
+    pub enabled:   bool,
+    #[serde(default)]
+    pub condition: Option<String>,
+}
+
+/// The breakpoints of a workspace which aren't in a source file, sent to every
+/// debugger once it is initialized
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+pub struct GlobalBreakpoints {
+    /// By filter id, the filters not in it using the debugger's default
+    #[serde(default)]
+    pub exception_filters: HashMap<String, ExceptionFilterSetting>,
+    #[serde(default)]
+    pub functions:         Vec<FunctionBreakpoint>,
+}
+
+impl GlobalBreakpoints {
+    pub fn exception_filter_enabled(
+        &self,
+        filter: &ExceptionBreakpointsFilter,
+    ) -> bool {
+        self.exception_filters
+            .get(&filter.filter)
+            .map(|setting| setting.enabled)
+            .unwrap_or_else(|| filter.default.unwrap_or(false))
+    }
+
+    /// The setting of `filter`, added with its default when it isn't set yet.
+    pub fn exception_filter_mut(
+        &mut self,
+        filter: &ExceptionBreakpointsFilter,
+    ) -> &mut ExceptionFilterSetting {
+        self.exception_filters
+            .entry(filter.filter.clone())
+            .or_insert_with(|| ExceptionFilterSetting {
+                enabled:   filter.default.unwrap_or(false),
+                condition: None,
+            })
+    }
+
+    pub fn exception_filter_condition(&self, filter_id: &str) -> Option<&str> {
+        self.exception_filters
+            .get(filter_id)
+            .and_then(|setting| setting.condition.as_deref())
+    }
+
+    /// The `setExceptionBreakpoints` arguments for the `filters` of a
+    /// debugger, conditions only given when it supports `filter_options`.
+    pub fn exception_breakpoints_arguments(
+        &self,
+        filters: &[ExceptionBreakpointsFilter],
+        filter_options: bool,
+    ) -> SetExceptionBreakpointsArguments {
+        let enabled = filters
+            .iter()
+            .filter(|filter| self.exception_filter_enabled(filter));
+        if filter_options {
+            SetExceptionBreakpointsArguments {
+                filters:        Vec::new(),
+                filter_options: Some(
+                    enabled
+                        .map(|filter| ExceptionFilterOptions {
+                            filter_id: filter.filter.clone(),
+                            condition: self
+                                .exception_filter_condition(&filter.filter)
+                                .filter(|_| {
+                                    filter.supports_condition.unwrap_or(false)
+                                })
+                                .map(|c| c.to_string()),
+                        })
+                        .collect(),
+                ),
+            }
+        } else {
+            SetExceptionBreakpointsArguments {
+                filters:        enabled
+                    .map(|filter| filter.filter.clone())
+                    .collect(),
+                filter_options: None,
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum ConfigurationDone {}
 
@@ -898,6 +1126,117 @@ impl Request for Variables {
     const COMMAND: &'static str = "variables";
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluateArguments {
+    pub expression: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_id:   Option<usize>,
+    /// `watch`, `repl`, `hover` or `clipboard`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context:    Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format:     Option<ValueFormat>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluateResponse {
+    pub result:              String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub ty:                  Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presentation_hint:   Option<VariablePresentationHint>,
+    pub variables_reference: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub named_variables:     Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indexed_variables:   Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_reference:    Option<String>,
+}
+
+#[derive(Debug)]
+pub enum Evaluate {}
+
+impl Request for Evaluate {
+    type Arguments = EvaluateArguments;
+    type Result = EvaluateResponse;
+
+    const COMMAND: &'static str = "evaluate";
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetVariableArguments {
+    pub variables_reference: usize,
+    pub name:                String,
+    pub value:               String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format:              Option<ValueFormat>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetVariableResponse {
+    pub value:               String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub ty:                  Option<String>,
+    #[serde(default)]
+    pub variables_reference: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub named_variables:     Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indexed_variables:   Option<usize>,
+}
+
+#[derive(Debug)]
+pub enum SetVariable {}
+
+impl Request for SetVariable {
+    type Arguments = SetVariableArguments;
+    type Result = SetVariableResponse;
+
+    const COMMAND: &'static str = "setVariable";
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetExpressionArguments {
+    pub expression: String,
+    pub value:      String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_id:   Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format:     Option<ValueFormat>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetExpressionResponse {
+    pub value:               String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub ty:                  Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presentation_hint:   Option<VariablePresentationHint>,
+    #[serde(default)]
+    pub variables_reference: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub named_variables:     Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indexed_variables:   Option<usize>,
+}
+
+#[derive(Debug)]
+pub enum SetExpression {}
+
+impl Request for SetExpression {
+    type Arguments = SetExpressionArguments;
+    type Result = SetExpressionResponse;
+
+    const COMMAND: &'static str = "setExpression";
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct NextArguments {
@@ -953,3 +1292,59 @@ impl Request for StepOut {
 
     const COMMAND: &'static str = "stepOut";
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ExceptionBreakpointsFilter, ExceptionFilterOptions, ExceptionFilterSetting,
+        GlobalBreakpoints,
+    };
+
+    fn filter(id: &str, default: bool) -> ExceptionBreakpointsFilter {
+        ExceptionBreakpointsFilter {
+            filter:                id.to_string(),
+            label:                 id.to_string(),
+            description:           None,
+            default:               Some(default),
+            supports_condition:    Some(true),
+            condition_description: None,
+        }
+    }
+
+    #[test]
+    fn test_exception_breakpoints_arguments() {
+        let filters = [filter("panic", true), filter("cpp_throw", false)];
+        let mut breakpoints = GlobalBreakpoints::default();
+
+        let args = breakpoints.exception_breakpoints_arguments(&filters, false);
+        assert_eq!(args.filters, vec!["panic".to_string()]);
+
+        breakpoints.exception_filters.insert(
+            "panic".to_string(),
+            ExceptionFilterSetting {
+                enabled:   false,
+                condition: None,
+            },
+        );
+        breakpoints.exception_filters.insert(
+            "cpp_throw".to_string(),
+            ExceptionFilterSetting {
+                enabled:   true,
+                condition: Some("x > 1".to_string()),
+            },
+        );
+        let args = breakpoints.exception_breakpoints_arguments(&filters, false);
+        assert_eq!(args.filters, vec!["cpp_throw".to_string()]);
+        assert_eq!(args.filter_options, None);
+
+        let args = breakpoints.exception_breakpoints_arguments(&filters, true);
+        assert!(args.filters.is_empty());
+        assert_eq!(
+            args.filter_options,
+            Some(vec![ExceptionFilterOptions {
+                filter_id: "cpp_throw".to_string(),
+                condition: Some("x > 1".to_string()),
+            }])
+        );
+    }
+}
@@ -235,6 +235,7 @@ impl Naming {
                 is_root: false,
                 open:    false,
                 level:   level + 1,
+                ignored: false,
             }),
             Naming::Duplicating(d) if d.path == path => Some(FileNodeViewData {
                 kind: FileNodeViewKind::Duplicating {
@@ -245,6 +246,7 @@ impl Naming {
                 is_root: false,
                 open: false,
                 level: level + 1,
+                ignored: false,
             }),
             _ => None,
         }
@@ -258,6 +260,8 @@ pub struct FileNodeViewData {
     pub is_root: bool,
     pub open:    bool,
     pub level:   usize,
+    /// Whether the path is ignored by git
+    pub ignored: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -273,6 +277,9 @@ pub struct FileNodeItem {
     /// The number of child (directories) that are open themselves  
     /// Used for sizing of the explorer list
     pub children_open_count: usize,
+    /// Whether the path is ignored by git
+    #[serde(default)]
+    pub ignored:             bool,
 }
 
 impl PartialOrd for FileNodeItem {
@@ -338,6 +345,7 @@ impl FileNodeItem {
     /// #    open: false,
     /// #    children: HashMap::new(),
     /// #    children_open_count: 0,
+    /// #    ignored: false,
     /// };
     /// let mut iter = node_item.ancestors_rev(Path::new("/pre/fix/foo/bar")).unwrap();
     /// assert_eq!(Some(Path::new("/pre/fix/foo")), iter.next());
@@ -384,10 +392,12 @@ impl FileNodeItem {
         Some(node)
     }
 
-    /// Add a new (unread & unopened) child to the node.
+    /// Add a new (unread & unopened) child to the node.  
+    /// The child is ignored by git if its parent is.
     pub fn add_child(&mut self, path: &Path, is_dir: bool) -> Option<()> {
         let parent = path.parent()?;
         let node = self.get_file_node_mut(parent)?;
+        let ignored = node.ignored;
         node.children.insert(
             PathBuf::from(path),
             FileNodeItem {
@@ -480,6 +490,7 @@ impl FileNodeItem {
                 is_root: level == 1,
                 open: self.open,
                 level,
+                ignored: self.ignored,
             });
         }
 
@@ -12,12 +12,12 @@ use crossbeam_channel::{Receiver, Sender};
 use indexmap::IndexMap;
 use lapce_xi_rope::RopeDelta;
 use lsp_types::{
-    CallHierarchyIncomingCall, CallHierarchyItem, CodeAction, CodeActionResponse,
-    CodeLens, CompletionItem, Diagnostic, DocumentHighlight, DocumentSymbolResponse,
-    FoldingRange, GotoDefinitionResponse, Hover, InlayHint,
+    CallHierarchyIncomingCall, CallHierarchyItem, CodeAction, CodeActionKind,
+    CodeActionResponse, CodeLens, CompletionItem, Diagnostic, DocumentHighlight,
+    DocumentSymbolResponse, FoldingRange, GotoDefinitionResponse, Hover, InlayHint,
     InlineCompletionResponse, InlineCompletionTriggerKind, Location, Position,
-    PrepareRenameResponse, SelectionRange, SymbolInformation, TextDocumentItem,
-    TextEdit, WorkspaceEdit,
+    PrepareRenameResponse, Range, SelectionRange, SymbolInformation,
+    TextDocumentItem, TextEdit, WorkspaceEdit,
     request::{GotoImplementationResponse, GotoTypeDefinitionResponse},
 };
 use parking_lot::Mutex;
@@ -27,12 +27,18 @@ use super::plugin::VoltID;
 use crate::{
     RequestId, RpcError, RpcMessage, RpcResult,
     buffer::BufferId,
-    dap_types::{self, DapId, RunDebugConfig, SourceBreakpoint, ThreadId},
+    dap_types::{
+        self, DapId, DebuggerPaths, GlobalBreakpoints, RunDebugConfig,
+        SourceBreakpoint, ThreadId,
+    },
     file::{FileNodeItem, PathObject},
     file_line::FileLine,
     plugin::{PluginId, VoltInfo, VoltMetadata},
     rust_module_resolve::CargoContext,
-    source_control::FileDiff,
+    source_control::{
+        BlameHunk, CheckoutOutcome, CommitOptions, FileDiff, FileHistoryEntry,
+        LogEntry, MergeOutcome, PatchFile, PatchSource, PullOutcome, StageSelection,
+    },
     style::SemanticStyles,
     terminal::{TermId, TerminalProfile},
 };
@@ -104,6 +110,13 @@ pub enum ProxyLspRequest {
         position:    Position,
         diagnostics: Vec<Diagnostic>,
     },
+    /// Request the code actions of the given kinds for a range, such as
+    /// `source.organizeImports` over the whole document.
+    GetCodeActionsOfKind {
+        path:  PathBuf,
+        range: Range,
+        only:  Vec<CodeActionKind>,
+    },
     GetCodeLens {
         path: PathBuf,
     },
@@ -203,9 +216,143 @@ pub enum ProxyRequest {
         whole_word:     bool,
         is_regex:       bool,
     },
+    /// A link to `file` in the web view of the forge hosting the remote,
+    /// to the 0-based `lines` when set. `templates` are the URL templates by
+    /// host.
     GitGetRemoteFileUrl {
-        file: PathBuf,
+        file:      PathBuf,
+        lines:     Option<(u32, u32)>,
+        templates: HashMap<String, String>,
+    },
+    GitCommit {
+        message: String,
+        diffs:   Vec<FileDiff>,
+        options: CommitOptions,
+    },
+    /// The message to start the commit box with: the message of the last
+    /// commit when amending it, otherwise the `commit.template` file
+    GitCommitMessage {
+        amend: bool,
+    },
+    GitStage {
+        selection: StageSelection,
+    },
+    GitUnstage {
+        selection: StageSelection,
+    },
+    GitBlame {
+        path: PathBuf,
+    },
+    /// The paths among `paths` that the `.gitignore` files ignore
+    GitIgnoredPaths {
+        paths: Vec<PathBuf>,
+    },
+    GitGetFileAtRevision {
+        path:     PathBuf,
+        revision: String,
+    },
+    /// A page of the commits that changed a file, newest first
+    GitFileHistory {
+        /// The path of the file at `from`
+        path:  PathBuf,
+        /// The id of the last commit of the previous page, `None` for the
+        /// first page
+        from:  Option<String>,
+        limit: usize,
+    },
+    /// A page of the commits reachable from `HEAD` and the branches, newest
+    /// first
+    GitLog {
+        /// The id of the last commit of the previous page, `None` for the
+        /// first page
+        from:        Option<String>,
+        limit:       usize,
+        /// Only list the commits changing this file or directory
+        path_filter: Option<PathBuf>,
+    },
+    /// The files `commit` changed compared to its first parent
+    GitCommitFiles {
+        commit: String,
+    },
+    /// The files changed from `base` to `target`, or to the working copy
+    /// without a `target`
+    GitCompare {
+        base:   String,
+        target: Option<String>,
+    },
+    /// The files and hunks of `patch`, checked against the working copy
+    GitPreviewPatch {
+        patch: String,
+    },
+    /// Apply `patch` to the working copy, only the hunks at the given
+    /// indexes, counted across the files, when `hunks` is set
+    GitApplyPatch {
+        patch: String,
+        hunks: Option<Vec<usize>>,
+    },
+    /// The changes of `source` as a patch
+    GitCreatePatch {
+        source: PatchSource,
+    },
+    /// Apply the changes of `commit` on top of `HEAD`
+    GitCherryPick {
+        commit: String,
+    },
+    /// Commit the reverse of the changes of `commit`
+    GitRevert {
+        commit: String,
+    },
+    GitCheckout {
+        reference:  String,
+        auto_stash: bool,
+    },
+    GitCreateBranch {
+        name:        String,
+        /// The ref to branch from, `HEAD` when `None`
+        start_point: Option<String>,
+    },
+    GitRenameBranch {
+        old_name: String,
+        new_name: String,
+    },
+    GitDeleteBranch {
+        name:  String,
+        force: bool,
+    },
+    GitMerge {
+        reference: String,
+        no_ff:     bool,
+    },
+    GitStashSave {
+        include_untracked: bool,
+    },
+    /// The stashes are given by the id of their commit, as their indexes
+    /// change when stashes are saved and dropped
+    GitStashApply {
+        id: String,
+    },
+    GitStashPop {
+        id: String,
+    },
+    GitStashDrop {
+        id: String,
+    },
+    /// The files a stash changed, listed when it is expanded
+    GitStashFiles {
+        id: String,
     },
+    /// Fetch `remote`, or the remote of the current branch's upstream
+    GitFetch {
+        remote: Option<String>,
+    },
+    /// Fetch the upstream of the current branch and fast-forward to it, or
+    /// rebase onto it
+    GitPull {
+        rebase: bool,
+    },
+    /// Push the current branch to its upstream, setting one up on `origin`
+    /// if it has none
+    GitPush {},
     LspRequest(ProxyLspRequest),
     PrepareRename {
         path:     PathBuf,
@@ -269,6 +416,25 @@ pub enum ProxyRequest {
         dap_id:   DapId,
         frame_id: usize,
     },
+    DapEvaluate {
+        dap_id:     DapId,
+        expression: String,
+        frame_id:   Option<usize>,
+        context:    String,
+    },
+    DapSetVariable {
+        dap_id:    DapId,
+        reference: usize,
+        name:      String,
+        value:     String,
+    },
+    DapSetExpression {
+        dap_id:     DapId,
+        expression: String,
+        value:      String,
+        frame_id:   Option<usize>,
+    },
+    ListProcesses {},
     ReferencesResolve {
         items: Vec<Location>,
     },
@@ -328,13 +494,6 @@ pub enum ProxyNotification {
         raw_id:  u64,
         profile: TerminalProfile,
     },
-    GitCommit {
-        message: String,
-        diffs:   Vec<FileDiff>,
-    },
-    GitCheckout {
-        reference: String,
-    },
     GitDiscardFilesChanges {
         files: Vec<PathBuf>,
     },
@@ -358,8 +517,10 @@ pub enum ProxyNotification {
         raw_id:  u64,
     },
     DapStart {
-        config:      RunDebugConfig,
-        breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        config:             RunDebugConfig,
+        breakpoints:        HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
+        debugger_paths:     DebuggerPaths,
     },
     DapProcessId {
         dap_id:     DapId,
@@ -393,14 +554,20 @@ pub enum ProxyNotification {
         dap_id: DapId,
     },
     DapRestart {
-        config:      RunDebugConfig,
-        breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        config:             RunDebugConfig,
+        breakpoints:        HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
+        debugger_paths:     DebuggerPaths,
     },
     DapSetBreakpoints {
         dap_id:      DapId,
         path:        PathBuf,
         breakpoints: Vec<SourceBreakpoint>,
     },
+    DapSetGlobalBreakpoints {
+        dap_id:      DapId,
+        breakpoints: GlobalBreakpoints,
+    },
     RustBuild {
         rev:       u64,
         command:   String,
@@ -415,6 +582,71 @@ pub enum ProxyResponse {
     GitGetRemoteFileUrl {
         file_url: String,
     },
+    GitCommitResponse {},
+    GitCommitMessageResponse {
+        message: Option<String>,
+    },
+    GitStageResponse {},
+    GitBlameResponse {
+        blame: Vec<BlameHunk>,
+    },
+    GitIgnoredPathsResponse {
+        paths: Vec<PathBuf>,
+    },
+    GitGetFileAtRevisionResponse {
+        content: String,
+    },
+    GitFileHistoryResponse {
+        history: Vec<FileHistoryEntry>,
+        /// Whether there are more commits after this page
+        more:    bool,
+    },
+    GitLogResponse {
+        entries: Vec<LogEntry>,
+        /// Whether there are more commits after this page
+        more:    bool,
+    },
+    GitCommitFilesResponse {
+        files: Vec<FileDiff>,
+    },
+    GitCompareResponse {
+        files: Vec<FileDiff>,
+    },
+    GitPreviewPatchResponse {
+        files: Vec<PatchFile>,
+    },
+    GitApplyPatchResponse {},
+    GitCreatePatchResponse {
+        patch: String,
+    },
+    /// The result of a cherry-pick or a revert
+    GitApplyCommitResponse {
+        /// `true` when it stopped with conflicts left in the working copy,
+        /// in which case nothing was committed
+        conflicts: bool,
+    },
+    GitCheckoutResponse {
+        outcome: CheckoutOutcome,
+    },
+    GitRenameBranchResponse {},
+    GitDeleteBranchResponse {
+        /// `false` when the branch isn't merged and `force` wasn't set
+        deleted: bool,
+    },
+    GitMergeResponse {
+        outcome: MergeOutcome,
+    },
+    GitStashResponse {},
+    GitStashFilesResponse {
+        /// The changes of the working copy and the index the stash recorded
+        files:     Vec<FileDiff>,
+        /// The untracked files the stash recorded
+        untracked: Vec<PathBuf>,
+    },
+    GitRemoteResponse {},
+    GitPullResponse {
+        outcome: PullOutcome,
+    },
     NewBufferResponse {
         rs: RpcResult<(String, bool, Option<PathBuf>)>,
         // content:   String,
@@ -527,6 +759,18 @@ pub enum ProxyResponse {
     DapGetScopesResponse {
         scopes: Vec<(dap_types::Scope, Vec<dap_types::Variable>)>,
     },
+    DapEvaluateResponse {
+        result: dap_types::EvaluateResponse,
+    },
+    DapSetVariableResponse {
+        result: dap_types::SetVariableResponse,
+    },
+    DapSetExpressionResponse {
+        result: dap_types::SetExpressionResponse,
+    },
+    ListProcessesResponse {
+        processes: Vec<dap_types::ProcessInfo>,
+    },
     CreatePathResponse {
         path: PathBuf,
     },
@@ -699,12 +943,26 @@ impl ProxyRpcHandler {
         self.notification(ProxyNotification::GitInit {});
     }
 
-    pub fn git_commit(&self, message: String, diffs: Vec<FileDiff>) {
-        self.notification(ProxyNotification::GitCommit { message, diffs });
+    /// Commit `diffs`, or what is staged when it is empty.
+    pub fn git_commit(
+        &self,
+        message: String,
+        diffs: Vec<FileDiff>,
+        options: CommitOptions,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GitCommit {
+                message,
+                diffs,
+                options,
+            },
+            f,
+        );
     }
 
-    pub fn git_checkout(&self, reference: String) {
-        self.notification(ProxyNotification::GitCheckout { reference });
+    pub fn git_commit_message(&self, amend: bool, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitCommitMessage { amend }, f);
     }
 
     pub fn install_volt(&self, volt: VoltInfo) {
@@ -1142,6 +1400,19 @@ impl ProxyRpcHandler {
         );
     }
 
+    pub fn get_code_actions_of_kind(
+        &self,
+        path: PathBuf,
+        range: Range,
+        only: Vec<CodeActionKind>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyLspRequest::GetCodeActionsOfKind { path, range, only },
+            f,
+        );
+    }
+
     pub fn get_code_lens(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyLspRequest::GetCodeLens { path }, f);
     }
@@ -1239,9 +1510,18 @@ impl ProxyRpcHandler {
     pub fn git_get_remote_file_url(
         &self,
         file: PathBuf,
+        lines: Option<(u32, u32)>,
+        templates: HashMap<String, String>,
         f: impl ProxyCallback + 'static,
     ) {
-        self.request_async(ProxyRequest::GitGetRemoteFileUrl { file }, f);
+        self.request_async(
+            ProxyRequest::GitGetRemoteFileUrl {
+                file,
+                lines,
+                templates,
+            },
+            f,
+        );
     }
 
     pub fn rename(
@@ -1293,6 +1573,203 @@ impl ProxyRpcHandler {
         self.notification(ProxyNotification::UpdatePluginConfigs { configs });
     }
 
+    pub fn git_stage(
+        &self,
+        selection: StageSelection,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitStage { selection }, f);
+    }
+
+    pub fn git_unstage(
+        &self,
+        selection: StageSelection,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitUnstage { selection }, f);
+    }
+
+    pub fn git_blame(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitBlame { path }, f);
+    }
+
+    pub fn git_ignored_paths(
+        &self,
+        paths: Vec<PathBuf>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitIgnoredPaths { paths }, f);
+    }
+
+    pub fn git_get_file_at_revision(
+        &self,
+        path: PathBuf,
+        revision: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitGetFileAtRevision { path, revision }, f);
+    }
+
+    pub fn git_file_history(
+        &self,
+        path: PathBuf,
+        from: Option<String>,
+        limit: usize,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitFileHistory { path, from, limit }, f);
+    }
+
+    pub fn git_log(
+        &self,
+        from: Option<String>,
+        limit: usize,
+        path_filter: Option<PathBuf>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GitLog {
+                from,
+                limit,
+                path_filter,
+            },
+            f,
+        );
+    }
+
+    pub fn git_commit_files(&self, commit: String, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitCommitFiles { commit }, f);
+    }
+
+    pub fn git_compare(
+        &self,
+        base: String,
+        target: Option<String>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitCompare { base, target }, f);
+    }
+
+    pub fn git_preview_patch(&self, patch: String, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitPreviewPatch { patch }, f);
+    }
+
+    pub fn git_apply_patch(
+        &self,
+        patch: String,
+        hunks: Option<Vec<usize>>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitApplyPatch { patch, hunks }, f);
+    }
+
+    pub fn git_create_patch(
+        &self,
+        source: PatchSource,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitCreatePatch { source }, f);
+    }
+
+    pub fn git_cherry_pick(&self, commit: String, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitCherryPick { commit }, f);
+    }
+
+    pub fn git_revert(&self, commit: String, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitRevert { commit }, f);
+    }
+
+    pub fn git_checkout(
+        &self,
+        reference: String,
+        auto_stash: bool,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GitCheckout {
+                reference,
+                auto_stash,
+            },
+            f,
+        );
+    }
+
+    pub fn git_create_branch(
+        &self,
+        name: String,
+        start_point: Option<String>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitCreateBranch { name, start_point }, f);
+    }
+
+    pub fn git_rename_branch(
+        &self,
+        old_name: String,
+        new_name: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitRenameBranch { old_name, new_name }, f);
+    }
+
+    pub fn git_delete_branch(
+        &self,
+        name: String,
+        force: bool,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitDeleteBranch { name, force }, f);
+    }
+
+    pub fn git_merge(
+        &self,
+        reference: String,
+        no_ff: bool,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitMerge { reference, no_ff }, f);
+    }
+
+    pub fn git_stash_save(
+        &self,
+        include_untracked: bool,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitStashSave { include_untracked }, f);
+    }
+
+    pub fn git_stash_apply(&self, id: String, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitStashApply { id }, f);
+    }
+
+    pub fn git_stash_pop(&self, id: String, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitStashPop { id }, f);
+    }
+
+    pub fn git_stash_drop(&self, id: String, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitStashDrop { id }, f);
+    }
+
+    pub fn git_stash_files(&self, id: String, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitStashFiles { id }, f);
+    }
+
+    pub fn git_fetch(
+        &self,
+        remote: Option<String>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitFetch { remote }, f);
+    }
+
+    pub fn git_pull(&self, rebase: bool, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitPull { rebase }, f);
+    }
+
+    pub fn git_push(&self, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitPush {}, f);
+    }
+
     pub fn git_discard_files_changes(&self, files: Vec<PathBuf>) {
         self.notification(ProxyNotification::GitDiscardFilesChanges { files });
     }
@@ -1317,10 +1794,14 @@ impl ProxyRpcHandler {
         &self,
         config: RunDebugConfig,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
+        debugger_paths: DebuggerPaths,
     ) {
         self.notification(ProxyNotification::DapStart {
             config,
             breakpoints,
+            global_breakpoints,
+            debugger_paths,
         })
     }
 
@@ -1341,10 +1822,14 @@ impl ProxyRpcHandler {
         &self,
         config: RunDebugConfig,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        global_breakpoints: GlobalBreakpoints,
+        debugger_paths: DebuggerPaths,
     ) {
         self.notification(ProxyNotification::DapRestart {
             config,
             breakpoints,
+            global_breakpoints,
+            debugger_paths,
         })
     }
 
@@ -1389,6 +1874,17 @@ impl ProxyRpcHandler {
         })
     }
 
+    pub fn dap_set_global_breakpoints(
+        &self,
+        dap_id: DapId,
+        breakpoints: GlobalBreakpoints,
+    ) {
+        self.notification(ProxyNotification::DapSetGlobalBreakpoints {
+            dap_id,
+            breakpoints,
+        })
+    }
+
     pub fn dap_variable(
         &self,
         dap_id: DapId,
@@ -1406,6 +1902,67 @@ impl ProxyRpcHandler {
     ) {
         self.request_async(ProxyRequest::DapGetScopes { dap_id, frame_id }, f);
     }
+
+    pub fn dap_evaluate(
+        &self,
+        dap_id: DapId,
+        expression: String,
+        frame_id: Option<usize>,
+        context: &str,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::DapEvaluate {
+                dap_id,
+                expression,
+                frame_id,
+                context: context.to_string(),
+            },
+            f,
+        );
+    }
+
+    pub fn dap_set_variable(
+        &self,
+        dap_id: DapId,
+        reference: usize,
+        name: String,
+        value: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::DapSetVariable {
+                dap_id,
+                reference,
+                name,
+                value,
+            },
+            f,
+        );
+    }
+
+    pub fn dap_set_expression(
+        &self,
+        dap_id: DapId,
+        expression: String,
+        value: String,
+        frame_id: Option<usize>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::DapSetExpression {
+                dap_id,
+                expression,
+                value,
+                frame_id,
+            },
+            f,
+        );
+    }
+
+    pub fn list_processes(&self, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::ListProcesses {}, f);
+    }
 }
 
 impl Default for ProxyRpcHandler {
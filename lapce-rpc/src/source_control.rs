@@ -2,12 +2,37 @@ use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
+/// The code of the error answering a request for the content of a file at a
+/// revision which doesn't have the file.
+pub const FILE_NOT_IN_REVISION: i64 = 1;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
 pub struct DiffInfo {
-    pub head:     String,
-    pub branches: Vec<String>,
-    pub tags:     Vec<String>,
-    pub diffs:    Vec<FileDiff>,
+    pub head:         String,
+    /// The id of the commit checked out, `None` on an unborn branch
+    pub head_commit:  Option<String>,
+    pub branches:     Vec<String>,
+    pub tags:         Vec<String>,
+    /// Changes of the working copy that are not staged (index vs workdir)
+    pub diffs:        Vec<FileDiff>,
+    /// Changes staged for the next commit (HEAD vs index)
+    pub staged:       Vec<FileDiff>,
+    /// Most recent first
+    pub stashes:      Vec<StashEntry>,
+    /// How many commits the current branch is ahead of and behind its
+    /// upstream, if it has one
+    pub ahead_behind: Option<(usize, usize)>,
+    /// Files left with conflicts by a merge, rebase or stash apply
+    pub conflicts:    Vec<PathBuf>,
+}
+
+/// The stash `stash@{index}`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StashEntry {
+    pub index:   usize,
+    /// The id of the stash commit
+    pub id:      String,
+    pub message: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -28,6 +53,14 @@ impl FileDiff {
         }
     }
 
+    /// The paths to stage or unstage for this change.
+    pub fn paths(&self) -> Vec<&PathBuf> {
+        match self {
+            FileDiff::Renamed(new, old) => vec![new, old],
+            _ => vec![self.path()],
+        }
+    }
+
     pub fn kind(&self) -> FileDiffKind {
         match self {
             FileDiff::Modified(_) => FileDiffKind::Modified,
@@ -38,6 +71,131 @@ impl FileDiff {
     }
 }
 
+/// The part of the changes to move in or out of the git index. Line numbers
+/// are 0-based lines of the working copy.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum StageSelection {
+    Files(Vec<PathBuf>),
+    /// The hunk of `path` touching `line`
+    Hunk {
+        path: PathBuf,
+        line: u32,
+    },
+    /// The changed lines of `path` in `start..=end`
+    Lines {
+        path:  PathBuf,
+        start: u32,
+        end:   u32,
+    },
+}
+
+/// A run of consecutive lines last changed by the same commit.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BlameHunk {
+    /// 0-based first line of the hunk in the blamed text
+    pub start_line: u32,
+    pub lines:      u32,
+    /// `None` for lines that are not committed yet
+    pub commit:     Option<CommitInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CommitInfo {
+    pub id:      String,
+    pub author:  String,
+    /// Author time in seconds since the unix epoch
+    pub time:    i64,
+    pub summary: String,
+}
+
+/// A commit that changed a file, as listed in the history of the file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileHistoryEntry {
+    pub commit:        CommitInfo,
+    /// The path of the file in this commit
+    pub path:          PathBuf,
+    /// The path of the file in the parent commit, when this commit renamed it
+    pub previous_path: Option<PathBuf>,
+}
+
+/// A commit listed in the log, with what drawing the graph of branches and
+/// merges needs.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LogEntry {
+    pub commit:  CommitInfo,
+    /// The ids of the parent commits, the first parent first
+    pub parents: Vec<String>,
+    /// The branches, remote branches and tags pointing at the commit, and
+    /// `HEAD` when it is checked out
+    pub refs:    Vec<String>,
+}
+
+/// How a commit is made from the commit message box.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CommitOptions {
+    /// Replace the last commit instead of adding one on top of it
+    pub amend:     bool,
+    /// End the message with a `Signed-off-by` trailer for the committer
+    pub sign_off:  bool,
+    /// Run the `pre-commit` and `commit-msg` hooks of the repository
+    pub run_hooks: bool,
+}
+
+/// The result of switching to another ref.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CheckoutOutcome {
+    CheckedOut,
+    /// Nothing was done because local changes would be overwritten
+    LocalChanges,
+    /// The ref was checked out but the auto-stashed changes conflict with
+    /// it. They are kept in the stash list.
+    StashConflicts,
+}
+
+/// How a merge was carried out.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum MergeOutcome {
+    UpToDate,
+    FastForward,
+    /// A merge commit was created
+    Merged,
+    /// The merge stopped with conflicts left in the working copy
+    Conflicts,
+}
+
+/// How a pull updated the current branch.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PullOutcome {
+    UpToDate,
+    FastForward,
+    /// The local commits were replayed on top of the upstream ones
+    Rebased,
+}
+
+/// A file changed by a patch, with whether each of its hunks applies to the
+/// working copy.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct PatchFile {
+    pub path:  PathBuf,
+    pub hunks: Vec<PatchHunk>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct PatchHunk {
+    /// The `@@ -a,b +c,d @@` line of the hunk
+    pub header:  String,
+    pub applies: bool,
+}
+
+/// The changes to write as a patch.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PatchSource {
+    Unstaged,
+    Staged,
+    /// The changes of a commit compared to its first parent
+    Commit(String),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileDiffKind {
     Modified,